@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use audio_backend::instruments::{GrainWindow, GranularParams};
+use audio_backend::{BlightAudio, GranularCmd, InstrumentCmd, Result, SynthCmd};
+
+fn main() -> Result<()> {
+    match &mut BlightAudio::new() {
+        Ok(audio) => {
+            let resource_manager = audio.get_resource_manager();
+            let sample_id = 1;
+            let path = PathBuf::from(
+                "audio_backend/examples/assets/sample 2 chan - 24 bit - 44.1 khz.wav",
+            );
+            resource_manager.add_sample_from_file(sample_id, path)?;
+            let sample_data = resource_manager.get_sample_unsafe(sample_id);
+
+            let instrument_id = 0;
+            let params = GranularParams {
+                position: 0.3,
+                spray: 0.1,
+                grain_size: 0.12,
+                density: 30.0,
+                window: GrainWindow::Hann,
+                reverse_probability: 0.25,
+                ..GranularParams::default()
+            };
+            audio.send_command(
+                InstrumentCmd::AddInstrument {
+                    instrument: audio.get_instrument_factory().create_granular_sampler(
+                        instrument_id,
+                        0.0,
+                        sample_data,
+                        4,
+                        params,
+                    ),
+                }
+                .into(),
+            );
+            audio.send_command(audio_backend::TransportCmd::PlayLastSong.into());
+
+            for note in [48, 55, 60] {
+                audio.send_command(
+                    InstrumentCmd::NoteOn {
+                        instrument_id,
+                        note,
+                        velocity: 100,
+                    }
+                    .into(),
+                );
+            }
+
+            // Sweep the read position through the sample while the chord sustains.
+            for step in 0..=40 {
+                audio.send_command(
+                    InstrumentCmd::PassOnSynthCmd {
+                        instrument_id,
+                        synth_cmd: SynthCmd::GranularCommand {
+                            command: GranularCmd::SetPosition {
+                                position: step as f32 / 40.0,
+                            },
+                        },
+                    }
+                    .into(),
+                );
+                thread::sleep(Duration::from_millis(150));
+            }
+
+            audio.send_command(InstrumentCmd::NoteOff { instrument_id }.into());
+            thread::sleep(Duration::from_millis(2000));
+        }
+        Err(e) => {
+            eprintln!("Error initializing audio: {}", e);
+        }
+    }
+    Ok(())
+}
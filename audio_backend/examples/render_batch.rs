@@ -0,0 +1,126 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::{bail, Result};
+use audio_backend::{
+    collect_song_files, render_batch, OfflineGoldenManifest, OfflineRender, OfflineRenderConfig,
+    OfflineRenderReference, OfflineTailConfig,
+};
+use clap::Parser;
+
+/// Renders many JSON songs concurrently with the canonical offline profile.
+///
+/// Each song renders on its own player, so the output does not depend on the
+/// number of jobs. The optional manifest uses the golden reference format.
+#[derive(Parser)]
+struct Args {
+    /// Song files, or directories whose `.json` files are all rendered.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    /// Directory for one canonical WAV per song, named after the song file.
+    #[arg(long, value_name = "DIR")]
+    output: Option<PathBuf>,
+    /// Write the references of every song as an offline render manifest.
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+    /// Also render every song with the canonical tail profile (`_tail.wav`
+    /// files and the manifest's `tail_songs`).
+    #[arg(long)]
+    tail: bool,
+    /// Worker threads; defaults to the number of CPU cores.
+    #[arg(long, short)]
+    jobs: Option<NonZeroUsize>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let songs = collect_song_files(&args.inputs)?;
+    if songs.is_empty() {
+        bail!("no songs found");
+    }
+    let mut stems = BTreeSet::new();
+    for song in &songs {
+        if !stems.insert(song.file_stem()) {
+            bail!("{} shares its file name with another song", song.display());
+        }
+    }
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN));
+    if let Some(output) = &args.output {
+        std::fs::create_dir_all(output)?;
+    }
+
+    let config = OfflineRenderConfig::canonical();
+    let tail = OfflineTailConfig::canonical();
+    let started = Instant::now();
+    println!("rendering {} songs on {jobs} threads", songs.len());
+    let references = render(&songs, config, jobs, args.output.as_deref(), "")?;
+    let tail_references = if args.tail {
+        let config = OfflineRenderConfig {
+            tail: Some(tail),
+            ..config
+        };
+        render(&songs, config, jobs, args.output.as_deref(), "_tail")?
+    } else {
+        BTreeMap::new()
+    };
+    println!("finished in {:.1} s", started.elapsed().as_secs_f64());
+
+    if let Some(path) = &args.manifest {
+        let manifest =
+            OfflineGoldenManifest::characterization(config, references, tail, tail_references);
+        let mut json = serde_json::to_string_pretty(&manifest)?;
+        json.push('\n');
+        std::fs::write(path, json)?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Renders every song with `config`, writing `<song><wav_suffix>.wav` into
+/// `output`, and returns the references keyed like the golden manifest.
+fn render(
+    songs: &[PathBuf],
+    config: OfflineRenderConfig,
+    jobs: NonZeroUsize,
+    output: Option<&Path>,
+    wav_suffix: &str,
+) -> Result<BTreeMap<String, OfflineRenderReference>> {
+    let results = render_batch(songs, config, jobs, |path, render: OfflineRender| {
+        if let Some(output) = output {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            render.write_wav(&output.join(format!("{stem}{wav_suffix}.wav")))?;
+        }
+        Ok(render.reference())
+    });
+
+    let mut references = BTreeMap::new();
+    let mut failed = 0;
+    for (path, result) in songs.iter().zip(results) {
+        match result {
+            Ok(reference) => {
+                let label = if config.tail.is_some() { " (tail)" } else { "" };
+                println!(
+                    "{}{label}: {} frames, {}",
+                    path.display(),
+                    reference.frames,
+                    reference.pcm_sha256
+                );
+                references.insert(path.to_string_lossy().into_owned(), reference);
+            }
+            Err(error) => {
+                eprintln!("{error:#}");
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{failed} of {} songs failed to render", songs.len());
+    }
+    Ok(references)
+}
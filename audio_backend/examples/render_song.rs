@@ -1,32 +1,147 @@
 use std::path::PathBuf;
 
-use anyhow::{bail, Context, Result};
-use audio_backend::{render_json_song, OfflineRenderConfig};
+use anyhow::{Context, Result};
+use audio_backend::{
+    render_json_song, Dither, Normalization, OfflineLoop, OfflineLoopPart, OfflineRenderConfig,
+    OfflineSelection, OfflineTailConfig, TrackSet, WavExportOptions, WavSampleFormat,
+};
+use clap::{Parser, ValueEnum};
+
+/// Renders a JSON song offline and exports it as WAV.
+///
+/// Without export options the output is the canonical 16-bit PCM whose hash
+/// the golden tests check.
+#[derive(Parser)]
+struct Args {
+    /// Song to render.
+    song: PathBuf,
+    /// WAV file for the master mix.
+    output: PathBuf,
+    /// Also write `master.wav` and one WAV per instrument into this directory.
+    #[arg(long, value_name = "DIR")]
+    stems: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = Format::Pcm16)]
+    format: Format,
+    /// Dither applied when rounding to 16 or 24 bits.
+    #[arg(long, value_enum, default_value_t = DitherArg::None)]
+    dither: DitherArg,
+    /// Scale so that the master's sample peak reaches this level.
+    #[arg(
+        long,
+        value_name = "DBFS",
+        allow_negative_numbers = true,
+        conflicts_with = "normalize_lufs"
+    )]
+    normalize_peak: Option<f32>,
+    /// Scale so that the master's integrated loudness reaches this level.
+    #[arg(long, value_name = "LUFS", allow_negative_numbers = true)]
+    normalize_lufs: Option<f32>,
+    /// Keep rendering after the song ends until releases and effects decay.
+    #[arg(long)]
+    tail: bool,
+    #[arg(long, default_value_t = OfflineRenderConfig::canonical().sample_rate)]
+    sample_rate: u32,
+    /// First arrangement row to play.
+    #[arg(long, default_value_t = 0, value_name = "ROW")]
+    start_row: usize,
+    /// Arrangement row to stop at, exclusive.
+    #[arg(long, value_name = "ROW")]
+    end_row: Option<usize>,
+    /// Silence a track; may be repeated.
+    #[arg(long, value_name = "TRACK")]
+    mute: Vec<usize>,
+    /// Play only the soloed tracks; may be repeated.
+    #[arg(long, value_name = "TRACK")]
+    solo: Vec<usize>,
+    /// Loop a chain from the chain bank instead of playing the arrangement.
+    #[arg(
+        long,
+        value_name = "CHAIN",
+        conflicts_with_all = ["loop_phrase", "start_row", "end_row"]
+    )]
+    loop_chain: Option<usize>,
+    /// Loop a phrase from the phrase bank instead of playing the arrangement.
+    #[arg(long, value_name = "PHRASE", conflicts_with_all = ["start_row", "end_row"])]
+    loop_phrase: Option<usize>,
+    /// Track that plays the loop.
+    #[arg(long, default_value_t = 0, value_name = "TRACK")]
+    loop_track: usize,
+    /// How many times the loop plays.
+    #[arg(long, default_value_t = 1, value_name = "N")]
+    times: usize,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Pcm16,
+    Pcm24,
+    Float32,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DitherArg {
+    None,
+    Tpdf,
+    Shaped,
+}
 
 fn main() -> Result<()> {
-    let mut args = std::env::args_os().skip(1);
-    let Some(input) = args.next() else {
-        bail!(
-            "usage: cargo run -p audio_backend --example render_song -- <song.json> <output.wav>"
-        );
+    let args = Args::parse();
+
+    let canonical = OfflineRenderConfig::canonical();
+    let config = OfflineRenderConfig {
+        sample_rate: args.sample_rate,
+        max_frames: args.sample_rate as usize * 120,
+        stems: args.stems.is_some(),
+        tail: args.tail.then(OfflineTailConfig::canonical),
+        selection: OfflineSelection {
+            start_row: args.start_row,
+            end_row: args.end_row,
+            muted: TrackSet::from_tracks(args.mute)?,
+            soloed: TrackSet::from_tracks(args.solo)?,
+            looped: args
+                .loop_chain
+                .map(OfflineLoopPart::Chain)
+                .or(args.loop_phrase.map(OfflineLoopPart::Phrase))
+                .map(|part| OfflineLoop {
+                    part,
+                    track: args.loop_track,
+                    times: args.times,
+                }),
+        },
+        ..canonical
     };
-    let Some(output) = args.next() else {
-        bail!(
-            "usage: cargo run -p audio_backend --example render_song -- <song.json> <output.wav>"
-        );
+    let options = WavExportOptions {
+        format: match args.format {
+            Format::Pcm16 => WavSampleFormat::Pcm16,
+            Format::Pcm24 => WavSampleFormat::Pcm24,
+            Format::Float32 => WavSampleFormat::Float32,
+        },
+        dither: match args.dither {
+            DitherArg::None => Dither::None,
+            DitherArg::Tpdf => Dither::Tpdf,
+            DitherArg::Shaped => Dither::NoiseShapedTpdf,
+        },
+        normalization: match (args.normalize_peak, args.normalize_lufs) {
+            (Some(dbfs), _) => Normalization::Peak { dbfs },
+            (None, Some(lufs)) => Normalization::Loudness { lufs },
+            (None, None) => Normalization::None,
+        },
     };
-    if args.next().is_some() {
-        bail!("render_song accepts exactly one input and one output path");
-    }
 
-    let input = PathBuf::from(input);
-    let output = PathBuf::from(output);
-    let render = render_json_song(&input, OfflineRenderConfig::canonical())
+    let input = &args.song;
+    let output = &args.output;
+    let render = render_json_song(input, config)
         .with_context(|| format!("failed to render {}", input.display()))?;
-    render.write_wav(&output)?;
+    render.export_wav(output, &options)?;
     let reference = render.reference();
     println!("rendered {}", input.display());
     println!("  output: {}", output.display());
+    if let Some(stems_dir) = &args.stems {
+        for path in render.write_wavs(stems_dir, &options)? {
+            println!("  wrote: {}", path.display());
+        }
+    }
     println!("  frames: {}", reference.frames);
     println!("  PCM SHA-256: {}", reference.pcm_sha256);
     println!(
@@ -37,5 +152,36 @@ fn main() -> Result<()> {
         "  RMS L/R: {:.6} / {:.6}",
         reference.rms_left, reference.rms_right
     );
+    match render.integrated_loudness() {
+        Some(loudness) => println!("  loudness: {loudness:.1} LUFS"),
+        None => println!("  loudness: too quiet to measure"),
+    }
+    let gain = render.normalization_gain(options.normalization)?;
+    if gain != 1.0 {
+        println!("  normalization gain: {:+.2} dB", 20.0 * gain.log10());
+    }
+    let dsp_load = render.dsp_load();
+    println!(
+        "  DSP load: {:.2}% avg / {:.2}% peak over {} blocks",
+        dsp_load.block.average * 100.0,
+        dsp_load.block.peak * 100.0,
+        dsp_load.blocks
+    );
+    for instrument in &dsp_load.instruments {
+        println!(
+            "    instrument {}: {:.2}% avg / {:.2}% peak",
+            instrument.instrument_id,
+            instrument.load.average * 100.0,
+            instrument.load.peak * 100.0
+        );
+    }
+    for effect in &dsp_load.master_effects {
+        println!(
+            "    master effect {}: {:.2}% avg / {:.2}% peak",
+            effect.effect_id,
+            effect.load.average * 100.0,
+            effect.load.peak * 100.0
+        );
+    }
     Ok(())
 }
@@ -1,29 +1,73 @@
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{bail, Context, Result};
 use audio_backend::{
-    render_json_song, OfflineGoldenManifest, OfflineRenderConfig, OfflineRenderReference,
+    render_json_song, AudioComparison, AudioFingerprint, OfflineGoldenManifest,
+    OfflineRenderConfig, OfflineRenderReference, OfflineTailConfig, StereoAudio,
 };
 
 const SONGS: [&str; 2] = ["calibration.json", "ending_theme_no_effect.json"];
 
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().skip(1).collect();
-    if args.as_slice() != ["--update-reference"] {
-        bail!(
-            "this command rewrites reviewed audio references; run explicitly with --update-reference"
-        );
-    }
-
     let workspace = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .parent()
         .expect("audio_backend must be inside the workspace")
         .to_path_buf();
     let output_dir = workspace.join("target/offline-renders");
+    let golden_dir = workspace.join("audio_backend/tests/golden");
     std::fs::create_dir_all(&output_dir)?;
+    match args.as_slice() {
+        [flag] if flag == "--update-reference" => {
+            update_references(&output_dir, &golden_dir, &workspace)
+        }
+        [flag] if flag == "--compare" => compare_references(&output_dir, &golden_dir, &workspace),
+        _ => bail!(
+            "this command rewrites reviewed audio references; run explicitly with \
+             --update-reference, or with --compare to check renders against them"
+        ),
+    }
+}
+
+fn update_references(output_dir: &Path, golden_dir: &Path, workspace: &Path) -> Result<()> {
     let config = OfflineRenderConfig::canonical();
-    let mut references = BTreeMap::<String, OfflineRenderReference>::new();
+    let tail = OfflineTailConfig::canonical();
+    let references = render_references(workspace, output_dir, golden_dir, config, false)?;
+    let tail_references = render_references(
+        workspace,
+        output_dir,
+        golden_dir,
+        OfflineRenderConfig {
+            tail: Some(tail),
+            ..config
+        },
+        true,
+    )?;
 
+    let manifest =
+        OfflineGoldenManifest::characterization(config, references, tail, tail_references);
+    let manifest_path = golden_dir.join("offline_render_manifest.json");
+    let mut json = serde_json::to_string_pretty(&manifest)?;
+    json.push('\n');
+    std::fs::write(&manifest_path, json)?;
+    println!("updated {}", manifest_path.display());
+    println!("listen to every WAV before committing the reference update");
+    Ok(())
+}
+
+/// Renders every reference song twice with `config`, writing review WAVs
+/// (ending in `_tail.wav` for tail renders) and fingerprints.
+fn render_references(
+    workspace: &Path,
+    output_dir: &Path,
+    golden_dir: &Path,
+    config: OfflineRenderConfig,
+    tail: bool,
+) -> Result<BTreeMap<String, OfflineRenderReference>> {
+    let mut references = BTreeMap::<String, OfflineRenderReference>::new();
     for song_name in SONGS {
         let song_path = workspace.join(song_name);
         let first = render_json_song(&song_path, config)
@@ -34,8 +78,16 @@ fn main() -> Result<()> {
         if reference != second.reference() {
             bail!("{song_name} was not deterministic across repeated renders");
         }
-        let wav_path = output_dir.join(song_name.replace(".json", ".wav"));
+        let wav_path = output_dir.join(review_wav_name(song_name, tail, ""));
         first.write_wav(&wav_path)?;
+        let fingerprint_path =
+            golden_dir.join(OfflineGoldenManifest::fingerprint_file(song_name, tail));
+        std::fs::create_dir_all(
+            fingerprint_path
+                .parent()
+                .expect("fingerprint must have a parent"),
+        )?;
+        std::fs::write(&fingerprint_path, first.fingerprint().to_json()?)?;
         println!(
             "{song_name}: {} frames, {} -> {}",
             reference.frames,
@@ -44,18 +96,57 @@ fn main() -> Result<()> {
         );
         references.insert(song_name.to_string(), reference);
     }
+    Ok(references)
+}
 
-    let manifest = OfflineGoldenManifest::characterization(config, references);
-    let manifest_path = workspace.join("audio_backend/tests/golden/offline_render_manifest.json");
-    std::fs::create_dir_all(
-        manifest_path
-            .parent()
-            .expect("golden manifest must have a parent"),
+/// Renders every reference song and reports how far it drifted from its
+/// committed fingerprint. Review WAVs left in `output_dir` by the last
+/// `--update-reference` are null-tested too, writing `_residual.wav` files.
+fn compare_references(output_dir: &Path, golden_dir: &Path, workspace: &Path) -> Result<()> {
+    let manifest_path = golden_dir.join("offline_render_manifest.json");
+    let manifest: OfflineGoldenManifest = serde_json::from_str(
+        &std::fs::read_to_string(&manifest_path)
+            .with_context(|| format!("failed to read {}", manifest_path.display()))?,
     )?;
-    let mut json = serde_json::to_string_pretty(&manifest)?;
-    json.push('\n');
-    std::fs::write(&manifest_path, json)?;
-    println!("updated {}", manifest_path.display());
-    println!("listen to every WAV before committing the reference update");
+
+    let mut drifted = 0;
+    for (config, tail) in [(manifest.config, false), (manifest.tail_config(), true)] {
+        for song_name in SONGS {
+            let render = render_json_song(&workspace.join(song_name), config)
+                .with_context(|| format!("failed to render {song_name}"))?;
+            let expected = AudioFingerprint::load(
+                &golden_dir.join(OfflineGoldenManifest::fingerprint_file(song_name, tail)),
+            )?;
+            let mut comparison =
+                AudioComparison::new(&expected, &render.fingerprint(), manifest.comparison);
+
+            let reference_wav = output_dir.join(review_wav_name(song_name, tail, ""));
+            let mut residual_wav = None;
+            if reference_wav.exists() {
+                let null_test = render.null_test(&StereoAudio::read_wav(&reference_wav)?)?;
+                let path = output_dir.join(review_wav_name(song_name, tail, "_residual"));
+                null_test.write_residual_wav(&path, render.sample_rate())?;
+                residual_wav = Some(path);
+                comparison = comparison.with_null_test(null_test);
+            }
+
+            let label = if tail { " (tail)" } else { "" };
+            print!("{song_name}{label}: {comparison}");
+            if let Some(path) = residual_wav {
+                println!("  residual: {}", path.display());
+            }
+            if !comparison.passed() {
+                drifted += 1;
+            }
+        }
+    }
+    if drifted > 0 {
+        bail!("{drifted} renders drifted beyond the manifest's comparison tolerances");
+    }
     Ok(())
 }
+
+fn review_wav_name(song_name: &str, tail: bool, suffix: &str) -> String {
+    let tail = if tail { "_tail" } else { "" };
+    song_name.replace(".json", &format!("{tail}{suffix}.wav"))
+}
@@ -1,16 +1,106 @@
-use std::io::{self, Write};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
 
 use anyhow::Result;
-use audio_backend::{BlightAudio, MixerCmd, OscServer, MASTER_GAIN_EFFECT_ID};
+use audio_backend::{
+    list_output_devices, load_song_file_into_audio, master_parameter_bindings, AudioDeviceConfig,
+    BlightAudio, MixerCmd, NullOutputConfig, OscServer, MASTER_GAIN_EFFECT_ID, OSC_LISTEN_ADDR,
+    OSC_SEND_ADDR,
+};
+use clap::Parser;
+
+/// Standalone DSP engine controlled over OSC.
+///
+/// Prints `READY <json>` on stdout once audio runs and OSC is listening; the
+/// JSON reports the audio output, OSC addresses and preloaded song.
+#[derive(Parser)]
+struct Args {
+    /// Print the output devices of every audio host and exit.
+    #[arg(long)]
+    list_devices: bool,
+    /// Audio host as listed by `--list-devices`; defaults to the platform's.
+    #[arg(long, conflicts_with = "null_audio")]
+    host: Option<String>,
+    /// Output device name as listed by `--list-devices`.
+    #[arg(long, conflicts_with = "null_audio")]
+    device: Option<String>,
+    /// Output sample rate; defaults to the device's, or 48 kHz without one.
+    #[arg(long, value_name = "HZ")]
+    sample_rate: Option<u32>,
+    /// Frames per audio callback; defaults to the host's choice, or 512
+    /// without a device.
+    #[arg(long, value_name = "FRAMES")]
+    buffer_size: Option<u32>,
+    /// Run without an audio device, driven by a wall-clock timer.
+    #[arg(long)]
+    null_audio: bool,
+    /// Run without an audio device and write the output to a WAV file.
+    #[arg(long, value_name = "WAV", conflicts_with_all = ["host", "device"])]
+    render_to: Option<PathBuf>,
+    /// Address to receive OSC on; port 0 picks a free port.
+    #[arg(long, value_name = "ADDR", default_value = OSC_LISTEN_ADDR)]
+    osc_listen: String,
+    /// Address to send OSC responses and meters to.
+    #[arg(long, value_name = "ADDR", default_value = OSC_SEND_ADDR)]
+    osc_send: String,
+    /// JSON song to load before reporting READY.
+    #[arg(long, value_name = "PATH")]
+    song: Option<PathBuf>,
+}
+
+impl Args {
+    fn open_audio(&self) -> Result<BlightAudio> {
+        if self.null_audio || self.render_to.is_some() {
+            let defaults = NullOutputConfig::default();
+            BlightAudio::with_null_output(NullOutputConfig {
+                sample_rate: self.sample_rate.unwrap_or(defaults.sample_rate),
+                block_size: self
+                    .buffer_size
+                    .map_or(defaults.block_size, |frames| frames as usize),
+                render_to: self.render_to.clone(),
+                ..defaults
+            })
+        } else {
+            BlightAudio::with_device_config(&AudioDeviceConfig {
+                host: self.host.clone(),
+                device: self.device.clone(),
+                sample_rate: self.sample_rate,
+                buffer_size: self.buffer_size,
+            })
+        }
+    }
+}
+
+fn print_output_devices() -> Result<()> {
+    for device in list_output_devices()? {
+        let default = if device.is_default { " (default)" } else { "" };
+        let (min_rate, max_rate) = device.sample_rates;
+        let buffers = match device.buffer_sizes {
+            Some((min, max)) => format!(", buffers {min}-{max} frames"),
+            None => String::new(),
+        };
+        println!(
+            "{}: {}{default}: {} Hz, {} channels; supports {min_rate}-{max_rate} Hz{buffers}",
+            device.host, device.name, device.default_sample_rate, device.channels
+        );
+    }
+    Ok(())
+}
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
     env_logger::init();
+    let args = Args::parse();
+    if args.list_devices {
+        return print_output_devices();
+    }
 
     log::info!("starting standalone DSP core");
 
     // Keep the audio stream alive for the lifetime of this process.
-    let mut audio = BlightAudio::new()?;
+    let mut audio = args.open_audio()?;
 
     // Install the existing master gain effect that OSC `/param/set gain <db>` controls.
     audio.send_command(
@@ -21,13 +111,28 @@ async fn main() -> Result<()> {
         }
         .into(),
     );
+    // `/param/set` writes go through the parameter bank rather than the command queue.
+    for binding in master_parameter_bindings() {
+        audio.send_command(binding.into());
+    }
+    let song = match &args.song {
+        Some(path) => Some(load_song_file_into_audio(&mut audio, path)?),
+        None => None,
+    };
 
-    let osc_server = OscServer::bind().await?;
+    let osc_server = OscServer::bind_to(args.osc_listen, args.osc_send).await?;
     let meter = audio.meter_state();
 
-    // Contract with the future Bun host: stdout readiness detection waits for this line.
+    // Contract with the future Bun host: stdout readiness detection waits for a
+    // line starting with `READY`, followed by the configuration as JSON.
     // Print it only after audio is initialized and OSC is listening.
-    println!("READY");
+    let ready = serde_json::json!({
+        "audio": audio.output_info(),
+        "osc_listen": osc_server.local_addr()?,
+        "osc_send": osc_server.send_addr(),
+        "song": song.map(|song| song.name),
+    });
+    println!("READY {ready}");
     io::stdout().flush()?;
 
     log::info!("standalone DSP core ready; waiting for shutdown signal");
@@ -1,4 +1,4 @@
-pub use engine::{EngineCommand, InstrumentCmd, MixerCmd};
+pub use engine::{EngineCommand, InstrumentCmd, MixerCmd, StateCmd};
 use sequencer::models::Song;
 use std::sync::Arc;
 
@@ -31,6 +31,7 @@ pub enum Command {
     Sequencer(SequencerCmd),
     Mixer(MixerCmd),
     Instrument(InstrumentCmd),
+    State(StateCmd),
 }
 
 impl From<TransportCmd> for Command {
@@ -57,11 +58,18 @@ impl From<InstrumentCmd> for Command {
     }
 }
 
+impl From<StateCmd> for Command {
+    fn from(value: StateCmd) -> Self {
+        Self::State(value)
+    }
+}
+
 impl From<EngineCommand> for Command {
     fn from(value: EngineCommand) -> Self {
         match value {
             EngineCommand::Instrument(command) => Self::Instrument(command),
             EngineCommand::Mixer(command) => Self::Mixer(command),
+            EngineCommand::State(command) => Self::State(command),
         }
     }
 }
@@ -0,0 +1,672 @@
+//! Tolerance-based comparison of rendered audio.
+//!
+//! Exact PCM hashes break on any floating-point change. An [`AudioFingerprint`]
+//! instead keeps a compact per-window summary (octave band levels and sample
+//! peak) that a new render is checked against within [`ComparisonTolerances`].
+//! When the reference audio itself is available, a [`NullTest`] subtracts it
+//! from the render and measures what is left.
+
+use std::{f64::consts::PI, fmt, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{write_wav_file, OfflineRender, WavExportOptions, WavSampleFormat};
+
+/// Frames per fingerprint window (about 85 ms at 48 kHz). A power of two, as
+/// the band levels come from one FFT per window.
+pub const FINGERPRINT_WINDOW_FRAMES: usize = 4_096;
+/// Edges between the octave bands of a fingerprint; the lowest band starts at
+/// DC and the highest ends at Nyquist.
+const BAND_EDGES_HZ: [f32; 9] = [
+    44.0, 88.0, 177.0, 355.0, 710.0, 1_420.0, 2_840.0, 5_680.0, 11_360.0,
+];
+pub const FINGERPRINT_BANDS: usize = BAND_EDGES_HZ.len() + 1;
+/// Level stored for silence.
+const LEVEL_FLOOR_DB: f32 = -120.0;
+
+/// Band levels and sample peak of one fingerprint window, in dBFS rounded to
+/// 0.01 dB.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FingerprintWindow {
+    pub peak_db: f32,
+    /// Mean square of each band, averaged over both channels.
+    pub bands_db: [f32; FINGERPRINT_BANDS],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioFingerprint {
+    pub sample_rate: u32,
+    pub frames: usize,
+    pub window_frames: usize,
+    pub band_edges_hz: Vec<f32>,
+    pub windows: Vec<FingerprintWindow>,
+}
+
+impl AudioFingerprint {
+    pub fn new(left: &[f32], right: &[f32], sample_rate: u32) -> Self {
+        let frames = left.len().min(right.len());
+        let n = FINGERPRINT_WINDOW_FRAMES;
+        let hann = (0..n)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / n as f64).cos())
+            .collect::<Vec<_>>();
+        let window_energy = hann.iter().map(|w| w * w).sum::<f64>();
+        let twiddles = (0..n / 2)
+            .map(|k| (-2.0 * PI * k as f64 / n as f64).sin_cos())
+            .collect::<Vec<_>>();
+        let band_of_bin = (0..=n / 2)
+            .map(|bin| {
+                let hz = bin as f32 * sample_rate as f32 / n as f32;
+                BAND_EDGES_HZ.iter().filter(|&&edge| hz >= edge).count()
+            })
+            .collect::<Vec<_>>();
+
+        let mut re = vec![0.0; n];
+        let mut im = vec![0.0; n];
+        let mut windows = Vec::with_capacity(frames.div_ceil(n));
+        for start in (0..frames).step_by(n) {
+            let end = (start + n).min(frames);
+            let (left, right) = (&left[start..end], &right[start..end]);
+            let peak = left
+                .iter()
+                .chain(right)
+                .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+
+            // Both real channels share one complex FFT: left as the real part,
+            // right as the imaginary part.
+            re.fill(0.0);
+            im.fill(0.0);
+            for (i, (&l, &r)) in left.iter().zip(right).enumerate() {
+                re[i] = f64::from(l) * hann[i];
+                im[i] = f64::from(r) * hann[i];
+            }
+            fft(&mut re, &mut im, &twiddles);
+
+            let mut band_energy = [0.0_f64; FINGERPRINT_BANDS];
+            for (bin, &band) in band_of_bin.iter().enumerate() {
+                let mirror = (n - bin) % n;
+                // Sum of both channels' power at this bin.
+                let power = (re[bin] * re[bin]
+                    + im[bin] * im[bin]
+                    + re[mirror] * re[mirror]
+                    + im[mirror] * im[mirror])
+                    / 2.0;
+                let one_sided = if bin == 0 || bin == n / 2 { 1.0 } else { 2.0 };
+                band_energy[band] += one_sided * power;
+            }
+            let bands_db =
+                band_energy.map(|energy| power_to_db(energy / (n as f64 * window_energy * 2.0)));
+            windows.push(FingerprintWindow {
+                peak_db: power_to_db(f64::from(peak) * f64::from(peak)),
+                bands_db,
+            });
+        }
+
+        Self {
+            sample_rate,
+            frames,
+            window_frames: n,
+            band_edges_hz: BAND_EDGES_HZ.to_vec(),
+            windows,
+        }
+    }
+
+    /// Pretty JSON with one window per line, compact enough to commit.
+    pub fn to_json(&self) -> Result<String> {
+        let windows = self
+            .windows
+            .iter()
+            .map(|window| Ok(format!("    {}", serde_json::to_string(window)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(format!(
+            "{{\n  \"sample_rate\": {},\n  \"frames\": {},\n  \"window_frames\": {},\n  \
+             \"band_edges_hz\": {},\n  \"windows\": [\n{}\n  ]\n}}\n",
+            self.sample_rate,
+            self.frames,
+            self.window_frames,
+            serde_json::to_string(&self.band_edges_hz)?,
+            windows.join(",\n")
+        ))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    fn band_label(&self, band: usize) -> String {
+        let low = band
+            .checked_sub(1)
+            .map_or(0.0, |edge| self.band_edges_hz[edge]);
+        match self.band_edges_hz.get(band) {
+            Some(high) => format!("{low}-{high} Hz"),
+            None => format!("{low} Hz-Nyquist"),
+        }
+    }
+}
+
+/// How far a render may drift from its fingerprint before it counts as
+/// changed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ComparisonTolerances {
+    /// Allowed difference in length.
+    pub frames: usize,
+    /// Allowed change of any band level in any window.
+    pub band_db: f32,
+    /// Allowed change of any window's sample peak.
+    pub peak_db: f32,
+    /// Levels below this count as silence and are compared as equal.
+    pub floor_db: f32,
+    /// Highest allowed residual peak of a null test.
+    pub residual_peak_dbfs: f32,
+}
+
+impl Default for ComparisonTolerances {
+    fn default() -> Self {
+        Self {
+            frames: 0,
+            band_db: 0.5,
+            peak_db: 0.1,
+            floor_db: -80.0,
+            residual_peak_dbfs: -60.0,
+        }
+    }
+}
+
+/// One window's level in the reference and in the render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelDeviation {
+    pub seconds: f32,
+    pub expected_db: f32,
+    pub actual_db: f32,
+}
+
+impl LevelDeviation {
+    pub fn delta_db(&self) -> f32 {
+        self.actual_db - self.expected_db
+    }
+}
+
+/// Windows where one measure left its tolerance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drift {
+    pub label: String,
+    pub windows: usize,
+    pub worst: LevelDeviation,
+    pub tolerance_db: f32,
+}
+
+/// The residual of subtracting reference audio from a render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NullTest {
+    pub residual_peak_dbfs: f32,
+    pub residual_rms_dbfs: f32,
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+}
+
+impl NullTest {
+    /// Subtracts the reference from the render. Frames past the end of the
+    /// shorter signal are compared against silence.
+    pub fn new(
+        reference_left: &[f32],
+        reference_right: &[f32],
+        left: &[f32],
+        right: &[f32],
+    ) -> Self {
+        let residual = |reference: &[f32], actual: &[f32]| {
+            (0..reference.len().max(actual.len()))
+                .map(|i| {
+                    actual.get(i).copied().unwrap_or(0.0) - reference.get(i).copied().unwrap_or(0.0)
+                })
+                .collect::<Vec<_>>()
+        };
+        let left = residual(reference_left, left);
+        let right = residual(reference_right, right);
+        let samples = left.len() + right.len();
+        let peak = left
+            .iter()
+            .chain(&right)
+            .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+        let sum_squares = left
+            .iter()
+            .chain(&right)
+            .map(|&sample| f64::from(sample) * f64::from(sample))
+            .sum::<f64>();
+        Self {
+            residual_peak_dbfs: power_to_db(f64::from(peak) * f64::from(peak)),
+            residual_rms_dbfs: power_to_db(sum_squares / samples.max(1) as f64),
+            left,
+            right,
+        }
+    }
+
+    /// Writes the residual as a float WAV, so that it can be listened to at
+    /// any level.
+    pub fn write_residual_wav(&self, path: &Path, sample_rate: u32) -> Result<()> {
+        let options = WavExportOptions {
+            format: WavSampleFormat::Float32,
+            ..WavExportOptions::default()
+        };
+        write_wav_file(path, sample_rate, &self.left, &self.right, 1.0, &options)
+    }
+}
+
+/// The result of checking a render against its fingerprint, displayed as a
+/// human-readable report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioComparison {
+    pub tolerances: ComparisonTolerances,
+    pub expected_sample_rate: u32,
+    pub actual_sample_rate: u32,
+    pub expected_frames: usize,
+    pub actual_frames: usize,
+    /// `false` when the fingerprints were taken with different sample rates,
+    /// window lengths or bands, so their windows could not be compared.
+    pub comparable: bool,
+    /// Band and peak measures that left their tolerance, lowest band first
+    /// and the window peak last.
+    pub drifts: Vec<Drift>,
+    pub null_test: Option<NullTest>,
+}
+
+impl AudioComparison {
+    pub fn new(
+        expected: &AudioFingerprint,
+        actual: &AudioFingerprint,
+        tolerances: ComparisonTolerances,
+    ) -> Self {
+        let comparable = expected.sample_rate == actual.sample_rate
+            && expected.window_frames == actual.window_frames
+            && expected.band_edges_hz == actual.band_edges_hz;
+        let mut comparison = Self {
+            tolerances,
+            expected_sample_rate: expected.sample_rate,
+            actual_sample_rate: actual.sample_rate,
+            expected_frames: expected.frames,
+            actual_frames: actual.frames,
+            comparable,
+            drifts: Vec::new(),
+            null_test: None,
+        };
+        if !comparable {
+            return comparison;
+        }
+
+        let window_seconds = expected.window_frames as f32 / expected.sample_rate as f32;
+        let windows = expected.windows.iter().zip(&actual.windows).enumerate();
+        let measures = (0..FINGERPRINT_BANDS)
+            .map(|band| {
+                (
+                    expected.band_label(band),
+                    tolerances.band_db,
+                    Box::new(move |window: &FingerprintWindow| window.bands_db[band])
+                        as Box<dyn Fn(&FingerprintWindow) -> f32>,
+                )
+            })
+            .chain(std::iter::once((
+                "window peak".to_string(),
+                tolerances.peak_db,
+                Box::new(|window: &FingerprintWindow| window.peak_db) as Box<_>,
+            )));
+        for (label, tolerance_db, level) in measures {
+            let mut drift: Option<Drift> = None;
+            for (index, (expected, actual)) in windows.clone() {
+                let deviation = LevelDeviation {
+                    seconds: index as f32 * window_seconds,
+                    expected_db: level(expected),
+                    actual_db: level(actual),
+                };
+                let floor = tolerances.floor_db;
+                let delta = deviation.actual_db.max(floor) - deviation.expected_db.max(floor);
+                if delta.abs() <= tolerance_db {
+                    continue;
+                }
+                let drift = drift.get_or_insert_with(|| Drift {
+                    label: label.clone(),
+                    windows: 0,
+                    worst: deviation,
+                    tolerance_db,
+                });
+                drift.windows += 1;
+                if deviation.delta_db().abs() > drift.worst.delta_db().abs() {
+                    drift.worst = deviation;
+                }
+            }
+            comparison.drifts.extend(drift);
+        }
+        comparison
+    }
+
+    pub fn with_null_test(mut self, null_test: NullTest) -> Self {
+        self.null_test = Some(null_test);
+        self
+    }
+
+    pub fn passed(&self) -> bool {
+        self.comparable
+            && self.expected_frames.abs_diff(self.actual_frames) <= self.tolerances.frames
+            && self.drifts.is_empty()
+            && self.null_test.as_ref().is_none_or(|null_test| {
+                null_test.residual_peak_dbfs <= self.tolerances.residual_peak_dbfs
+            })
+    }
+}
+
+impl fmt::Display for AudioComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed() {
+            writeln!(f, "within tolerances")?;
+        } else {
+            writeln!(f, "drifted beyond tolerances")?;
+        }
+        if self.expected_sample_rate != self.actual_sample_rate {
+            writeln!(
+                f,
+                "  sample rate: expected {} Hz, got {} Hz",
+                self.expected_sample_rate, self.actual_sample_rate
+            )?;
+        } else if !self.comparable {
+            writeln!(
+                f,
+                "  fingerprints use different windows or bands; levels were not compared"
+            )?;
+        }
+        let frame_delta = self.actual_frames as i64 - self.expected_frames as i64;
+        writeln!(
+            f,
+            "  frames: expected {}, got {} ({frame_delta:+}, tolerance ±{})",
+            self.expected_frames, self.actual_frames, self.tolerances.frames
+        )?;
+        for drift in &self.drifts {
+            writeln!(
+                f,
+                "  {}: {} windows off, worst {:+.2} dB at {:.2} s \
+                 (expected {:.2} dB, got {:.2} dB; tolerance ±{:.2} dB)",
+                drift.label,
+                drift.windows,
+                drift.worst.delta_db(),
+                drift.worst.seconds,
+                drift.worst.expected_db,
+                drift.worst.actual_db,
+                drift.tolerance_db
+            )?;
+        }
+        if let Some(null_test) = &self.null_test {
+            writeln!(
+                f,
+                "  null test: residual peak {:.1} dBFS, RMS {:.1} dBFS (limit {:.1} dBFS peak)",
+                null_test.residual_peak_dbfs,
+                null_test.residual_rms_dbfs,
+                self.tolerances.residual_peak_dbfs
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Stereo audio read back from a WAV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StereoAudio {
+    pub sample_rate: u32,
+    /// Format of the file; [`WavSampleFormat::Pcm16`] for 8-bit and 32-bit
+    /// integer files too, which clip like it.
+    pub format: WavSampleFormat,
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+}
+
+impl StereoAudio {
+    /// Reads a mono or stereo WAV in any format the exporter writes. Integer
+    /// samples are scaled like the canonical quantizer, so a 16-bit export
+    /// reads back within half a step of the rendered samples.
+    pub fn read_wav(path: &Path) -> Result<Self> {
+        let mut reader = hound::WavReader::open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+            hound::SampleFormat::Int => {
+                let min = -((1_i64 << (spec.bits_per_sample - 1)) as f32);
+                reader
+                    .samples::<i32>()
+                    .map(|sample| {
+                        sample.map(|value| {
+                            let value = value as f32;
+                            if value >= 0.0 {
+                                value / (-min - 1.0)
+                            } else {
+                                value / -min
+                            }
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            }
+        }
+        .with_context(|| format!("failed to read {}", path.display()))?;
+        let (left, right) = match spec.channels {
+            1 => (samples.clone(), samples),
+            2 => samples
+                .chunks_exact(2)
+                .map(|frame| (frame[0], frame[1]))
+                .unzip(),
+            channels => bail!("{} has {channels} channels", path.display()),
+        };
+        let format = match (spec.sample_format, spec.bits_per_sample) {
+            (hound::SampleFormat::Float, _) => WavSampleFormat::Float32,
+            (hound::SampleFormat::Int, 24) => WavSampleFormat::Pcm24,
+            (hound::SampleFormat::Int, _) => WavSampleFormat::Pcm16,
+        };
+        Ok(Self {
+            sample_rate: spec.sample_rate,
+            format,
+            left,
+            right,
+        })
+    }
+}
+
+impl OfflineRender {
+    pub fn fingerprint(&self) -> AudioFingerprint {
+        AudioFingerprint::new(self.left(), self.right(), self.sample_rate())
+    }
+
+    /// Subtracts `reference` from this render. Integer references are
+    /// compared with the render clipped to full scale, as it was exported.
+    pub fn null_test(&self, reference: &StereoAudio) -> Result<NullTest> {
+        if reference.sample_rate != self.sample_rate() {
+            bail!(
+                "reference audio is at {} Hz, the render at {} Hz",
+                reference.sample_rate,
+                self.sample_rate()
+            );
+        }
+        if reference.format == WavSampleFormat::Float32 {
+            return Ok(NullTest::new(
+                &reference.left,
+                &reference.right,
+                self.left(),
+                self.right(),
+            ));
+        }
+        let clip = |channel: &[f32]| {
+            channel
+                .iter()
+                .map(|sample| sample.clamp(-1.0, 1.0))
+                .collect::<Vec<_>>()
+        };
+        Ok(NullTest::new(
+            &reference.left,
+            &reference.right,
+            &clip(self.left()),
+            &clip(self.right()),
+        ))
+    }
+}
+
+/// In-place iterative radix-2 FFT; `twiddles[k]` is `(sin, cos)` of
+/// `-2πk/n`.
+fn fft(re: &mut [f64], im: &mut [f64], twiddles: &[(f64, f64)]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let stride = n / len;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = twiddles[k * stride];
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Power to dB, rounded to 0.01 dB and floored at [`LEVEL_FLOOR_DB`].
+fn power_to_db(power: f64) -> f32 {
+    if power <= 0.0 {
+        return LEVEL_FLOOR_DB;
+    }
+    let db = (10.0 * power.log10()).max(f64::from(LEVEL_FLOOR_DB));
+    ((db * 100.0).round() / 100.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|n| {
+                amplitude * (2.0 * std::f32::consts::PI * frequency * n as f32 / 48_000.0).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_sine_lands_in_its_octave_band() {
+        let signal = sine(1_000.0, 1.0, 4 * FINGERPRINT_WINDOW_FRAMES);
+        let fingerprint = AudioFingerprint::new(&signal, &signal, 48_000);
+        assert_eq!(fingerprint.windows.len(), 4);
+        let window = fingerprint.windows[1];
+        // A full-scale sine has a mean square of 1/2.
+        assert!((window.bands_db[5] + 3.01).abs() < 0.1, "{window:?}");
+        for (band, level) in window.bands_db.iter().enumerate() {
+            if band != 5 {
+                assert!(*level < -40.0, "band {band}: {level} dB");
+            }
+        }
+        assert!(window.peak_db.abs() < 0.01);
+    }
+
+    #[test]
+    fn small_changes_pass_and_band_changes_are_reported() {
+        let frames = 8 * FINGERPRINT_WINDOW_FRAMES;
+        let low = sine(100.0, 0.5, frames);
+        let high = sine(5_000.0, 0.1, frames);
+        let mix = |high_gain: f32| {
+            low.iter()
+                .zip(&high)
+                .map(|(low, high)| low + high * high_gain)
+                .collect::<Vec<_>>()
+        };
+        let reference = mix(1.0);
+        let expected = AudioFingerprint::new(&reference, &reference, 48_000);
+        let tolerances = ComparisonTolerances::default();
+
+        let nudged = mix(1.001);
+        let comparison = AudioComparison::new(
+            &expected,
+            &AudioFingerprint::new(&nudged, &nudged, 48_000),
+            tolerances,
+        )
+        .with_null_test(NullTest::new(&reference, &reference, &nudged, &nudged));
+        assert!(comparison.passed(), "{comparison}");
+
+        let louder = mix(2.0);
+        let comparison = AudioComparison::new(
+            &expected,
+            &AudioFingerprint::new(&louder, &louder, 48_000),
+            tolerances,
+        );
+        assert!(!comparison.passed());
+        let labels = comparison
+            .drifts
+            .iter()
+            .map(|drift| drift.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["2840-5680 Hz", "window peak"], "{comparison}");
+        assert!((comparison.drifts[0].worst.delta_db() - 6.02).abs() < 0.05);
+        assert!(comparison
+            .to_string()
+            .contains("2840-5680 Hz: 8 windows off"));
+    }
+
+    #[test]
+    fn fingerprints_with_different_layouts_do_not_pass() {
+        let signal = sine(1_000.0, 0.5, 4 * FINGERPRINT_WINDOW_FRAMES);
+        let expected = AudioFingerprint::new(&signal, &signal, 48_000);
+        let tolerances = ComparisonTolerances::default();
+
+        let mut longer_windows = expected.clone();
+        longer_windows.window_frames *= 2;
+        let mut other_bands = expected.clone();
+        other_bands.band_edges_hz[0] *= 2.0;
+        for actual in [longer_windows, other_bands] {
+            let comparison = AudioComparison::new(&expected, &actual, tolerances);
+            assert!(!comparison.comparable);
+            assert!(!comparison.passed());
+            assert!(comparison.to_string().contains("levels were not compared"));
+        }
+        assert!(AudioComparison::new(&expected, &expected, tolerances).passed());
+    }
+
+    #[test]
+    fn null_tests_measure_the_residual() {
+        let reference = sine(440.0, 0.5, 1_000);
+        let identical = NullTest::new(&reference, &reference, &reference, &reference);
+        assert_eq!(identical.residual_peak_dbfs, LEVEL_FLOOR_DB);
+
+        let mut shifted = reference.clone();
+        shifted[10] += 0.01;
+        let null_test = NullTest::new(&reference, &reference, &shifted, &reference);
+        assert!((null_test.residual_peak_dbfs + 40.0).abs() < 0.01);
+        assert_eq!(null_test.left.len(), 1_000);
+
+        let tolerances = ComparisonTolerances::default();
+        let fingerprint = AudioFingerprint::new(&reference, &reference, 48_000);
+        let comparison =
+            AudioComparison::new(&fingerprint, &fingerprint, tolerances).with_null_test(null_test);
+        assert!(!comparison.passed(), "{comparison}");
+    }
+
+    #[test]
+    fn fingerprints_round_trip_through_json() {
+        let signal = sine(250.0, 0.25, 3 * FINGERPRINT_WINDOW_FRAMES + 100);
+        let fingerprint = AudioFingerprint::new(&signal, &signal, 48_000);
+        let json = fingerprint.to_json().unwrap();
+        assert_eq!(json.lines().count(), 4 + 2 + fingerprint.windows.len() + 2);
+        let parsed: AudioFingerprint = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, fingerprint);
+    }
+}
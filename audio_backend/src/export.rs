@@ -0,0 +1,317 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{integrated_loudness, quantize_pcm16, OfflineRender};
+
+/// Seed of the dither noise, fixed so that exports are reproducible.
+const DITHER_SEED: u32 = 0x9E37_79B9;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WavSampleFormat {
+    /// Signed 16-bit PCM; without dither or normalization this is exactly the
+    /// hashed canonical PCM.
+    #[default]
+    Pcm16,
+    Pcm24,
+    /// IEEE float, written unclamped.
+    Float32,
+}
+
+impl WavSampleFormat {
+    fn bits(self) -> u16 {
+        match self {
+            Self::Pcm16 => 16,
+            Self::Pcm24 => 24,
+            Self::Float32 => 32,
+        }
+    }
+}
+
+/// Noise added before rounding to an integer format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Dither {
+    #[default]
+    None,
+    /// Triangular noise of ±1 LSB, which decorrelates the rounding error
+    /// from the signal.
+    Tpdf,
+    /// TPDF dither with first-order error feedback, moving the noise towards
+    /// high frequencies where it is less audible.
+    NoiseShapedTpdf,
+}
+
+/// Gain applied to the whole render before export. Stems get the master's
+/// gain so that they still add up to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Normalization {
+    #[default]
+    None,
+    /// Scale so that the highest sample peak of the master reaches `dbfs`.
+    Peak { dbfs: f32 },
+    /// Scale so that the master's integrated loudness, as measured by the
+    /// EBU R128 meter in [`crate::analyze_loudness`], reaches `lufs`. May push
+    /// peaks above full scale, which integer formats clip.
+    Loudness { lufs: f32 },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct WavExportOptions {
+    pub format: WavSampleFormat,
+    pub dither: Dither,
+    pub normalization: Normalization,
+}
+
+impl WavExportOptions {
+    fn validate(self) -> Result<Self> {
+        if self.format == WavSampleFormat::Float32 && self.dither != Dither::None {
+            bail!("dither only applies to integer sample formats");
+        }
+        match self.normalization {
+            Normalization::Peak { dbfs: target } | Normalization::Loudness { lufs: target }
+                if !target.is_finite() =>
+            {
+                bail!("normalization target must be finite");
+            }
+            _ => Ok(self),
+        }
+    }
+}
+
+impl OfflineRender {
+    /// Linear gain `normalization` applies to this render, measured on the
+    /// master mix.
+    pub fn normalization_gain(&self, normalization: Normalization) -> Result<f32> {
+        match normalization {
+            Normalization::None => Ok(1.0),
+            Normalization::Peak { dbfs } => {
+                let peak = self
+                    .left()
+                    .iter()
+                    .chain(self.right())
+                    .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+                if peak == 0.0 {
+                    bail!("cannot peak-normalize a silent render");
+                }
+                Ok(db_to_gain(dbfs) / peak)
+            }
+            Normalization::Loudness { lufs } => {
+                let Some(loudness) = self.integrated_loudness() else {
+                    bail!("render is too short or too quiet to measure its loudness");
+                };
+                Ok(db_to_gain(lufs - loudness))
+            }
+        }
+    }
+
+    /// Integrated loudness of the master mix in LUFS, if it is long and loud
+    /// enough to measure.
+    pub fn integrated_loudness(&self) -> Option<f32> {
+        integrated_loudness(self.left(), self.right(), self.sample_rate())
+    }
+
+    /// Writes the master mix as a WAV file in the requested format.
+    ///
+    /// CPAL only streams buffers to audio devices; it does not encode offline
+    /// files. Hound remains confined to this host/I/O crate and is not an
+    /// `engine` or `dsp` dependency.
+    pub fn export_wav(&self, path: &Path, options: &WavExportOptions) -> Result<()> {
+        let options = options.validate()?;
+        let gain = self.normalization_gain(options.normalization)?;
+        write_wav_file(
+            path,
+            self.sample_rate(),
+            self.left(),
+            self.right(),
+            gain,
+            &options,
+        )
+    }
+
+    /// Writes the master mix as `master.wav` and every stem under its
+    /// [`crate::OfflineStem::file_name`] into `dir`. Returns the written paths,
+    /// master first.
+    pub fn write_wavs(&self, dir: &Path, options: &WavExportOptions) -> Result<Vec<PathBuf>> {
+        let options = options.validate()?;
+        let gain = self.normalization_gain(options.normalization)?;
+        let master = dir.join("master.wav");
+        write_wav_file(
+            &master,
+            self.sample_rate(),
+            self.left(),
+            self.right(),
+            gain,
+            &options,
+        )?;
+        let mut paths = vec![master];
+        for stem in self.stems() {
+            let path = dir.join(stem.file_name());
+            write_wav_file(
+                &path,
+                self.sample_rate(),
+                stem.left(),
+                stem.right(),
+                gain,
+                &options,
+            )?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+pub(crate) fn write_wav_file(
+    path: &Path,
+    sample_rate: u32,
+    left: &[f32],
+    right: &[f32],
+    gain: f32,
+    options: &WavExportOptions,
+) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: options.format.bits(),
+        sample_format: match options.format {
+            WavSampleFormat::Float32 => hound::SampleFormat::Float,
+            WavSampleFormat::Pcm16 | WavSampleFormat::Pcm24 => hound::SampleFormat::Int,
+        },
+    };
+    let mut writer = hound::WavWriter::create(path, spec)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    let frames = left
+        .iter()
+        .zip(right)
+        .map(|(&left, &right)| [left * gain, right * gain]);
+    match (options.format, options.dither) {
+        (WavSampleFormat::Float32, _) => {
+            for frame in frames {
+                writer.write_sample(frame[0])?;
+                writer.write_sample(frame[1])?;
+            }
+        }
+        // The canonical path, kept separate so that it stays bit-identical to
+        // the hashed PCM.
+        (WavSampleFormat::Pcm16, Dither::None) => {
+            for frame in frames {
+                writer.write_sample(quantize_pcm16(frame[0]))?;
+                writer.write_sample(quantize_pcm16(frame[1]))?;
+            }
+        }
+        (format, dither) => {
+            let mut quantizer = Quantizer::new(u32::from(format.bits()), dither);
+            for frame in frames {
+                for (channel, sample) in frame.into_iter().enumerate() {
+                    writer.write_sample(quantizer.quantize(channel, sample))?;
+                }
+            }
+        }
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Rounds samples to a signed integer format, optionally dithered. Scales like
+/// the canonical 16-bit quantizer: -1.0 maps to the most negative code and
+/// 1.0 to the most positive one.
+struct Quantizer {
+    max: f64,
+    min: f64,
+    dither: Dither,
+    rng: u32,
+    /// Previous rounding error per channel, fed back when noise shaping.
+    error: [f64; 2],
+}
+
+impl Quantizer {
+    fn new(bits: u32, dither: Dither) -> Self {
+        let min = -(1_i64 << (bits - 1)) as f64;
+        Self {
+            max: -min - 1.0,
+            min,
+            dither,
+            rng: DITHER_SEED,
+            error: [0.0; 2],
+        }
+    }
+
+    fn quantize(&mut self, channel: usize, sample: f32) -> i32 {
+        let sample = f64::from(sample.clamp(-1.0, 1.0));
+        let mut value = sample * if sample >= 0.0 { self.max } else { -self.min };
+        if self.dither == Dither::NoiseShapedTpdf {
+            value -= self.error[channel];
+        }
+        let noise = match self.dither {
+            Dither::None => 0.0,
+            Dither::Tpdf | Dither::NoiseShapedTpdf => self.uniform() + self.uniform(),
+        };
+        let quantized = (value + noise).round().clamp(self.min, self.max);
+        self.error[channel] = quantized - value;
+        quantized as i32
+    }
+
+    /// Uniform noise in `[-0.5, 0.5)` LSB from a xorshift generator.
+    fn uniform(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        f64::from(self.rng) / 4_294_967_296.0 - 0.5
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undithered_24_bit_uses_the_canonical_endpoints() {
+        let mut quantizer = Quantizer::new(24, Dither::None);
+        assert_eq!(quantizer.quantize(0, 1.0), 8_388_607);
+        assert_eq!(quantizer.quantize(0, -1.0), -8_388_608);
+        assert_eq!(quantizer.quantize(0, 2.0), 8_388_607);
+        assert_eq!(quantizer.quantize(1, 0.5), 4_194_304);
+    }
+
+    #[test]
+    fn tpdf_dither_stays_within_its_noise_bounds_and_averages_out() {
+        // Plain TPDF adds at most ±1 LSB before rounding. Noise shaping also
+        // subtracts the previous rounding error, so a code differs from the
+        // input by the difference of two errors of at most 1.5 LSB each.
+        for (dither, codes_range) in [(Dither::Tpdf, -1..=1), (Dither::NoiseShapedTpdf, -2..=3)] {
+            let mut quantizer = Quantizer::new(16, dither);
+            let input = 0.25 / 32_767.0;
+            let codes = (0..10_000)
+                .map(|_| quantizer.quantize(0, input))
+                .collect::<Vec<_>>();
+            assert!(
+                codes.iter().all(|code| codes_range.contains(code)),
+                "{dither:?} codes outside {codes_range:?}"
+            );
+            // Rounding alone would always give 0; dither keeps the average.
+            let mean = codes.iter().sum::<i32>() as f64 / codes.len() as f64;
+            assert!((mean - 0.25).abs() < 0.05, "{dither:?} mean {mean}");
+        }
+    }
+
+    #[test]
+    fn float_exports_reject_dither() {
+        let options = WavExportOptions {
+            format: WavSampleFormat::Float32,
+            dither: Dither::Tpdf,
+            ..WavExportOptions::default()
+        };
+        assert!(options.validate().is_err());
+    }
+}
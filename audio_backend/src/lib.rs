@@ -1,9 +1,13 @@
 mod commands;
+mod comparison;
+mod export;
+mod loudness;
 mod offline;
 mod player;
 mod resources;
 mod result;
 mod song_hydration;
+mod sound_state;
 #[cfg(feature = "standalone")]
 pub mod standalone;
 
@@ -12,10 +16,14 @@ pub use dsp::*;
 pub use engine::*;
 
 pub use commands::*;
+pub use comparison::*;
+pub use export::*;
+pub use loudness::*;
 pub use offline::*;
 pub(crate) use player::*;
 pub use resources::*;
 pub use result::*;
 pub use song_hydration::*;
+pub use sound_state::*;
 #[cfg(feature = "standalone")]
 pub use standalone::*;
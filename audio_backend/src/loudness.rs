@@ -0,0 +1,547 @@
+//! Programme loudness after EBU R128: momentary, short-term and integrated
+//! loudness as defined by ITU-R BS.1770, loudness range after EBU Tech 3342,
+//! and 4x oversampled true peak.
+//!
+//! [`LoudnessMeter`] keeps its gating history in fixed-size histograms, so it
+//! allocates only when created and can run in the audio callback.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+/// Length of a momentary gating block.
+const BLOCK_SECONDS: f64 = 0.4;
+/// Gating blocks overlap by 75 %, so a new one completes every 100 ms.
+const STEPS_PER_BLOCK: usize = 4;
+/// The 3 s short-term window, in 100 ms steps.
+const STEPS_PER_SHORT_TERM: usize = 30;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Relative gate of the short-term values that make up the loudness range.
+const RANGE_RELATIVE_GATE_LU: f64 = -20.0;
+const RANGE_LOW_PERCENTILE: f64 = 0.10;
+const RANGE_HIGH_PERCENTILE: f64 = 0.95;
+/// Gated values are binned at 0.1 LU between the absolute gate and +10 LUFS;
+/// louder values share the top bin.
+const HISTOGRAM_BIN_LU: f64 = 0.1;
+const HISTOGRAM_BINS: usize = 800;
+const TRUE_PEAK_OVERSAMPLING: usize = 4;
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// One biquad section of the K-weighting filter.
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The BS.1770 pre-filter (a high shelf modelling the head) followed by the
+/// RLB high-pass, derived for any sample rate.
+#[derive(Debug, Clone, Copy)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: f64) -> Self {
+        let k = (PI * 1_681.974_450_955_533 / sample_rate).tan();
+        let q = 0.707_175_236_955_419_6;
+        let vh = 10.0_f64.powf(3.999_843_853_973_347 / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        };
+
+        let k = (PI * 38.135_470_876_024_44 / sample_rate).tan();
+        let q = 0.500_327_037_323_877_3;
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        };
+        Self { shelf, high_pass }
+    }
+
+    fn process(&mut self, x: f32) -> f64 {
+        self.high_pass.process(self.shelf.process(f64::from(x)))
+    }
+}
+
+/// Polyphase windowed-sinc interpolator. Row `p` estimates the signal `p / 4`
+/// of a sample after the middle of the history; row 0 is the sample itself.
+type InterpolationFilter = [[f32; TRUE_PEAK_TAPS]; TRUE_PEAK_OVERSAMPLING];
+
+fn interpolation_filter() -> InterpolationFilter {
+    let half_width = TRUE_PEAK_TAPS as f64 / 2.0 + 0.5;
+    let mut filter = [[0.0; TRUE_PEAK_TAPS]; TRUE_PEAK_OVERSAMPLING];
+    for (phase, row) in filter.iter_mut().enumerate() {
+        let offset = phase as f64 / TRUE_PEAK_OVERSAMPLING as f64;
+        let taps: [f64; TRUE_PEAK_TAPS] = std::array::from_fn(|tap| {
+            let distance = (TRUE_PEAK_TAPS / 2 - 1) as f64 - tap as f64 + offset;
+            let sinc = if distance == 0.0 {
+                1.0
+            } else {
+                (PI * distance).sin() / (PI * distance)
+            };
+            sinc * 0.5 * (1.0 + (PI * distance / half_width).cos())
+        });
+        // Unity gain at DC for every phase.
+        let sum = taps.iter().sum::<f64>();
+        for (coefficient, tap) in row.iter_mut().zip(taps) {
+            *coefficient = (tap / sum) as f32;
+        }
+    }
+    filter
+}
+
+/// Highest absolute value of one channel's 4x oversampled signal.
+#[derive(Debug, Clone, Copy, Default)]
+struct TruePeak {
+    /// Latest samples, oldest first.
+    history: [f32; TRUE_PEAK_TAPS],
+    peak: f32,
+}
+
+impl TruePeak {
+    fn process(&mut self, filter: &InterpolationFilter, x: f32) {
+        self.history.copy_within(1.., 0);
+        self.history[TRUE_PEAK_TAPS - 1] = x;
+        self.peak = self.peak.max(x.abs());
+        for row in &filter[1..] {
+            let y = row
+                .iter()
+                .zip(&self.history)
+                .map(|(coefficient, sample)| coefficient * sample)
+                .sum::<f32>();
+            self.peak = self.peak.max(y.abs());
+        }
+    }
+}
+
+/// Counts and summed mean squares of gated values, binned by loudness.
+#[derive(Debug, Clone)]
+struct LoudnessHistogram {
+    counts: Vec<u32>,
+    powers: Vec<f64>,
+}
+
+impl LoudnessHistogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; HISTOGRAM_BINS],
+            powers: vec![0.0; HISTOGRAM_BINS],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.fill(0);
+        self.powers.fill(0.0);
+    }
+
+    /// Adds a mean square unless it is at or below the absolute gate.
+    fn add(&mut self, power: f64) {
+        let loudness = power_to_loudness(power);
+        if loudness <= ABSOLUTE_GATE_LUFS {
+            return;
+        }
+        let bin = ((loudness - ABSOLUTE_GATE_LUFS) / HISTOGRAM_BIN_LU) as usize;
+        let bin = bin.min(HISTOGRAM_BINS - 1);
+        self.counts[bin] += 1;
+        self.powers[bin] += power;
+    }
+
+    fn bin_loudness(bin: usize) -> f64 {
+        ABSOLUTE_GATE_LUFS + (bin as f64 + 0.5) * HISTOGRAM_BIN_LU
+    }
+
+    /// Bins whose centre lies above `gate`.
+    fn bins_above(&self, gate: f64) -> impl Iterator<Item = usize> + '_ {
+        (0..HISTOGRAM_BINS)
+            .filter(move |&bin| self.counts[bin] > 0 && Self::bin_loudness(bin) > gate)
+    }
+
+    /// Mean of the values above `gate`, as a mean square.
+    fn mean_power(&self, gate: f64) -> Option<f64> {
+        let (sum, count) = self
+            .bins_above(gate)
+            .fold((0.0, 0_u64), |(sum, count), bin| {
+                (sum + self.powers[bin], count + u64::from(self.counts[bin]))
+            });
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    /// Mean loudness of the values passing the absolute gate and a gate
+    /// `relative_gate` LU below their own mean.
+    fn gated_loudness(&self, relative_gate: f64) -> Option<f64> {
+        let gate = power_to_loudness(self.mean_power(ABSOLUTE_GATE_LUFS)?) + relative_gate;
+        self.mean_power(gate).map(power_to_loudness)
+    }
+
+    /// Loudness range: the spread between the 10th and 95th percentile of the
+    /// values that pass both gates.
+    fn range(&self) -> f64 {
+        let Some(mean) = self.mean_power(ABSOLUTE_GATE_LUFS) else {
+            return 0.0;
+        };
+        let gate = power_to_loudness(mean) + RANGE_RELATIVE_GATE_LU;
+        let total = self
+            .bins_above(gate)
+            .map(|bin| u64::from(self.counts[bin]))
+            .sum::<u64>();
+        if total == 0 {
+            return 0.0;
+        }
+        let percentile = |fraction: f64| {
+            let rank = (fraction * (total - 1) as f64).round() as u64;
+            let mut seen = 0;
+            for bin in self.bins_above(gate) {
+                seen += u64::from(self.counts[bin]);
+                if seen > rank {
+                    return Self::bin_loudness(bin);
+                }
+            }
+            unreachable!("rank is below the number of gated values")
+        };
+        percentile(RANGE_HIGH_PERCENTILE) - percentile(RANGE_LOW_PERCENTILE)
+    }
+}
+
+/// Loudness statistics of a whole programme, as stored in offline render
+/// references.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessSummary {
+    /// Gated integrated loudness in LUFS; `None` for programmes too short or
+    /// too quiet to pass the gates.
+    pub integrated_lufs: Option<f32>,
+    /// Loudness range in LU; zero when there are no gated short-term values.
+    pub loudness_range_lu: f32,
+    pub max_momentary_lufs: Option<f32>,
+    pub max_short_term_lufs: Option<f32>,
+    /// Highest oversampled peak per channel, linear amplitude.
+    pub true_peak_left: f32,
+    pub true_peak_right: f32,
+}
+
+/// Measures the loudness of a stereo signal fed in any block sizes.
+///
+/// Readings refresh every 100 ms of input. Non-finite samples are measured
+/// as silence so that they cannot poison the filters.
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter {
+    filters: [KWeighting; 2],
+    interpolation: InterpolationFilter,
+    true_peaks: [TruePeak; 2],
+    step_frames: usize,
+    /// K-weighted energy and frame count of the step being filled.
+    step_energy: f64,
+    step_len: usize,
+    /// Mean square of the latest completed steps; `next_step` is the oldest.
+    recent_steps: [f64; STEPS_PER_SHORT_TERM],
+    next_step: usize,
+    completed_steps: usize,
+    /// Every momentary block, for the integrated loudness.
+    momentary_blocks: LoudnessHistogram,
+    /// Every short-term value, for the loudness range.
+    short_term_values: LoudnessHistogram,
+    momentary_power: Option<f64>,
+    short_term_power: Option<f64>,
+    max_momentary_power: Option<f64>,
+    max_short_term_power: Option<f64>,
+    integrated: Option<f32>,
+    loudness_range: f32,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let sample_rate = f64::from(sample_rate);
+        Self {
+            filters: [KWeighting::new(sample_rate); 2],
+            interpolation: interpolation_filter(),
+            true_peaks: [TruePeak::default(); 2],
+            step_frames: ((sample_rate * BLOCK_SECONDS) as usize / STEPS_PER_BLOCK).max(1),
+            step_energy: 0.0,
+            step_len: 0,
+            recent_steps: [0.0; STEPS_PER_SHORT_TERM],
+            next_step: 0,
+            completed_steps: 0,
+            momentary_blocks: LoudnessHistogram::new(),
+            short_term_values: LoudnessHistogram::new(),
+            momentary_power: None,
+            short_term_power: None,
+            max_momentary_power: None,
+            max_short_term_power: None,
+            integrated: None,
+            loudness_range: 0.0,
+        }
+    }
+
+    /// Starts a new measurement, keeping the sample rate. Does not allocate.
+    pub fn reset(&mut self) {
+        for filter in &mut self.filters {
+            filter.shelf.state = [0.0; 2];
+            filter.high_pass.state = [0.0; 2];
+        }
+        self.true_peaks = [TruePeak::default(); 2];
+        self.step_energy = 0.0;
+        self.step_len = 0;
+        self.recent_steps = [0.0; STEPS_PER_SHORT_TERM];
+        self.next_step = 0;
+        self.completed_steps = 0;
+        self.momentary_blocks.clear();
+        self.short_term_values.clear();
+        self.momentary_power = None;
+        self.short_term_power = None;
+        self.max_momentary_power = None;
+        self.max_short_term_power = None;
+        self.integrated = None;
+        self.loudness_range = 0.0;
+    }
+
+    /// Measures one block. Realtime-safe: no allocation, no locking.
+    pub fn process(&mut self, left: &[f32], right: &[f32]) {
+        for (&left, &right) in left.iter().zip(right) {
+            let mut energy = 0.0;
+            for (channel, sample) in [left, right].into_iter().enumerate() {
+                let sample = if sample.is_finite() { sample } else { 0.0 };
+                self.true_peaks[channel].process(&self.interpolation, sample);
+                let weighted = self.filters[channel].process(sample);
+                // Left and right both carry a channel weight of 1.0.
+                energy += weighted * weighted;
+            }
+            self.step_energy += energy;
+            self.step_len += 1;
+            if self.step_len == self.step_frames {
+                self.complete_step();
+            }
+        }
+    }
+
+    fn complete_step(&mut self) {
+        self.recent_steps[self.next_step] = self.step_energy / self.step_len as f64;
+        self.next_step = (self.next_step + 1) % STEPS_PER_SHORT_TERM;
+        self.step_energy = 0.0;
+        self.step_len = 0;
+        self.completed_steps += 1;
+
+        if self.completed_steps >= STEPS_PER_BLOCK {
+            let power = self.window_power(STEPS_PER_BLOCK);
+            self.momentary_power = Some(power);
+            self.max_momentary_power =
+                Some(self.max_momentary_power.map_or(power, |max| max.max(power)));
+            self.momentary_blocks.add(power);
+            self.integrated = self
+                .momentary_blocks
+                .gated_loudness(RELATIVE_GATE_LU)
+                .map(|loudness| loudness as f32);
+        }
+        if self.completed_steps >= STEPS_PER_SHORT_TERM {
+            let power = self.window_power(STEPS_PER_SHORT_TERM);
+            self.short_term_power = Some(power);
+            self.max_short_term_power = Some(
+                self.max_short_term_power
+                    .map_or(power, |max| max.max(power)),
+            );
+            self.short_term_values.add(power);
+            self.loudness_range = self.short_term_values.range() as f32;
+        }
+    }
+
+    /// Mean square of the latest `steps` completed steps.
+    fn window_power(&self, steps: usize) -> f64 {
+        (1..=steps)
+            .map(|age| {
+                self.recent_steps
+                    [(self.next_step + STEPS_PER_SHORT_TERM - age) % STEPS_PER_SHORT_TERM]
+            })
+            .sum::<f64>()
+            / steps as f64
+    }
+
+    /// Loudness of the latest 400 ms in LUFS, once that much has been measured.
+    pub fn momentary(&self) -> Option<f32> {
+        self.momentary_power
+            .map(|power| power_to_loudness(power) as f32)
+    }
+
+    /// Loudness of the latest 3 s in LUFS, once that much has been measured.
+    pub fn short_term(&self) -> Option<f32> {
+        self.short_term_power
+            .map(|power| power_to_loudness(power) as f32)
+    }
+
+    /// Gated integrated loudness in LUFS, or `None` until a gating block
+    /// louder than the absolute gate has been measured.
+    pub fn integrated(&self) -> Option<f32> {
+        self.integrated
+    }
+
+    /// Loudness range in LU.
+    pub fn loudness_range(&self) -> f32 {
+        self.loudness_range
+    }
+
+    /// Highest oversampled peak per channel since the start, linear amplitude.
+    pub fn true_peak(&self) -> [f32; 2] {
+        [self.true_peaks[0].peak, self.true_peaks[1].peak]
+    }
+
+    pub fn summary(&self) -> LoudnessSummary {
+        let [true_peak_left, true_peak_right] = self.true_peak();
+        LoudnessSummary {
+            integrated_lufs: self.integrated,
+            loudness_range_lu: self.loudness_range,
+            max_momentary_lufs: self
+                .max_momentary_power
+                .map(|power| power_to_loudness(power) as f32),
+            max_short_term_lufs: self
+                .max_short_term_power
+                .map(|power| power_to_loudness(power) as f32),
+            true_peak_left,
+            true_peak_right,
+        }
+    }
+}
+
+/// Loudness statistics of a whole stereo signal.
+pub fn analyze_loudness(left: &[f32], right: &[f32], sample_rate: u32) -> LoudnessSummary {
+    let mut meter = LoudnessMeter::new(sample_rate);
+    meter.process(left, right);
+    meter.summary()
+}
+
+/// Integrated loudness of a whole stereo signal in LUFS; `None` for signals
+/// too short or too quiet to pass the gates. Loudness normalization of WAV
+/// exports relies on it.
+pub fn integrated_loudness(left: &[f32], right: &[f32], sample_rate: u32) -> Option<f32> {
+    analyze_loudness(left, right, sample_rate).integrated_lufs
+}
+
+fn power_to_loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, amplitude: f32, seconds: f32, sample_rate: u32) -> Vec<f32> {
+        (0..(seconds * sample_rate as f32) as usize)
+            .map(|n| {
+                let phase = 2.0 * std::f32::consts::PI * frequency * n as f32 / sample_rate as f32;
+                amplitude * phase.sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_1k_sine_on_both_channels_reads_about_zero_lufs() {
+        // A 0 dBFS 997 Hz sine in one channel reads -3.01 LUFS (EBU Tech
+        // 3341); the same signal in both channels adds 3 dB.
+        for sample_rate in [44_100, 48_000] {
+            let signal = sine(997.0, 1.0, 5.0, sample_rate);
+            let loudness = integrated_loudness(&signal, &signal, sample_rate).unwrap();
+            assert!(loudness.abs() < 0.1, "{sample_rate} Hz: {loudness} LUFS");
+        }
+    }
+
+    #[test]
+    fn steady_signals_read_the_same_on_every_time_scale() {
+        let sample_rate = 48_000;
+        let signal = sine(997.0, 0.1, 5.0, sample_rate);
+        let mut meter = LoudnessMeter::new(sample_rate);
+        // Odd block sizes must not matter.
+        for (left, right) in signal.chunks(173).zip(signal.chunks(173)) {
+            meter.process(left, right);
+        }
+        for loudness in [meter.momentary(), meter.short_term(), meter.integrated()] {
+            assert!((loudness.unwrap() + 20.0).abs() < 0.1, "{loudness:?}");
+        }
+        assert!(meter.loudness_range() < 0.2);
+    }
+
+    #[test]
+    fn quieter_passages_below_the_relative_gate_are_ignored() {
+        let sample_rate = 48_000;
+        let mut signal = sine(997.0, 0.5, 10.0, sample_rate);
+        // 30 dB quieter, far below the -10 LU relative gate.
+        signal.extend(sine(997.0, 0.5 * 0.0316, 10.0, sample_rate));
+        let loud = integrated_loudness(&signal[..480_000], &signal[..480_000], sample_rate);
+        let gated = integrated_loudness(&signal, &signal, sample_rate);
+        assert!((loud.unwrap() - gated.unwrap()).abs() < 0.1);
+    }
+
+    #[test]
+    fn loudness_range_spans_two_levels_ten_lu_apart() {
+        // EBU Tech 3342 test case: 20 s at -20 LUFS, then 20 s at -30 LUFS.
+        let sample_rate = 48_000;
+        let mut signal = sine(1_000.0, 0.1, 20.0, sample_rate);
+        signal.extend(sine(1_000.0, 0.0316, 20.0, sample_rate));
+        let summary = analyze_loudness(&signal, &signal, sample_rate);
+        assert!(
+            (summary.loudness_range_lu - 10.0).abs() < 1.0,
+            "{} LU",
+            summary.loudness_range_lu
+        );
+        assert!((summary.max_momentary_lufs.unwrap() + 20.0).abs() < 0.1);
+        assert!((summary.max_short_term_lufs.unwrap() + 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn true_peak_finds_peaks_between_samples() {
+        // A quarter-rate sine sampled 45 degrees off its peaks never shows a
+        // sample above -3 dBFS, but the reconstructed wave reaches 0 dBFS.
+        let sample_rate = 48_000;
+        let signal = (0..4_800)
+            .map(|n| (std::f32::consts::FRAC_PI_2 * n as f32 + std::f32::consts::FRAC_PI_4).sin())
+            .collect::<Vec<_>>();
+        let sample_peak = signal.iter().fold(0.0_f32, |peak, x| peak.max(x.abs()));
+        let summary = analyze_loudness(&signal, &signal, sample_rate);
+        assert!(sample_peak < 0.71);
+        assert!(
+            (summary.true_peak_left - 1.0).abs() < 0.02,
+            "{}",
+            summary.true_peak_left
+        );
+    }
+
+    #[test]
+    fn silence_has_no_loudness() {
+        let silence = vec![0.0; 48_000];
+        let summary = analyze_loudness(&silence, &silence, 48_000);
+        assert_eq!(summary.integrated_lufs, None);
+        assert_eq!(summary.loudness_range_lu, 0.0);
+        assert_eq!(summary.true_peak_left, 0.0);
+    }
+
+    #[test]
+    fn reset_starts_a_new_measurement() {
+        let sample_rate = 48_000;
+        let loud = sine(997.0, 1.0, 1.0, sample_rate);
+        let quiet = sine(997.0, 0.1, 1.0, sample_rate);
+        let mut meter = LoudnessMeter::new(sample_rate);
+        meter.process(&loud, &loud);
+        meter.reset();
+        meter.process(&quiet, &quiet);
+        assert!((meter.integrated().unwrap() + 20.0).abs() < 0.1);
+        assert!(meter.true_peak()[0] < 0.11);
+    }
+}
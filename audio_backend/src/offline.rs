@@ -1,11 +1,30 @@
-use std::{collections::BTreeMap, path::Path, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use anyhow::{bail, Context, Result};
-use sequencer::{cli::FileFormat, models::Song, project::open_song_from_file};
+use sequencer::{
+    cli::FileFormat,
+    models::{
+        Chain, Song, SongRow, DEFAULT_CHAIN_LENGTH, DEFAULT_PHRASE_LENGTH, EMPTY_CHAIN_SLOT,
+        MAX_TRACKS,
+    },
+    project::open_song_from_file,
+};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::{build_song_hydration_commands, Player, SequencerCmd, TransportCmd};
+use crate::{
+    analyze_loudness, build_song_hydration_commands, id::InstrumentId, ComparisonTolerances,
+    DspLoadReport, LoudnessSummary, Player, SequencerCmd, StemBuses, TransportCmd,
+    WavExportOptions, DEFAULT_INSTRUMENT_CAPACITY,
+};
 
 /// Versioned golden-render profile values, not device/runtime defaults.
 ///
@@ -16,13 +35,281 @@ use crate::{build_song_hydration_commands, Player, SequencerCmd, TransportCmd};
 pub const CANONICAL_SAMPLE_RATE: u32 = 48_000;
 pub const CANONICAL_BLOCK_SIZE: usize = 256;
 pub const CANONICAL_MAX_FRAMES: usize = CANONICAL_SAMPLE_RATE as usize * 120;
-const MAX_ENGINE_BLOCK_SIZE: usize = 4_096;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct OfflineRenderConfig {
     pub sample_rate: u32,
     pub block_size: usize,
     pub max_frames: usize,
+    /// Also render every instrument into its own stem. The master mix is
+    /// bit-identical to a render without stems.
+    #[serde(default)]
+    pub stems: bool,
+    /// Keep rendering after the song ends so that releases and effect tails
+    /// ring out. `None` stops with the transport.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tail: Option<OfflineTailConfig>,
+    /// The part of the song to play; the default is the whole arrangement.
+    #[serde(default, skip_serializing_if = "OfflineSelection::is_whole_song")]
+    pub selection: OfflineSelection,
+}
+
+/// Which rows, tracks or loop of a song an offline render plays.
+///
+/// The default plays every row of the arrangement on every track. A loop
+/// replaces the arrangement, so it cannot be combined with a row range; muted
+/// and soloed tracks apply to both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OfflineSelection {
+    /// First arrangement row to play. Tracks keep the instruments selected by
+    /// the rows before it.
+    pub start_row: usize,
+    /// Row at which playback stops, exclusive; `None` plays to the end.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_row: Option<usize>,
+    /// Tracks left silent.
+    #[serde(skip_serializing_if = "TrackSet::is_empty")]
+    pub muted: TrackSet,
+    /// When not empty, only these tracks play.
+    #[serde(skip_serializing_if = "TrackSet::is_empty")]
+    pub soloed: TrackSet,
+    /// Play a single chain or phrase repeatedly instead of the arrangement.
+    #[serde(rename = "loop", skip_serializing_if = "Option::is_none")]
+    pub looped: Option<OfflineLoop>,
+}
+
+/// A chain or phrase played `times` times in a row on `track`, whose
+/// instrument is used by events that do not select one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OfflineLoop {
+    pub part: OfflineLoopPart,
+    pub track: usize,
+    pub times: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfflineLoopPart {
+    /// An index into the song's chain bank.
+    Chain(usize),
+    /// An index into the song's phrase bank.
+    Phrase(usize),
+}
+
+const _: () = assert!(MAX_TRACKS <= u8::BITS as usize);
+
+/// A set of track indices, serialized as a sorted list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "Vec<usize>", try_from = "Vec<usize>")]
+pub struct TrackSet(u8);
+
+impl TrackSet {
+    pub fn from_tracks(tracks: impl IntoIterator<Item = usize>) -> Result<Self> {
+        let mut set = Self::default();
+        for track in tracks {
+            if track >= MAX_TRACKS {
+                bail!("track {track} does not exist; songs have {MAX_TRACKS} tracks");
+            }
+            set.0 |= 1 << track;
+        }
+        Ok(set)
+    }
+
+    pub fn contains(self, track: usize) -> bool {
+        track < MAX_TRACKS && self.0 & (1 << track) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..MAX_TRACKS).filter(move |&track| self.contains(track))
+    }
+}
+
+impl From<TrackSet> for Vec<usize> {
+    fn from(set: TrackSet) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl TryFrom<Vec<usize>> for TrackSet {
+    type Error = anyhow::Error;
+
+    fn try_from(tracks: Vec<usize>) -> Result<Self> {
+        Self::from_tracks(tracks)
+    }
+}
+
+impl OfflineSelection {
+    pub const WHOLE_SONG: Self = Self {
+        start_row: 0,
+        end_row: None,
+        muted: TrackSet(0),
+        soloed: TrackSet(0),
+        looped: None,
+    };
+
+    pub fn is_whole_song(&self) -> bool {
+        *self == Self::WHOLE_SONG
+    }
+
+    fn track_plays(&self, track: usize) -> bool {
+        !self.muted.contains(track) && (self.soloed.is_empty() || self.soloed.contains(track))
+    }
+
+    /// Builds the song the player runs for this selection, with the row to
+    /// cue and the number of phrase rows to stop after.
+    fn apply(&self, song: &Song) -> Result<(Song, usize, Option<usize>)> {
+        let mut selected = song.clone();
+        let mut line_limit = None;
+        if let Some(looped) = self.looped {
+            if self.start_row != 0 || self.end_row.is_some() {
+                bail!("a looped chain or phrase cannot be combined with an arrangement range");
+            }
+            if looped.track >= MAX_TRACKS {
+                bail!(
+                    "loop track {} does not exist; songs have {MAX_TRACKS} tracks",
+                    looped.track
+                );
+            }
+            if looped.times == 0 {
+                bail!("a loop must play at least once");
+            }
+            let (chain_index, rows) = match looped.part {
+                OfflineLoopPart::Chain(index) => {
+                    if index >= song.chain_bank.len() {
+                        bail!("chain {index} does not exist");
+                    }
+                    (index, looped.times)
+                }
+                OfflineLoopPart::Phrase(index) => {
+                    if index >= song.phrase_bank.len() {
+                        bail!("phrase {index} does not exist");
+                    }
+                    // Fill a chain with the phrase and stop partway through
+                    // its last repeat.
+                    selected
+                        .chain_bank
+                        .push(Chain::new([index; DEFAULT_CHAIN_LENGTH]));
+                    line_limit = Some(looped.times * DEFAULT_PHRASE_LENGTH);
+                    (
+                        selected.chain_bank.len() - 1,
+                        looped.times.div_ceil(DEFAULT_CHAIN_LENGTH),
+                    )
+                }
+            };
+            let mut row = SongRow::default();
+            row.chain_indices[looped.track] = chain_index;
+            selected.arrangement = vec![row; rows];
+        } else {
+            let end_row = self.end_row.unwrap_or(song.arrangement.len());
+            if end_row > song.arrangement.len() {
+                bail!(
+                    "end row {end_row} is past the arrangement's {} rows",
+                    song.arrangement.len()
+                );
+            }
+            if self.start_row >= end_row {
+                bail!(
+                    "start row {} must come before end row {end_row}",
+                    self.start_row
+                );
+            }
+            selected.arrangement.truncate(end_row);
+        }
+        for row in &mut selected.arrangement {
+            for (track, chain_index) in row.chain_indices.iter_mut().enumerate() {
+                if !self.track_plays(track) {
+                    *chain_index = EMPTY_CHAIN_SLOT;
+                }
+            }
+        }
+        Ok((selected, self.start_row, line_limit))
+    }
+}
+
+/// When to stop rendering once the song has ended.
+///
+/// The tail ends once the output has stayed below `threshold_dbfs` for
+/// `hold_seconds`, so that gaps between delay repeats do not cut it short, or
+/// after `max_seconds`, whichever comes first. The quiet stretch is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OfflineTailConfig {
+    pub threshold_dbfs: f32,
+    pub hold_seconds: f32,
+    pub max_seconds: f32,
+}
+
+impl OfflineTailConfig {
+    /// Reference tail profile: below 16-bit resolution for a quarter second,
+    /// at most ten seconds.
+    pub const fn canonical() -> Self {
+        Self {
+            threshold_dbfs: -96.0,
+            hold_seconds: 0.25,
+            max_seconds: 10.0,
+        }
+    }
+
+    fn validate(self) -> Result<Self> {
+        if !self.threshold_dbfs.is_finite() {
+            bail!("offline tail threshold must be finite");
+        }
+        if !(self.hold_seconds >= 0.0 && self.max_seconds >= 0.0) {
+            bail!("offline tail durations must not be negative");
+        }
+        Ok(self)
+    }
+}
+
+impl Default for OfflineTailConfig {
+    fn default() -> Self {
+        Self::canonical()
+    }
+}
+
+/// Progress of a tail through [`OfflineTailConfig`]'s stopping rules.
+struct TailState {
+    threshold: f32,
+    hold_frames: usize,
+    max_frames: usize,
+    frames: usize,
+    quiet_frames: usize,
+}
+
+impl TailState {
+    fn new(config: OfflineTailConfig, sample_rate: u32) -> Self {
+        let frames = |seconds: f32| (seconds as f64 * sample_rate as f64).round() as usize;
+        Self {
+            threshold: 10.0_f32.powf(config.threshold_dbfs / 20.0),
+            hold_frames: frames(config.hold_seconds),
+            max_frames: frames(config.max_seconds),
+            frames: 0,
+            quiet_frames: 0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.frames >= self.max_frames || self.quiet_frames >= self.hold_frames.max(1)
+    }
+
+    fn remaining_frames(&self) -> usize {
+        self.max_frames - self.frames
+    }
+
+    fn observe(&mut self, left: &[f32], right: &[f32]) {
+        for (left, right) in left.iter().zip(right) {
+            if left.abs().max(right.abs()) >= self.threshold {
+                self.quiet_frames = 0;
+            } else {
+                self.quiet_frames += 1;
+            }
+        }
+        self.frames += left.len();
+    }
 }
 
 impl OfflineRenderConfig {
@@ -31,6 +318,9 @@ impl OfflineRenderConfig {
             sample_rate: CANONICAL_SAMPLE_RATE,
             block_size: CANONICAL_BLOCK_SIZE,
             max_frames: CANONICAL_MAX_FRAMES,
+            stems: false,
+            tail: None,
+            selection: OfflineSelection::WHOLE_SONG,
         }
     }
 
@@ -38,15 +328,16 @@ impl OfflineRenderConfig {
         if self.sample_rate == 0 {
             bail!("offline sample rate must be greater than zero");
         }
-        if self.block_size == 0 || self.block_size > MAX_ENGINE_BLOCK_SIZE {
-            bail!(
-                "offline block size must be in 1..={MAX_ENGINE_BLOCK_SIZE}, got {}",
-                self.block_size
-            );
+        // Blocks longer than the engine's maximum are split into sub-blocks.
+        if self.block_size == 0 {
+            bail!("offline block size must be greater than zero");
         }
         if self.max_frames == 0 {
             bail!("offline maximum frame count must be greater than zero");
         }
+        if let Some(tail) = self.tail {
+            tail.validate()?;
+        }
         Ok(self)
     }
 }
@@ -62,6 +353,58 @@ pub struct OfflineRender {
     sample_rate: u32,
     left: Vec<f32>,
     right: Vec<f32>,
+    stems: Vec<OfflineStem>,
+    dsp_load: DspLoadReport,
+}
+
+/// One instrument's output before the master effects, as long as the master.
+#[derive(Debug)]
+pub struct OfflineStem {
+    instrument_id: InstrumentId,
+    name: String,
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+impl OfflineStem {
+    pub fn instrument_id(&self) -> InstrumentId {
+        self.instrument_id
+    }
+
+    /// The song's name for the instrument, empty if the song has none.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn left(&self) -> &[f32] {
+        &self.left
+    }
+
+    pub fn right(&self) -> &[f32] {
+        &self.right
+    }
+
+    /// File name [`OfflineRender::write_wavs`] uses: the instrument id plus
+    /// the name reduced to characters safe on every file system.
+    pub fn file_name(&self) -> String {
+        let name = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        let name = name.trim_matches('_');
+        if name.is_empty() {
+            format!("stem_{:02}.wav", self.instrument_id)
+        } else {
+            format!("stem_{:02}_{name}.wav", self.instrument_id)
+        }
+    }
 }
 
 impl OfflineRender {
@@ -81,6 +424,19 @@ impl OfflineRender {
         &self.right
     }
 
+    /// Per-instrument stems in mix order; empty unless
+    /// [`OfflineRenderConfig::stems`] was set.
+    pub fn stems(&self) -> &[OfflineStem] {
+        &self.stems
+    }
+
+    /// Processing time measured while rendering, relative to the real time the
+    /// render represents. Machine-dependent, so it is not part of
+    /// [`OfflineRenderReference`].
+    pub fn dsp_load(&self) -> &DspLoadReport {
+        &self.dsp_load
+    }
+
     pub fn canonical_pcm(&self) -> Vec<i16> {
         let mut pcm = Vec::with_capacity(self.frame_count() * 2);
         for (&left, &right) in self.left.iter().zip(&self.right) {
@@ -112,35 +468,14 @@ impl OfflineRender {
             rms_left,
             rms_right,
             clipped_samples: clipped_left + clipped_right,
+            loudness: analyze_loudness(&self.left, &self.right, self.sample_rate),
         }
     }
 
-    /// Wrap canonical PCM in a WAV container for listening.
-    ///
-    /// CPAL only streams buffers to audio devices; it does not encode offline
-    /// files. Hound remains confined to this host/I/O crate and is not an
-    /// `engine` or `dsp` dependency.
+    /// Wrap canonical PCM in a WAV container for listening. See
+    /// [`Self::export_wav`] for other formats.
     pub fn write_wav(&self, path: &Path) -> Result<()> {
-        if let Some(parent) = path
-            .parent()
-            .filter(|parent| !parent.as_os_str().is_empty())
-        {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
-        }
-        let spec = hound::WavSpec {
-            channels: 2,
-            sample_rate: self.sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-        let mut writer = hound::WavWriter::create(path, spec)
-            .with_context(|| format!("failed to create {}", path.display()))?;
-        for sample in self.canonical_pcm() {
-            writer.write_sample(sample)?;
-        }
-        writer.finalize()?;
-        Ok(())
+        self.export_wav(path, &WavExportOptions::default())
     }
 }
 
@@ -155,6 +490,11 @@ pub struct OfflineRenderReference {
     pub rms_left: f32,
     pub rms_right: f32,
     pub clipped_samples: usize,
+    /// Loudness and true peak of the pre-quantization master mix. References
+    /// recorded before loudness was measured read the default, unmeasured
+    /// summary.
+    #[serde(default)]
+    pub loudness: LoudnessSummary,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -165,24 +505,56 @@ pub struct OfflineGoldenManifest {
     pub known_limitations: Vec<String>,
     pub config: OfflineRenderConfig,
     pub songs: BTreeMap<String, OfflineRenderReference>,
+    /// Tail profile of `tail_songs`, which are otherwise rendered with `config`.
+    #[serde(default)]
+    pub tail: OfflineTailConfig,
+    /// References of the same songs rendered until their tails have decayed.
+    #[serde(default)]
+    pub tail_songs: BTreeMap<String, OfflineRenderReference>,
+    /// Tolerances of the fingerprint comparison used where exact hashes
+    /// cannot be (see [`Self::fingerprint_file`]).
+    #[serde(default)]
+    pub comparison: ComparisonTolerances,
 }
 
 impl OfflineGoldenManifest {
     pub fn characterization(
         config: OfflineRenderConfig,
         songs: BTreeMap<String, OfflineRenderReference>,
+        tail: OfflineTailConfig,
+        tail_songs: BTreeMap<String, OfflineRenderReference>,
     ) -> Self {
         Self {
             format_version: 1,
             baseline_kind: "characterization".to_string(),
             canonical_platform: current_platform(),
             known_limitations: vec![
-                "#132 transport-independent rendering and release/effect tails".to_string(),
+                "#132 transport-independent rendering; only tail_songs include release/effect tails"
+                    .to_string(),
                 "#134 sample-accurate event scheduling".to_string(),
                 "#136 mixer gain staging and clipping".to_string(),
             ],
             config,
             songs,
+            tail,
+            tail_songs,
+            comparison: ComparisonTolerances::default(),
+        }
+    }
+
+    /// Fingerprint of a reference song, relative to the manifest's directory;
+    /// `tail` selects the `tail_songs` render.
+    pub fn fingerprint_file(song_name: &str, tail: bool) -> PathBuf {
+        let stem = song_name.strip_suffix(".json").unwrap_or(song_name);
+        let suffix = if tail { "_tail" } else { "" };
+        Path::new("fingerprints").join(format!("{stem}{suffix}.json"))
+    }
+
+    /// The configuration `tail_songs` were rendered with.
+    pub fn tail_config(&self) -> OfflineRenderConfig {
+        OfflineRenderConfig {
+            tail: Some(self.tail),
+            ..self.config
         }
     }
 }
@@ -201,15 +573,109 @@ pub fn render_json_song(path: &Path, config: OfflineRenderConfig) -> Result<Offl
     render_song(&song, config)
 }
 
+/// Expands directories into the `.json` files directly inside them, sorted by
+/// name. Other inputs are kept as given.
+pub fn collect_song_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut songs = Vec::new();
+    for input in inputs {
+        if !input.is_dir() {
+            songs.push(input.clone());
+            continue;
+        }
+        let mut entries = std::fs::read_dir(input)
+            .with_context(|| format!("failed to read {}", input.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()
+            .with_context(|| format!("failed to read {}", input.display()))?;
+        entries.retain(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+        });
+        entries.sort();
+        songs.extend(entries);
+    }
+    Ok(songs)
+}
+
+/// Renders `songs` on up to `threads` worker threads and hands each render to
+/// `process` on the thread that rendered it, so that only one render per
+/// thread is held in memory. Results are in the order of `songs`. Every song
+/// gets its own player, so its audio does not depend on the thread count or
+/// on the other songs.
+pub fn render_batch<T, F>(
+    songs: &[PathBuf],
+    config: OfflineRenderConfig,
+    threads: NonZeroUsize,
+    process: F,
+) -> Vec<Result<T>>
+where
+    T: Send,
+    F: Fn(&Path, OfflineRender) -> Result<T> + Sync,
+{
+    let next_song = AtomicUsize::new(0);
+    let mut results = std::thread::scope(|scope| {
+        let workers = (0..threads.get().min(songs.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next_song.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = songs.get(index) else {
+                            return results;
+                        };
+                        let result = render_json_song(path, config)
+                            .and_then(|render| process(path, render))
+                            .with_context(|| format!("failed to render {}", path.display()));
+                        results.push((index, result));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("batch render worker panicked"))
+            .collect::<Vec<_>>()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Renders `songs` in parallel and collects their references keyed by path as
+/// given, the way [`OfflineGoldenManifest::songs`] keys them. Fails if any
+/// song fails or two paths share a key.
+pub fn render_batch_references(
+    songs: &[PathBuf],
+    config: OfflineRenderConfig,
+    threads: NonZeroUsize,
+) -> Result<BTreeMap<String, OfflineRenderReference>> {
+    let mut references = BTreeMap::new();
+    let results = render_batch(songs, config, threads, |_, render| Ok(render.reference()));
+    for (path, result) in songs.iter().zip(results) {
+        let key = path.to_string_lossy().into_owned();
+        if references.insert(key, result?).is_some() {
+            bail!("{} is listed more than once", path.display());
+        }
+    }
+    Ok(references)
+}
+
 pub fn render_song(song: &Song, config: OfflineRenderConfig) -> Result<OfflineRender> {
     let config = config.validate()?;
     let hydration_commands = build_song_hydration_commands(song, config.sample_rate as f32)?;
-    let song = Arc::new(song.clone());
-    let mut player = Player::new(song.clone(), config.sample_rate as f64);
-    player.handle_command(SequencerCmd::LoadSong { song }.into());
+    // Every instrument in the bank must fit, however large the bank.
+    let instrument_capacity = song.instrument_bank.len().max(DEFAULT_INSTRUMENT_CAPACITY);
+    let (song, start_row, line_limit) = config.selection.apply(song)?;
+    let song = Arc::new(song);
+    let mut player = Player::new(song.clone(), config.sample_rate as f64)
+        .with_instrument_capacity(instrument_capacity);
+    player.handle_command(SequencerCmd::LoadSong { song: song.clone() }.into());
     for command in hydration_commands {
         player.handle_command(command);
     }
+    player.cue(start_row);
+    player.set_line_limit(line_limit);
     player.handle_command(TransportCmd::PlayLastSong.into());
 
     let initial_capacity = config.max_frames.min(config.sample_rate as usize * 60);
@@ -217,25 +683,56 @@ pub fn render_song(song: &Song, config: OfflineRenderConfig) -> Result<OfflineRe
         sample_rate: config.sample_rate,
         left: Vec::with_capacity(initial_capacity),
         right: Vec::with_capacity(initial_capacity),
+        stems: Vec::new(),
+        dsp_load: DspLoadReport::default(),
     };
 
     let mut block_left = vec![0.0; config.block_size];
     let mut block_right = vec![0.0; config.block_size];
-    while player.is_playing() {
+    let mut stems = config
+        .stems
+        .then(|| StemBuses::new(player.instrument_capacity(), config.block_size));
+    let mut tail = config
+        .tail
+        .map(|tail| TailState::new(tail, config.sample_rate));
+    loop {
+        let playing = player.is_playing();
+        let mut frame_count = config.block_size;
+        if !playing {
+            match &tail {
+                Some(tail) if !tail.is_finished() => {
+                    frame_count = frame_count.min(tail.remaining_frames())
+                }
+                _ => break,
+            }
+        }
         if rendered.frame_count() >= config.max_frames {
+            // The ceiling guards against songs that never end; a tail may
+            // simply stop at it.
+            if !playing {
+                break;
+            }
             bail!(
                 "offline render exceeded maximum of {} frames",
                 config.max_frames
             );
         }
-        let frame_count = config
-            .block_size
-            .min(config.max_frames - rendered.frame_count());
+        let frame_count = frame_count.min(config.max_frames - rendered.frame_count());
         let left = &mut block_left[..frame_count];
         let right = &mut block_right[..frame_count];
         left.fill(0.0);
         right.fill(0.0);
-        player.process(left, right, config.sample_rate as f32, frame_count);
+        let sample_rate = config.sample_rate as f32;
+        match (&mut stems, playing) {
+            (Some(stems), true) => {
+                player.process_with_stems(left, right, stems, sample_rate, frame_count)
+            }
+            (None, true) => player.process(left, right, sample_rate, frame_count),
+            (stems, false) => player.render_tail(left, right, stems.as_mut(), sample_rate),
+        }
+        if let Some(stems) = &stems {
+            append_stems(&mut rendered, &song, stems);
+        }
         if left
             .iter()
             .chain(right.iter())
@@ -243,9 +740,17 @@ pub fn render_song(song: &Song, config: OfflineRenderConfig) -> Result<OfflineRe
         {
             bail!("offline render produced a non-finite sample");
         }
+        if let (Some(tail), false) = (&mut tail, playing) {
+            tail.observe(left, right);
+        }
         rendered.left.extend_from_slice(left);
         rendered.right.extend_from_slice(right);
     }
+    // Instruments removed before the end stop contributing; pad them to length.
+    for stem in &mut rendered.stems {
+        stem.left.resize(rendered.left.len(), 0.0);
+        stem.right.resize(rendered.right.len(), 0.0);
+    }
 
     if rendered.left.iter().all(|sample| *sample == 0.0)
         && rendered.right.iter().all(|sample| *sample == 0.0)
@@ -253,10 +758,49 @@ pub fn render_song(song: &Song, config: OfflineRenderConfig) -> Result<OfflineRe
         bail!("offline render produced only silence");
     }
 
+    rendered.dsp_load = player.dsp_load_state().take_report();
     Ok(rendered)
 }
 
-fn quantize_pcm16(sample: f32) -> i16 {
+/// Appends the block in `stems` to the render's stems, which still end where
+/// the master ends before this block. Instruments first heard mid-render get a
+/// stem that starts with silence.
+fn append_stems(rendered: &mut OfflineRender, song: &Song, stems: &StemBuses) {
+    let start = rendered.left.len();
+    for (instrument_id, left, right) in stems.iter() {
+        let index = match rendered
+            .stems
+            .binary_search_by_key(&instrument_id, |stem| stem.instrument_id)
+        {
+            Ok(index) => index,
+            Err(index) => {
+                let name = song
+                    .instrument_bank
+                    .iter()
+                    .find(|instrument| instrument.id as InstrumentId == instrument_id)
+                    .map(|instrument| instrument.name.clone())
+                    .unwrap_or_default();
+                rendered.stems.insert(
+                    index,
+                    OfflineStem {
+                        instrument_id,
+                        name,
+                        left: Vec::with_capacity(rendered.left.capacity()),
+                        right: Vec::with_capacity(rendered.right.capacity()),
+                    },
+                );
+                index
+            }
+        };
+        let stem = &mut rendered.stems[index];
+        stem.left.resize(start, 0.0);
+        stem.right.resize(start, 0.0);
+        stem.left.extend_from_slice(left);
+        stem.right.extend_from_slice(right);
+    }
+}
+
+pub(crate) fn quantize_pcm16(sample: f32) -> i16 {
     let sample = sample.clamp(-1.0, 1.0);
     if sample >= 0.0 {
         (sample * i16::MAX as f32).round() as i16
@@ -286,6 +830,9 @@ fn channel_statistics(channel: &[f32]) -> (f32, f32, usize) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sequencer::models::{
+        Event, Instrument, InstrumentData, Phrase, SimpleOscillatorParams, Waveform,
+    };
 
     #[test]
     fn validates_offline_render_limits() {
@@ -296,12 +843,208 @@ mod tests {
                 sample_rate: 48_000,
                 block_size: 256,
                 max_frames: 256,
+                stems: false,
+                tail: None,
+                selection: OfflineSelection::WHOLE_SONG,
             },
         )
         .expect_err("default song should exceed one block");
         assert!(error.to_string().contains("exceeded maximum"));
     }
 
+    #[test]
+    fn songs_with_more_instruments_than_the_default_capacity_render_them_all() {
+        let instrument_count = DEFAULT_INSTRUMENT_CAPACITY + 2;
+        let mut song = Song::new("capacity test");
+        song.instrument_bank = (1..=instrument_count)
+            .map(|id| Instrument {
+                id,
+                name: format!("Osc {id}"),
+                data: InstrumentData::SimpleOscillator(SimpleOscillatorParams {
+                    waveform: Waveform::Sine,
+                    audio_effects: Vec::new(),
+                    amp_envelope: Default::default(),
+                    unison: Default::default(),
+                }),
+            })
+            .collect();
+        song.phrase_bank = vec![Phrase::from_events([Event {
+            note: 60,
+            volume: 100,
+            instrument_id: instrument_count as u8,
+            ..Default::default()
+        }])];
+        song.chain_bank = vec![Chain::from_phrases([0])];
+        song.arrangement[0].chain_indices[0] = 0;
+
+        let render = render_song(
+            &song,
+            OfflineRenderConfig {
+                stems: true,
+                ..OfflineRenderConfig::default()
+            },
+        )
+        .expect("the last instrument should sound");
+
+        let stems = render.stems();
+        assert_eq!(stems.len(), instrument_count);
+        let last = &stems[instrument_count - 1];
+        assert_eq!(last.instrument_id(), instrument_count as InstrumentId);
+        assert!(last.left().iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn selections_keep_rows_and_tracks_within_the_song() {
+        let mut song = Song::new("selection test");
+        song.chain_bank = vec![Chain::default(); 2];
+        song.arrangement = vec![SongRow::new([0, 1, 0, 1, 0, 1, 0, 1]); 3];
+
+        let selection = OfflineSelection {
+            start_row: 1,
+            end_row: Some(2),
+            muted: TrackSet::from_tracks([1]).unwrap(),
+            soloed: TrackSet::from_tracks([0, 1, 2]).unwrap(),
+            looped: None,
+        };
+        let (selected, start_row, line_limit) = selection.apply(&song).unwrap();
+        assert_eq!((start_row, line_limit), (1, None));
+        assert_eq!(selected.arrangement.len(), 2);
+        let e = EMPTY_CHAIN_SLOT;
+        assert_eq!(
+            selected.arrangement[1].chain_indices,
+            [0, e, 0, e, e, e, e, e]
+        );
+
+        for (start_row, end_row) in [(3, None), (2, Some(2)), (0, Some(4))] {
+            let selection = OfflineSelection {
+                start_row,
+                end_row,
+                ..OfflineSelection::WHOLE_SONG
+            };
+            assert!(selection.apply(&song).is_err(), "{start_row}..{end_row:?}");
+        }
+        assert!(TrackSet::from_tracks([MAX_TRACKS]).is_err());
+    }
+
+    #[test]
+    fn phrase_loops_fill_chains_and_stop_after_the_last_repeat() {
+        let mut song = Song::new("loop test");
+        song.phrase_bank = vec![Default::default(); 2];
+        let selection = OfflineSelection {
+            looped: Some(OfflineLoop {
+                part: OfflineLoopPart::Phrase(1),
+                track: 3,
+                times: 20,
+            }),
+            ..OfflineSelection::WHOLE_SONG
+        };
+
+        let (selected, start_row, line_limit) = selection.apply(&song).unwrap();
+        assert_eq!(
+            (start_row, line_limit),
+            (0, Some(20 * DEFAULT_PHRASE_LENGTH))
+        );
+        assert_eq!(selected.arrangement.len(), 2);
+        let chain_index = selected.arrangement[0].chain_indices[3];
+        assert_eq!(selected.chain_bank[chain_index].phrase_indices, [1; 16]);
+
+        let out_of_range = OfflineSelection {
+            looped: Some(OfflineLoop {
+                part: OfflineLoopPart::Chain(song.chain_bank.len()),
+                track: 0,
+                times: 1,
+            }),
+            ..OfflineSelection::WHOLE_SONG
+        };
+        assert!(out_of_range.apply(&song).is_err());
+    }
+
+    #[test]
+    fn whole_song_selections_are_left_out_of_serialized_configs() {
+        let json = serde_json::to_value(OfflineRenderConfig::canonical()).unwrap();
+        assert!(json.get("selection").is_none());
+
+        let config = OfflineRenderConfig {
+            selection: OfflineSelection {
+                soloed: TrackSet::from_tracks([2, 0]).unwrap(),
+                looped: Some(OfflineLoop {
+                    part: OfflineLoopPart::Chain(1),
+                    track: 2,
+                    times: 4,
+                }),
+                ..OfflineSelection::WHOLE_SONG
+            },
+            ..OfflineRenderConfig::canonical()
+        };
+        let json = serde_json::to_value(config).unwrap();
+        assert_eq!(
+            json["selection"],
+            serde_json::json!({
+                "start_row": 0,
+                "soloed": [0, 2],
+                "loop": { "part": { "chain": 1 }, "track": 2, "times": 4 },
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<OfflineRenderConfig>(json).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn references_without_loudness_still_load() {
+        let reference: OfflineRenderReference = serde_json::from_value(serde_json::json!({
+            "sample_rate": 48_000,
+            "channels": 2,
+            "frames": 0,
+            "pcm_sha256": "",
+            "peak_left": 0.0,
+            "peak_right": 0.0,
+            "rms_left": 0.0,
+            "rms_right": 0.0,
+            "clipped_samples": 0,
+        }))
+        .unwrap();
+        assert_eq!(reference.loudness, LoudnessSummary::default());
+    }
+
+    #[test]
+    fn tails_end_after_a_quiet_hold_or_at_the_maximum_length() {
+        let config = OfflineTailConfig {
+            threshold_dbfs: -20.0,
+            hold_seconds: 3.0,
+            max_seconds: 10.0,
+        };
+        let mut tail = TailState::new(config, 1);
+        // A loud echo restarts the hold.
+        tail.observe(&[0.0, 0.0, 0.5], &[0.0; 3]);
+        assert!(!tail.is_finished());
+        tail.observe(&[0.05, 0.0], &[0.0, -0.01]);
+        assert!(!tail.is_finished());
+        tail.observe(&[0.0], &[0.0]);
+        assert!(tail.is_finished());
+
+        let mut tail = TailState::new(config, 1);
+        tail.observe(&[1.0; 9], &[1.0; 9]);
+        assert_eq!(tail.remaining_frames(), 1);
+        tail.observe(&[1.0], &[1.0]);
+        assert!(tail.is_finished());
+    }
+
+    #[test]
+    fn song_directories_expand_to_their_sorted_json_files() {
+        let dir = std::env::temp_dir().join(format!("blight-batch-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested.json")).unwrap();
+        for name in ["b.json", "a.json", "notes.txt"] {
+            std::fs::write(dir.join(name), "{}").unwrap();
+        }
+        let single = PathBuf::from("calibration.json");
+
+        let songs = collect_song_files(&[single.clone(), dir.clone()]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(songs, [single, dir.join("a.json"), dir.join("b.json")]);
+    }
+
     #[test]
     fn pcm_quantization_has_explicit_endpoints() {
         assert_eq!(quantize_pcm16(-2.0), i16::MIN);
@@ -325,6 +1068,8 @@ mod tests {
             sample_rate: 48_000,
             left: vec![0.5, 0.25],
             right: vec![-0.5, -0.25],
+            stems: Vec::new(),
+            dsp_load: DspLoadReport::default(),
         };
 
         let pcm = render.canonical_pcm();
@@ -8,7 +8,15 @@ use sequencer::{
     timing::TimingState,
 };
 
-use crate::{id::InstrumentId, Command, SequencerCmd, TransportCmd};
+use crate::{
+    id::InstrumentId, Command, DspLoadState, SequencerCmd, StemBuses, TransportCmd,
+    DEFAULT_MAX_BLOCK_SIZE,
+};
+#[cfg(feature = "standalone")]
+use crate::{
+    CaptureSender, EventSender, InstalledInstruments, ParameterBank, RetireSender,
+    VoiceActivityState,
+};
 
 /// Holds the playback position for a single track.
 #[derive(Debug, Clone, Copy, Default)]
@@ -59,24 +67,49 @@ pub struct Player {
     position: PlayerPosition,
     is_playing: bool,
     loop_enabled: bool,
+    /// Phrase rows to play before stopping as if the song ended there.
+    line_limit: Option<usize>,
+    lines_played: usize,
     engine_adapter: tracker_engine_adapter::TrackerEngineAdapter,
 }
 
 impl Player {
     pub fn new(song: Arc<Song>, sample_rate: f64) -> Self {
+        Self::with_max_block_size(song, sample_rate, DEFAULT_MAX_BLOCK_SIZE)
+    }
+
+    /// Creates a player whose engine renders in sub-blocks of at most
+    /// `max_block_size` frames.
+    pub fn with_max_block_size(song: Arc<Song>, sample_rate: f64, max_block_size: usize) -> Self {
         let timing = TimingState::new_with_bpm_tpl(
             sample_rate,
             song.initial_bpm as f64,   // Initial BPM
             song.initial_speed as u32, // Initial Ticks Per Line (TPL)
         );
 
+        let mut engine_adapter =
+            tracker_engine_adapter::TrackerEngineAdapter::with_max_block_size(max_block_size);
+        engine_adapter.set_tempo(song.initial_bpm as f32);
+
         Self {
             song,
             timing,
             position: PlayerPosition::default(),
             is_playing: false,
             loop_enabled: false,
-            engine_adapter: tracker_engine_adapter::TrackerEngineAdapter::new(),
+            line_limit: None,
+            lines_played: 0,
+            engine_adapter,
+        }
+    }
+
+    /// Sets the most instruments the engine holds at once; see
+    /// [`crate::Engine::with_instrument_capacity`]. Call before any instrument
+    /// is added.
+    pub fn with_instrument_capacity(self, capacity: usize) -> Self {
+        Self {
+            engine_adapter: self.engine_adapter.with_instrument_capacity(capacity),
+            ..self
         }
     }
 
@@ -84,6 +117,28 @@ impl Player {
         self.is_playing = true;
     }
 
+    #[cfg(feature = "standalone")]
+    pub fn max_block_size(&self) -> usize {
+        self.engine_adapter.max_block_size()
+    }
+
+    /// Returns the engine's DSP load counters; see [`DspLoadState::take_report`].
+    pub fn dsp_load_state(&self) -> Arc<DspLoadState> {
+        self.engine_adapter.dsp_load_state()
+    }
+
+    /// Maximum number of instruments the engine holds at once.
+    pub fn instrument_capacity(&self) -> usize {
+        self.engine_adapter.instrument_capacity()
+    }
+
+    /// Returns the voices sounding after the latest block; see
+    /// [`VoiceActivityState::snapshot`].
+    #[cfg(feature = "standalone")]
+    pub fn voice_activity(&self) -> Arc<VoiceActivityState> {
+        self.engine_adapter.voice_activity()
+    }
+
     pub(crate) fn is_playing(&self) -> bool {
         self.is_playing
     }
@@ -91,17 +146,55 @@ impl Player {
     pub fn stop(&mut self) {
         self.is_playing = false;
         self.position = PlayerPosition::default(); // Reset position
+        self.lines_played = 0;
         self.engine_adapter.stop_all_notes(); // Stop all notes when stopping playback
+                                              // Releasing voices are no longer processed, so do not keep listing them.
+        self.engine_adapter.clear_voice_activity();
     }
 
     fn set_song(&mut self, song: Arc<Song>) {
         self.timing.set_bpm(song.initial_bpm as f64);
         self.timing.set_tpl(song.initial_speed as u32);
+        self.engine_adapter.set_tempo(song.initial_bpm as f32);
         self.timing.reset();
         self.position.reset();
+        self.lines_played = 0;
         self.song = song;
     }
 
+    /// Moves playback to the start of arrangement row `song_step`. Each track
+    /// keeps the instrument that the skipped rows last selected, so events
+    /// without an instrument sound as they would when played from the start.
+    pub(crate) fn cue(&mut self, song_step: usize) {
+        let song = self.song.clone();
+        for row in song.arrangement.iter().take(song_step) {
+            for (track_index, &chain_index) in row.chain_indices.iter().enumerate() {
+                let Some(chain) = song.chain_bank.get(chain_index) else {
+                    continue;
+                };
+                for &phrase_index in &chain.phrase_indices {
+                    let Some(phrase) = song.phrase_bank.get(phrase_index) else {
+                        continue;
+                    };
+                    for event in &phrase.events {
+                        self.engine_adapter.cache_instrument_id_for_track(
+                            track_index,
+                            event.instrument_id as InstrumentId,
+                        );
+                    }
+                }
+            }
+        }
+        self.position.reset();
+        self.position.song_step = song_step;
+    }
+
+    /// Stops playback once `lines` phrase rows have played, as if the song
+    /// ended there. `None` plays to the end of the arrangement.
+    pub(crate) fn set_line_limit(&mut self, lines: Option<usize>) {
+        self.line_limit = lines;
+    }
+
     fn load_song(&mut self, song: Arc<Song>) {
         debug!("Loading song: {}", song.name);
         self.stop();
@@ -130,9 +223,53 @@ impl Player {
                 self.engine_adapter.handle_engine_command(command.into())
             }
             Command::Mixer(command) => self.engine_adapter.handle_engine_command(command.into()),
+            Command::State(command) => self.engine_adapter.handle_engine_command(command.into()),
         }
     }
 
+    /// Ships instruments displaced by song loads and replacements to a
+    /// non-RT collector instead of freeing them during playback.
+    #[cfg(feature = "standalone")]
+    pub fn set_retire_sender(&mut self, sender: RetireSender) {
+        self.engine_adapter.set_retire_sender(sender);
+    }
+
+    /// Reports engine-side command rejections, such as a full instrument bank.
+    #[cfg(feature = "standalone")]
+    pub fn set_event_sender(&mut self, sender: EventSender) {
+        self.engine_adapter.set_event_sender(sender);
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn installed_instruments(&self) -> Arc<InstalledInstruments> {
+        self.engine_adapter.installed_instruments()
+    }
+
+    /// Returns captures requested with `StateCmd::Capture` to the host.
+    #[cfg(feature = "standalone")]
+    pub fn set_capture_sender(&mut self, sender: CaptureSender) {
+        self.engine_adapter.set_capture_sender(sender);
+    }
+
+    /// Adapts timing and every loaded instrument and effect to a new output
+    /// rate and block size without reloading the song. The playback position
+    /// is kept, so a device switch continues at the same musical time.
+    /// Allocates; call it while the player is not being processed.
+    #[cfg(feature = "standalone")]
+    pub fn prepare(&mut self, sample_rate: f64, max_block_size: usize) {
+        self.timing.set_sample_rate(sample_rate);
+        self.engine_adapter
+            .prepare(sample_rate as f32, max_block_size);
+    }
+
+    /// Applies values written to `bank` since the previous block. Runs whether or
+    /// not the song is playing, so controls stay current while stopped.
+    #[cfg(feature = "standalone")]
+    pub fn apply_parameters(&mut self, bank: &ParameterBank, frame_count: usize, sample_rate: f32) {
+        self.engine_adapter
+            .apply_parameters(bank, frame_count, sample_rate);
+    }
+
     /// This is the main function to be called from your audio callback.
     /// It processes a block of samples, advances the sequencer state,
     /// and forwards audio buffers to the engine adapter.
@@ -143,8 +280,49 @@ impl Player {
         sample_rate: f32,
         buffer_len_samples: usize,
     ) {
+        if self.advance(buffer_len_samples) {
+            self.engine_adapter.process(left, right, sample_rate);
+        }
+    }
+
+    /// Like [`Self::process`], but also leaves each instrument's output before
+    /// the master effects in `stems`.
+    pub fn process_with_stems(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        stems: &mut StemBuses,
+        sample_rate: f32,
+        buffer_len_samples: usize,
+    ) {
+        if self.advance(buffer_len_samples) {
+            self.engine_adapter
+                .process_with_stems(left, right, stems, sample_rate);
+        }
+    }
+
+    /// Renders the loaded instruments without advancing the sequencer, so that
+    /// releases and effect tails can ring out after playback stopped.
+    pub fn render_tail(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        stems: Option<&mut StemBuses>,
+        sample_rate: f32,
+    ) {
+        match stems {
+            Some(stems) => self
+                .engine_adapter
+                .process_with_stems(left, right, stems, sample_rate),
+            None => self.engine_adapter.process(left, right, sample_rate),
+        }
+    }
+
+    /// Runs the sequencer ticks that fall within the next block. Returns
+    /// whether the block should be rendered, i.e. the song was playing.
+    fn advance(&mut self, buffer_len_samples: usize) -> bool {
         if !self.is_playing {
-            return;
+            return false;
         }
 
         let ticks_to_process = self.timing.advance(buffer_len_samples);
@@ -155,8 +333,7 @@ impl Player {
                 break;
             }
         }
-
-        self.engine_adapter.process(left, right, sample_rate);
+        true
     }
 
     /// This is the heart of the sequencer. It processes a single tick,
@@ -177,6 +354,13 @@ impl Player {
             // A row has finished, reset tick counter and advance to the next phrase step.
             self.position.tick_counter = 0;
 
+            self.lines_played += 1;
+            if self.line_limit == Some(self.lines_played) {
+                self.stop();
+                debug!("Reached line limit, stopping playback");
+                return;
+            }
+
             let mut song_step_needs_advancing = false;
             for track_pos in self.position.track_positions.iter_mut() {
                 track_pos.phrase_step += 1;
@@ -305,4 +489,21 @@ mod tests {
             .iter()
             .all(|position| position.chain_step == 0 && position.phrase_step == 0));
     }
+
+    #[test]
+    fn line_limits_stop_playback_partway_through_a_row() {
+        let mut song = Song::new("line limit");
+        song.initial_bpm = 120;
+        song.initial_speed = 1;
+        let mut player = Player::new(Arc::new(song), 48_000.0);
+        player.set_line_limit(Some(3));
+        player.play();
+        let mut left = [0.0];
+        let mut right = [0.0];
+
+        player.process(&mut left, &mut right, 48_000.0, 2_000);
+        assert!(player.is_playing());
+        player.process(&mut left, &mut right, 48_000.0, 1_000);
+        assert!(!player.is_playing());
+    }
 }
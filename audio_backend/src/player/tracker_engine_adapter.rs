@@ -1,8 +1,14 @@
 use std::collections::HashMap;
 
-use engine::{Engine, EngineCommand};
+#[cfg(feature = "standalone")]
+use engine::{
+    CaptureSender, EventSender, InstalledInstruments, ParameterBank, RetireSender,
+    VoiceActivityState,
+};
+use engine::{DspLoadState, Engine, EngineCommand, InstrumentCmd, StateCmd, StemBuses};
 use log::debug;
 use sequencer::models::{MAX_TRACKS, NO_INSTRUMENT};
+use std::sync::Arc;
 
 use crate::id::InstrumentId;
 
@@ -16,13 +22,47 @@ pub struct TrackerEngineAdapter {
 }
 
 impl TrackerEngineAdapter {
-    pub fn new() -> Self {
+    pub fn with_max_block_size(max_block_size: usize) -> Self {
         Self {
-            engine: Engine::new(),
+            engine: Engine::with_max_block_size(max_block_size),
             track_last_instrument: HashMap::with_capacity(MAX_TRACKS),
         }
     }
 
+    pub fn with_instrument_capacity(self, capacity: usize) -> Self {
+        Self {
+            engine: self.engine.with_instrument_capacity(capacity),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn max_block_size(&self) -> usize {
+        self.engine.max_block_size()
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        self.engine.prepare(sample_rate, max_block_size);
+    }
+
+    pub fn dsp_load_state(&self) -> Arc<DspLoadState> {
+        self.engine.dsp_load_state()
+    }
+
+    pub fn instrument_capacity(&self) -> usize {
+        self.engine.instrument_capacity()
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn voice_activity(&self) -> Arc<VoiceActivityState> {
+        self.engine.voice_activity()
+    }
+
+    pub fn clear_voice_activity(&self) {
+        self.engine.clear_voice_activity();
+    }
+
     pub fn note_on(&mut self, instrument_id: InstrumentId, note: u8, velocity: u8) {
         debug!("Playing note: {} on instrument: {}", note, instrument_id);
         self.engine.note_on(instrument_id, note, velocity);
@@ -36,6 +76,46 @@ impl TrackerEngineAdapter {
         self.engine.process(left, right, sample_rate);
     }
 
+    pub fn process_with_stems(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        stems: &mut StemBuses,
+        sample_rate: f32,
+    ) {
+        self.engine
+            .process_with_stems(left, right, stems, sample_rate);
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn apply_parameters(&mut self, bank: &ParameterBank, frame_count: usize, sample_rate: f32) {
+        self.engine.apply_parameters(bank, frame_count, sample_rate);
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn set_retire_sender(&mut self, sender: RetireSender) {
+        self.engine.set_retire_sender(sender);
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn set_event_sender(&mut self, sender: EventSender) {
+        self.engine.set_event_sender(sender);
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn set_capture_sender(&mut self, sender: CaptureSender) {
+        self.engine.set_capture_sender(sender);
+    }
+
+    #[cfg(feature = "standalone")]
+    pub fn installed_instruments(&self) -> Arc<InstalledInstruments> {
+        self.engine.installed_instruments()
+    }
+
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.engine.set_tempo(bpm);
+    }
+
     pub fn clear_instruments(&mut self) {
         self.engine.clear_instruments();
         self.track_last_instrument.clear();
@@ -46,6 +126,16 @@ impl TrackerEngineAdapter {
     }
 
     pub fn handle_engine_command(&mut self, command: EngineCommand) {
+        match &command {
+            EngineCommand::Instrument(InstrumentCmd::RemoveInstrument { instrument_id }) => {
+                // Tracks that last played the removed instrument fall back to none.
+                self.track_last_instrument
+                    .retain(|_, last_instrument| last_instrument != instrument_id);
+            }
+            // Every instrument is replaced, so no track has a last one left.
+            EngineCommand::State(StateCmd::Restore(_)) => self.track_last_instrument.clear(),
+            _ => {}
+        }
         self.engine.handle_command(command);
     }
 
@@ -1,17 +1,25 @@
-#[cfg(feature = "standalone")]
-use anyhow::Context;
-use anyhow::{bail, Result};
-use sequencer::models::{AmpEnvelopeParams, AudioEffect, InstrumentData, Song, Waveform};
+use anyhow::{Context, Result};
+use sequencer::models::{
+    AmpEnvelopeParams, AudioEffect, DFAMParams, DetuneCurve, DrumSynthParams, GrainWindow,
+    GranularParams, InstrumentData, PitchEnvelopeParams, SampleData as SongSample, SampleEncoding,
+    SequencerClock, Song, UnisonParams, Waveform,
+};
 #[cfg(feature = "standalone")]
 use sequencer::{cli::FileFormat, project::open_song_from_file};
 #[cfg(feature = "standalone")]
-use std::{path::Path, sync::Arc};
+use std::path::Path;
+use std::sync::Arc;
 
 use crate::{
-    effects::{DelayParameter as DP, ReverbParameter as RP},
-    id::{EffectId, InstrumentId},
-    instruments::Waveform as BackendWaveform,
-    Command, EffectFactory, EnvelopeCmd, InstrumentCmd, InstrumentFactory, MonoEffect, SynthCmd,
+    find_parameter,
+    id::{EffectId, InstrumentId, SampleId},
+    instruments::{
+        DetuneCurve as BackendDetuneCurve, GrainWindow as BackendGrainWindow,
+        UnisonParams as BackendUnisonParams, Waveform as BackendWaveform, DFAM_STEP_PITCH_KEYS,
+        DFAM_STEP_VELOCITY_KEYS,
+    },
+    Command, EffectFactory, EffectSpec, InstrumentCmd, InstrumentFactory, InstrumentSpec,
+    InstrumentType, Registry, SampleData, AMP_ENVELOPE_KEYS,
 };
 #[cfg(feature = "standalone")]
 use crate::{BlightAudio, SequencerCmd};
@@ -68,6 +76,8 @@ fn build_hydration_commands_with_factories(
     instrument_factory: &InstrumentFactory,
     effect_factory: &EffectFactory,
 ) -> Result<Vec<Command>> {
+    let mut registry = Registry::with_builtins();
+    register_song_samples(&mut registry, song);
     let mut commands = Vec::new();
 
     for instrument in &song.instrument_bank {
@@ -77,200 +87,310 @@ fn build_hydration_commands_with_factories(
             instrument_id,
             instrument.name
         );
-        match &instrument.data {
-            InstrumentData::SimpleOscillator(params) => {
-                commands.push(
-                    InstrumentCmd::AddInstrument {
-                        instrument: instrument_factory.create_oscillator_with_waveform(
-                            instrument_id,
-                            0.0,
-                            map_waveform_to_backend(params.waveform),
-                        ),
-                    }
-                    .into(),
-                );
-                push_effect_commands(
-                    &mut commands,
-                    effect_factory,
-                    instrument_id,
-                    &params.audio_effects,
-                );
-                push_amp_envelope_commands(&mut commands, instrument_id, &params.amp_envelope);
-            }
-            InstrumentData::HiHat(params) => {
-                commands.push(
-                    InstrumentCmd::AddInstrument {
-                        instrument: instrument_factory.create_hihat(instrument_id, 0.0),
-                    }
-                    .into(),
-                );
-                push_effect_commands(
-                    &mut commands,
-                    effect_factory,
-                    instrument_id,
-                    &params.audio_effects,
-                );
-                push_amp_envelope_commands(&mut commands, instrument_id, &params.amp_envelope);
-            }
-            InstrumentData::KickDrum(params) => {
-                commands.push(
-                    InstrumentCmd::AddInstrument {
-                        instrument: instrument_factory.create_kick_drum(instrument_id, 0.0),
-                    }
-                    .into(),
-                );
-                push_effect_commands(
-                    &mut commands,
-                    effect_factory,
-                    instrument_id,
-                    &params.audio_effects,
-                );
-                commands.push(
-                    InstrumentCmd::PassOnSynthCmd {
-                        instrument_id,
-                        synth_cmd: SynthCmd::EnvelopeCommand {
-                            envelope_id: None,
-                            command: EnvelopeCmd::SetPitchEnvFreqDelta {
-                                freq_delta: params.pitch_envelope.freq_delta,
-                            },
-                        },
-                    }
-                    .into(),
-                );
-                push_amp_envelope_commands(&mut commands, instrument_id, &params.amp_envelope);
-            }
-            InstrumentData::SnareDrum(params) => {
-                commands.push(
-                    InstrumentCmd::AddInstrument {
-                        instrument: instrument_factory.create_snare_drum(instrument_id, 0.0),
-                    }
-                    .into(),
-                );
-                push_effect_commands(
-                    &mut commands,
-                    effect_factory,
-                    instrument_id,
-                    &params.audio_effects,
-                );
-                push_amp_envelope_commands(&mut commands, instrument_id, &params.amp_envelope);
-            }
-            InstrumentData::DFAM(params) => {
-                commands.push(
-                    InstrumentCmd::AddInstrument {
-                        instrument: instrument_factory.create_dfam(instrument_id, 0.0),
-                    }
-                    .into(),
-                );
-                commands.push(
-                    InstrumentCmd::AddEffect {
-                        instrument_id,
-                        effect: effect_factory.create_moog_ladder(
-                            DEFAULT_INSTRUMENT_EFFECT_ID,
-                            500.0,
-                            0.5,
-                        ),
-                    }
-                    .into(),
-                );
-                push_effect_commands(
-                    &mut commands,
-                    effect_factory,
-                    instrument_id,
-                    &params.audio_effects,
-                );
-                push_amp_envelope_commands(&mut commands, instrument_id, &params.amp_envelope);
-            }
-            unsupported => {
-                bail!("unsupported instrument type in song hydration: {unsupported:?}");
+        let spec = instrument_spec(&instrument.data, DEFAULT_INSTRUMENT_EFFECT_ID);
+        let instrument = registry
+            .create_instrument(instrument_factory, effect_factory, instrument_id, &spec)
+            .with_context(|| format!("failed to hydrate instrument {}", instrument.name))?;
+        commands.push(
+            InstrumentCmd::AddInstrumentFromSpec {
+                instrument,
+                spec: Arc::new(spec),
             }
-        }
+            .into(),
+        );
     }
 
     Ok(commands)
 }
 
-fn push_amp_envelope_commands(
-    commands: &mut Vec<Command>,
-    instrument_id: InstrumentId,
-    envelope: &AmpEnvelopeParams,
-) {
-    for command in [
-        EnvelopeCmd::SetAttack {
-            attack: envelope.attack,
-        },
-        EnvelopeCmd::SetDecay {
-            decay: envelope.decay,
-        },
-        EnvelopeCmd::SetSustain {
-            sustain: envelope.sustain,
-        },
-        EnvelopeCmd::SetRelease {
-            release: envelope.release,
-        },
-    ] {
-        commands.push(
-            InstrumentCmd::PassOnSynthCmd {
-                instrument_id,
-                synth_cmd: SynthCmd::EnvelopeCommand {
-                    envelope_id: Some(0),
-                    command,
-                },
-            }
-            .into(),
-        );
+/// Adds the song's sample bank to `registry`, keyed by bank index, so that sample
+/// instruments can refer to it.
+pub fn register_song_samples(registry: &mut Registry, song: &Song) {
+    for (index, sample) in song.sample_bank.iter().enumerate() {
+        registry.add_sample(index as SampleId, Arc::new(decode_sample(sample)));
     }
 }
 
-fn push_effect_commands(
-    commands: &mut Vec<Command>,
-    effect_factory: &EffectFactory,
+fn decode_sample(sample: &SongSample) -> SampleData {
+    let data = match &sample.data {
+        SampleEncoding::Signed8(data) => {
+            data.iter().map(|&value| f32::from(value) / 128.0).collect()
+        }
+        SampleEncoding::Signed16(data) => data
+            .iter()
+            .map(|&value| f32::from(value) / 32_768.0)
+            .collect(),
+    };
+    let looped = sample.loop_length > 0;
+    SampleData {
+        data,
+        sample_rate: sample.sample_rate as f32,
+        channels: 1,
+        loop_start: looped.then_some(sample.loop_start),
+        loop_end: looped.then(|| sample.loop_start + sample.loop_length),
+    }
+}
+
+/// Commands that push an instrument's song-model values to the running instrument
+/// through its published parameters, for live edits that do not need a rebuild.
+///
+/// Values that only take effect when the instrument is built, such as its polyphony
+/// or waveform, are skipped, as is the amplitude envelope.
+pub fn instrument_parameter_commands(
     instrument_id: InstrumentId,
-    effects: &[AudioEffect],
-) {
-    for effect in effects {
-        let effect = match effect {
-            AudioEffect::Reverb {
-                mix,
-                decay_time,
-                room_size,
-                diffusion,
-                damping,
-            } => {
-                let mut reverb = effect_factory.create_mono_reverb(DEFAULT_INSTRUMENT_EFFECT_ID);
-                MonoEffect::set_parameter(&mut *reverb, RP::Mix.as_index(), (*mix).clamp(0.0, 1.0));
-                MonoEffect::set_parameter(&mut *reverb, RP::Decay.as_index(), *decay_time);
-                MonoEffect::set_parameter(&mut *reverb, RP::RoomSize.as_index(), *room_size);
-                MonoEffect::set_parameter(&mut *reverb, RP::Damping.as_index(), *damping);
-                MonoEffect::set_parameter(&mut *reverb, RP::Diffusion.as_index(), *diffusion);
-                reverb
-            }
-            AudioEffect::Delay {
-                time,
-                num_taps,
-                feedback,
-                mix,
-            } => {
-                let mut delay = effect_factory.create_mono_delay(
-                    DEFAULT_INSTRUMENT_EFFECT_ID,
-                    *time,
-                    *num_taps as usize,
-                    *feedback,
-                    *mix,
-                );
-                MonoEffect::set_parameter(&mut *delay, DP::Time.as_index(), *time);
-                MonoEffect::set_parameter(&mut *delay, DP::NumTaps.as_index(), *num_taps as f32);
-                MonoEffect::set_parameter(&mut *delay, DP::Feedback.as_index(), *feedback);
-                MonoEffect::set_parameter(&mut *delay, DP::Mix.as_index(), *mix);
-                delay
-            }
-        };
-        commands.push(
-            InstrumentCmd::AddEffect {
-                instrument_id,
-                effect,
+    data: &InstrumentData,
+) -> Vec<Command> {
+    let Some(instrument_type) = instrument_type(data) else {
+        return Vec::new();
+    };
+    let parameters = InstrumentFactory::parameters(instrument_type);
+    instrument_spec(data, DEFAULT_INSTRUMENT_EFFECT_ID)
+        .values
+        .into_iter()
+        .filter_map(|(id, value)| {
+            let descriptor = find_parameter(parameters, id)?;
+            Some(
+                InstrumentCmd::SetParameter {
+                    instrument_id,
+                    param_index: descriptor.index,
+                    value: descriptor.clamp(value),
+                }
+                .into(),
+            )
+        })
+        .collect()
+}
+
+fn instrument_type(data: &InstrumentData) -> Option<InstrumentType> {
+    match data {
+        InstrumentData::SimpleOscillator(params) if params.unison.voices > 1 => {
+            Some(InstrumentType::UnisonOscillator)
+        }
+        InstrumentData::SimpleOscillator(_) => Some(InstrumentType::Oscillator),
+        InstrumentData::HiHat(_) => Some(InstrumentType::HiHat),
+        InstrumentData::KickDrum(_) => Some(InstrumentType::KickDrum),
+        InstrumentData::SnareDrum(_) => Some(InstrumentType::SnareDrum),
+        InstrumentData::DFAM(_) => Some(InstrumentType::DFAM),
+        InstrumentData::PluckedString(_) => Some(InstrumentType::PluckedString),
+        InstrumentData::Sample(_) => Some(InstrumentType::SamplePlayer),
+        InstrumentData::Granular(_) => Some(InstrumentType::GranularSampler),
+        InstrumentData::Synth(_) => None,
+    }
+}
+
+/// Serializes an instrument's song-model parameters into the flat form the [`Registry`]
+/// builds instruments from. Every effect on the instrument gets `effect_id`.
+pub fn instrument_spec(data: &InstrumentData, effect_id: EffectId) -> InstrumentSpec {
+    let (type_id, mut values, effects): (_, _, &[AudioEffect]) = match data {
+        InstrumentData::SimpleOscillator(params) => {
+            let unison = map_unison_to_backend(&params.unison);
+            let mut values = vec![
+                (
+                    "waveform",
+                    map_waveform_to_backend(params.waveform) as u8 as f32,
+                ),
+                ("voices", unison.voices as f32),
+            ];
+            if unison.voices > 1 {
+                values.extend([
+                    ("detune", unison.detune_cents),
+                    ("detune_curve", unison.detune_curve as u8 as f32),
+                    ("phase_randomness", unison.phase_randomness),
+                    ("stereo_spread", unison.stereo_spread),
+                ]);
             }
-            .into(),
-        );
+            ("oscillator", values, params.audio_effects.as_slice())
+        }
+        InstrumentData::HiHat(params) => (
+            "hihat",
+            drum_values(&params.pitch_envelope, &params.synthesis),
+            params.audio_effects.as_slice(),
+        ),
+        InstrumentData::KickDrum(params) => (
+            "kick_drum",
+            drum_values(&params.pitch_envelope, &params.synthesis),
+            params.audio_effects.as_slice(),
+        ),
+        InstrumentData::SnareDrum(params) => (
+            "snare_drum",
+            drum_values(&params.pitch_envelope, &params.synthesis),
+            params.audio_effects.as_slice(),
+        ),
+        InstrumentData::DFAM(params) => {
+            ("dfam", dfam_values(params), params.audio_effects.as_slice())
+        }
+        InstrumentData::PluckedString(params) => (
+            "plucked_string",
+            vec![
+                ("polyphony", params.polyphony as f32),
+                ("damping", params.damping),
+                ("brightness", params.brightness),
+                ("pick_position", params.pick_position),
+                ("decay", params.decay),
+            ],
+            params.audio_effects.as_slice(),
+        ),
+        // The backend player holds a single sample, so a multi-sample instrument plays
+        // the one mapped to its lowest note.
+        InstrumentData::Sample(params) => (
+            "sample",
+            vec![("sample", f32::from(params.note_to_sample_map[0]))],
+            &[],
+        ),
+        InstrumentData::Granular(params) => (
+            "granular",
+            granular_values(params),
+            params.audio_effects.as_slice(),
+        ),
+        InstrumentData::Synth(_) => ("synth", Vec::new(), &[]),
+    };
+    if let Some(envelope) = amp_envelope(data) {
+        values.extend(AMP_ENVELOPE_KEYS.into_iter().zip([
+            envelope.attack,
+            envelope.decay,
+            envelope.sustain,
+            envelope.release,
+        ]));
+    }
+    InstrumentSpec {
+        type_id,
+        version: 1,
+        values,
+        effects: effects
+            .iter()
+            .map(|effect| effect_spec(effect, effect_id))
+            .collect(),
+    }
+}
+
+/// Serializes a song-model effect for the [`Registry`], which clamps the values
+/// to the effect's published ranges when it builds the effect.
+pub fn effect_spec(effect: &AudioEffect, effect_id: EffectId) -> EffectSpec {
+    let (type_id, values) = match effect {
+        AudioEffect::Reverb {
+            mix,
+            decay_time,
+            room_size,
+            diffusion,
+            damping,
+        } => (
+            "reverb",
+            vec![
+                ("mix", *mix),
+                ("decay", *decay_time),
+                ("room_size", *room_size),
+                ("damping", *damping),
+                ("diffusion", *diffusion),
+            ],
+        ),
+        AudioEffect::Delay {
+            time,
+            num_taps,
+            feedback,
+            mix,
+        } => (
+            "delay",
+            vec![
+                ("time", *time),
+                ("num_taps", *num_taps as f32),
+                ("feedback", *feedback),
+                ("mix", *mix),
+            ],
+        ),
+    };
+    EffectSpec {
+        type_id,
+        version: 1,
+        effect_id,
+        values,
+    }
+}
+
+fn amp_envelope(data: &InstrumentData) -> Option<AmpEnvelopeParams> {
+    match data {
+        InstrumentData::SimpleOscillator(params) => Some(params.amp_envelope.clone()),
+        InstrumentData::HiHat(params) => Some(params.amp_envelope.clone()),
+        InstrumentData::KickDrum(params) => Some(params.amp_envelope.clone()),
+        InstrumentData::SnareDrum(params) => Some(params.amp_envelope.clone()),
+        InstrumentData::DFAM(params) => Some(params.playback_amp_envelope()),
+        InstrumentData::Granular(params) => Some(params.amp_envelope.clone()),
+        InstrumentData::PluckedString(_) | InstrumentData::Sample(_) | InstrumentData::Synth(_) => {
+            None
+        }
+    }
+}
+
+fn drum_values(
+    pitch_envelope: &PitchEnvelopeParams,
+    synthesis: &DrumSynthParams,
+) -> Vec<(&'static str, f32)> {
+    vec![
+        ("pitch_env_amount", pitch_envelope.freq_delta),
+        ("pitch_decay", pitch_envelope.decay_time),
+        ("tone_frequency", synthesis.tone_frequency),
+        ("noise_mix", synthesis.noise_mix),
+        ("noise_color", synthesis.noise_color),
+        ("click", synthesis.click),
+        ("drive", synthesis.drive),
+    ]
+}
+
+fn dfam_values(params: &DFAMParams) -> Vec<(&'static str, f32)> {
+    let voice = &params.voice;
+    let sequencer = &params.sequencer;
+    let (clock_mode, clock_rate) = match sequencer.clock {
+        SequencerClock::Tempo { steps_per_beat } => (0.0, steps_per_beat),
+        SequencerClock::Free { steps_per_second } => (1.0, steps_per_second),
+    };
+    let mut values = vec![
+        ("fm_amount", voice.fm_amount),
+        ("vco_decay", voice.vco_decay),
+        ("vco_env_amount", voice.vco_env_amount),
+        ("vcf_cutoff", voice.vcf_cutoff),
+        ("vcf_resonance", voice.vcf_resonance),
+        ("vcf_decay", voice.vcf_decay),
+        ("vcf_env_amount", voice.vcf_env_amount),
+        ("vca_decay", voice.vca_decay),
+        ("clock_mode", clock_mode),
+        ("clock_rate", clock_rate),
+        ("sequencer_enabled", f32::from(u8::from(sequencer.enabled))),
+    ];
+    values.extend(DFAM_STEP_PITCH_KEYS.into_iter().zip(sequencer.step_pitches));
+    values.extend(
+        DFAM_STEP_VELOCITY_KEYS
+            .into_iter()
+            .zip(sequencer.step_velocities),
+    );
+    values
+}
+
+fn granular_values(params: &GranularParams) -> Vec<(&'static str, f32)> {
+    let window = map_grain_window_to_backend(params.window);
+    vec![
+        ("sample", f32::from(params.sample)),
+        ("polyphony", f32::from(params.polyphony)),
+        (
+            "window",
+            BackendGrainWindow::ALL
+                .iter()
+                .position(|candidate| *candidate == window)
+                .unwrap_or_default() as f32,
+        ),
+        ("position", params.position),
+        ("spray", params.spray),
+        ("grain_size", params.grain_size),
+        ("density", params.density),
+        ("pitch", params.pitch),
+        ("reverse_probability", params.reverse_probability),
+    ]
+}
+
+fn map_grain_window_to_backend(window: GrainWindow) -> BackendGrainWindow {
+    match window {
+        GrainWindow::Hann => BackendGrainWindow::Hann,
+        GrainWindow::Triangle => BackendGrainWindow::Triangle,
+        GrainWindow::Trapezoid => BackendGrainWindow::Trapezoid,
+        GrainWindow::Gaussian => BackendGrainWindow::Gaussian,
     }
 }
 
@@ -283,3 +403,244 @@ fn map_waveform_to_backend(waveform: Waveform) -> BackendWaveform {
         Waveform::NesTriangle => BackendWaveform::NesTriangle,
     }
 }
+
+/// Convert the serialized unison settings into the DSP representation.
+pub fn map_unison_to_backend(unison: &UnisonParams) -> BackendUnisonParams {
+    BackendUnisonParams {
+        voices: unison.voices,
+        detune_cents: unison.detune_cents,
+        detune_curve: match unison.detune_curve {
+            DetuneCurve::Linear => BackendDetuneCurve::Linear,
+            DetuneCurve::Exponential => BackendDetuneCurve::Exponential,
+            DetuneCurve::Logarithmic => BackendDetuneCurve::Logarithmic,
+        },
+        phase_randomness: unison.phase_randomness,
+        stereo_spread: unison.stereo_spread,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, MonoEffect};
+    use sequencer::models::{
+        DFAMSequencerParams, Envelope, Instrument, KickDrumParams, SampleData as SongSample,
+        SampleParams,
+    };
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn build(effect: &AudioEffect) -> Box<dyn MonoEffect> {
+        Registry::with_builtins()
+            .create_effect(&EffectFactory::new(SAMPLE_RATE), &effect_spec(effect, 1))
+            .unwrap()
+    }
+
+    fn value(effect: &dyn MonoEffect, id: &str) -> Option<f32> {
+        effect.parameter(find_parameter(effect.parameters(), id)?.index)
+    }
+
+    fn impulse_response(mut effect: Box<dyn MonoEffect>) -> Vec<f32> {
+        let mut buffer = vec![0.0; 8_192];
+        buffer[0] = 1.0;
+        effect.process(&mut buffer, SAMPLE_RATE);
+        buffer
+    }
+
+    #[test]
+    fn hydrated_effect_values_are_clamped_to_published_ranges() {
+        let reverb = |decay_time, damping| AudioEffect::Reverb {
+            mix: 0.5,
+            decay_time,
+            room_size: 1.0,
+            diffusion: 0.5,
+            damping,
+        };
+        let out_of_range = build(&reverb(1.5, 1.5));
+        assert_eq!(value(&*out_of_range, "decay"), Some(1.0));
+        assert_eq!(value(&*out_of_range, "damping"), Some(1.0));
+        // Out-of-range decays already rendered like the longest in-range one.
+        assert_eq!(
+            impulse_response(build(&reverb(1.5, 1.0))),
+            impulse_response(build(&reverb(0.95, 1.0)))
+        );
+
+        let delay = |feedback| AudioEffect::Delay {
+            time: 0.01,
+            num_taps: 1,
+            feedback,
+            mix: 0.5,
+        };
+        assert_eq!(value(&*build(&delay(1.2)), "feedback"), Some(0.95));
+        assert_eq!(
+            impulse_response(build(&delay(1.2))),
+            impulse_response(build(&delay(0.95)))
+        );
+    }
+
+    #[test]
+    fn sample_instruments_hydrate_from_the_song_sample_bank() {
+        let envelope = Envelope {
+            points: Vec::new(),
+            sustain_point: 0,
+            loop_start_point: 0,
+            loop_end_point: 0,
+            enabled: false,
+        };
+        let mut song = Song::new("samples");
+        song.sample_bank = vec![
+            SongSample {
+                name: "silence".to_string(),
+                data: SampleEncoding::Signed8(vec![0; 64]),
+                sample_rate: 48_000,
+                loop_start: 0,
+                loop_length: 0,
+                volume: 64,
+                panning: 128,
+            },
+            SongSample {
+                name: "square".to_string(),
+                data: SampleEncoding::Signed16((0..4_800).map(|i| (i / 50 % 2) * 16_000).collect()),
+                sample_rate: 48_000,
+                loop_start: 100,
+                loop_length: 200,
+                volume: 64,
+                panning: 128,
+            },
+        ];
+        song.instrument_bank.push(Instrument {
+            id: 1,
+            name: "sampler".to_string(),
+            data: InstrumentData::Sample(SampleParams {
+                note_to_sample_map: [1; 96],
+                volume_envelope: envelope.clone(),
+                panning_envelope: envelope,
+            }),
+        });
+
+        let mut engine = Engine::new();
+        for command in build_song_hydration_commands(&song, SAMPLE_RATE).unwrap() {
+            if let Command::Instrument(command) = command {
+                engine.handle_command(command.into());
+            }
+        }
+        engine.note_on(1, 60, 100);
+        let mut left = vec![0.0; 512];
+        let mut right = vec![0.0; 512];
+        engine.process(&mut left, &mut right, SAMPLE_RATE);
+        assert!(left.iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn granular_instruments_hydrate_with_their_options_and_parameters() {
+        let mut song = Song::new("granular");
+        song.sample_bank = vec![SongSample {
+            name: "ramp".to_string(),
+            data: SampleEncoding::Signed16((0..4_800).map(|i| i as i16 * 6).collect()),
+            sample_rate: 48_000,
+            loop_start: 0,
+            loop_length: 0,
+            volume: 64,
+            panning: 128,
+        }];
+        let data = InstrumentData::Granular(GranularParams {
+            polyphony: 2,
+            window: GrainWindow::Trapezoid,
+            position: 0.25,
+            ..GranularParams::default()
+        });
+        let spec = instrument_spec(&data, 1);
+        assert_eq!(spec.type_id, "granular");
+        assert!(spec.values.contains(&("window", 2.0)));
+
+        let mut registry = Registry::with_builtins();
+        register_song_samples(&mut registry, &song);
+        let instrument = registry
+            .create_instrument(
+                &InstrumentFactory::new(SAMPLE_RATE),
+                &EffectFactory::new(SAMPLE_RATE),
+                1,
+                &spec,
+            )
+            .unwrap();
+        assert_eq!(instrument.voice_count(), 2);
+        let position = find_parameter(instrument.parameters(), "position").unwrap();
+        assert_eq!(instrument.parameter(position.index), Some(0.25));
+
+        song.instrument_bank.push(Instrument {
+            id: 1,
+            name: "grains".to_string(),
+            data,
+        });
+        let mut engine = Engine::new();
+        for command in build_song_hydration_commands(&song, SAMPLE_RATE).unwrap() {
+            if let Command::Instrument(command) = command {
+                engine.handle_command(command.into());
+            }
+        }
+        engine.note_on(1, 60, 100);
+        let mut left = vec![0.0; 4_800];
+        let mut right = vec![0.0; 4_800];
+        engine.process(&mut left, &mut right, SAMPLE_RATE);
+        assert!(left.iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn live_edits_set_the_published_parameters() {
+        let data = InstrumentData::KickDrum(KickDrumParams {
+            audio_effects: Vec::new(),
+            amp_envelope: AmpEnvelopeParams::default(),
+            pitch_envelope: PitchEnvelopeParams {
+                freq_delta: 120.0,
+                decay_time: 0.05,
+            },
+            synthesis: DrumSynthParams {
+                drive: 0.4,
+                ..DrumSynthParams::kick()
+            },
+        });
+        let mut kick = InstrumentFactory::new(SAMPLE_RATE).create_kick_drum(3, 0.0);
+        for command in instrument_parameter_commands(3, &data) {
+            let Command::Instrument(InstrumentCmd::SetParameter {
+                instrument_id: 3,
+                param_index,
+                value,
+            }) = command
+            else {
+                panic!("expected parameter commands only");
+            };
+            kick.set_parameter(param_index, value);
+        }
+
+        let value = |id| kick.parameter(find_parameter(kick.parameters(), id).unwrap().index);
+        assert_eq!(value("pitch_env_amount"), Some(120.0));
+        assert_eq!(value("drive"), Some(0.4));
+    }
+
+    #[test]
+    fn sequencing_dfams_hold_full_sustain() {
+        let dfam = |enabled| {
+            InstrumentData::DFAM(DFAMParams {
+                audio_effects: Vec::new(),
+                amp_envelope: AmpEnvelopeParams {
+                    sustain: 0.2,
+                    ..AmpEnvelopeParams::default()
+                },
+                voice: Default::default(),
+                sequencer: DFAMSequencerParams {
+                    enabled,
+                    ..Default::default()
+                },
+            })
+        };
+        let sustain = |data| {
+            instrument_spec(&data, 1)
+                .values
+                .into_iter()
+                .find(|(id, _)| *id == "amp_sustain")
+                .map(|(_, value)| value)
+        };
+        assert_eq!(sustain(dfam(false)), Some(0.2));
+        assert_eq!(sustain(dfam(true)), Some(1.0));
+    }
+}
@@ -0,0 +1,307 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    id::{EffectId, InstrumentId},
+    EffectSpec, EffectType, EngineSnapshot, InstrumentSnapshot, InstrumentSpec,
+    MasterEffectSnapshot, Registry,
+};
+
+/// Version of the [`SoundState`] file layout. Type versions inside it are
+/// tracked separately by the [`Registry`].
+pub const SOUND_STATE_FORMAT_VERSION: u32 = 1;
+
+/// Serializable form of an [`EngineSnapshot`]: every instrument, voice effect
+/// and master effect with its current parameter values, keyed by stable ids.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoundState {
+    pub format_version: u32,
+    pub tempo_bpm: f32,
+    pub instruments: Vec<InstrumentState>,
+    pub master_effects: Vec<MasterEffectState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentState {
+    pub instrument_id: InstrumentId,
+    pub type_id: String,
+    pub version: u32,
+    pub values: Vec<ParamValue>,
+    pub effects: Vec<EffectState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectState {
+    pub effect_id: EffectId,
+    pub type_id: String,
+    pub version: u32,
+    pub values: Vec<ParamValue>,
+}
+
+/// A master effect, identified by the same type ids as voice effects.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MasterEffectState {
+    pub effect_id: EffectId,
+    pub type_id: String,
+    pub values: Vec<ParamValue>,
+}
+
+/// Values are listed in the order they are applied on restore.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamValue {
+    pub id: String,
+    pub value: f32,
+}
+
+impl SoundState {
+    /// Captures `snapshot`. Fails, listing them, if the snapshot skipped
+    /// instruments or master effects it could not describe, rather than
+    /// saving a state that would restore without them.
+    pub fn from_snapshot(snapshot: &EngineSnapshot) -> Result<Self> {
+        let mut skipped: Vec<String> = snapshot
+            .skipped_instruments
+            .iter()
+            .map(|instrument_id| format!("instrument {instrument_id} (installed without a spec)"))
+            .collect();
+        skipped.extend(
+            snapshot
+                .skipped_master_effects
+                .iter()
+                .map(|effect_id| format!("master effect {effect_id} (unknown type)")),
+        );
+        if !skipped.is_empty() {
+            return Err(anyhow!(
+                "cannot capture the sound state of {}",
+                skipped.join(", ")
+            ));
+        }
+        Ok(Self {
+            format_version: SOUND_STATE_FORMAT_VERSION,
+            tempo_bpm: snapshot.tempo_bpm,
+            instruments: snapshot
+                .instruments
+                .iter()
+                .map(|instrument| InstrumentState {
+                    instrument_id: instrument.instrument_id,
+                    type_id: instrument.spec.type_id.to_string(),
+                    version: instrument.spec.version,
+                    values: param_values(&instrument.spec.values),
+                    effects: instrument
+                        .spec
+                        .effects
+                        .iter()
+                        .map(|effect| EffectState {
+                            effect_id: effect.effect_id,
+                            type_id: effect.type_id.to_string(),
+                            version: effect.version,
+                            values: param_values(&effect.values),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            master_effects: snapshot
+                .master_effects
+                .iter()
+                .map(|effect| MasterEffectState {
+                    effect_id: effect.effect_id,
+                    type_id: effect.effect_type.type_id().to_string(),
+                    values: param_values(&effect.values),
+                })
+                .collect(),
+        })
+    }
+
+    /// Resolves every type and value id against `registry`, producing a
+    /// snapshot ready for [`EngineSnapshot::build`].
+    pub fn to_snapshot(&self, registry: &Registry) -> Result<EngineSnapshot> {
+        if self.format_version != SOUND_STATE_FORMAT_VERSION {
+            return Err(anyhow!(
+                "sound state format version {} is not supported (expected {})",
+                self.format_version,
+                SOUND_STATE_FORMAT_VERSION
+            ));
+        }
+        let instruments = self
+            .instruments
+            .iter()
+            .map(|instrument| {
+                let effects = instrument
+                    .effects
+                    .iter()
+                    .map(|effect| {
+                        Ok(EffectSpec {
+                            type_id: intern(registry, &effect.type_id)?,
+                            version: effect.version,
+                            effect_id: effect.effect_id,
+                            values: intern_values(registry, &effect.values)?,
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(InstrumentSnapshot {
+                    instrument_id: instrument.instrument_id,
+                    spec: InstrumentSpec {
+                        type_id: intern(registry, &instrument.type_id)?,
+                        version: instrument.version,
+                        values: intern_values(registry, &instrument.values)?,
+                        effects,
+                    },
+                })
+            })
+            .collect::<Result<_>>()
+            .context("invalid instrument in sound state")?;
+        let master_effects = self
+            .master_effects
+            .iter()
+            .map(|effect| {
+                let effect_type = EffectType::from_type_id(&effect.type_id)
+                    .ok_or_else(|| anyhow!("unknown master effect type '{}'", effect.type_id))?;
+                Ok(MasterEffectSnapshot {
+                    effect_type,
+                    effect_id: effect.effect_id,
+                    values: intern_values(registry, &effect.values)?,
+                })
+            })
+            .collect::<Result<_>>()
+            .context("invalid master effect in sound state")?;
+        Ok(EngineSnapshot {
+            tempo_bpm: self.tempo_bpm,
+            instruments,
+            master_effects,
+            skipped_instruments: Vec::new(),
+            skipped_master_effects: Vec::new(),
+        })
+    }
+}
+
+/// Writes `snapshot` to `path` as pretty-printed JSON.
+pub fn save_sound_state(path: &Path, snapshot: &EngineSnapshot) -> Result<()> {
+    let json = serde_json::to_string_pretty(&SoundState::from_snapshot(snapshot)?)?;
+    fs::write(path, json)
+        .with_context(|| format!("failed to write sound state to {}", path.display()))
+}
+
+/// Reads a sound state written by [`save_sound_state`].
+pub fn load_sound_state(path: &Path, registry: &Registry) -> Result<EngineSnapshot> {
+    let json = fs::read_to_string(path)
+        .with_context(|| format!("failed to read sound state from {}", path.display()))?;
+    let state: SoundState = serde_json::from_str(&json)
+        .with_context(|| format!("failed to parse sound state from {}", path.display()))?;
+    state.to_snapshot(registry)
+}
+
+fn param_values(values: &[(&'static str, f32)]) -> Vec<ParamValue> {
+    values
+        .iter()
+        .map(|&(id, value)| ParamValue {
+            id: id.to_string(),
+            value,
+        })
+        .collect()
+}
+
+fn intern(registry: &Registry, id: &str) -> Result<&'static str> {
+    registry
+        .intern(id)
+        .ok_or_else(|| anyhow!("unknown id '{id}'"))
+}
+
+fn intern_values(registry: &Registry, values: &[ParamValue]) -> Result<Vec<(&'static str, f32)>> {
+    values
+        .iter()
+        .map(|value| Ok((intern(registry, &value.id)?, value.value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_song_hydration_commands, Command, EffectFactory, Engine, InstrumentFactory};
+    use sequencer::models::{
+        AmpEnvelopeParams, AudioEffect, DrumSynthParams, Instrument, InstrumentData,
+        KickDrumParams, PitchEnvelopeParams, Song,
+    };
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn hydrated_engine() -> Engine {
+        let mut song = Song::new("state");
+        song.instrument_bank.push(Instrument {
+            id: 2,
+            name: "kick".to_string(),
+            data: InstrumentData::KickDrum(KickDrumParams {
+                audio_effects: vec![AudioEffect::Reverb {
+                    mix: 0.3,
+                    decay_time: 0.5,
+                    room_size: 0.4,
+                    diffusion: 0.5,
+                    damping: 0.5,
+                }],
+                amp_envelope: AmpEnvelopeParams::default(),
+                pitch_envelope: PitchEnvelopeParams::flat(),
+                synthesis: DrumSynthParams::kick(),
+            }),
+        });
+        let mut engine = Engine::new();
+        for command in build_song_hydration_commands(&song, SAMPLE_RATE).unwrap() {
+            if let Command::Instrument(command) = command {
+                engine.handle_command(command.into());
+            }
+        }
+        engine.add_master_effect(EffectFactory::new(SAMPLE_RATE).create_stereo_reverb(5));
+        engine
+    }
+
+    #[test]
+    fn saved_states_reload_with_live_parameter_values() {
+        let mut engine = hydrated_engine();
+        engine.set_instrument_effect_parameter(2, 1, 0, 0.8);
+        engine.set_master_effect_parameter(5, 3, 0.25);
+        let snapshot = engine.snapshot();
+
+        let path = std::env::temp_dir().join("audio_backend_sound_state_test.json");
+        save_sound_state(&path, &snapshot).unwrap();
+        let registry = Registry::with_builtins();
+        let loaded = load_sound_state(&path, &registry).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, snapshot);
+        let reverb = &loaded.instruments[0].spec.effects[0];
+        assert!(reverb.values.contains(&("mix", 0.8)));
+        assert!(loaded.master_effects[0].values.contains(&("damping", 0.25)));
+
+        let mut restored = Engine::new();
+        restored.restore(
+            loaded
+                .build(
+                    &registry,
+                    &InstrumentFactory::new(SAMPLE_RATE),
+                    &EffectFactory::new(SAMPLE_RATE),
+                )
+                .unwrap(),
+        );
+        assert_eq!(restored.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn states_that_cannot_be_captured_fail_to_save() {
+        let mut engine = hydrated_engine();
+        engine.add_instrument(InstrumentFactory::new(SAMPLE_RATE).create_hihat(4, 0.0));
+        let error = SoundState::from_snapshot(&engine.snapshot()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("instrument 4 (installed without a spec)"));
+    }
+
+    #[test]
+    fn unknown_ids_are_rejected_on_load() {
+        let mut state = SoundState::from_snapshot(&hydrated_engine().snapshot()).unwrap();
+        state.instruments[0].values.push(ParamValue {
+            id: "wobble".to_string(),
+            value: 1.0,
+        });
+        let error = state.to_snapshot(&Registry::with_builtins()).unwrap_err();
+        assert!(format!("{error:#}").contains("unknown id 'wobble'"));
+    }
+}
@@ -1,117 +1,426 @@
-use super::BlightAudio;
+use super::{
+    open_device, AudioDeviceConfig, AudioOutput, AudioOutputInfo, BlightAudio, NullOutput,
+    NullOutputConfig, OpenedDevice, PendingSnapshot,
+};
 use crate::{
-    AudioProcessor, Command, EffectFactory, InstrumentFactory, MeterState, ResourceManager,
-    VoiceFactory,
+    capture_channel, event_channel, id::InstrumentId, retire_channel, AudioProcessor, Command,
+    DspLoadState, EffectFactory, EngineCapture, EngineEvent, EngineSnapshot, InstrumentFactory,
+    MeterState, MissedBlocks, ParameterBank, Recorder, RecordingFailure, RecordingReport,
+    RecordingStatus, Registry, ResourceManager, RetireCollector, StateCmd, VoiceActivityState,
+    VoiceFactory, DEFAULT_EVENT_CAPACITY, DEFAULT_RETIRE_CAPACITY, RECORD_BUFFER_SAMPLES,
 };
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use log::info;
 use ringbuf::storage::Heap;
 use ringbuf::traits::*;
 use ringbuf::SharedRb;
 use sequencer::models::Song;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the collector thread frees state retired by the audio thread.
+const RETIRE_COLLECT_INTERVAL: Duration = Duration::from_millis(50);
+/// Captures the engine may have answered but the host not yet received.
+const CAPTURE_CHANNEL_CAPACITY: usize = 4;
+/// How long a snapshot request waits for the audio thread to answer.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How often [`BlightAudio::snapshot`] polls for the answer.
+const CAPTURE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Frees instruments and effects displaced on the audio thread. The thread
+/// exits once the audio processor, and with it the sender, has been dropped.
+fn spawn_retire_collector(mut collector: RetireCollector) -> std::io::Result<()> {
+    thread::Builder::new()
+        .name("blight-retire-collector".into())
+        .spawn(move || {
+            while !collector.is_disconnected() {
+                collector.collect();
+                // Polling keeps the receiver unparked, so sends from the audio
+                // thread never have to wake it.
+                thread::sleep(RETIRE_COLLECT_INTERVAL);
+            }
+        })?;
+    Ok(())
+}
+
+/// Builds an output stream that drives the shared processor. The lock is only
+/// contended while a device switch holds the processor; the callback outputs
+/// silence rather than wait for it, and counts the block as dropped from a
+/// running recording.
+fn build_stream(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    audio_processor: Arc<Mutex<AudioProcessor>>,
+    missed_blocks: MissedBlocks,
+) -> Result<cpal::Stream, anyhow::Error> {
+    let channels = usize::from(config.channels).max(1);
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            // This closure is the audio callback.
+            match audio_processor.try_lock() {
+                Ok(mut audio_processor) => audio_processor.process(data),
+                Err(_) => {
+                    data.fill(0.0);
+                    missed_blocks.count(data.len() / channels);
+                }
+            }
+        },
+        |err| eprintln!("an error occurred on stream: {}", err),
+        None,
+    )?;
+    Ok(stream)
+}
+
+impl AudioOutput {
+    /// Stops driving the processor, so that it can be prepared for another
+    /// output.
+    fn pause(&mut self) -> Result<(), anyhow::Error> {
+        match self {
+            AudioOutput::Device(stream) => stream.pause()?,
+            AudioOutput::Null(output) => output.stop(),
+        }
+        Ok(())
+    }
+}
 
 impl BlightAudio {
     pub fn new() -> Result<Self, anyhow::Error> {
-        let host = cpal::default_host();
-        let device = host
-            .default_output_device()
-            .expect("no output device available");
-        let config = device.default_output_config()?.config();
-        let sample_rate = config.sample_rate.0;
-        let channels = config.channels as usize;
+        Self::with_device(None, &AudioDeviceConfig::default())
+    }
+
+    pub fn with_song(song: Arc<Song>) -> Result<Self, anyhow::Error> {
+        Self::with_device(Some(song), &AudioDeviceConfig::default())
+    }
+
+    /// Opens the host, device, sample rate and buffer size that `config`
+    /// asks for, falling back to the platform defaults.
+    pub fn with_device_config(config: &AudioDeviceConfig) -> Result<Self, anyhow::Error> {
+        Self::with_device(None, config)
+    }
+
+    /// Starts an output device, seeding the processor with `song` or an
+    /// empty one.
+    fn with_device(
+        song: Option<Arc<Song>>,
+        config: &AudioDeviceConfig,
+    ) -> Result<Self, anyhow::Error> {
+        let OpenedDevice {
+            host,
+            device,
+            config,
+            info,
+        } = open_device(config)?;
+
+        info!("Audio output device: {} ({})", info.device, info.host);
+        info!("Output config: {:?}", config);
+        info!("Sample rate: {}", info.sample_rate);
+        info!("Channels: {}", info.channels);
+
+        Self::start(song, Some(host), info, |audio_processor, missed_blocks| {
+            let stream = build_stream(&device, &config, audio_processor, missed_blocks)?;
+            stream.play()?;
+            Ok(AudioOutput::Device(stream))
+        })
+    }
+
+    /// Runs the engine without an audio device, advancing it in real time
+    /// from a timer thread. Commands, parameters, meters and the transport
+    /// behave as with a device; the output is discarded or written to
+    /// `config.render_to`.
+    pub fn with_null_output(config: NullOutputConfig) -> Result<Self, anyhow::Error> {
+        if config.sample_rate == 0 || config.channels == 0 || config.block_size == 0 {
+            anyhow::bail!("null audio output needs a sample rate, channels and a block size");
+        }
+        let info = AudioOutputInfo {
+            host: "null".to_string(),
+            device: match &config.render_to {
+                Some(path) => path.display().to_string(),
+                None => "null".to_string(),
+            },
+            sample_rate: config.sample_rate,
+            channels: config.channels,
+            buffer_size: Some(config.block_size as u32),
+        };
+        Self::start(None, None, info, |audio_processor, missed_blocks| {
+            Ok(AudioOutput::Null(NullOutput::start(
+                &config,
+                audio_processor,
+                missed_blocks,
+            )?))
+        })
+    }
 
+    /// Builds the processor, seeded with `song` if given, and hands it to
+    /// `start_output`, which starts driving it.
+    fn start(
+        song: Option<Arc<Song>>,
+        host: Option<cpal::HostId>,
+        output_info: AudioOutputInfo,
+        start_output: impl FnOnce(
+            Arc<Mutex<AudioProcessor>>,
+            MissedBlocks,
+        ) -> Result<AudioOutput, anyhow::Error>,
+    ) -> Result<Self, anyhow::Error> {
+        let sample_rate = output_info.sample_rate;
+        let channels = output_info.channels;
         // Create the SPSC ring buffer for commands using a heap-allocated buffer.
-        // let rb = HeapRb::<Command>::new(1024); // Capacity for 1024 commands
         let rb = SharedRb::<Heap<Command>>::new(1024);
         let (command_tx, command_rx) = rb.split();
 
-        // Create the real-time processor and move it into the audio thread.
+        // Create the real-time processor, seeded with a Song if one is given.
         let meter = Arc::new(MeterState::new());
-        let mut audio_processor =
-            AudioProcessor::new(command_rx, sample_rate as f32, channels, meter.clone());
+        let parameters = Arc::new(ParameterBank::default());
+        let mut audio_processor = match song {
+            Some(song) => AudioProcessor::new_with_song(
+                song,
+                command_rx,
+                sample_rate as f32,
+                channels,
+                meter.clone(),
+                parameters.clone(),
+            ),
+            None => AudioProcessor::new(
+                command_rx,
+                sample_rate as f32,
+                channels,
+                meter.clone(),
+                parameters.clone(),
+            ),
+        };
+        let (retire_sender, retire_collector) = retire_channel(DEFAULT_RETIRE_CAPACITY);
+        audio_processor.set_retire_sender(retire_sender);
+        spawn_retire_collector(retire_collector)?;
+        let (event_sender, engine_events) = event_channel(DEFAULT_EVENT_CAPACITY);
+        audio_processor.set_event_sender(event_sender);
+        let (capture_sender, captures) = capture_channel(CAPTURE_CHANNEL_CAPACITY);
+        audio_processor.set_capture_sender(capture_sender);
+        let (recorder, record_tap) = Recorder::spawn(RECORD_BUFFER_SAMPLES)?;
+        audio_processor.set_record_tap(record_tap);
+        let installed_instruments = audio_processor.installed_instruments();
+        let dsp_load = audio_processor.dsp_load_state();
+        let voice_activity = audio_processor.voice_activity();
 
-        let stream = device.build_output_stream(
-            &config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                // This closure is the audio callback.
-                audio_processor.process(data);
-            },
-            |err| eprintln!("an error occurred on stream: {}", err),
-            None,
-        )?;
+        let audio_processor = Arc::new(Mutex::new(audio_processor));
+        let output = start_output(audio_processor.clone(), recorder.missed_blocks())?;
 
         let resource_manager = ResourceManager::new();
         let voice_factory = VoiceFactory::new(sample_rate as f32);
         let effect_factory = EffectFactory::new(sample_rate as f32);
         let instrument_factory = InstrumentFactory::new(sample_rate as f32);
 
-        stream.play()?;
-
         Ok(BlightAudio {
             command_tx,
             instrument_factory,
+            registry: Registry::with_builtins(),
             voice_factory,
             resource_manager,
             effect_factory,
             meter,
-            _stream: stream,
+            dsp_load,
+            voice_activity,
+            parameters,
+            engine_events,
+            captures,
+            capture_request: 0,
+            installed_instruments,
+            audio_processor,
+            output,
+            host,
+            output_info,
+            recorder,
         })
     }
 
-    pub fn with_song(song: Arc<Song>) -> Result<Self, anyhow::Error> {
-        let host = cpal::default_host();
-        let device = host
-            .default_output_device()
-            .expect("no output device available");
-        let config = device.default_output_config()?.config();
-        let sample_rate = config.sample_rate.0;
-        let channels = config.channels as usize;
-
-        info!("Audio output device: {}", device.name()?);
-        info!("Default output config: {:?}", config);
-        info!("Sample rate: {}", sample_rate);
-        info!("Channels: {}", channels);
+    /// Moves playback to another output device, or to the host default when
+    /// `device_name` is `None`, without reloading the song. The loaded
+    /// instruments and effects are prepared for the new device's sample rate,
+    /// and the factories are rebuilt so later instruments match it.
+    pub fn switch_output_device(&mut self, device_name: Option<&str>) -> Result<(), anyhow::Error> {
+        let OpenedDevice {
+            host,
+            device,
+            config,
+            info,
+        } = open_device(&AudioDeviceConfig {
+            host: self.host.map(|host| host.name().to_string()),
+            device: device_name.map(str::to_string),
+            ..AudioDeviceConfig::default()
+        })?;
+        let sample_rate = info.sample_rate as f32;
+        let channels = info.channels;
+        info!(
+            "Switching audio output to {} at {} Hz, {} channels",
+            info.device, sample_rate, channels
+        );
 
-        // Create the SPSC ring buffer for commands using a heap-allocated buffer.
-        let rb = SharedRb::<Heap<Command>>::new(1024);
-        let (command_tx, command_rx) = rb.split();
+        // A WAV file holds a single sample rate.
+        if self.recorder.status().recording {
+            let report = self.recorder.stop()?;
+            info!(
+                "Stopped recording {} for the device switch",
+                report.path.display()
+            );
+        }
 
-        // Create the real-time processor seeded with a Song.
-        let meter = Arc::new(MeterState::new());
-        let mut audio_processor = AudioProcessor::new_with_song(
-            song,
-            command_rx,
-            sample_rate as f32,
-            channels,
-            meter.clone(),
-        );
+        // Stop the old callback before preparing, since preparing allocates.
+        self.output.pause()?;
+        let max_block_size = {
+            let mut audio_processor = self
+                .audio_processor
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let max_block_size = self.instrument_factory.max_block_size();
+            audio_processor.prepare(sample_rate, channels, max_block_size);
+            max_block_size
+        };
+        self.voice_factory = VoiceFactory::with_max_block_size(sample_rate, max_block_size);
+        self.effect_factory = EffectFactory::new(sample_rate);
+        self.instrument_factory =
+            InstrumentFactory::with_max_block_size(sample_rate, max_block_size);
 
-        let stream = device.build_output_stream(
+        let stream = build_stream(
+            &device,
             &config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                audio_processor.process(data);
-            },
-            |err| eprintln!("an error occurred on stream: {}", err),
-            None,
+            self.audio_processor.clone(),
+            self.recorder.missed_blocks(),
         )?;
+        stream.play()?;
+        self.output = AudioOutput::Device(stream);
+        self.host = Some(host);
+        self.output_info = info;
+        Ok(())
+    }
 
-        let resource_manager = ResourceManager::new();
-        let voice_factory = VoiceFactory::new(sample_rate as f32);
-        let effect_factory = EffectFactory::new(sample_rate as f32);
-        let instrument_factory = InstrumentFactory::new(sample_rate as f32);
+    /// Starts writing the master output, including live parameter changes, to
+    /// a 32-bit float stereo WAV file at the output's sample rate. The audio
+    /// callback hands blocks to a writer thread through a lock-free ring
+    /// buffer and drops them if the writer falls behind.
+    pub fn start_recording(&mut self, path: &Path) -> Result<(), anyhow::Error> {
+        self.recorder.start(path, self.output_info.sample_rate)?;
+        info!("Recording output to {}", path.display());
+        Ok(())
+    }
 
-        stream.play()?;
+    /// Stops the recording, writes the frames still buffered and closes the
+    /// file. Switching the output device also stops a recording.
+    pub fn stop_recording(&mut self) -> Result<RecordingReport, anyhow::Error> {
+        let report = self.recorder.stop()?;
+        info!(
+            "Recorded {} frames to {} ({} dropped)",
+            report.frames,
+            report.path.display(),
+            report.dropped_frames
+        );
+        Ok(report)
+    }
 
-        Ok(BlightAudio {
-            command_tx,
-            instrument_factory,
-            voice_factory,
-            resource_manager,
-            effect_factory,
-            meter,
-            _stream: stream,
-        })
+    /// Returns the progress of the current recording.
+    pub fn recording_status(&self) -> RecordingStatus {
+        self.recorder.status()
+    }
+
+    /// Returns the recording that stopped on its own because the file could
+    /// not be written, once. `stop_recording` reports it too if it has not
+    /// been taken; a new recording discards it.
+    pub fn take_recording_failure(&self) -> Option<RecordingFailure> {
+        self.recorder.take_failure()
+    }
+
+    /// Returns the output the engine is currently running with.
+    pub fn output_info(&self) -> &AudioOutputInfo {
+        &self.output_info
+    }
+
+    /// Captures every instrument and master effect with its live parameter
+    /// values. The audio thread fills the capture between blocks, after the
+    /// commands already queued, so playback is not interrupted. Fails if the
+    /// command queue is full or the output does not process in time.
+    ///
+    /// Blocks the calling thread while it waits; async hosts use
+    /// [`Self::request_snapshot`] and [`Self::poll_snapshot`] instead.
+    pub fn snapshot(&mut self) -> Result<EngineSnapshot, anyhow::Error> {
+        let mut pending = self.request_snapshot()?;
+        loop {
+            if let Some(snapshot) = self.poll_snapshot(&mut pending)? {
+                return Ok(snapshot);
+            }
+            thread::sleep(CAPTURE_POLL_INTERVAL);
+        }
+    }
+
+    /// Asks the audio thread to capture the engine without waiting for it.
+    /// Only the latest request is answered; requesting again abandons it.
+    pub fn request_snapshot(&mut self) -> Result<PendingSnapshot, anyhow::Error> {
+        let mut pending = PendingSnapshot {
+            request: 0,
+            deadline: Instant::now(),
+        };
+        self.send_capture(EngineCapture::new(), &mut pending)?;
+        Ok(pending)
+    }
+
+    /// Returns the snapshot once the audio thread has answered `pending`,
+    /// dropping answers to abandoned requests. A capture that did not fit is
+    /// grown and sent again. Fails if the audio thread does not answer in time.
+    pub fn poll_snapshot(
+        &mut self,
+        pending: &mut PendingSnapshot,
+    ) -> Result<Option<EngineSnapshot>, anyhow::Error> {
+        // Polling keeps the receiver unparked, as for the retire collector.
+        while let Some(capture) = self.captures.try_recv() {
+            if capture.request() != pending.request {
+                continue;
+            }
+            return match capture.into_snapshot() {
+                Ok(snapshot) => Ok(Some(snapshot)),
+                Err(mut overflowed) => {
+                    overflowed.grow();
+                    self.send_capture(overflowed, pending)?;
+                    Ok(None)
+                }
+            };
+        }
+        if Instant::now() >= pending.deadline {
+            anyhow::bail!("the audio thread did not answer the capture request");
+        }
+        Ok(None)
+    }
+
+    fn send_capture(
+        &mut self,
+        mut capture: EngineCapture,
+        pending: &mut PendingSnapshot,
+    ) -> Result<(), anyhow::Error> {
+        self.capture_request += 1;
+        capture.set_request(self.capture_request);
+        self.push_command(StateCmd::Capture(capture).into())?;
+        pending.request = self.capture_request;
+        pending.deadline = Instant::now() + CAPTURE_TIMEOUT;
+        Ok(())
+    }
+
+    /// Replaces the loaded instruments and master effects with those in
+    /// `snapshot`, built at the current sample rate. The audio thread swaps
+    /// them in after the commands already queued. Nothing changes if any of
+    /// them cannot be built or the command queue is full.
+    pub fn restore(&mut self, snapshot: &EngineSnapshot) -> Result<(), anyhow::Error> {
+        let restored = snapshot.build(
+            &self.registry,
+            &self.instrument_factory,
+            &self.effect_factory,
+        )?;
+        self.push_command(StateCmd::Restore(restored).into())
+    }
+
+    /// Queues a command that must not be dropped silently.
+    fn push_command(&mut self, command: Command) -> Result<(), anyhow::Error> {
+        if self.command_tx.try_push(command).is_err() {
+            anyhow::bail!("command queue is full");
+        }
+        Ok(())
     }
 
     /// Public method to send a command to the audio thread.
@@ -138,6 +447,42 @@ impl BlightAudio {
         &self.instrument_factory
     }
 
+    /// The registry the engine's instruments and effects are built with.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Returns a handle to the shared parameter bank. Values written to a slot
+    /// bound with `MixerCmd::BindParameter` reach the audio thread without
+    /// going through the command queue.
+    pub fn parameter_bank(&self) -> Arc<ParameterBank> {
+        self.parameters.clone()
+    }
+
+    /// Returns the ids of the instruments the engine currently has installed,
+    /// in ascending order. Reflects commands the audio thread has applied.
+    pub fn installed_instrument_ids(&self) -> Vec<InstrumentId> {
+        self.installed_instruments.ids()
+    }
+
+    /// Takes the events the engine reported since the last call, such as
+    /// instruments rejected because the bank is full.
+    pub fn engine_events(&self) -> impl Iterator<Item = EngineEvent> + '_ {
+        self.engine_events.drain()
+    }
+
+    /// Returns a handle to the engine's DSP load counters. Callers read them
+    /// via [`DspLoadState::take_report`], which also starts a new window.
+    pub fn dsp_load_state(&self) -> Arc<DspLoadState> {
+        self.dsp_load.clone()
+    }
+
+    /// Returns a handle to the voices sounding after the latest block, read
+    /// via [`VoiceActivityState::snapshot`].
+    pub fn voice_activity(&self) -> Arc<VoiceActivityState> {
+        self.voice_activity.clone()
+    }
+
     /// Returns a handle to the shared metering state. Cloning is cheap (an
     /// `Arc` bump); callers read levels via [`MeterState::take_levels`].
     pub fn meter_state(&self) -> Arc<MeterState> {
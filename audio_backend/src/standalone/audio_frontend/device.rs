@@ -0,0 +1,157 @@
+use anyhow::{anyhow, bail};
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
+
+/// Which output device to open, and how. `None` fields use the defaults of
+/// the platform's host and of the device.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AudioDeviceConfig {
+    /// Host API name as listed by [`list_output_devices`], e.g. `ALSA`.
+    pub host: Option<String>,
+    pub device: Option<String>,
+    pub sample_rate: Option<u32>,
+    /// Frames per callback.
+    pub buffer_size: Option<u32>,
+}
+
+/// The output a `BlightAudio` is running with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AudioOutputInfo {
+    /// Host API name, or `null` for the timer-driven null output.
+    pub host: String,
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Frames per callback; `None` when the host chooses.
+    pub buffer_size: Option<u32>,
+}
+
+/// An output device and the stream settings it supports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDeviceInfo {
+    pub host: String,
+    pub name: String,
+    pub is_default: bool,
+    pub default_sample_rate: u32,
+    pub channels: u16,
+    /// Lowest and highest supported sample rates at `channels` channels.
+    pub sample_rates: (u32, u32),
+    /// Lowest and highest buffer sizes, when the host reports them.
+    pub buffer_sizes: Option<(u32, u32)>,
+}
+
+/// Lists the output devices of every available host. Devices that cannot
+/// report a default configuration are skipped.
+pub fn list_output_devices() -> Result<Vec<OutputDeviceInfo>, anyhow::Error> {
+    let mut devices = Vec::new();
+    for host_id in cpal::available_hosts() {
+        let host = cpal::host_from_id(host_id)?;
+        let default_name = host
+            .default_output_device()
+            .and_then(|device| device.name().ok());
+        for device in host.output_devices()? {
+            let Ok(name) = device.name() else {
+                continue;
+            };
+            let Ok(default_config) = device.default_output_config() else {
+                continue;
+            };
+            let channels = default_config.channels();
+            let sample_rates = device
+                .supported_output_configs()?
+                .filter(|range| range.channels() == channels)
+                .fold(None, |rates: Option<(u32, u32)>, range| {
+                    let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+                    Some(rates.map_or((min, max), |(low, high)| (low.min(min), high.max(max))))
+                })
+                .unwrap_or((
+                    default_config.sample_rate().0,
+                    default_config.sample_rate().0,
+                ));
+            devices.push(OutputDeviceInfo {
+                host: host_id.name().to_string(),
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                default_sample_rate: default_config.sample_rate().0,
+                channels,
+                sample_rates,
+                buffer_sizes: match default_config.buffer_size() {
+                    cpal::SupportedBufferSize::Range { min, max } => Some((*min, *max)),
+                    cpal::SupportedBufferSize::Unknown => None,
+                },
+            });
+        }
+    }
+    Ok(devices)
+}
+
+/// An output device picked by [`open_device`] and the stream settings to
+/// open it with.
+pub(super) struct OpenedDevice {
+    pub(super) host: cpal::HostId,
+    pub(super) device: cpal::Device,
+    pub(super) config: cpal::StreamConfig,
+    pub(super) info: AudioOutputInfo,
+}
+
+/// Finds the device `config` asks for and checks that it supports the
+/// requested sample rate and buffer size at its default channel count.
+pub(super) fn open_device(config: &AudioDeviceConfig) -> Result<OpenedDevice, anyhow::Error> {
+    let host = match &config.host {
+        Some(name) => {
+            let host_id = cpal::available_hosts()
+                .into_iter()
+                .find(|host_id| host_id.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| anyhow!("no audio host named {name:?}"))?;
+            cpal::host_from_id(host_id)?
+        }
+        None => cpal::default_host(),
+    };
+    let device = match &config.device {
+        Some(name) => host
+            .output_devices()?
+            .find(|device| device.name().is_ok_and(|device_name| device_name == *name))
+            .ok_or_else(|| anyhow!("no output device named {name:?}"))?,
+        None => host
+            .default_output_device()
+            .ok_or_else(|| anyhow!("no output device available"))?,
+    };
+    let name = device.name()?;
+    let default_config = device.default_output_config()?;
+    let mut stream_config = default_config.config();
+
+    if let Some(sample_rate) = config.sample_rate {
+        let supported = device.supported_output_configs()?.any(|range| {
+            range.channels() == stream_config.channels
+                && (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&sample_rate)
+        });
+        if !supported {
+            bail!(
+                "{name} does not support {sample_rate} Hz with {} channels",
+                stream_config.channels
+            );
+        }
+        stream_config.sample_rate = cpal::SampleRate(sample_rate);
+    }
+    if let Some(frames) = config.buffer_size {
+        if let cpal::SupportedBufferSize::Range { min, max } = default_config.buffer_size() {
+            if !(*min..=*max).contains(&frames) {
+                bail!("{name} supports buffers of {min} to {max} frames, not {frames}");
+            }
+        }
+        stream_config.buffer_size = cpal::BufferSize::Fixed(frames);
+    }
+
+    Ok(OpenedDevice {
+        host: host.id(),
+        info: AudioOutputInfo {
+            host: host.id().name().to_string(),
+            device: name,
+            sample_rate: stream_config.sample_rate.0,
+            channels: stream_config.channels as usize,
+            buffer_size: config.buffer_size,
+        },
+        device,
+        config: stream_config,
+    })
+}
@@ -1,12 +1,28 @@
 mod blight_audio;
+mod device;
+mod null_output;
 
+pub use device::{list_output_devices, AudioDeviceConfig, AudioOutputInfo, OutputDeviceInfo};
+use device::{open_device, OpenedDevice};
+pub use null_output::NullOutputConfig;
+
+use crate::AudioProcessor;
+use crate::CaptureReceiver;
 use crate::Command;
+use crate::DspLoadState;
+use crate::EventReceiver;
+use crate::InstalledInstruments;
 use crate::MeterState;
+use crate::ParameterBank;
+use crate::Recorder;
+use crate::VoiceActivityState;
+use null_output::NullOutput;
 use ringbuf::HeapProd;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use crate::EffectFactory;
-use crate::{InstrumentFactory, ResourceManager, VoiceFactory};
+use crate::{InstrumentFactory, Registry, ResourceManager, VoiceFactory};
 
 /// The public-facing API for the audio backend. Lives in the NRT (not real-time) world.
 pub struct BlightAudio {
@@ -14,6 +30,8 @@ pub struct BlightAudio {
     command_tx: HeapProd<Command>,
     /// Instrument factory for creating and managing instruments.
     instrument_factory: InstrumentFactory,
+    /// Builds instruments and effects from specs, for hydration and restores.
+    registry: Registry,
     /// Voice factory for creating and managing voices.
     voice_factory: VoiceFactory,
     /// Resource manager for audio samples and other resources.
@@ -22,6 +40,45 @@ pub struct BlightAudio {
     effect_factory: EffectFactory,
     /// Lock-free metering state written by the audio callback.
     meter: Arc<MeterState>,
-    /// The audio stream for real-time audio processing.
-    _stream: cpal::Stream,
+    /// Lock-free processing-time counters written by the engine every block.
+    dsp_load: Arc<DspLoadState>,
+    /// Lock-free list of sounding voices republished by the engine every block.
+    voice_activity: Arc<VoiceActivityState>,
+    /// Lock-free parameter slots polled by the audio callback once per block.
+    parameters: Arc<ParameterBank>,
+    /// Rejections and other outcomes reported by the engine.
+    engine_events: EventReceiver,
+    /// Captures filled by the engine in answer to `StateCmd::Capture`.
+    captures: CaptureReceiver,
+    /// Tag of the latest capture request; answers to older ones are dropped.
+    capture_request: u64,
+    /// Lock-free list of the instrument ids the engine has installed.
+    installed_instruments: Arc<InstalledInstruments>,
+    /// Processor driven by the stream callback; locked by the control thread
+    /// only while the stream is paused for a device switch.
+    audio_processor: Arc<Mutex<AudioProcessor>>,
+    /// The device stream or timer that drives the processor.
+    output: AudioOutput,
+    /// Host of the output device; `None` for the null output.
+    host: Option<cpal::HostId>,
+    /// What `output` is running with.
+    output_info: AudioOutputInfo,
+    /// Writes the output to WAV files on request.
+    recorder: Recorder,
+}
+
+/// A snapshot requested with [`BlightAudio::request_snapshot`] that the audio
+/// thread has not answered yet.
+pub struct PendingSnapshot {
+    /// Tag of the capture in flight.
+    request: u64,
+    deadline: Instant,
+}
+
+/// What drives the audio processor.
+enum AudioOutput {
+    /// A real-time callback of an output device.
+    Device(cpal::Stream),
+    /// A wall-clock timer, for hosts without an audio device.
+    Null(NullOutput),
 }
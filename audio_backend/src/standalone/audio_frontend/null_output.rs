@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use log::{error, info};
+
+use crate::{AudioProcessor, MissedBlocks};
+
+/// Settings for running the engine without an audio device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NullOutputConfig {
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Frames rendered per timer period.
+    pub block_size: usize,
+    /// Also write everything the engine outputs to this 32-bit float WAV.
+    pub render_to: Option<PathBuf>,
+}
+
+impl Default for NullOutputConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48_000,
+            channels: 2,
+            block_size: 512,
+            render_to: None,
+        }
+    }
+}
+
+/// Drives the processor from a wall-clock timer instead of a device callback,
+/// so hosts without audio output keep their transport, meters and OSC state
+/// advancing in real time.
+pub(crate) struct NullOutput {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl NullOutput {
+    pub(crate) fn start(
+        config: &NullOutputConfig,
+        audio_processor: Arc<Mutex<AudioProcessor>>,
+        missed_blocks: MissedBlocks,
+    ) -> Result<Self, anyhow::Error> {
+        let mut writer = config
+            .render_to
+            .as_deref()
+            .map(|path| create_wav(path, config))
+            .transpose()?;
+        let block_size = config.block_size;
+        let mut block = vec![0.0; block_size * config.channels];
+        let period = Duration::from_secs_f64(config.block_size as f64 / config.sample_rate as f64);
+        // Refresh the WAV header about once a second, so the file stays
+        // readable if the process is killed.
+        let flush_interval = (config.sample_rate as usize / config.block_size).max(1);
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = thread::Builder::new()
+            .name("blight-null-output".into())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    let mut deadline = Instant::now();
+                    let mut blocks = 0;
+                    while !stop.load(Ordering::Relaxed) {
+                        // Like the device callback, output silence rather than
+                        // wait for a device switch.
+                        match audio_processor.try_lock() {
+                            Ok(mut audio_processor) => audio_processor.process(&mut block),
+                            Err(_) => {
+                                block.fill(0.0);
+                                missed_blocks.count(block_size);
+                            }
+                        }
+                        blocks += 1;
+                        if let Some(wav) = &mut writer {
+                            let written = block
+                                .iter()
+                                .try_for_each(|&sample| wav.write_sample(sample))
+                                .and_then(|()| {
+                                    if blocks % flush_interval == 0 {
+                                        wav.flush()
+                                    } else {
+                                        Ok(())
+                                    }
+                                });
+                            if let Err(err) = written {
+                                error!("stopped writing the null audio output: {err}");
+                                writer = None;
+                            }
+                        }
+
+                        deadline += period;
+                        let now = Instant::now();
+                        if deadline > now {
+                            thread::sleep(deadline - now);
+                        } else {
+                            // Fall behind instead of catching up in a burst.
+                            deadline = now;
+                        }
+                    }
+                    if let Some(wav) = writer {
+                        if let Err(err) = wav.finalize() {
+                            error!("failed to finish the null audio output file: {err}");
+                        }
+                    }
+                }
+            })?;
+        info!(
+            "Null audio output at {} Hz, {} channels, {} frame blocks",
+            config.sample_rate, config.channels, config.block_size
+        );
+        Ok(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Stops the timer and finishes the WAV file, if any.
+    pub(crate) fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("null audio output thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for NullOutput {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn create_wav(
+    path: &Path,
+    config: &NullOutputConfig,
+) -> Result<hound::WavWriter<BufWriter<File>>, anyhow::Error> {
+    let spec = hound::WavSpec {
+        channels: config.channels as u16,
+        sample_rate: config.sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    hound::WavWriter::create(path, spec)
+        .with_context(|| format!("failed to create {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Command, MeterState, ParameterBank, Recorder};
+    use ringbuf::{storage::Heap, traits::Split, SharedRb};
+
+    #[test]
+    fn renders_in_real_time_to_a_valid_wav() {
+        let path = std::env::temp_dir().join(format!("blight-null-{}.wav", std::process::id()));
+        let config = NullOutputConfig {
+            render_to: Some(path.clone()),
+            ..NullOutputConfig::default()
+        };
+        let (_command_tx, command_rx) = SharedRb::<Heap<Command>>::new(8).split();
+        let meter = Arc::new(MeterState::new());
+        let audio_processor = AudioProcessor::new(
+            command_rx,
+            config.sample_rate as f32,
+            config.channels,
+            meter,
+            Arc::new(ParameterBank::default()),
+        );
+
+        let (recorder, _tap) = Recorder::spawn(8).unwrap();
+        let mut output = NullOutput::start(
+            &config,
+            Arc::new(Mutex::new(audio_processor)),
+            recorder.missed_blocks(),
+        )
+        .unwrap();
+        thread::sleep(Duration::from_millis(100));
+        output.stop();
+
+        let reader = hound::WavReader::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!((spec.channels, spec.sample_rate), (2, 48_000));
+        // About 4,800 frames, with room for a slow test machine.
+        let frames = reader.duration() as usize;
+        assert_eq!(frames % config.block_size, 0);
+        assert!((config.block_size..=48_000).contains(&frames), "{frames}");
+    }
+}
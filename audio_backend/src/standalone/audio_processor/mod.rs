@@ -1,24 +1,38 @@
 use ringbuf::traits::*;
 use ringbuf::HeapCons;
 
+use crate::CaptureSender;
 use crate::Command;
+use crate::DspLoadState;
+use crate::EventSender;
+use crate::InstalledInstruments;
+use crate::LoudnessMeter;
 use crate::MeterState;
+use crate::ParameterBank;
 use crate::Player;
+use crate::RecordTap;
+use crate::RetireSender;
+use crate::VoiceActivityState;
 use sequencer::models::Song;
 use std::sync::Arc;
 
-const MAX_BUFFER_SIZE: usize = 4096;
-
 pub struct AudioProcessor {
     pub(crate) command_rx: HeapCons<Command>,
     pub(crate) player: Player,
     pub(crate) sample_rate: f32,
     pub(crate) channels: usize,
-    // Pre-allocated, non-interleaved buffers for processing.
+    // Pre-allocated, non-interleaved buffers for processing, sized to the
+    // engine's maximum block.
     pub(crate) left_buf: Vec<f32>,
     pub(crate) right_buf: Vec<f32>,
     // Shared metering written once per block (read by the OSC server).
     pub(crate) meter: Arc<MeterState>,
+    // Loudness of the output, published through `meter` once per block.
+    pub(crate) loudness: LoudnessMeter,
+    // Coalesced control values polled once per block.
+    pub(crate) parameters: Arc<ParameterBank>,
+    // Copies the output to the recorder's writer thread while recording.
+    pub(crate) record_tap: Option<RecordTap>,
 }
 
 impl AudioProcessor {
@@ -28,15 +42,20 @@ impl AudioProcessor {
         sample_rate: f32,
         channels: usize,
         meter: Arc<MeterState>,
+        parameters: Arc<ParameterBank>,
     ) -> Self {
+        let player = Player::new(song, sample_rate as f64);
         Self {
             command_rx,
             sample_rate,
             channels,
-            left_buf: vec![0.0; MAX_BUFFER_SIZE],
-            right_buf: vec![0.0; MAX_BUFFER_SIZE],
+            left_buf: vec![0.0; player.max_block_size()],
+            right_buf: vec![0.0; player.max_block_size()],
             meter,
-            player: Player::new(song, sample_rate as f64),
+            loudness: LoudnessMeter::new(sample_rate as u32),
+            parameters,
+            record_tap: None,
+            player,
         }
     }
 
@@ -45,22 +64,62 @@ impl AudioProcessor {
         sample_rate: f32,
         channels: usize,
         meter: Arc<MeterState>,
+        parameters: Arc<ParameterBank>,
     ) -> Self {
         let default_song = Arc::new(sequencer::models::Song::new("Untitled"));
-        Self {
+        Self::new_with_song(
+            default_song,
             command_rx,
             sample_rate,
             channels,
-            left_buf: vec![0.0; MAX_BUFFER_SIZE],
-            right_buf: vec![0.0; MAX_BUFFER_SIZE],
             meter,
-            player: Player::new(default_song, sample_rate as f64),
-        }
+            parameters,
+        )
     }
 
-    /// The main processing function called by the audio driver.
-    pub fn process(&mut self, output_buffer: &mut [f32]) {
-        // 1. Drain the command queue to update state. This is non-blocking.
+    pub fn set_retire_sender(&mut self, sender: RetireSender) {
+        self.player.set_retire_sender(sender);
+    }
+
+    pub fn set_event_sender(&mut self, sender: EventSender) {
+        self.player.set_event_sender(sender);
+    }
+
+    pub fn set_capture_sender(&mut self, sender: CaptureSender) {
+        self.player.set_capture_sender(sender);
+    }
+
+    pub fn set_record_tap(&mut self, tap: RecordTap) {
+        self.record_tap = Some(tap);
+    }
+
+    pub fn installed_instruments(&self) -> Arc<InstalledInstruments> {
+        self.player.installed_instruments()
+    }
+
+    pub fn dsp_load_state(&self) -> Arc<DspLoadState> {
+        self.player.dsp_load_state()
+    }
+
+    pub fn voice_activity(&self) -> Arc<VoiceActivityState> {
+        self.player.voice_activity()
+    }
+
+    /// Reconfigures the processor for a new output stream, e.g. after a device
+    /// switch. Commands already queued are applied first so that instruments
+    /// built at the old rate are prepared too. Allocates; call it only while no
+    /// stream is driving [`Self::process`].
+    pub fn prepare(&mut self, sample_rate: f32, channels: usize, max_block_size: usize) {
+        self.drain_commands();
+        self.player.prepare(sample_rate as f64, max_block_size);
+        self.sample_rate = sample_rate;
+        self.channels = channels;
+        self.left_buf = vec![0.0; self.player.max_block_size()];
+        self.right_buf = vec![0.0; self.player.max_block_size()];
+        self.loudness = LoudnessMeter::new(sample_rate as u32);
+    }
+
+    fn drain_commands(&mut self) {
         while let Some(command) = self.command_rx.try_pop() {
             // For now route all to player; Engine/Mixer handled inside player.synthesizer
             self.player.handle_command(command);
@@ -68,6 +127,12 @@ impl AudioProcessor {
             // Here we need a way to select a self.synthesizer, from synth_infra/synthesizer.rs
             // for when we want to operate as an instrument and handle voice allocs through commands
         }
+    }
+
+    /// The main processing function called by the audio driver.
+    pub fn process(&mut self, output_buffer: &mut [f32]) {
+        // 1. Drain the command queue to update state. This is non-blocking.
+        self.drain_commands();
 
         if self.channels == 0 {
             // A CPAL stream always has at least one channel, but keep this method
@@ -76,10 +141,10 @@ impl AudioProcessor {
             return;
         }
 
-        // Scratch buffers in AudioProcessor and Voice are preallocated to this
-        // bound. A host callback may still provide more frames, so process it in
-        // bounded chunks instead of slicing past the buffers and panicking.
-        let samples_per_chunk = MAX_BUFFER_SIZE * self.channels;
+        // Scratch buffers are preallocated to the engine's maximum block. A host
+        // callback may still provide more frames, so process it in bounded
+        // chunks instead of slicing past the buffers and panicking.
+        let samples_per_chunk = self.left_buf.len() * self.channels;
         let complete_sample_count = (output_buffer.len() / self.channels) * self.channels;
         let (complete_frames, trailing_samples) = output_buffer.split_at_mut(complete_sample_count);
 
@@ -94,7 +159,7 @@ impl AudioProcessor {
 
     fn process_chunk(&mut self, output_buffer: &mut [f32]) {
         let frame_count = output_buffer.len() / self.channels;
-        debug_assert!(frame_count <= MAX_BUFFER_SIZE);
+        debug_assert!(frame_count <= self.left_buf.len());
 
         let (left, right) = (
             &mut self.left_buf[..frame_count],
@@ -104,12 +169,23 @@ impl AudioProcessor {
         left.fill(0.0);
         right.fill(0.0);
 
+        self.player
+            .apply_parameters(&self.parameters, frame_count, self.sample_rate);
+
         // Move the play-head by the frames in this bounded processing chunk.
         self.player
             .process(left, right, self.sample_rate, frame_count);
 
         // Record the post-master stereo chunk for meter streaming.
         self.meter.record_block(left, right);
+        if self.meter.take_loudness_reset() {
+            self.loudness.reset();
+        }
+        self.loudness.process(left, right);
+        self.meter.record_loudness(&self.loudness);
+        if let Some(record_tap) = &mut self.record_tap {
+            record_tap.record(left, right);
+        }
 
         // Re-interleave stereo output. Additional host channels are explicitly
         // silenced until the engine has a channel-layout/routing contract.
@@ -131,15 +207,21 @@ mod tests {
     fn processor(channels: usize) -> AudioProcessor {
         let rb = SharedRb::<Heap<Command>>::new(8);
         let (_command_tx, command_rx) = rb.split();
-        AudioProcessor::new(command_rx, 44_100.0, channels, Arc::new(MeterState::new()))
+        AudioProcessor::new(
+            command_rx,
+            44_100.0,
+            channels,
+            Arc::new(MeterState::new()),
+            Arc::new(ParameterBank::default()),
+        )
     }
 
     #[test]
     fn process_chunks_host_buffers_larger_than_internal_scratch_space() {
         let channels = 2;
-        let frame_count = MAX_BUFFER_SIZE * 2 + 17;
-        let mut output = vec![1.0; frame_count * channels];
         let mut processor = processor(channels);
+        let frame_count = processor.left_buf.len() * 2 + 17;
+        let mut output = vec![1.0; frame_count * channels];
 
         processor.process(&mut output);
 
@@ -148,6 +230,19 @@ mod tests {
         assert!(output.iter().all(|sample| *sample == 0.0));
     }
 
+    #[test]
+    fn prepare_resizes_buffers_for_the_new_stream() {
+        let mut processor = processor(2);
+
+        processor.prepare(96_000.0, 1, 128);
+
+        assert_eq!(processor.sample_rate, 96_000.0);
+        assert_eq!(processor.channels, 1);
+        assert_eq!(processor.left_buf.len(), 128);
+        assert_eq!(processor.right_buf.len(), 128);
+        assert_eq!(processor.player.max_block_size(), 128);
+    }
+
     #[test]
     fn process_silences_extra_channels_and_incomplete_frames() {
         let channels = 3;
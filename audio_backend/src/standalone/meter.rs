@@ -5,13 +5,18 @@
 //! once per processed block with the final post-master stereo signal. The OSC
 //! server is the sole reader; on its meter timer it calls
 //! [`MeterState::take_levels`] and streams the result as `/meter/level`.
+//! Loudness follows the same pattern: the callback runs a [`LoudnessMeter`]
+//! and publishes its readings with [`MeterState::record_loudness`], and the
+//! server streams [`MeterState::loudness`] as `/meter/loudness`.
 //!
 //! Values are stored as the IEEE-754 bit patterns of *non-negative* `f32`s
 //! (peak amplitude and mean-square). For non-negative floats the bit pattern
 //! is monotonic with the value, so peak-hold is implemented with a plain
 //! `fetch_max` on the underlying `AtomicU32`.
 
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::LoudnessMeter;
 
 /// Linear peak + RMS levels for one read window, per channel.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -36,6 +41,35 @@ impl MeterLevels {
     };
 }
 
+/// Latest EBU R128 readings of the output. Loudness is `None` until enough
+/// signal has been measured to fill its window or pass its gates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoudnessLevels {
+    /// Loudness of the latest 400 ms, LUFS.
+    pub momentary: Option<f32>,
+    /// Loudness of the latest 3 s, LUFS.
+    pub short_term: Option<f32>,
+    /// Gated loudness since the last reset, LUFS.
+    pub integrated: Option<f32>,
+    /// Loudness range since the last reset, LU.
+    pub loudness_range: f32,
+    /// Highest oversampled peak since the last reset, linear amplitude.
+    pub true_peak_left: f32,
+    pub true_peak_right: f32,
+}
+
+impl LoudnessLevels {
+    /// Nothing measured yet.
+    pub const UNMEASURED: LoudnessLevels = LoudnessLevels {
+        momentary: None,
+        short_term: None,
+        integrated: None,
+        loudness_range: 0.0,
+        true_peak_left: 0.0,
+        true_peak_right: 0.0,
+    };
+}
+
 /// Realtime-safe shared metering state.
 ///
 /// Cheap to clone behind an `Arc`; both the audio callback and the OSC server
@@ -48,6 +82,16 @@ pub struct MeterState {
     /// Mean-square (linear) of the most recently recorded block.
     mean_sq_left: AtomicU32,
     mean_sq_right: AtomicU32,
+    /// [`LoudnessLevels`] as `f32` bits; readings not yet available are
+    /// stored as NaN.
+    momentary: AtomicU32,
+    short_term: AtomicU32,
+    integrated: AtomicU32,
+    loudness_range: AtomicU32,
+    true_peak_left: AtomicU32,
+    true_peak_right: AtomicU32,
+    /// Set by the reader to ask the callback to restart the loudness meter.
+    loudness_reset: AtomicBool,
 }
 
 impl Default for MeterState {
@@ -63,6 +107,13 @@ impl MeterState {
             peak_right: AtomicU32::new(0),
             mean_sq_left: AtomicU32::new(0),
             mean_sq_right: AtomicU32::new(0),
+            momentary: AtomicU32::new(f32::NAN.to_bits()),
+            short_term: AtomicU32::new(f32::NAN.to_bits()),
+            integrated: AtomicU32::new(f32::NAN.to_bits()),
+            loudness_range: AtomicU32::new(0),
+            true_peak_left: AtomicU32::new(0),
+            true_peak_right: AtomicU32::new(0),
+            loudness_reset: AtomicBool::new(false),
         }
     }
 
@@ -103,6 +154,51 @@ impl MeterState {
     }
 }
 
+impl MeterState {
+    /// Publish the latest readings of the callback's loudness meter.
+    /// Realtime-safe.
+    pub fn record_loudness(&self, meter: &LoudnessMeter) {
+        let store = |atomic: &AtomicU32, value: Option<f32>| {
+            atomic.store(value.unwrap_or(f32::NAN).to_bits(), Ordering::Relaxed);
+        };
+        let [true_peak_left, true_peak_right] = meter.true_peak();
+        store(&self.momentary, meter.momentary());
+        store(&self.short_term, meter.short_term());
+        store(&self.integrated, meter.integrated());
+        store(&self.loudness_range, Some(meter.loudness_range()));
+        store(&self.true_peak_left, Some(true_peak_left));
+        store(&self.true_peak_right, Some(true_peak_right));
+    }
+
+    /// Read the latest loudness readings without resetting them.
+    pub fn loudness(&self) -> LoudnessLevels {
+        let load = |atomic: &AtomicU32| {
+            let value = f32::from_bits(atomic.load(Ordering::Relaxed));
+            (!value.is_nan()).then_some(value)
+        };
+        LoudnessLevels {
+            momentary: load(&self.momentary),
+            short_term: load(&self.short_term),
+            integrated: load(&self.integrated),
+            loudness_range: load(&self.loudness_range).unwrap_or(0.0),
+            true_peak_left: load(&self.true_peak_left).unwrap_or(0.0),
+            true_peak_right: load(&self.true_peak_right).unwrap_or(0.0),
+        }
+    }
+
+    /// Ask the audio callback to restart integrated loudness, loudness range
+    /// and true peak from its next block.
+    pub fn request_loudness_reset(&self) {
+        self.loudness_reset.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether a reset was requested since the last call. Called by
+    /// the audio callback.
+    pub fn take_loudness_reset(&self) -> bool {
+        self.loudness_reset.swap(false, Ordering::Relaxed)
+    }
+}
+
 /// Returns `(peak, mean_square)` for a block. Both are non-negative.
 /// Non-finite (NaN/inf) samples are fully ignored: they never update the peak
 /// and are excluded from *both* the sum of squares and the divisor, so they do
@@ -186,6 +282,29 @@ mod tests {
         approx(levels.rms_left, 0.5);
     }
 
+    #[test]
+    fn loudness_readings_round_trip_and_reset_is_taken_once() {
+        let meter = MeterState::new();
+        assert_eq!(meter.loudness(), LoudnessLevels::UNMEASURED);
+
+        let signal = (0..48_000)
+            .map(|n| if n % 2 == 0 { 0.5 } else { -0.5 })
+            .collect::<Vec<f32>>();
+        let mut loudness = LoudnessMeter::new(48_000);
+        loudness.process(&signal, &signal);
+        meter.record_loudness(&loudness);
+        let levels = meter.loudness();
+        assert_eq!(levels.momentary, loudness.momentary());
+        assert!(levels.momentary.is_some());
+        assert_eq!(levels.short_term, None);
+        assert_eq!(levels.true_peak_left, loudness.true_peak()[0]);
+
+        assert!(!meter.take_loudness_reset());
+        meter.request_loudness_reset();
+        assert!(meter.take_loudness_reset());
+        assert!(!meter.take_loudness_reset());
+    }
+
     #[test]
     fn empty_block_is_safe() {
         let meter = MeterState::new();
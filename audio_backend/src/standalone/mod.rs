@@ -2,8 +2,10 @@ mod audio_frontend;
 mod audio_processor;
 mod meter;
 mod osc;
+mod recorder;
 
 pub use audio_frontend::*;
 pub(crate) use audio_processor::*;
 pub use meter::*;
 pub use osc::*;
+pub use recorder::*;
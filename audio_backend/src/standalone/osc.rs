@@ -1,13 +1,17 @@
 use anyhow::{Context, Result};
-use rosc::{decoder, encoder, OscMessage, OscPacket, OscType};
+use rosc::{decoder, encoder, OscBundle, OscMessage, OscPacket, OscTime, OscType};
+use std::collections::VecDeque;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
 use std::time::Duration;
 use tokio::net::UdpSocket;
 
 use crate::{
-    id::EffectId, load_song_file_into_audio, BlightAudio, Command, MeterLevels, MeterState,
-    MixerCmd, TransportCmd,
+    builtin_parameter_id, find_parameter, id::EffectId, load_song_file_into_audio,
+    load_sound_state, save_sound_state, BlightAudio, Command, DspLoad, DspLoadReport,
+    EffectFactory, EffectType, EnvelopeState, LoudnessLevels, MeterLevels, MeterState, MixerCmd,
+    ParamDescriptor, ParamOwner, ParamTarget, PendingSnapshot, RecordingStatus, TransportCmd,
+    VoiceSnapshot, DEFAULT_PARAMETER_SMOOTHING_SECONDS,
 };
 
 pub const OSC_LISTEN_ADDR: &str = "127.0.0.1:9000";
@@ -15,39 +19,143 @@ pub const OSC_SEND_ADDR: &str = "127.0.0.1:9001";
 
 /// Reserved master gain effect used by the standalone OSC bridge.
 ///
-/// `/param/set gain <0..1>` carries a *normalized* control value (linear
-/// amplitude, the VST/AU parameter convention). The core maps it to the dB
-/// the master `Gain` effect expects via [`normalized_gain_to_db`] and emits
-/// `MixerCmd::SetMasterEffectParameter`. The standalone binary is responsible
-/// for installing this effect during startup.
+/// `/param/set <id> <0..1>` carries a *normalized* control value (the VST/AU
+/// parameter convention) for one of the master `Gain` effect's parameters. The
+/// core looks the id up in the effect's [`ParamDescriptor`]s, denormalizes it
+/// (for `gain`, linear amplitude to dB) and writes it to the parameter bank slot
+/// bound by [`master_parameter_bindings`], so rapid knob moves coalesce instead
+/// of filling the command queue. The standalone binary is responsible for
+/// installing this effect and its bindings during startup.
 pub const MASTER_GAIN_EFFECT_ID: EffectId = 0;
 pub const MASTER_GAIN_PARAM_INDEX: u32 = 0;
+/// Parameter bank slot of the first master effect parameter; the others follow
+/// in descriptor index order.
+pub const MASTER_PARAMETER_SLOT_BASE: usize = 0;
 
 /// Target meter streaming rate (`/meter/level`) in Hz.
 pub const METER_RATE_HZ: u32 = 30;
 /// Interval between `/meter/level` messages, derived from [`METER_RATE_HZ`].
 const METER_INTERVAL: Duration = Duration::from_micros(1_000_000 / METER_RATE_HZ as u64);
+/// Loudness streaming rate (`/meter/loudness`) in Hz, matching the 100 ms
+/// update interval of the loudness readings.
+pub const LOUDNESS_RATE_HZ: u32 = 10;
+const LOUDNESS_INTERVAL: Duration = Duration::from_micros(1_000_000 / LOUDNESS_RATE_HZ as u64);
+/// DSP load streaming rate (`/dsp/load` bundles) in Hz. Load is averaged over
+/// each window, so a slow rate still catches sustained overload; peaks are held.
+pub const DSP_LOAD_RATE_HZ: u32 = 4;
+const DSP_LOAD_INTERVAL: Duration = Duration::from_micros(1_000_000 / DSP_LOAD_RATE_HZ as u64);
+/// Voice activity streaming rate (`/voices` bundles) in Hz.
+pub const VOICE_ACTIVITY_RATE_HZ: u32 = 20;
+const VOICE_ACTIVITY_INTERVAL: Duration =
+    Duration::from_micros(1_000_000 / VOICE_ACTIVITY_RATE_HZ as u64);
+/// Recording progress streaming rate (`/record/status`) in Hz.
+pub const RECORD_STATUS_RATE_HZ: u32 = 2;
+const RECORD_STATUS_INTERVAL: Duration =
+    Duration::from_micros(1_000_000 / RECORD_STATUS_RATE_HZ as u64);
+/// How often a `/state/save` waiting for the audio thread checks for its capture.
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(5);
 /// Level reported for silence / non-finite values, in dBFS.
 const METER_FLOOR_DB: f32 = -120.0;
-/// dB floor for the normalized master gain mapping (effectively mute).
-const GAIN_FLOOR_DB: f32 = -120.0;
 
 pub struct OscServer {
     socket: UdpSocket,
     send_addr: SocketAddr,
 }
 
+/// A plain parameter value destined for a parameter bank slot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ParamWrite {
+    slot: usize,
+    value: f32,
+}
+
+/// A sound state file to write from, or restore into, the running engine.
+#[derive(Debug, Clone, PartialEq)]
+enum StateRequest {
+    Save(PathBuf),
+    Load(PathBuf),
+}
+
+/// Starts recording the master output to a WAV file, or stops it.
+#[derive(Debug, Clone, PartialEq)]
+enum RecordRequest {
+    Start(PathBuf),
+    Stop,
+}
+
+/// Sound state requests in arrival order. A save waits for the audio thread
+/// to capture the engine without blocking the OSC loop; requests behind it
+/// wait too, so a load never lands before an earlier save is captured.
+#[derive(Default)]
+struct StateRequests {
+    queued: VecDeque<StateRequest>,
+    saving: Option<(PathBuf, PendingSnapshot)>,
+}
+
+impl StateRequests {
+    fn is_idle(&self) -> bool {
+        self.saving.is_none() && self.queued.is_empty()
+    }
+
+    /// Queues `requests` and runs as many as can finish without waiting.
+    fn push(
+        &mut self,
+        audio: &mut BlightAudio,
+        requests: Vec<StateRequest>,
+        responses: &mut Vec<OscPacket>,
+    ) {
+        self.queued.extend(requests);
+        self.poll(audio, responses);
+    }
+
+    /// Finishes the save in flight once its capture arrives, then runs the
+    /// queued requests up to the next save.
+    fn poll(&mut self, audio: &mut BlightAudio, responses: &mut Vec<OscPacket>) {
+        loop {
+            if let Some((path, pending)) = &mut self.saving {
+                let result = match audio.poll_snapshot(pending) {
+                    Ok(None) => return,
+                    Ok(Some(snapshot)) => save_sound_state(path, &snapshot),
+                    Err(err) => Err(err),
+                };
+                responses.push(state_response(path, "/state/saved", result));
+                self.saving = None;
+            }
+            match self.queued.pop_front() {
+                None => return,
+                Some(StateRequest::Save(path)) => match audio.request_snapshot() {
+                    Ok(pending) => self.saving = Some((path, pending)),
+                    Err(err) => responses.push(state_response(&path, "/state/saved", Err(err))),
+                },
+                Some(StateRequest::Load(path)) => {
+                    let result = load_sound_state(&path, audio.registry())
+                        .and_then(|snapshot| audio.restore(&snapshot));
+                    responses.push(state_response(&path, "/state/loaded", result));
+                }
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 struct OscDispatch {
     commands: Vec<Command>,
+    param_writes: Vec<ParamWrite>,
     song_loads: Vec<PathBuf>,
+    state_requests: Vec<StateRequest>,
+    record_requests: Vec<RecordRequest>,
+    loudness_reset: bool,
     responses: Vec<OscPacket>,
 }
 
 impl OscDispatch {
     fn append(&mut self, mut other: Self) {
         self.commands.append(&mut other.commands);
+        self.param_writes.append(&mut other.param_writes);
         self.song_loads.append(&mut other.song_loads);
+        self.state_requests.append(&mut other.state_requests);
+        self.record_requests.append(&mut other.record_requests);
+        self.loudness_reset |= other.loudness_reset;
         self.responses.append(&mut other.responses);
     }
 }
@@ -79,18 +187,45 @@ impl OscServer {
         Ok(Self { socket, send_addr })
     }
 
+    /// The address the server receives on; useful when bound to port 0.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// The address responses and meter streams are sent to.
+    pub fn send_addr(&self) -> SocketAddr {
+        self.send_addr
+    }
+
     pub async fn run(&self, audio: &mut BlightAudio) -> Result<()> {
         let meter = audio.meter_state();
         self.run_with_meter(audio, &meter).await
     }
 
     /// Runs the OSC receive loop alongside `/meter/level` streaming at
-    /// [`METER_RATE_HZ`]. Incoming packets are translated into engine commands
-    /// while the meter timer reads the shared [`MeterState`] and emits levels.
+    /// [`METER_RATE_HZ`], `/meter/loudness` streaming at [`LOUDNESS_RATE_HZ`],
+    /// `/dsp/load` streaming at [`DSP_LOAD_RATE_HZ`] and
+    /// `/voices` streaming at [`VOICE_ACTIVITY_RATE_HZ`]. Incoming packets are
+    /// translated into engine commands while the timers read the shared
+    /// [`MeterState`], DSP load counters and voice snapshot.
     pub async fn run_with_meter(&self, audio: &mut BlightAudio, meter: &MeterState) -> Result<()> {
         let mut buf = [0_u8; decoder::MTU];
         let mut meter_timer = tokio::time::interval(METER_INTERVAL);
         meter_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut loudness_timer = tokio::time::interval(LOUDNESS_INTERVAL);
+        loudness_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let dsp_load = audio.dsp_load_state();
+        let mut dsp_load_timer = tokio::time::interval(DSP_LOAD_INTERVAL);
+        dsp_load_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let voice_activity = audio.voice_activity();
+        let mut voice_activity_timer = tokio::time::interval(VOICE_ACTIVITY_INTERVAL);
+        voice_activity_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut voices_were_active = true;
+        let mut record_status_timer = tokio::time::interval(RECORD_STATUS_INTERVAL);
+        record_status_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut state_requests = StateRequests::default();
+        let mut state_timer = tokio::time::interval(STATE_POLL_INTERVAL);
+        state_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
         loop {
             tokio::select! {
@@ -106,19 +241,63 @@ impl OscServer {
                         }
                     };
 
-                    self.apply_dispatch(audio, dispatch_packet(packet)).await?;
+                    let dispatch = dispatch_packet(packet);
+                    if dispatch.loudness_reset {
+                        meter.request_loudness_reset();
+                    }
+                    self.apply_dispatch(audio, &mut state_requests, dispatch).await?;
                 }
                 _ = meter_timer.tick() => {
                     let levels = meter.take_levels();
                     self.send_packet(&meter_level(&levels)).await?;
                 }
+                _ = loudness_timer.tick() => {
+                    self.send_packet(&meter_loudness(&meter.loudness())).await?;
+                }
+                _ = dsp_load_timer.tick() => {
+                    let report = dsp_load.take_report();
+                    // Nothing was rendered (e.g. transport stopped); stay quiet.
+                    if report.blocks > 0 {
+                        self.send_packet(&dsp_load_bundle(&report)).await?;
+                    }
+                }
+                _ = voice_activity_timer.tick() => {
+                    let snapshot = voice_activity.snapshot();
+                    // Send one empty bundle so clients clear, then stay quiet.
+                    if snapshot.active > 0 || voices_were_active {
+                        self.send_packet(&voice_activity_bundle(&snapshot)).await?;
+                    }
+                    voices_were_active = snapshot.active > 0;
+                }
+                _ = record_status_timer.tick() => {
+                    let status = audio.recording_status();
+                    if status.recording {
+                        self.send_packet(&record_status(&status)).await?;
+                    }
+                    if let Some(failure) = audio.take_recording_failure() {
+                        self.send_packet(&record_error(&failure.to_string())).await?;
+                    }
+                }
+                _ = state_timer.tick(), if !state_requests.is_idle() => {
+                    let mut responses = Vec::new();
+                    state_requests.poll(audio, &mut responses);
+                    for response in responses {
+                        self.send_packet(&response).await?;
+                    }
+                }
             }
         }
     }
 
-    /// Applies a decoded dispatch: runs song loads, forwards commands to the
-    /// audio thread, and sends any OSC responses.
-    async fn apply_dispatch(&self, audio: &mut BlightAudio, dispatch: OscDispatch) -> Result<()> {
+    /// Applies a decoded dispatch: runs song loads, queues sound state saves
+    /// and loads, runs recording requests, forwards commands to the audio thread, and sends any OSC
+    /// responses.
+    async fn apply_dispatch(
+        &self,
+        audio: &mut BlightAudio,
+        state_requests: &mut StateRequests,
+        dispatch: OscDispatch,
+    ) -> Result<()> {
         let mut responses = dispatch.responses;
 
         for path in dispatch.song_loads {
@@ -134,6 +313,19 @@ impl OscServer {
             }
         }
 
+        state_requests.push(audio, dispatch.state_requests, &mut responses);
+
+        for request in dispatch.record_requests {
+            responses.push(apply_record_request(audio, request));
+        }
+
+        let parameters = audio.parameter_bank();
+        for ParamWrite { slot, value } in dispatch.param_writes {
+            if !parameters.set(slot, value) {
+                log::warn!("parameter slot {slot} is outside the parameter bank");
+            }
+        }
+
         for command in dispatch.commands {
             log::debug!("dispatching OSC-derived command");
             audio.send_command(command);
@@ -173,12 +365,33 @@ fn dispatch_packet(packet: OscPacket) -> OscDispatch {
 fn handle_message(message: OscMessage) -> OscDispatch {
     match message.addr.as_str() {
         "/param/set" => handle_param_set(message),
+        "/param/bind" => handle_param_bind(message),
+        "/param/slot" => handle_param_slot(message),
         "/song/load" => handle_song_load(message),
+        "/state/save" => handle_state_request(message, StateRequest::Save),
+        "/state/load" => handle_state_request(message, StateRequest::Load),
+        "/record/start" => handle_record_start(message),
+        "/record/stop" => {
+            log::info!("OSC /record/stop");
+            OscDispatch {
+                commands: Vec::new(),
+                param_writes: Vec::new(),
+                song_loads: Vec::new(),
+                state_requests: Vec::new(),
+                record_requests: vec![RecordRequest::Stop],
+                loudness_reset: false,
+                responses: Vec::new(),
+            }
+        }
         "/transport/play" => {
             log::info!("OSC /transport/play -> TransportCmd::PlayLastSong");
             OscDispatch {
                 commands: vec![TransportCmd::PlayLastSong.into()],
+                param_writes: Vec::new(),
                 song_loads: Vec::new(),
+                state_requests: Vec::new(),
+                record_requests: Vec::new(),
+                loudness_reset: false,
                 responses: Vec::new(),
             }
         }
@@ -186,7 +399,23 @@ fn handle_message(message: OscMessage) -> OscDispatch {
             log::info!("OSC /transport/stop -> TransportCmd::StopSong");
             OscDispatch {
                 commands: vec![TransportCmd::StopSong.into()],
+                param_writes: Vec::new(),
                 song_loads: Vec::new(),
+                state_requests: Vec::new(),
+                record_requests: Vec::new(),
+                loudness_reset: false,
+                responses: Vec::new(),
+            }
+        }
+        "/meter/loudness/reset" => {
+            log::info!("OSC /meter/loudness/reset");
+            OscDispatch {
+                commands: Vec::new(),
+                param_writes: Vec::new(),
+                song_loads: Vec::new(),
+                state_requests: Vec::new(),
+                record_requests: Vec::new(),
+                loudness_reset: true,
                 responses: Vec::new(),
             }
         }
@@ -206,11 +435,117 @@ fn handle_song_load(message: OscMessage) -> OscDispatch {
     log::info!("OSC /song/load {path}");
     OscDispatch {
         commands: Vec::new(),
+        param_writes: Vec::new(),
         song_loads: vec![PathBuf::from(path)],
+        state_requests: Vec::new(),
+        record_requests: Vec::new(),
+        loudness_reset: false,
+        responses: Vec::new(),
+    }
+}
+
+fn handle_state_request(message: OscMessage, request: fn(PathBuf) -> StateRequest) -> OscDispatch {
+    let [OscType::String(path)] = message.args.as_slice() else {
+        log::warn!("invalid {} args; expected [string path]", message.addr);
+        return OscDispatch::default();
+    };
+
+    log::info!("OSC {} {path}", message.addr);
+    OscDispatch {
+        commands: Vec::new(),
+        param_writes: Vec::new(),
+        song_loads: Vec::new(),
+        state_requests: vec![request(PathBuf::from(path))],
+        record_requests: Vec::new(),
+        loudness_reset: false,
+        responses: Vec::new(),
+    }
+}
+
+fn handle_record_start(message: OscMessage) -> OscDispatch {
+    let [OscType::String(path)] = message.args.as_slice() else {
+        log::warn!("invalid /record/start args; expected [string path]");
+        return OscDispatch::default();
+    };
+
+    log::info!("OSC /record/start {path}");
+    OscDispatch {
+        commands: Vec::new(),
+        param_writes: Vec::new(),
+        song_loads: Vec::new(),
+        state_requests: Vec::new(),
+        record_requests: vec![RecordRequest::Start(PathBuf::from(path))],
+        loudness_reset: false,
         responses: Vec::new(),
     }
 }
 
+fn apply_record_request(audio: &mut BlightAudio, request: RecordRequest) -> OscPacket {
+    let result = match request {
+        RecordRequest::Start(path) => audio.start_recording(&path).map(|()| {
+            OscPacket::Message(OscMessage {
+                addr: "/record/started".to_string(),
+                args: vec![
+                    OscType::String(path.display().to_string()),
+                    OscType::Int(osc_int(audio.output_info().sample_rate.into())),
+                ],
+            })
+        }),
+        RecordRequest::Stop => audio.stop_recording().map(|report| {
+            OscPacket::Message(OscMessage {
+                addr: "/record/stopped".to_string(),
+                args: vec![
+                    OscType::String(report.path.display().to_string()),
+                    OscType::Int(osc_int(report.frames)),
+                    OscType::Int(osc_int(report.dropped_frames)),
+                ],
+            })
+        }),
+    };
+    result.unwrap_or_else(|err| {
+        log::error!("record request failed: {err:#}");
+        record_error(&format!("{err:#}"))
+    })
+}
+
+/// Builds a `/record/error` message: `[error]`.
+fn record_error(error: &str) -> OscPacket {
+    OscPacket::Message(OscMessage {
+        addr: "/record/error".to_string(),
+        args: vec![OscType::String(error.to_string())],
+    })
+}
+
+/// Builds a `/record/status` message: `[frames, dropped_frames]`.
+fn record_status(status: &RecordingStatus) -> OscPacket {
+    OscPacket::Message(OscMessage {
+        addr: "/record/status".to_string(),
+        args: vec![
+            OscType::Int(osc_int(status.frames)),
+            OscType::Int(osc_int(status.dropped_frames)),
+        ],
+    })
+}
+
+/// Saturates a count to an OSC `int`; frame counts pass `i32::MAX` after
+/// about 12 hours at 48 kHz.
+fn osc_int(count: u64) -> i32 {
+    i32::try_from(count).unwrap_or(i32::MAX)
+}
+
+fn state_response(path: &std::path::Path, done_addr: &str, result: Result<()>) -> OscPacket {
+    match result {
+        Ok(()) => {
+            log::info!("{done_addr} {}", path.display());
+            path_message(done_addr, path, None)
+        }
+        Err(err) => {
+            log::error!("sound state request for {} failed: {err:?}", path.display());
+            path_message("/state/error", path, Some(&format!("{err:#}")))
+        }
+    }
+}
+
 fn handle_param_set(message: OscMessage) -> OscDispatch {
     let [OscType::String(param_id), value] = message.args.as_slice() else {
         log::warn!("invalid /param/set args; expected [string, float or int]");
@@ -226,43 +561,139 @@ fn handle_param_set(message: OscMessage) -> OscDispatch {
         }
     };
 
-    match param_id.as_str() {
-        // Wire format is a normalized 0..1 control value (linear amplitude);
-        // map it to the dB the master Gain effect expects.
-        "gain" => {
-            let normalized = value.clamp(0.0, 1.0);
-            let db = normalized_gain_to_db(normalized);
-            log::info!(
-                "OSC /param/set gain {normalized} (norm) -> {db} dB -> MixerCmd::SetMasterEffectParameter"
-            );
-            OscDispatch {
-                commands: vec![MixerCmd::SetMasterEffectParameter {
-                    effect_id: MASTER_GAIN_EFFECT_ID,
-                    param_index: MASTER_GAIN_PARAM_INDEX,
-                    value: db,
-                }
-                .into()],
-                song_loads: Vec::new(),
-                // Echo the normalized value the core accepted (clamped).
-                responses: vec![param_echo(param_id, normalized)],
-            }
-        }
-        unknown => {
-            log::warn!("unknown parameter id: {unknown}");
-            OscDispatch::default()
+    let Some(descriptor) = find_parameter(master_parameters(), param_id) else {
+        log::warn!("unknown parameter id: {param_id}");
+        return OscDispatch::default();
+    };
+
+    // Wire format is a normalized 0..1 control value; the descriptor maps it to
+    // the plain value the effect expects.
+    let normalized = value.clamp(0.0, 1.0);
+    let plain = descriptor.denormalize(normalized);
+    let slot = master_parameter_slot(descriptor);
+    log::debug!(
+        "OSC /param/set {param_id} {normalized} (norm) -> {plain} {} -> parameter slot {slot}",
+        descriptor.unit.suffix()
+    );
+    OscDispatch {
+        commands: Vec::new(),
+        param_writes: vec![ParamWrite { slot, value: plain }],
+        song_loads: Vec::new(),
+        state_requests: Vec::new(),
+        record_requests: Vec::new(),
+        loudness_reset: false,
+        // Echo the normalized value the core accepted (clamped).
+        responses: vec![param_echo(param_id, normalized)],
+    }
+}
+
+fn handle_param_bind(message: OscMessage) -> OscDispatch {
+    let [OscType::Int(slot), OscType::String(target), OscType::String(param_id)] =
+        message.args.as_slice()
+    else {
+        log::warn!("invalid /param/bind args; expected [int, string, string]");
+        return OscDispatch::default();
+    };
+    let Ok(slot) = usize::try_from(*slot) else {
+        log::warn!("invalid /param/bind slot {slot}");
+        return OscDispatch::default();
+    };
+    let Some(owner) = parse_param_owner(target) else {
+        log::warn!(
+            "invalid /param/bind target {target}; expected master/<effect>, \
+             instrument/<instrument> or instrument/<instrument>/effect/<effect>"
+        );
+        return OscDispatch::default();
+    };
+    let Some(param_id) = builtin_parameter_id(param_id) else {
+        log::warn!("unknown parameter id: {param_id}");
+        return OscDispatch::default();
+    };
+
+    log::debug!("OSC /param/bind {slot} {target} {param_id}");
+    OscDispatch {
+        commands: vec![MixerCmd::BindParameterById {
+            slot,
+            owner,
+            param_id,
+            smoothing_seconds: DEFAULT_PARAMETER_SMOOTHING_SECONDS,
         }
+        .into()],
+        param_writes: Vec::new(),
+        song_loads: Vec::new(),
+        state_requests: Vec::new(),
+        record_requests: Vec::new(),
+        loudness_reset: false,
+        responses: Vec::new(),
     }
 }
 
-/// Maps a normalized gain control value (`0..1`, linear amplitude) to dB for
-/// the master `Gain` effect. `1.0` is unity (`0 dB`), `0.0` (and any value at
-/// or below it) is silence, floored at [`GAIN_FLOOR_DB`]. Values are clamped to
-/// `0..1` by the caller.
-fn normalized_gain_to_db(value: f32) -> f32 {
-    if value <= 0.0 {
-        return GAIN_FLOOR_DB;
+/// Parses a `/param/bind` target such as `instrument/2/effect/1`.
+fn parse_param_owner(target: &str) -> Option<ParamOwner> {
+    let parts: Vec<&str> = target.split('/').collect();
+    Some(match parts.as_slice() {
+        ["master", effect_id] => ParamOwner::MasterEffect {
+            effect_id: effect_id.parse().ok()?,
+        },
+        ["instrument", instrument_id] => ParamOwner::Instrument {
+            instrument_id: instrument_id.parse().ok()?,
+        },
+        ["instrument", instrument_id, "effect", effect_id] => ParamOwner::InstrumentEffect {
+            instrument_id: instrument_id.parse().ok()?,
+            effect_id: effect_id.parse().ok()?,
+        },
+        _ => return None,
+    })
+}
+
+fn handle_param_slot(message: OscMessage) -> OscDispatch {
+    let (slot, value) = match message.args.as_slice() {
+        [OscType::Int(slot), OscType::Float(value)] => (*slot, *value),
+        [OscType::Int(slot), OscType::Int(value)] => (*slot, *value as f32),
+        _ => {
+            log::warn!("invalid /param/slot args; expected [int, float or int]");
+            return OscDispatch::default();
+        }
+    };
+    let Ok(slot) = usize::try_from(slot) else {
+        log::warn!("invalid /param/slot slot {slot}");
+        return OscDispatch::default();
+    };
+
+    OscDispatch {
+        commands: Vec::new(),
+        param_writes: vec![ParamWrite { slot, value }],
+        song_loads: Vec::new(),
+        state_requests: Vec::new(),
+        record_requests: Vec::new(),
+        loudness_reset: false,
+        responses: Vec::new(),
     }
-    (20.0 * value.log10()).max(GAIN_FLOOR_DB)
+}
+
+/// Parameters of the reserved master effect addressed by `/param/set`.
+fn master_parameters() -> &'static [ParamDescriptor] {
+    EffectFactory::parameters(EffectType::Gain)
+}
+
+fn master_parameter_slot(descriptor: &ParamDescriptor) -> usize {
+    MASTER_PARAMETER_SLOT_BASE + descriptor.index as usize
+}
+
+/// Commands binding every master effect parameter to its parameter bank slot.
+/// Send them once the master effect is installed.
+pub fn master_parameter_bindings() -> Vec<MixerCmd> {
+    master_parameters()
+        .iter()
+        .map(|descriptor| MixerCmd::BindParameter {
+            slot: master_parameter_slot(descriptor),
+            target: ParamTarget::MasterEffect {
+                effect_id: MASTER_GAIN_EFFECT_ID,
+                param_index: descriptor.index,
+            },
+            smoothing_seconds: DEFAULT_PARAMETER_SMOOTHING_SECONDS,
+        })
+        .collect()
 }
 
 fn param_echo(param_id: &str, value: f32) -> OscPacket {
@@ -292,6 +723,15 @@ fn song_load_error(path: &std::path::Path, error: &str) -> OscPacket {
     })
 }
 
+fn path_message(addr: &str, path: &std::path::Path, error: Option<&str>) -> OscPacket {
+    let mut args = vec![OscType::String(path.display().to_string())];
+    args.extend(error.map(|error| OscType::String(error.to_string())));
+    OscPacket::Message(OscMessage {
+        addr: addr.to_string(),
+        args,
+    })
+}
+
 /// Converts a non-negative linear amplitude to dBFS, flooring silence and
 /// non-finite values at [`METER_FLOOR_DB`].
 fn amp_to_db(amp: f32) -> f32 {
@@ -314,6 +754,105 @@ fn meter_level(levels: &MeterLevels) -> OscPacket {
     })
 }
 
+/// Builds a `/meter/loudness` message: `[momentary, short_term, integrated]`
+/// in LUFS, the loudness range in LU, then `[true_peak_l, true_peak_r]` in
+/// dBTP. Loudness not measured yet is reported at [`METER_FLOOR_DB`].
+fn meter_loudness(levels: &LoudnessLevels) -> OscPacket {
+    let lufs = |loudness: Option<f32>| OscType::Float(loudness.unwrap_or(METER_FLOOR_DB));
+    OscPacket::Message(OscMessage {
+        addr: "/meter/loudness".to_string(),
+        args: vec![
+            lufs(levels.momentary),
+            lufs(levels.short_term),
+            lufs(levels.integrated),
+            OscType::Float(levels.loudness_range),
+            OscType::Float(amp_to_db(levels.true_peak_left)),
+            OscType::Float(amp_to_db(levels.true_peak_right)),
+        ],
+    })
+}
+
+fn dsp_load_args(load: DspLoad) -> [OscType; 2] {
+    [OscType::Float(load.average), OscType::Float(load.peak)]
+}
+
+/// Builds one bundle per report so a client sees a consistent window:
+/// `/dsp/load [average, peak]` for the whole block, then
+/// `/dsp/load/instrument [id, average, peak]` per instrument and
+/// `/dsp/load/effect [id, average, peak]` per master effect. Loads are
+/// fractions of the real-time budget.
+fn dsp_load_bundle(report: &DspLoadReport) -> OscPacket {
+    let mut content = vec![OscPacket::Message(OscMessage {
+        addr: "/dsp/load".to_string(),
+        args: dsp_load_args(report.block).to_vec(),
+    })];
+    content.extend(report.instruments.iter().map(|instrument| {
+        let mut args = vec![OscType::Int(instrument.instrument_id as i32)];
+        args.extend(dsp_load_args(instrument.load));
+        OscPacket::Message(OscMessage {
+            addr: "/dsp/load/instrument".to_string(),
+            args,
+        })
+    }));
+    content.extend(report.master_effects.iter().map(|effect| {
+        let mut args = vec![OscType::Int(effect.effect_id as i32)];
+        args.extend(dsp_load_args(effect.load));
+        OscPacket::Message(OscMessage {
+            addr: "/dsp/load/effect".to_string(),
+            args,
+        })
+    }));
+    OscPacket::Bundle(OscBundle {
+        // The OSC "immediately" time tag.
+        timetag: OscTime {
+            seconds: 0,
+            fractional: 1,
+        },
+        content,
+    })
+}
+
+fn envelope_stage_name(stage: Option<EnvelopeState>) -> &'static str {
+    match stage {
+        None => "none",
+        Some(EnvelopeState::Idle) => "idle",
+        Some(EnvelopeState::Attack) => "attack",
+        Some(EnvelopeState::Decay) => "decay",
+        Some(EnvelopeState::Sustain) => "sustain",
+        Some(EnvelopeState::Release) => "release",
+    }
+}
+
+/// Builds one bundle per snapshot: `/voices [active]` with the total number of
+/// sounding voices, then `/voice [instrument_id, voice, note, stage, level]`
+/// for each listed voice. A bundle with only `/voices 0` means nothing plays.
+fn voice_activity_bundle(snapshot: &VoiceSnapshot) -> OscPacket {
+    let mut content = vec![OscPacket::Message(OscMessage {
+        addr: "/voices".to_string(),
+        args: vec![OscType::Int(snapshot.active as i32)],
+    })];
+    content.extend(snapshot.voices.iter().map(|voice| {
+        OscPacket::Message(OscMessage {
+            addr: "/voice".to_string(),
+            args: vec![
+                OscType::Int(voice.instrument_id as i32),
+                OscType::Int(i32::from(voice.voice)),
+                OscType::Int(i32::from(voice.note)),
+                OscType::String(envelope_stage_name(voice.stage).to_string()),
+                OscType::Float(voice.level),
+            ],
+        })
+    }));
+    OscPacket::Bundle(OscBundle {
+        // The OSC "immediately" time tag.
+        timetag: OscTime {
+            seconds: 0,
+            fractional: 1,
+        },
+        content,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -359,6 +898,72 @@ mod tests {
         assert!(dispatch.responses.is_empty());
     }
 
+    #[test]
+    fn state_save_and_load_record_their_paths() {
+        let dispatch = dispatch_packet(OscPacket::Bundle(OscBundle {
+            timetag: OscTime {
+                seconds: 0,
+                fractional: 1,
+            },
+            content: vec![
+                message(
+                    "/state/save",
+                    vec![OscType::String("live.json".to_string())],
+                ),
+                message(
+                    "/state/load",
+                    vec![OscType::String("preset.json".to_string())],
+                ),
+            ],
+        }));
+
+        assert!(dispatch.commands.is_empty());
+        assert_eq!(
+            dispatch.state_requests,
+            vec![
+                StateRequest::Save(PathBuf::from("live.json")),
+                StateRequest::Load(PathBuf::from("preset.json")),
+            ]
+        );
+        assert!(dispatch.responses.is_empty());
+    }
+
+    #[test]
+    fn invalid_state_request_does_not_emit_action() {
+        let dispatch = dispatch_packet(message("/state/load", Vec::new()));
+
+        assert!(dispatch.state_requests.is_empty());
+        assert!(dispatch.responses.is_empty());
+    }
+
+    #[test]
+    fn record_start_and_stop_become_record_requests() {
+        let dispatch = dispatch_packet(OscPacket::Bundle(OscBundle {
+            timetag: OscTime {
+                seconds: 0,
+                fractional: 1,
+            },
+            content: vec![
+                message(
+                    "/record/start",
+                    vec![OscType::String("take.wav".to_string())],
+                ),
+                message("/record/stop", Vec::new()),
+                message("/record/start", vec![OscType::Int(1)]),
+            ],
+        }));
+
+        assert!(dispatch.commands.is_empty());
+        assert_eq!(
+            dispatch.record_requests,
+            vec![
+                RecordRequest::Start(PathBuf::from("take.wav")),
+                RecordRequest::Stop,
+            ]
+        );
+        assert!(dispatch.responses.is_empty());
+    }
+
     #[test]
     fn param_set_gain_maps_normalized_value_to_db_and_echoes_normalized() {
         let dispatch = dispatch_packet(message(
@@ -366,19 +971,12 @@ mod tests {
             vec![OscType::String("gain".to_string()), OscType::Float(0.5)],
         ));
 
-        assert_eq!(dispatch.commands.len(), 1);
-        let Command::Mixer(MixerCmd::SetMasterEffectParameter {
-            effect_id,
-            param_index,
-            value,
-        }) = &dispatch.commands[0]
-        else {
-            panic!("expected MixerCmd::SetMasterEffectParameter");
-        };
-        assert_eq!(*effect_id, MASTER_GAIN_EFFECT_ID);
-        assert_eq!(*param_index, MASTER_GAIN_PARAM_INDEX);
+        assert!(dispatch.commands.is_empty());
+        assert_eq!(dispatch.param_writes.len(), 1);
+        let ParamWrite { slot, value } = dispatch.param_writes[0];
+        assert_eq!(slot, MASTER_PARAMETER_SLOT_BASE);
         // 0.5 linear amplitude ~= -6.02 dB.
-        assert!((*value - (-6.0206)).abs() < 1e-3, "got {value}");
+        assert!((value - (-6.0206)).abs() < 1e-3, "got {value}");
 
         // Echo mirrors the normalized value the core accepted.
         let (id, echoed) = param_echo_args(&dispatch.responses[0]);
@@ -394,13 +992,9 @@ mod tests {
             vec![OscType::String("gain".to_string()), OscType::Int(1)],
         ));
 
-        let Command::Mixer(MixerCmd::SetMasterEffectParameter { value, .. }) =
-            &dispatch.commands[0]
-        else {
-            panic!("expected MixerCmd::SetMasterEffectParameter");
-        };
+        let ParamWrite { value, .. } = dispatch.param_writes[0];
         assert!(
-            (*value - 0.0).abs() < 1e-4,
+            (value - 0.0).abs() < 1e-4,
             "unity gain -> 0 dB, got {value}"
         );
         assert!((param_echo_args(&dispatch.responses[0]).1 - 1.0).abs() < 1e-6);
@@ -413,11 +1007,8 @@ mod tests {
             "/param/set",
             vec![OscType::String("gain".to_string()), OscType::Float(2.0)],
         ));
-        let Command::Mixer(MixerCmd::SetMasterEffectParameter { value, .. }) = &high.commands[0]
-        else {
-            panic!("expected MixerCmd::SetMasterEffectParameter");
-        };
-        assert!((*value - 0.0).abs() < 1e-4);
+        let ParamWrite { value, .. } = high.param_writes[0];
+        assert!((value - 0.0).abs() < 1e-4);
         assert!((param_echo_args(&high.responses[0]).1 - 1.0).abs() < 1e-6);
 
         // Zero (and below) floors to silence.
@@ -425,11 +1016,9 @@ mod tests {
             "/param/set",
             vec![OscType::String("gain".to_string()), OscType::Float(0.0)],
         ));
-        let Command::Mixer(MixerCmd::SetMasterEffectParameter { value, .. }) = &low.commands[0]
-        else {
-            panic!("expected MixerCmd::SetMasterEffectParameter");
-        };
-        assert_eq!(*value, GAIN_FLOOR_DB);
+        let ParamWrite { value, .. } = low.param_writes[0];
+        let gain = find_parameter(master_parameters(), "gain").unwrap();
+        assert_eq!(value, gain.min);
         assert!((param_echo_args(&low.responses[0]).1 - 0.0).abs() < 1e-6);
     }
 
@@ -450,6 +1039,127 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn master_parameter_bindings_cover_every_slot_written_by_param_set() {
+        let bindings = master_parameter_bindings();
+        assert_eq!(bindings.len(), master_parameters().len());
+        let MixerCmd::BindParameter { slot, target, .. } = &bindings[0] else {
+            panic!("expected MixerCmd::BindParameter");
+        };
+        assert_eq!(*slot, MASTER_PARAMETER_SLOT_BASE);
+        assert_eq!(
+            *target,
+            ParamTarget::MasterEffect {
+                effect_id: MASTER_GAIN_EFFECT_ID,
+                param_index: MASTER_GAIN_PARAM_INDEX,
+            }
+        );
+    }
+
+    #[test]
+    fn param_bind_routes_a_slot_to_any_published_parameter() {
+        let bind = |slot, target: &str, param: &str| {
+            dispatch_packet(message(
+                "/param/bind",
+                vec![
+                    OscType::Int(slot),
+                    OscType::String(target.to_string()),
+                    OscType::String(param.to_string()),
+                ],
+            ))
+        };
+
+        let dispatch = bind(4, "instrument/2/effect/1", "feedback");
+        let [Command::Mixer(MixerCmd::BindParameterById {
+            slot,
+            owner,
+            param_id,
+            ..
+        })] = dispatch.commands.as_slice()
+        else {
+            panic!("expected one MixerCmd::BindParameterById");
+        };
+        assert_eq!(*slot, 4);
+        assert_eq!(
+            *owner,
+            ParamOwner::InstrumentEffect {
+                instrument_id: 2,
+                effect_id: 1,
+            }
+        );
+        assert_eq!(*param_id, "feedback");
+
+        for target in ["master/3", "instrument/7"] {
+            assert_eq!(bind(5, target, "drive").commands.len(), 1, "{target}");
+        }
+        assert!(bind(5, "instrument/x", "drive").commands.is_empty());
+        assert!(bind(5, "voice/1", "drive").commands.is_empty());
+        assert!(bind(5, "instrument/1", "wobble").commands.is_empty());
+        assert!(bind(-1, "instrument/1", "drive").commands.is_empty());
+    }
+
+    #[test]
+    fn param_slot_writes_plain_values() {
+        let dispatch = dispatch_packet(message(
+            "/param/slot",
+            vec![OscType::Int(4), OscType::Float(0.6)],
+        ));
+        assert_eq!(
+            dispatch.param_writes,
+            [ParamWrite {
+                slot: 4,
+                value: 0.6
+            }]
+        );
+        assert!(dispatch.responses.is_empty());
+
+        let dispatch = dispatch_packet(message(
+            "/param/slot",
+            vec![OscType::Int(-4), OscType::Float(0.6)],
+        ));
+        assert!(dispatch.param_writes.is_empty());
+    }
+
+    #[test]
+    fn state_requests_answer_in_order_without_blocking() {
+        let mut audio = BlightAudio::with_null_output(crate::NullOutputConfig::default()).unwrap();
+        let dir = std::env::temp_dir();
+        let first = dir.join(format!("blight-osc-state-a-{}.json", std::process::id()));
+        let second = dir.join(format!("blight-osc-state-b-{}.json", std::process::id()));
+        let mut requests = StateRequests::default();
+        let mut responses = Vec::new();
+        requests.push(
+            &mut audio,
+            vec![
+                StateRequest::Save(first.clone()),
+                StateRequest::Load(first.clone()),
+                StateRequest::Save(second.clone()),
+            ],
+            &mut responses,
+        );
+        // The first save waits for the audio thread; nothing behind it runs.
+        assert!(responses.is_empty());
+        while !requests.is_idle() {
+            std::thread::sleep(STATE_POLL_INTERVAL);
+            requests.poll(&mut audio, &mut responses);
+        }
+
+        let addrs: Vec<_> = responses
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(message) => message.addr.as_str(),
+                OscPacket::Bundle(_) => panic!("expected OSC message"),
+            })
+            .collect();
+        assert_eq!(addrs, ["/state/saved", "/state/loaded", "/state/saved"]);
+        assert_eq!(
+            std::fs::read_to_string(&first).unwrap(),
+            std::fs::read_to_string(&second).unwrap()
+        );
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+    }
+
     #[test]
     fn invalid_param_set_does_not_emit_command_or_echo() {
         let dispatch = dispatch_packet(message(
@@ -461,6 +1171,7 @@ mod tests {
         ));
 
         assert!(dispatch.commands.is_empty());
+        assert!(dispatch.param_writes.is_empty());
         assert!(dispatch.responses.is_empty());
     }
 
@@ -509,4 +1220,135 @@ mod tests {
         assert!((rms_l - 0.0).abs() < 1e-4);
         assert_eq!(*rms_r, METER_FLOOR_DB);
     }
+
+    #[test]
+    fn meter_loudness_floors_unmeasured_readings() {
+        let packet = meter_loudness(&LoudnessLevels {
+            momentary: Some(-14.0),
+            short_term: None,
+            integrated: Some(-16.5),
+            loudness_range: 4.0,
+            true_peak_left: 1.0,
+            true_peak_right: 0.0,
+        });
+
+        let OscPacket::Message(message) = packet else {
+            panic!("expected OSC message");
+        };
+        assert_eq!(message.addr, "/meter/loudness");
+        assert_eq!(
+            message.args,
+            vec![
+                OscType::Float(-14.0),
+                OscType::Float(METER_FLOOR_DB),
+                OscType::Float(-16.5),
+                OscType::Float(4.0),
+                OscType::Float(0.0),
+                OscType::Float(METER_FLOOR_DB),
+            ]
+        );
+    }
+
+    #[test]
+    fn loudness_reset_survives_bundling() {
+        let dispatch = dispatch_packet(OscPacket::Bundle(OscBundle {
+            timetag: OscTime {
+                seconds: 0,
+                fractional: 1,
+            },
+            content: vec![
+                message("/meter/loudness/reset", Vec::new()),
+                message("/transport/stop", Vec::new()),
+            ],
+        }));
+
+        assert!(dispatch.loudness_reset);
+        assert_eq!(dispatch.commands.len(), 1);
+    }
+
+    #[test]
+    fn dsp_load_bundle_lists_block_instruments_and_effects() {
+        let load = DspLoad {
+            average: 0.25,
+            peak: 0.5,
+        };
+        let packet = dsp_load_bundle(&DspLoadReport {
+            blocks: 4,
+            block: load,
+            instruments: vec![crate::InstrumentLoad {
+                instrument_id: 3,
+                load,
+            }],
+            master_effects: vec![crate::EffectLoad { effect_id: 0, load }],
+            ..DspLoadReport::default()
+        });
+
+        let OscPacket::Bundle(bundle) = packet else {
+            panic!("expected OSC bundle");
+        };
+        let messages = bundle
+            .content
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(message) => (message.addr.as_str(), message.args.clone()),
+                OscPacket::Bundle(_) => panic!("expected flat bundle"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                ("/dsp/load", vec![OscType::Float(0.25), OscType::Float(0.5)]),
+                (
+                    "/dsp/load/instrument",
+                    vec![OscType::Int(3), OscType::Float(0.25), OscType::Float(0.5)]
+                ),
+                (
+                    "/dsp/load/effect",
+                    vec![OscType::Int(0), OscType::Float(0.25), OscType::Float(0.5)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn voice_activity_bundle_counts_and_lists_voices() {
+        let packet = voice_activity_bundle(&VoiceSnapshot {
+            active: 2,
+            voices: vec![crate::ActiveVoice {
+                instrument_id: 4,
+                voice: 1,
+                note: 60,
+                stage: Some(EnvelopeState::Release),
+                level: 0.5,
+            }],
+        });
+
+        let OscPacket::Bundle(bundle) = packet else {
+            panic!("expected OSC bundle");
+        };
+        let messages = bundle
+            .content
+            .iter()
+            .map(|packet| match packet {
+                OscPacket::Message(message) => (message.addr.as_str(), message.args.clone()),
+                OscPacket::Bundle(_) => panic!("expected flat bundle"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                ("/voices", vec![OscType::Int(2)]),
+                (
+                    "/voice",
+                    vec![
+                        OscType::Int(4),
+                        OscType::Int(1),
+                        OscType::Int(60),
+                        OscType::String("release".to_string()),
+                        OscType::Float(0.5),
+                    ]
+                ),
+            ]
+        );
+    }
 }
@@ -0,0 +1,458 @@
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use ringbuf::traits::*;
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+
+/// Interleaved stereo samples buffered between the audio callback and the
+/// WAV writer: almost three seconds at 96 kHz.
+pub const RECORD_BUFFER_SAMPLES: usize = 1 << 19;
+
+/// How often the writer thread moves buffered samples into the file.
+const RECORD_WRITE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Counters shared by the audio callback, the writer thread and the control
+/// thread.
+#[derive(Default)]
+struct RecordingState {
+    /// Set by the writer thread while a file is open; the callback only
+    /// buffers samples while it is set.
+    active: AtomicBool,
+    frames: AtomicU64,
+    dropped_frames: AtomicU64,
+    /// A recording the writer stopped on a write error, until the control
+    /// thread takes it. The callback never touches it.
+    failure: Mutex<Option<RecordingFailure>>,
+}
+
+impl RecordingState {
+    fn failure(&self) -> std::sync::MutexGuard<'_, Option<RecordingFailure>> {
+        self.failure.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Progress of the current recording, read without blocking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    /// Frames written to the file so far.
+    pub frames: u64,
+    /// Frames the callback discarded because the writer fell behind.
+    pub dropped_frames: u64,
+    /// The last recording stopped on a write error that has not been taken
+    /// with [`crate::BlightAudio::take_recording_failure`] yet.
+    pub failed: bool,
+}
+
+/// The outcome of a finished recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingReport {
+    pub path: PathBuf,
+    pub sample_rate: u32,
+    pub frames: u64,
+    pub dropped_frames: u64,
+}
+
+/// A recording that stopped on its own because the file could not be written.
+/// The file is closed and holds the frames written before the error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingFailure {
+    pub report: RecordingReport,
+    pub error: String,
+}
+
+impl fmt::Display for RecordingFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "writing {} failed: {}",
+            self.report.path.display(),
+            self.error
+        )
+    }
+}
+
+/// The audio thread's end of a recording: copies the post-master output into
+/// the ring buffer while a recording runs. Never blocks or allocates.
+pub(crate) struct RecordTap {
+    producer: HeapProd<f32>,
+    state: Arc<RecordingState>,
+}
+
+impl RecordTap {
+    pub(crate) fn record(&mut self, left: &[f32], right: &[f32]) {
+        if !self.state.active.load(Ordering::Acquire) {
+            return;
+        }
+        // Drop whole blocks so that the file never holds half a frame.
+        if self.producer.vacant_len() < left.len() * 2 {
+            self.state
+                .dropped_frames
+                .fetch_add(left.len() as u64, Ordering::Relaxed);
+            return;
+        }
+        let samples = left
+            .iter()
+            .zip(right)
+            .flat_map(|(&left, &right)| [left, right]);
+        self.producer.push_iter(samples);
+    }
+}
+
+/// Held by the output driving the processor: counts blocks it filled with
+/// silence because the processor was locked, which never reach the tap, as
+/// dropped. Never blocks or allocates.
+#[derive(Clone)]
+pub(crate) struct MissedBlocks {
+    state: Arc<RecordingState>,
+}
+
+impl MissedBlocks {
+    pub(crate) fn count(&self, frames: usize) {
+        if self.state.active.load(Ordering::Acquire) {
+            self.state
+                .dropped_frames
+                .fetch_add(frames as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+enum RecorderRequest {
+    Start {
+        path: PathBuf,
+        sample_rate: u32,
+        reply: mpsc::Sender<Result<()>>,
+    },
+    Stop {
+        reply: mpsc::Sender<Result<RecordingReport>>,
+    },
+}
+
+/// The control thread's end of a recording. A writer thread drains the ring
+/// buffer into a 32-bit float stereo WAV file.
+pub(crate) struct Recorder {
+    requests: Option<mpsc::Sender<RecorderRequest>>,
+    state: Arc<RecordingState>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Starts the writer thread. The returned tap goes to the audio thread.
+    pub(crate) fn spawn(capacity: usize) -> std::io::Result<(Self, RecordTap)> {
+        let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+        let state = Arc::new(RecordingState::default());
+        let (requests, receiver) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("blight-recorder".into())
+            .spawn({
+                let state = state.clone();
+                move || RecordWriter::new(consumer, state).run(receiver)
+            })?;
+        Ok((
+            Self {
+                requests: Some(requests),
+                state: state.clone(),
+                thread: Some(thread),
+            },
+            RecordTap { producer, state },
+        ))
+    }
+
+    /// Opens `path` and records from the next audio block on.
+    pub(crate) fn start(&self, path: &Path, sample_rate: u32) -> Result<()> {
+        let (reply, response) = mpsc::channel();
+        self.send(RecorderRequest::Start {
+            path: path.to_path_buf(),
+            sample_rate,
+            reply,
+        })?;
+        response.recv()?
+    }
+
+    /// Stops recording, writes what is still buffered and closes the file.
+    pub(crate) fn stop(&self) -> Result<RecordingReport> {
+        let (reply, response) = mpsc::channel();
+        self.send(RecorderRequest::Stop { reply })?;
+        response.recv()?
+    }
+
+    pub(crate) fn missed_blocks(&self) -> MissedBlocks {
+        MissedBlocks {
+            state: self.state.clone(),
+        }
+    }
+
+    pub(crate) fn status(&self) -> RecordingStatus {
+        RecordingStatus {
+            recording: self.state.active.load(Ordering::Acquire),
+            frames: self.state.frames.load(Ordering::Relaxed),
+            dropped_frames: self.state.dropped_frames.load(Ordering::Relaxed),
+            failed: self.state.failure().is_some(),
+        }
+    }
+
+    /// Returns the recording that stopped on a write error, once.
+    pub(crate) fn take_failure(&self) -> Option<RecordingFailure> {
+        self.state.failure().take()
+    }
+
+    fn send(&self, request: RecorderRequest) -> Result<()> {
+        self.requests
+            .as_ref()
+            .and_then(|requests| requests.send(request).ok())
+            .ok_or_else(|| anyhow!("the recorder thread has stopped"))
+    }
+}
+
+impl Drop for Recorder {
+    /// Finishes a recording still in progress.
+    fn drop(&mut self) {
+        self.requests = None;
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("recorder thread panicked");
+            }
+        }
+    }
+}
+
+struct Recording {
+    path: PathBuf,
+    sample_rate: u32,
+    writer: hound::WavWriter<BufWriter<File>>,
+}
+
+struct RecordWriter {
+    consumer: HeapCons<f32>,
+    state: Arc<RecordingState>,
+    buffer: Vec<f32>,
+    recording: Option<Recording>,
+    written_since_flush: u64,
+}
+
+impl RecordWriter {
+    fn new(consumer: HeapCons<f32>, state: Arc<RecordingState>) -> Self {
+        Self {
+            buffer: vec![0.0; consumer.capacity().get()],
+            consumer,
+            state,
+            recording: None,
+            written_since_flush: 0,
+        }
+    }
+
+    fn run(mut self, requests: mpsc::Receiver<RecorderRequest>) {
+        loop {
+            // Polling keeps the audio callback from ever waking this thread.
+            match requests.recv_timeout(RECORD_WRITE_INTERVAL) {
+                Ok(RecorderRequest::Start {
+                    path,
+                    sample_rate,
+                    reply,
+                }) => {
+                    let _ = reply.send(self.start(path, sample_rate));
+                }
+                Ok(RecorderRequest::Stop { reply }) => {
+                    let _ = reply.send(self.stop());
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => self.drain(),
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    if self.recording.is_some() {
+                        match self.stop() {
+                            Ok(report) => {
+                                log::info!("finished recording {}", report.path.display())
+                            }
+                            Err(err) => log::error!("{err:#}"),
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, path: PathBuf, sample_rate: u32) -> Result<()> {
+        if let Some(recording) = &self.recording {
+            bail!("already recording to {}", recording.path.display());
+        }
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let writer = hound::WavWriter::create(&path, spec)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        // Discard the tail of a previous recording's final block.
+        self.consumer.clear();
+        self.state.frames.store(0, Ordering::Relaxed);
+        self.state.dropped_frames.store(0, Ordering::Relaxed);
+        self.written_since_flush = 0;
+        // A new recording supersedes a failure nobody asked about.
+        self.state.failure().take();
+        self.recording = Some(Recording {
+            path,
+            sample_rate,
+            writer,
+        });
+        self.state.active.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<RecordingReport> {
+        self.state.active.store(false, Ordering::Release);
+        self.drain();
+        let Some(recording) = self.recording.take() else {
+            if let Some(failure) = self.state.failure().take() {
+                bail!("{failure}");
+            }
+            bail!("not recording");
+        };
+        let report = self.report(&recording);
+        recording
+            .writer
+            .finalize()
+            .with_context(|| format!("failed to finish {}", report.path.display()))?;
+        Ok(report)
+    }
+
+    fn report(&self, recording: &Recording) -> RecordingReport {
+        RecordingReport {
+            path: recording.path.clone(),
+            sample_rate: recording.sample_rate,
+            frames: self.state.frames.load(Ordering::Relaxed),
+            dropped_frames: self.state.dropped_frames.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stops recording after a write error and closes the file, keeping the
+    /// report for [`Recorder::stop`] and [`Recorder::take_failure`].
+    fn fail(&mut self, error: String) {
+        self.state.active.store(false, Ordering::Release);
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+        log::error!("recording to {} failed: {error}", recording.path.display());
+        let report = self.report(&recording);
+        if let Err(err) = recording.writer.finalize() {
+            log::error!("failed to finish {}: {err}", report.path.display());
+        }
+        *self.state.failure() = Some(RecordingFailure { report, error });
+    }
+
+    /// Moves buffered samples into the file, refreshing its header about once
+    /// a second so that it stays readable if the process dies.
+    fn drain(&mut self) {
+        let count = self.consumer.pop_slice(&mut self.buffer);
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+        if count == 0 {
+            return;
+        }
+        let samples = &self.buffer[..count];
+        let mut written = samples
+            .iter()
+            .try_for_each(|&sample| recording.writer.write_sample(sample));
+        let frames = (count / 2) as u64;
+        self.written_since_flush += frames;
+        if written.is_ok() && self.written_since_flush >= u64::from(recording.sample_rate) {
+            self.written_since_flush = 0;
+            written = recording.writer.flush();
+        }
+        match written {
+            Ok(()) => {
+                self.state.frames.fetch_add(frames, Ordering::Relaxed);
+            }
+            Err(err) => self.fail(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_wav(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("blight-record-{name}-{}.wav", std::process::id()))
+    }
+
+    #[test]
+    fn records_blocks_between_start_and_stop() {
+        let path = temp_wav("blocks");
+        let (recorder, mut tap) = Recorder::spawn(64).unwrap();
+        tap.record(&[1.0; 4], &[1.0; 4]);
+        recorder.start(&path, 48_000).unwrap();
+        assert!(recorder.start(&path, 48_000).is_err());
+        tap.record(&[0.5, 0.25], &[-0.5, -0.25]);
+        let report = recorder.stop().unwrap();
+        tap.record(&[1.0; 4], &[1.0; 4]);
+
+        assert_eq!((report.frames, report.dropped_frames), (2, 0));
+        assert!(!recorder.status().recording);
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let samples: Vec<f32> = reader.samples().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(samples, [0.5, -0.5, 0.25, -0.25]);
+        assert!(recorder.stop().is_err());
+    }
+
+    #[test]
+    fn counts_blocks_dropped_while_the_buffer_is_full() {
+        let path = temp_wav("dropped");
+        let (recorder, mut tap) = Recorder::spawn(8).unwrap();
+        recorder.start(&path, 48_000).unwrap();
+        tap.record(&[0.0; 3], &[0.0; 3]);
+        // Five stereo frames never fit in eight samples.
+        tap.record(&[0.0; 5], &[0.0; 5]);
+        assert_eq!(recorder.status().dropped_frames, 5);
+        let report = recorder.stop().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((report.frames, report.dropped_frames), (3, 5));
+    }
+
+    #[test]
+    fn counts_blocks_missed_by_the_output_while_recording() {
+        let path = temp_wav("missed");
+        let (recorder, mut tap) = Recorder::spawn(64).unwrap();
+        let missed = recorder.missed_blocks();
+        missed.count(16);
+        recorder.start(&path, 48_000).unwrap();
+        tap.record(&[0.0; 2], &[0.0; 2]);
+        missed.count(4);
+        let report = recorder.stop().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((report.frames, report.dropped_frames), (2, 4));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn write_errors_end_the_recording_and_are_reported() {
+        let (recorder, mut tap) = Recorder::spawn(64).unwrap();
+        // The header fits in the write buffer; the first flush, after one
+        // frame at 1 Hz, fails.
+        recorder.start(Path::new("/dev/full"), 1).unwrap();
+        tap.record(&[0.5; 4], &[0.5; 4]);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !recorder.status().failed {
+            assert!(std::time::Instant::now() < deadline, "no write error");
+            thread::sleep(RECORD_WRITE_INTERVAL);
+        }
+        assert!(!recorder.status().recording);
+        let error = recorder.stop().unwrap_err().to_string();
+        assert!(error.starts_with("writing /dev/full failed"), "{error}");
+        assert!(recorder.take_failure().is_none());
+
+        let path = temp_wav("after-failure");
+        recorder.start(&path, 48_000).unwrap();
+        recorder.stop().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,8 +1,10 @@
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{collections::BTreeMap, num::NonZeroUsize, path::PathBuf};
 
 use audio_backend::{
-    current_platform, load_json_song, render_json_song, render_song, OfflineGoldenManifest,
-    OfflineRenderReference,
+    current_platform, load_json_song, render_batch_references, render_json_song, render_song,
+    AudioComparison, AudioFingerprint, LoudnessSummary, OfflineGoldenManifest, OfflineLoop,
+    OfflineLoopPart, OfflineRenderConfig, OfflineRenderReference, OfflineSelection, TrackSet,
+    WavExportOptions, DEFAULT_MAX_BLOCK_SIZE,
 };
 
 fn workspace_root() -> PathBuf {
@@ -27,12 +29,49 @@ fn repository_songs_match_reviewed_offline_references() {
     assert_eq!(manifest.baseline_kind, "characterization");
     assert_eq!(manifest.songs.len(), 2);
 
+    assert_songs_match(&manifest, manifest.config, &manifest.songs, false);
+}
+
+#[test]
+fn repository_song_tails_match_reviewed_offline_references() {
+    let manifest = load_manifest();
+    assert_eq!(
+        manifest.tail_songs.keys().collect::<Vec<_>>(),
+        manifest.songs.keys().collect::<Vec<_>>()
+    );
+
+    assert_songs_match(
+        &manifest,
+        manifest.tail_config(),
+        &manifest.tail_songs,
+        true,
+    );
+    for (song_name, tail) in &manifest.tail_songs {
+        let max_tail_frames = (manifest.tail.max_seconds * tail.sample_rate as f32) as usize;
+        let song_frames = manifest.songs[song_name].frames;
+        assert!(
+            tail.frames > song_frames && tail.frames <= song_frames + max_tail_frames,
+            "{song_name} tail should extend the song by at most the maximum tail"
+        );
+    }
+}
+
+fn assert_songs_match(
+    manifest: &OfflineGoldenManifest,
+    config: OfflineRenderConfig,
+    songs: &BTreeMap<String, OfflineRenderReference>,
+    tail: bool,
+) {
+    // Exact hashes are the gate on the canonical platform; elsewhere, or when
+    // asked to, renders only need to stay within the fingerprint tolerances.
+    let tolerance_only = current_platform() != manifest.canonical_platform
+        || std::env::var("OFFLINE_GOLDEN_COMPARISON").is_ok_and(|mode| mode == "tolerance");
     let mut actual_references = BTreeMap::<String, OfflineRenderReference>::new();
-    for (song_name, expected) in &manifest.songs {
+    for (song_name, expected) in songs {
         let path = workspace_root().join(song_name);
-        let first = render_json_song(&path, manifest.config)
+        let first = render_json_song(&path, config)
             .unwrap_or_else(|error| panic!("failed to render {song_name}: {error:#}"));
-        let second = render_json_song(&path, manifest.config)
+        let second = render_json_song(&path, config)
             .unwrap_or_else(|error| panic!("failed repeat render for {song_name}: {error:#}"));
         let actual = first.reference();
 
@@ -41,7 +80,7 @@ fn repository_songs_match_reviewed_offline_references() {
             second.reference(),
             "{song_name} is nondeterministic"
         );
-        assert_eq!(actual.sample_rate, manifest.config.sample_rate);
+        assert_eq!(actual.sample_rate, config.sample_rate);
         assert_eq!(actual.channels, 2);
         assert_eq!(
             actual.frames, expected.frames,
@@ -50,13 +89,24 @@ fn repository_songs_match_reviewed_offline_references() {
         assert!(actual.peak_left > 0.0 || actual.peak_right > 0.0);
         assert_eq!(actual.pcm_sha256.len(), 64);
 
-        if current_platform() == manifest.canonical_platform {
+        let comparison = AudioComparison::new(
+            &load_fingerprint(song_name, tail),
+            &first.fingerprint(),
+            manifest.comparison,
+        );
+        if !tolerance_only {
             assert_eq!(
                 &actual, expected,
-                "{song_name} audio changed; inspect rendered WAVs and run the explicit \
-                 update_offline_references -- --update-reference command only when intentional"
+                "{song_name} audio changed; fingerprint comparison: {comparison}\
+                 inspect rendered WAVs (update_offline_references -- --compare writes null-test \
+                 residuals) and run the explicit update_offline_references -- --update-reference \
+                 command only when intentional"
             );
         } else {
+            assert!(
+                comparison.passed(),
+                "{song_name} drifted from its fingerprint: {comparison}"
+            );
             assert_eq!(actual.clipped_samples, expected.clipped_samples);
             assert_metric_close(song_name, "peak_left", actual.peak_left, expected.peak_left);
             assert_metric_close(
@@ -67,13 +117,68 @@ fn repository_songs_match_reviewed_offline_references() {
             );
             assert_metric_close(song_name, "rms_left", actual.rms_left, expected.rms_left);
             assert_metric_close(song_name, "rms_right", actual.rms_right, expected.rms_right);
+            assert_loudness_close(song_name, &actual.loudness, &expected.loudness);
         }
         actual_references.insert(song_name.clone(), actual);
     }
 
     assert_eq!(
         actual_references.keys().collect::<Vec<_>>(),
-        manifest.songs.keys().collect::<Vec<_>>()
+        songs.keys().collect::<Vec<_>>()
+    );
+}
+
+fn load_fingerprint(song_name: &str, tail: bool) -> AudioFingerprint {
+    let path = workspace_root()
+        .join("audio_backend/tests/golden")
+        .join(OfflineGoldenManifest::fingerprint_file(song_name, tail));
+    AudioFingerprint::load(&path).unwrap_or_else(|error| panic!("{error:#}"))
+}
+
+fn assert_loudness_close(song: &str, actual: &LoudnessSummary, expected: &LoudnessSummary) {
+    const LOUDNESS_TOLERANCE_LU: f32 = 0.01;
+    for (metric, actual, expected) in [
+        (
+            "integrated_lufs",
+            actual.integrated_lufs,
+            expected.integrated_lufs,
+        ),
+        (
+            "max_momentary_lufs",
+            actual.max_momentary_lufs,
+            expected.max_momentary_lufs,
+        ),
+        (
+            "max_short_term_lufs",
+            actual.max_short_term_lufs,
+            expected.max_short_term_lufs,
+        ),
+        (
+            "loudness_range_lu",
+            Some(actual.loudness_range_lu),
+            Some(expected.loudness_range_lu),
+        ),
+    ] {
+        let close = match (actual, expected) {
+            (Some(actual), Some(expected)) => (actual - expected).abs() <= LOUDNESS_TOLERANCE_LU,
+            (actual, expected) => actual == expected,
+        };
+        assert!(
+            close,
+            "{song} {metric} changed: expected {expected:?}, got {actual:?}"
+        );
+    }
+    assert_metric_close(
+        song,
+        "true_peak_left",
+        actual.true_peak_left,
+        expected.true_peak_left,
+    );
+    assert_metric_close(
+        song,
+        "true_peak_right",
+        actual.true_peak_right,
+        expected.true_peak_right,
     );
 }
 
@@ -85,6 +190,35 @@ fn assert_metric_close(song: &str, metric: &str, actual: f32, expected: f32) {
     );
 }
 
+#[test]
+fn batch_renders_match_the_reviewed_references() {
+    let manifest = load_manifest();
+    let songs = manifest
+        .songs
+        .keys()
+        .map(|song_name| workspace_root().join(song_name))
+        .collect::<Vec<_>>();
+    let threads = NonZeroUsize::new(songs.len()).unwrap();
+
+    let references = render_batch_references(&songs, manifest.config, threads)
+        .expect("render reference songs in parallel");
+
+    assert_eq!(references.len(), manifest.songs.len());
+    for (path, (song_name, expected)) in songs.iter().zip(&manifest.songs) {
+        let actual = &references[path.to_string_lossy().as_ref()];
+        assert_eq!(
+            actual.frames, expected.frames,
+            "{song_name} duration changed"
+        );
+        if current_platform() == manifest.canonical_platform {
+            assert_eq!(
+                actual, expected,
+                "{song_name} renders differently in a batch"
+            );
+        }
+    }
+}
+
 #[test]
 fn changing_a_song_note_changes_its_pcm_reference() {
     let manifest = load_manifest();
@@ -100,4 +234,229 @@ fn changing_a_song_note_changes_its_pcm_reference() {
         manifest.songs[song_name].pcm_sha256,
         "a note change must invalidate the end-to-end PCM reference"
     );
+    let comparison = AudioComparison::new(
+        &load_fingerprint(song_name, false),
+        &changed.fingerprint(),
+        manifest.comparison,
+    );
+    assert!(
+        !comparison.passed(),
+        "a note change must also leave the fingerprint tolerances"
+    );
+}
+
+#[test]
+fn soloed_and_muted_tracks_split_the_mix() {
+    let manifest = load_manifest();
+    let path = workspace_root().join("ending_theme_no_effect.json");
+    let full = render_json_song(&path, manifest.config).expect("render the full mix");
+    let track = TrackSet::from_tracks([0]).unwrap();
+    let render_selection = |selection| {
+        let config = OfflineRenderConfig {
+            selection,
+            ..manifest.config
+        };
+        render_json_song(&path, config).expect("render a track selection")
+    };
+    let soloed = render_selection(OfflineSelection {
+        soloed: track,
+        ..OfflineSelection::WHOLE_SONG
+    });
+    let muted = render_selection(OfflineSelection {
+        muted: track,
+        ..OfflineSelection::WHOLE_SONG
+    });
+
+    assert_eq!(soloed.frame_count(), full.frame_count());
+    assert_eq!(muted.frame_count(), full.frame_count());
+    assert_ne!(soloed.pcm_sha256(), full.pcm_sha256());
+    for (channel, full, soloed, muted) in [
+        ("left", full.left(), soloed.left(), muted.left()),
+        ("right", full.right(), soloed.right(), muted.right()),
+    ] {
+        let error = full
+            .iter()
+            .zip(soloed.iter().zip(muted))
+            .map(|(full, (soloed, muted))| (full - soloed - muted).abs())
+            .fold(0.0_f32, f32::max);
+        assert!(
+            error < 1e-5,
+            "{channel} solo and mute differ from the mix by {error}"
+        );
+    }
+}
+
+#[test]
+fn loops_repeat_a_chain_or_phrase_the_requested_number_of_times() {
+    let manifest = load_manifest();
+    let path = workspace_root().join("calibration.json");
+    let render_loop = |part, times| {
+        let config = OfflineRenderConfig {
+            selection: OfflineSelection {
+                looped: Some(OfflineLoop {
+                    part,
+                    track: 0,
+                    times,
+                }),
+                ..OfflineSelection::WHOLE_SONG
+            },
+            ..manifest.config
+        };
+        render_json_song(&path, config).expect("render a loop")
+    };
+    let song = render_json_song(&path, manifest.config).expect("render the song");
+    let block = manifest.config.block_size as f64;
+    let assert_length = |label: &str, frames: usize, expected: f64| {
+        assert!(
+            (frames as f64 - expected).abs() <= block,
+            "{label} lasts {frames} frames, expected about {expected}"
+        );
+    };
+
+    // The calibration song is a single chain on track 0.
+    let chain = render_loop(OfflineLoopPart::Chain(0), 1);
+    assert_eq!(chain.pcm_sha256(), song.pcm_sha256());
+    let chains = render_loop(OfflineLoopPart::Chain(0), 2);
+    assert_length(
+        "two chains",
+        chains.frame_count(),
+        2.0 * song.frame_count() as f64,
+    );
+
+    let phrase = render_loop(OfflineLoopPart::Phrase(0), 1);
+    let phrase_frames = song.frame_count() as f64 / 16.0;
+    assert_length("a phrase", phrase.frame_count(), phrase_frames);
+    let phrases = render_loop(OfflineLoopPart::Phrase(0), 3);
+    assert_length("three phrases", phrases.frame_count(), 3.0 * phrase_frames);
+    assert_eq!(
+        phrases.left()[..phrase.frame_count()],
+        phrase.left()[..],
+        "the loop starts like a single pass"
+    );
+}
+
+#[test]
+fn blocks_longer_than_the_engine_maximum_are_rendered() {
+    let manifest = load_manifest();
+    let config = OfflineRenderConfig {
+        block_size: DEFAULT_MAX_BLOCK_SIZE * 3 + 5,
+        ..manifest.config
+    };
+
+    let render = render_json_song(&workspace_root().join("calibration.json"), config)
+        .expect("render calibration song with oversized blocks");
+
+    assert!(render.frame_count() > config.block_size);
+    let reference = render.reference();
+    assert!(reference.peak_left.max(reference.peak_right) > 0.0);
+}
+
+#[test]
+fn supported_sample_rates_render_the_same_music() {
+    let manifest = load_manifest();
+    let path = workspace_root().join("calibration.json");
+    let canonical = render_json_song(&path, manifest.config)
+        .expect("render calibration song at the canonical rate")
+        .reference();
+    let canonical_seconds = canonical.frames as f64 / canonical.sample_rate as f64;
+
+    for sample_rate in [44_100, 96_000] {
+        let config = OfflineRenderConfig {
+            sample_rate,
+            max_frames: sample_rate as usize * 120,
+            ..manifest.config
+        };
+        let render = render_json_song(&path, config)
+            .unwrap_or_else(|error| panic!("failed to render at {sample_rate} Hz: {error:#}"));
+        let reference = render.reference();
+
+        // Ticks land on block boundaries, so durations may differ by a block.
+        let seconds = reference.frames as f64 / sample_rate as f64;
+        let block_seconds = config.block_size as f64 / sample_rate as f64;
+        assert!(
+            (seconds - canonical_seconds).abs() <= 2.0 * block_seconds,
+            "{sample_rate} Hz lasts {seconds} s, expected {canonical_seconds} s"
+        );
+        for (metric, actual, expected) in [
+            ("rms_left", reference.rms_left, canonical.rms_left),
+            ("rms_right", reference.rms_right, canonical.rms_right),
+            ("peak_left", reference.peak_left, canonical.peak_left),
+        ] {
+            assert!(
+                (actual - expected).abs() <= 0.01 * expected,
+                "{sample_rate} Hz {metric} is {actual}, expected about {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn offline_renders_report_dsp_load_per_instrument() {
+    let manifest = load_manifest();
+    let render = render_json_song(&workspace_root().join("calibration.json"), manifest.config)
+        .expect("render calibration song");
+
+    let dsp_load = render.dsp_load();
+    let blocks = render.frame_count().div_ceil(manifest.config.block_size) as u64;
+    assert!(
+        dsp_load.blocks >= blocks,
+        "one engine block per render block"
+    );
+    let seconds = render.frame_count() as f64 / manifest.config.sample_rate as f64;
+    assert!((dsp_load.budget.as_secs_f64() - seconds).abs() < 1e-3);
+    assert!(dsp_load.block.average > 0.0);
+    assert!(dsp_load.block.peak >= dsp_load.block.average);
+    assert!(!dsp_load.instruments.is_empty());
+}
+
+#[test]
+fn stems_leave_the_master_mix_bit_identical() {
+    let manifest = load_manifest();
+    let with_stems = OfflineRenderConfig {
+        stems: true,
+        ..manifest.config
+    };
+    for song_name in manifest.songs.keys() {
+        let path = workspace_root().join(song_name);
+        let plain = render_json_song(&path, manifest.config).expect("render song");
+        let stemmed = render_json_song(&path, with_stems).expect("render song with stems");
+
+        assert!(!stemmed.stems().is_empty());
+        assert_eq!(stemmed.left(), plain.left(), "{song_name} left channel");
+        assert_eq!(stemmed.right(), plain.right(), "{song_name} right channel");
+    }
+}
+
+#[test]
+fn stems_add_up_to_the_master_mix() {
+    let manifest = load_manifest();
+    let song_name = "calibration.json";
+    let config = OfflineRenderConfig {
+        stems: true,
+        ..manifest.config
+    };
+    let render = render_json_song(&workspace_root().join(song_name), config)
+        .expect("render calibration song with stems");
+
+    let stems = render.stems();
+    assert!(stems.len() > 1);
+    assert!(stems
+        .windows(2)
+        .all(|pair| pair[0].instrument_id() < pair[1].instrument_id()));
+    // Offline songs have no master effects, so the stems sum to the master.
+    for frame in 0..render.frame_count() {
+        let left = stems.iter().map(|stem| stem.left()[frame]).sum::<f32>();
+        let right = stems.iter().map(|stem| stem.right()[frame]).sum::<f32>();
+        assert!((left - render.left()[frame]).abs() < 1e-5);
+        assert!((right - render.right()[frame]).abs() < 1e-5);
+    }
+
+    let dir = std::env::temp_dir().join("audio_backend_stem_test");
+    let paths = render
+        .write_wavs(&dir, &WavExportOptions::default())
+        .expect("write stem WAVs");
+    assert_eq!(paths.len(), stems.len() + 1);
+    assert!(paths[0].ends_with("master.wav"));
+    assert!(paths.iter().all(|path| path.is_file()));
+    std::fs::remove_dir_all(&dir).expect("remove stem WAVs");
 }
@@ -1,5 +1,5 @@
 use crate::id::{EffectId, EnvelopeId, VoiceId};
-use crate::instruments::Waveform;
+use crate::instruments::{DetuneCurve, GrainWindow, SequencerClock, Waveform};
 
 pub enum SynthCmd {
     SetWaveform {
@@ -14,6 +14,25 @@ pub enum SynthCmd {
         effect_id: EffectId,
         command: EffectCmd,
     },
+    UnisonCommand {
+        command: UnisonCmd,
+    },
+    PluckedStringCommand {
+        command: PluckedStringCmd,
+    },
+    GranularCommand {
+        command: GranularCmd,
+    },
+    DrumCommand {
+        command: DrumCmd,
+    },
+    DFAMCommand {
+        command: DFAMCmd,
+    },
+    /// Host tempo in beats per minute, broadcast by the engine to every instrument.
+    SetTempo {
+        bpm: f32,
+    },
 }
 
 pub enum EffectCmd {
@@ -28,3 +47,94 @@ pub enum EnvelopeCmd {
     SetSustain { sustain: f32 },
     SetRelease { release: f32 },
 }
+
+pub enum UnisonCmd {
+    SetVoices { voices: u8 },
+    SetDetune { cents: f32 },
+    SetDetuneCurve { curve: DetuneCurve },
+    SetPhaseRandomness { amount: f32 },
+    SetStereoSpread { spread: f32 },
+}
+
+pub enum PluckedStringCmd {
+    SetDamping { damping: f32 },
+    SetBrightness { brightness: f32 },
+    SetPickPosition { position: f32 },
+    SetDecay { seconds: f32 },
+}
+
+pub enum GranularCmd {
+    SetPosition { position: f32 },
+    SetSpray { spray: f32 },
+    SetGrainSize { seconds: f32 },
+    SetDensity { grains_per_second: f32 },
+    SetPitch { semitones: f32 },
+    SetWindow { window: GrainWindow },
+    SetReverseProbability { probability: f32 },
+}
+
+/// Synthesis controls shared by the kick, snare and hi-hat voices.
+pub enum DrumCmd {
+    /// Fixed tone frequency in Hz; 0.0 follows the triggering note.
+    SetToneFrequency { frequency: f32 },
+    /// 0.0 is tone only, 1.0 is noise only.
+    SetNoiseMix { mix: f32 },
+    /// -1.0 (dark) to 1.0 (bright); 0.0 leaves the noise white.
+    SetNoiseColor { color: f32 },
+    /// Level of the transient click at the start of each hit.
+    SetClick { amount: f32 },
+    /// Decay time of the pitch sweep in seconds.
+    SetPitchDecay { seconds: f32 },
+    /// 0.0 is clean, 1.0 is heavy saturation.
+    SetDrive { amount: f32 },
+}
+
+/// Controls for the DFAM voice and its built-in step sequencer.
+pub enum DFAMCmd {
+    /// When enabled, a note-on starts the 8-step sequencer and a note-off stops it.
+    SetSequencerEnabled {
+        enabled: bool,
+    },
+    SetClock {
+        clock: SequencerClock,
+    },
+    /// Pitch offset of one step relative to the triggering note.
+    SetStepPitch {
+        step: u8,
+        semitones: f32,
+    },
+    /// 0.0 to 1.0; scales the VCA level and the VCF envelope depth of one step.
+    SetStepVelocity {
+        step: u8,
+        velocity: f32,
+    },
+    /// Depth of the VCO 2 to VCO 1 linear FM.
+    SetFmAmount {
+        amount: f32,
+    },
+    SetVcoDecay {
+        seconds: f32,
+    },
+    /// Pitch sweep depth of the VCO envelope.
+    SetVcoEnvAmount {
+        semitones: f32,
+    },
+    SetVcfCutoff {
+        frequency: f32,
+    },
+    /// 0.0 to 4.0, self-oscillating near the top of the range.
+    SetVcfResonance {
+        resonance: f32,
+    },
+    SetVcfDecay {
+        seconds: f32,
+    },
+    /// Cutoff sweep depth of the VCF envelope.
+    SetVcfEnvAmount {
+        octaves: f32,
+    },
+    /// Per-step amplitude decay, used while the sequencer is enabled.
+    SetVcaDecay {
+        seconds: f32,
+    },
+}
@@ -1,4 +1,4 @@
-use crate::{id::EffectId, MonoEffect};
+use crate::{id::EffectId, MonoEffect, ParamDescriptor, ParamSkew, ParamUnit};
 use log::warn;
 
 #[repr(u32)]
@@ -16,6 +16,39 @@ impl DelayParameter {
     }
 }
 
+/// Parameters of [`Delay`], indexed by [`DelayParameter`].
+pub const DELAY_PARAMETERS: [ParamDescriptor; 4] = [
+    ParamDescriptor::new(
+        0,
+        "time",
+        "Time",
+        ParamUnit::Seconds,
+        0.0,
+        MAX_DELAY_SECONDS,
+        0.3,
+    ),
+    ParamDescriptor::new(
+        1,
+        "num_taps",
+        "Taps",
+        ParamUnit::Count,
+        1.0,
+        MAX_TAPS as f32,
+        1.0,
+    )
+    .with_skew(ParamSkew::Stepped),
+    ParamDescriptor::new(
+        2,
+        "feedback",
+        "Feedback",
+        ParamUnit::Generic,
+        0.0,
+        0.95,
+        0.3,
+    ),
+    ParamDescriptor::new(3, "mix", "Mix", ParamUnit::Generic, 0.0, 1.0, 0.3),
+];
+
 pub struct Delay {
     id: EffectId,
     sample_rate: f32,
@@ -26,6 +59,7 @@ pub struct Delay {
     // Buffer size (calculated from max delay time and sample rate)
     buffer_size: usize,
     // Delay parameters
+    delay_time_seconds: f32,
     delay_time_samples: usize,
     num_taps: usize,
     // Feedback and mix parameters
@@ -73,6 +107,7 @@ impl Delay {
             buffer,
             write_pos: 0,
             buffer_size: max_delay_samples,
+            delay_time_seconds,
             delay_time_samples,
             num_taps,
             feedback,
@@ -82,6 +117,7 @@ impl Delay {
 
     pub fn set_delay_time(&mut self, delay_time_seconds: f32) {
         let delay_time_seconds = delay_time_seconds.clamp(0.0, MAX_DELAY_SECONDS);
+        self.delay_time_seconds = delay_time_seconds;
         self.delay_time_samples = (self.sample_rate * delay_time_seconds) as usize;
     }
 
@@ -150,6 +186,17 @@ impl MonoEffect for Delay {
         }
     }
 
+    fn prepare(&mut self, sample_rate: f32, _max_block_size: usize) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        self.buffer_size = (sample_rate * MAX_DELAY_SECONDS) as usize + 1024;
+        self.buffer = vec![0.0f32; self.buffer_size];
+        self.write_pos = 0;
+        self.sample_rate = sample_rate;
+        self.set_delay_time(self.delay_time_seconds);
+    }
+
     fn set_parameter(&mut self, _index: u32, value: f32) {
         match _index {
             0 => self.set_delay_time(value),
@@ -164,6 +211,20 @@ impl MonoEffect for Delay {
         }
     }
 
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &DELAY_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.delay_time_seconds),
+            1 => Some(self.num_taps as f32),
+            2 => Some(self.feedback),
+            3 => Some(self.mix),
+            _ => None,
+        }
+    }
+
     fn reset(&mut self) {
         for s in self.buffer.iter_mut() {
             *s = 0.0;
@@ -1,7 +1,14 @@
-use crate::{id::EffectId, MonoEffect};
+use crate::{id::EffectId, MonoEffect, ParamDescriptor, ParamUnit};
+
+/// Parameters of [`Distortion`].
+pub const DISTORTION_PARAMETERS: [ParamDescriptor; 3] = [
+    ParamDescriptor::new(0, "drive", "Drive", ParamUnit::Generic, 0.0, 20.0, 1.0),
+    ParamDescriptor::new(1, "level", "Level", ParamUnit::Generic, 0.0, 2.0, 1.0),
+    ParamDescriptor::new(2, "mix", "Mix", ParamUnit::Generic, 0.0, 1.0, 1.0),
+];
 
 /// Various distortion algorithms
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DistortionType {
     /// Soft clipping using tanh
     Soft,
@@ -13,6 +20,16 @@ pub enum DistortionType {
     Foldback,
 }
 
+impl DistortionType {
+    /// Every distortion type, in the order used to serialize them as a number.
+    pub const ALL: [DistortionType; 4] = [
+        DistortionType::Soft,
+        DistortionType::Hard,
+        DistortionType::Tube,
+        DistortionType::Foldback,
+    ];
+}
+
 /// Distortion effect with pre/post filtering
 ///
 /// Signal chain:
@@ -80,16 +97,14 @@ impl MonoEffect for Distortion {
                     }
                 }
                 DistortionType::Foldback => {
-                    // Foldback distortion
-                    let mut x = driven;
-                    while !(-1.0..=1.0).contains(&x) {
-                        if x > 1.0 {
-                            x = 2.0 - x;
-                        } else if x < -1.0 {
-                            x = -2.0 - x;
-                        }
+                    // Foldback distortion: reflect at ±1, as a triangle wave of
+                    // period 4 so large or non-finite input cannot loop forever.
+                    let folded = (driven + 1.0).rem_euclid(4.0);
+                    if folded > 2.0 {
+                        3.0 - folded
+                    } else {
+                        folded - 1.0
                     }
-                    x
                 }
             };
 
@@ -99,7 +114,25 @@ impl MonoEffect for Distortion {
         }
     }
 
-    fn set_parameter(&mut self, _index: u32, _value: f32) {
-        // not implemented yet
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        match index {
+            0 => self.pre_gain = value,
+            1 => self.post_gain = value,
+            2 => self.mix = value.clamp(0.0, 1.0),
+            _ => (),
+        }
+    }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &DISTORTION_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.pre_gain),
+            1 => Some(self.post_gain),
+            2 => Some(self.mix),
+            _ => None,
+        }
     }
 }
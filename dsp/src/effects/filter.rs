@@ -1,8 +1,31 @@
-use crate::{id::EffectId, MonoEffect};
+use crate::{id::EffectId, MonoEffect, ParamDescriptor, ParamSkew, ParamUnit};
 use std::f32::consts::PI;
 
+/// Parameters of [`Filter`].
+pub const FILTER_PARAMETERS: [ParamDescriptor; 2] = [
+    ParamDescriptor::new(
+        0,
+        "cutoff",
+        "Cutoff",
+        ParamUnit::Hertz,
+        20.0,
+        20_000.0,
+        1000.0,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+    ParamDescriptor::new(
+        1,
+        "resonance",
+        "Resonance",
+        ParamUnit::Generic,
+        0.5,
+        10.0,
+        0.707,
+    ),
+];
+
 /// Filter types
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FilterType {
     /// Attenuates high frequencies
     LowPass,
@@ -14,6 +37,16 @@ pub enum FilterType {
     Notch,
 }
 
+impl FilterType {
+    /// Every filter type, in the order used to serialize them as a number.
+    pub const ALL: [FilterType; 4] = [
+        FilterType::LowPass,
+        FilterType::HighPass,
+        FilterType::BandPass,
+        FilterType::Notch,
+    ];
+}
+
 /// Biquad filter implementation
 ///
 /// Digital implementation of various filter types using the biquad structure.
@@ -27,6 +60,7 @@ pub struct Filter {
     filter_type: FilterType,
     cutoff: f32,
     resonance: f32,
+    sample_rate: f32,
     // Biquad coefficients
     b0: f32,
     b1: f32,
@@ -57,6 +91,7 @@ impl Filter {
             filter_type,
             cutoff,
             resonance,
+            sample_rate,
             b0: 1.0,
             b1: 0.0,
             b2: 0.0,
@@ -72,7 +107,9 @@ impl Filter {
     }
 
     fn update_coefficients(&mut self, sample_rate: f32) {
-        let omega = 2.0 * PI * self.cutoff / sample_rate;
+        self.sample_rate = sample_rate;
+        // Cutoffs at or above Nyquist would fold the response back down.
+        let omega = 2.0 * PI * self.cutoff.min(0.49 * sample_rate) / sample_rate;
         let sin_omega = omega.sin();
         let cos_omega = omega.cos();
         let q = 1.0 / (2.0 * self.resonance.max(0.5));
@@ -174,13 +211,28 @@ impl MonoEffect for Filter {
         }
     }
 
-    fn set_parameter(&mut self, _index: u32, _value: f32) {
-        // TODO: figure out how to get sample_rate here
-        // match index {
-        //     0 => self.set_cutoff(value, sample_rate),
-        //     1 => self.set_resonance(value, sample_rate),
-        //     _ => (),
-        // }
+    fn prepare(&mut self, sample_rate: f32, _max_block_size: usize) {
+        self.update_coefficients(sample_rate);
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        match index {
+            0 => self.set_cutoff(value, self.sample_rate),
+            1 => self.set_resonance(value, self.sample_rate),
+            _ => (),
+        }
+    }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &FILTER_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.cutoff),
+            1 => Some(self.resonance),
+            _ => None,
+        }
     }
 
     fn reset(&mut self) {
@@ -1,4 +1,14 @@
-use crate::{id::EffectId, MonoEffect, StereoEffect};
+use crate::{
+    id::EffectId, EffectType, MonoEffect, ParamDescriptor, ParamSkew, ParamUnit, StereoEffect,
+};
+
+/// Parameter of [`Gain`]. The normalized mapping treats the control value as a linear
+/// amplitude, so `0.5` is about -6 dB and `0.0` is silence.
+pub const GAIN_PARAMETERS: [ParamDescriptor; 1] =
+    [
+        ParamDescriptor::new(0, "gain", "Gain", ParamUnit::Decibels, -120.0, 0.0, 0.0)
+            .with_skew(ParamSkew::Decibel),
+    ];
 
 /// A simple effect that adjusts the volume of the audio signal. Units are in decibels (dB).
 pub struct Gain {
@@ -11,6 +21,11 @@ impl Gain {
     pub fn new(id: EffectId, gain_factor: f32) -> Self {
         Self { id, gain_factor } // 1.0 Default to no change in volume.
     }
+
+    /// The gain in decibels, floored at the bottom of the parameter range.
+    fn gain_db(&self) -> f32 {
+        (20.0 * self.gain_factor.log10()).max(GAIN_PARAMETERS[0].min)
+    }
 }
 
 impl StereoEffect for Gain {
@@ -18,6 +33,10 @@ impl StereoEffect for Gain {
         self.id
     }
 
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Gain)
+    }
+
     fn process(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], _sample_rate: f32) {
         // Use zip to iterate over both channels safely and efficiently.
         for (left_sample, right_sample) in left_buf.iter_mut().zip(right_buf.iter_mut()) {
@@ -35,6 +54,14 @@ impl StereoEffect for Gain {
             self.gain_factor = 10.0_f32.powf(value / 20.0);
         }
     }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &GAIN_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        (index == 0).then(|| self.gain_db())
+    }
 }
 
 impl MonoEffect for Gain {
@@ -55,4 +82,12 @@ impl MonoEffect for Gain {
             self.gain_factor = 10.0_f32.powf(value / 20.0);
         }
     }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &GAIN_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        (index == 0).then(|| self.gain_db())
+    }
 }
@@ -1,4 +1,27 @@
-use crate::{id::EffectId, MonoEffect};
+use crate::{id::EffectId, MonoEffect, ParamDescriptor, ParamSkew, ParamUnit};
+
+/// Parameters of [`MoogLadder`].
+pub const MOOG_LADDER_PARAMETERS: [ParamDescriptor; 2] = [
+    ParamDescriptor::new(
+        0,
+        "cutoff",
+        "Cutoff",
+        ParamUnit::Hertz,
+        20.0,
+        20_000.0,
+        1000.0,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+    ParamDescriptor::new(
+        1,
+        "resonance",
+        "Resonance",
+        ParamUnit::Generic,
+        0.0,
+        4.0,
+        0.0,
+    ),
+];
 
 pub struct MoogLadder {
     id: EffectId,
@@ -35,12 +58,19 @@ impl MoogLadder {
         self.resonance = resonance.clamp(0.0, 4.0);
     }
 
+    /// Recomputes the cutoff coefficient for a new host rate, keeping the filter state.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
     fn update_coefficients(&mut self) {
         let f = (std::f32::consts::PI * self.cutoff / self.sample_rate).tanh();
         self.g = f;
     }
 
-    fn process_private(&mut self, input: f32) -> f32 {
+    /// Filters a single sample; used by synth nodes that embed the ladder and modulate its cutoff.
+    pub(crate) fn process_sample(&mut self, input: f32) -> f32 {
         let k = self.resonance;
         let x = (input - k * self.y[4]).tanh();
 
@@ -62,10 +92,14 @@ impl MonoEffect for MoogLadder {
 
     fn process(&mut self, buffer: &mut [f32], _sample_rate: f32) {
         for sample in buffer.iter_mut() {
-            *sample = self.process_private(*sample);
+            *sample = self.process_sample(*sample);
         }
     }
 
+    fn prepare(&mut self, sample_rate: f32, _max_block_size: usize) {
+        self.set_sample_rate(sample_rate);
+    }
+
     fn set_parameter(&mut self, param_index: u32, value: f32) {
         match param_index {
             0 => self.set_cutoff(value),
@@ -73,4 +107,16 @@ impl MonoEffect for MoogLadder {
             _ => {}
         }
     }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &MOOG_LADDER_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.cutoff),
+            1 => Some(self.resonance),
+            _ => None,
+        }
+    }
 }
@@ -1,6 +1,9 @@
 use log::{info, warn};
 
-use crate::{id::EffectId, MonoEffect, Smoother, StereoEffect};
+use crate::{
+    id::EffectId, EffectType, MonoEffect, ParamDescriptor, ParamSkew, ParamUnit, Smoother,
+    StereoEffect,
+};
 
 #[repr(u32)]
 #[derive(Clone, Copy, Debug)]
@@ -18,11 +21,38 @@ impl ReverbParameter {
     }
 }
 
+/// Parameters shared by [`Reverb`] and [`StereoReverb`], indexed by [`ReverbParameter`].
+pub const REVERB_PARAMETERS: [ParamDescriptor; 5] = [
+    ParamDescriptor::new(0, "mix", "Mix", ParamUnit::Generic, 0.0, 1.0, 0.3),
+    ParamDescriptor::new(1, "decay", "Decay", ParamUnit::Generic, 0.0, 1.0, 1.0),
+    ParamDescriptor::new(
+        2,
+        "room_size",
+        "Room Size",
+        ParamUnit::Generic,
+        0.1,
+        3.0,
+        1.0,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+    ParamDescriptor::new(3, "damping", "Damping", ParamUnit::Generic, 0.0, 1.0, 0.0),
+    ParamDescriptor::new(
+        4,
+        "diffusion",
+        "Diffusion",
+        ParamUnit::Generic,
+        0.0,
+        1.0,
+        1.0,
+    ),
+];
+
 // The main Reverb effect
 pub struct Reverb {
     id: EffectId,
     // We need to store the sample_rate to recalculate delay sizes if room size changes
     sample_rate: f32,
+    room_size: f32,
 
     // Comb filter delays (in samples) - pre-allocated max size
     comb_buffers: [Vec<f32>; 4],
@@ -38,6 +68,13 @@ pub struct Reverb {
 
     // Single mix control (0.0 = dry only, 1.0 = wet only)
     mix: Smoother<f32>,
+
+    // Decay and damping both rewrite the comb feedback, so the values last set
+    // are kept for reporting; `None` until set, as the initial feedback matches
+    // neither.
+    decay: Option<f32>,
+    damping: Option<f32>,
+    diffusion: Option<f32>,
 }
 
 impl Reverb {
@@ -93,6 +130,7 @@ impl Reverb {
         Self {
             id,
             sample_rate,
+            room_size: 1.0,
 
             comb_buffers,
             comb_indices: [0; 4],
@@ -105,6 +143,9 @@ impl Reverb {
             allpass_feedback: 0.7,
 
             mix: Smoother::new(sample_rate, 0.1, 0.3), // Default mix at 0.3
+            decay: None,
+            damping: None,
+            diffusion: None,
         }
     }
 
@@ -145,6 +186,7 @@ impl Reverb {
     // Adjust reverb decay time by changing comb filter feedback
     pub fn set_decay_time(&mut self, decay: f32) {
         // decay: 0.0 = very short, 1.0 = very long
+        self.decay = Some(decay);
         let base_feedbacks = [0.84, 0.82, 0.79, 0.76];
         for (feedback, base_feedback) in self.comb_feedback.iter_mut().zip(base_feedbacks) {
             // Scale feedback but keep different values for each comb
@@ -159,6 +201,7 @@ impl Reverb {
         const BASE_COMB_DELAYS_MS: [f32; 4] = [29.7, 37.1, 41.1, 43.7];
         const BASE_ALLPASS_DELAYS_MS: [f32; 2] = [5.0, 1.7];
         let clamped_size = size.clamp(0.1, 3.0);
+        self.room_size = clamped_size;
 
         // Update comb filter delay lengths
         for (i, base_delay_ms) in BASE_COMB_DELAYS_MS.iter().enumerate() {
@@ -178,6 +221,7 @@ impl Reverb {
     // Adjust high frequency damping (simulates air absorption)
     pub fn set_damping(&mut self, damping: f32) {
         // damping: 0.0 = bright, 1.0 = very dark
+        self.damping = Some(damping);
         // The constants control how much damping affects shorter vs longer delays:
         // - DAMPING_BASE (0.3): Maximum damping reduction for the shortest delay
         // - DAMPING_STEP (0.05): How much less damping each subsequent delay gets
@@ -200,6 +244,7 @@ impl Reverb {
     // Adjust diffusion (how scattered/smooth the reverb sounds)
     pub fn set_diffusion(&mut self, diffusion: f32) {
         // diffusion: 0.0 = echoey, 1.0 = very smooth
+        self.diffusion = Some(diffusion);
         let base_allpass_feedback = 0.7;
         self.allpass_feedback = base_allpass_feedback * diffusion.clamp(0.0, 0.95);
     }
@@ -214,9 +259,7 @@ impl MonoEffect for Reverb {
         self.id
     }
 
-    fn process(&mut self, buf: &mut [f32], sample_rate: f32) {
-        self.sample_rate = sample_rate;
-
+    fn process(&mut self, buf: &mut [f32], _sample_rate: f32) {
         for sample in buf.iter_mut() {
             let input = *sample;
             let mut output = 0.0;
@@ -240,6 +283,21 @@ impl MonoEffect for Reverb {
         }
     }
 
+    fn prepare(&mut self, sample_rate: f32, _max_block_size: usize) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        // Delay lines are sized in samples, so a new rate needs new buffers.
+        let resized = Reverb::new(self.id, sample_rate);
+        self.comb_buffers = resized.comb_buffers;
+        self.comb_indices = [0; 4];
+        self.allpass_buffers = resized.allpass_buffers;
+        self.allpass_indices = [0; 2];
+        self.sample_rate = sample_rate;
+        self.set_room_size(self.room_size, sample_rate);
+        self.mix.set_sample_rate(sample_rate);
+    }
+
     fn set_parameter(&mut self, index: u32, value: f32) {
         match index {
             0 => self.set_mix(value),
@@ -251,6 +309,21 @@ impl MonoEffect for Reverb {
         }
     }
 
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &REVERB_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.mix.target()),
+            1 => self.decay,
+            2 => Some(self.room_size),
+            3 => self.damping,
+            4 => self.diffusion,
+            _ => None,
+        }
+    }
+
     fn reset(&mut self) {
         // Clear all buffers and reset indices
         for i in 0..4 {
@@ -284,17 +357,34 @@ impl StereoEffect for StereoReverb {
         self.left.id()
     }
 
+    fn effect_type(&self) -> Option<EffectType> {
+        Some(EffectType::Reverb)
+    }
+
     fn process(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], sample_rate: f32) {
         // Process left and right channels independently
         self.left.process(left_buf, sample_rate);
         self.right.process(right_buf, sample_rate);
     }
 
+    fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        self.left.prepare(sample_rate, max_block_size);
+        self.right.prepare(sample_rate, max_block_size);
+    }
+
     fn set_parameter(&mut self, index: u32, value: f32) {
         self.left.set_parameter(index, value);
         self.right.set_parameter(index, value);
     }
 
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &REVERB_PARAMETERS
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        MonoEffect::parameter(&self.left, index)
+    }
+
     fn reset(&mut self) {
         self.left.reset();
         self.right.reset();
@@ -1,8 +1,52 @@
 use crate::effects::{
     Delay, Distortion, DistortionType, Filter, FilterType, Gain, MoogLadder, Reverb, StereoReverb,
+    DELAY_PARAMETERS, DISTORTION_PARAMETERS, FILTER_PARAMETERS, GAIN_PARAMETERS,
+    MOOG_LADDER_PARAMETERS, REVERB_PARAMETERS,
 };
 use crate::id::EffectId;
-use crate::{MonoEffect, StereoEffect};
+use crate::{MonoEffect, ParamDescriptor, StereoEffect};
+
+/// Effects the factory can build, used to query their parameters before creating one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectType {
+    Gain,
+    Reverb,
+    Delay,
+    MoogLadder,
+    Filter,
+    Distortion,
+}
+
+impl EffectType {
+    /// Every effect type the factory can build.
+    pub const ALL: [EffectType; 6] = [
+        EffectType::Gain,
+        EffectType::Reverb,
+        EffectType::Delay,
+        EffectType::MoogLadder,
+        EffectType::Filter,
+        EffectType::Distortion,
+    ];
+
+    /// Stable id the [`crate::Registry`] and serialized specs know the type by.
+    pub const fn type_id(self) -> &'static str {
+        match self {
+            EffectType::Gain => "gain",
+            EffectType::Reverb => "reverb",
+            EffectType::Delay => "delay",
+            EffectType::MoogLadder => "moog_ladder",
+            EffectType::Filter => "filter",
+            EffectType::Distortion => "distortion",
+        }
+    }
+
+    /// The type registered under `type_id`, if any.
+    pub fn from_type_id(type_id: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|effect_type| effect_type.type_id() == type_id)
+    }
+}
 
 pub struct EffectFactory {
     sample_rate: f32,
@@ -13,6 +57,35 @@ impl EffectFactory {
         Self { sample_rate }
     }
 
+    /// Parameters published by an effect type, shared by its mono and stereo variants.
+    pub fn parameters(effect_type: EffectType) -> &'static [ParamDescriptor] {
+        match effect_type {
+            EffectType::Gain => &GAIN_PARAMETERS,
+            EffectType::Reverb => &REVERB_PARAMETERS,
+            EffectType::Delay => &DELAY_PARAMETERS,
+            EffectType::MoogLadder => &MOOG_LADDER_PARAMETERS,
+            EffectType::Filter => &FILTER_PARAMETERS,
+            EffectType::Distortion => &DISTORTION_PARAMETERS,
+        }
+    }
+
+    /// Creates the stereo variant of `effect_type` at its default settings, for
+    /// rebuilding master effects. `None` for types without a stereo variant.
+    pub fn create_stereo(
+        &self,
+        effect_type: EffectType,
+        id: EffectId,
+    ) -> Option<Box<dyn StereoEffect>> {
+        match effect_type {
+            EffectType::Gain => Some(self.create_stereo_gain(id, 1.0)),
+            EffectType::Reverb => Some(self.create_stereo_reverb(id)),
+            EffectType::Delay
+            | EffectType::MoogLadder
+            | EffectType::Filter
+            | EffectType::Distortion => None,
+        }
+    }
+
     /// Create a mono reverb effect
     pub fn create_mono_reverb(&self, id: EffectId) -> Box<dyn MonoEffect> {
         Box::new(Reverb::new(id, self.sample_rate))
@@ -43,7 +116,6 @@ impl EffectFactory {
     }
 
     /// Create a distortion effect
-    #[deprecated(note = "Doesn't work correctly")]
     pub fn create_distortion(
         &self,
         id: EffectId,
@@ -56,7 +128,6 @@ impl EffectFactory {
     }
 
     /// Create a filter effect
-    #[deprecated(note = "Doesn't work correctly")]
     pub fn create_filter(
         &self,
         id: EffectId,
@@ -3,19 +3,78 @@ use std::sync::Arc;
 use crate::{
     id::InstrumentId,
     instruments::{
-        HiHat, KickDrum, LoopRegion, MonophonicOscillator, MoogDFAM, PolyphonicOscillator,
-        SnareDrum, Waveform,
+        GranularParams, GranularSampler, HiHat, KickDrum, LoopRegion, MonophonicOscillator,
+        MoogDFAM, PluckedString, PolyphonicOscillator, SnareDrum, UnisonOscillator, UnisonParams,
+        Waveform, DFAM_PARAMETERS, GRANULAR_PARAMETERS, HIHAT_PARAMETERS, KICK_PARAMETERS,
+        PLUCKED_STRING_PARAMETERS, SNARE_PARAMETERS, UNISON_PARAMETERS,
     },
-    InstrumentTrait, SampleData,
+    InstrumentTrait, ParamDescriptor, SampleData, DEFAULT_MAX_BLOCK_SIZE,
 };
 
+/// Instruments the factory can build, used to query their parameters before creating one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstrumentType {
+    Oscillator,
+    UnisonOscillator,
+    HiHat,
+    KickDrum,
+    SnareDrum,
+    DFAM,
+    PluckedString,
+    SamplePlayer,
+    GranularSampler,
+}
+
+impl InstrumentType {
+    /// Every instrument type the factory can build.
+    pub const ALL: [InstrumentType; 9] = [
+        InstrumentType::Oscillator,
+        InstrumentType::UnisonOscillator,
+        InstrumentType::HiHat,
+        InstrumentType::KickDrum,
+        InstrumentType::SnareDrum,
+        InstrumentType::DFAM,
+        InstrumentType::PluckedString,
+        InstrumentType::SamplePlayer,
+        InstrumentType::GranularSampler,
+    ];
+}
+
 pub struct InstrumentFactory {
     sample_rate: f32,
+    max_block_size: usize,
 }
 
 impl InstrumentFactory {
     pub fn new(sample_rate: f32) -> Self {
-        InstrumentFactory { sample_rate }
+        Self::with_max_block_size(sample_rate, DEFAULT_MAX_BLOCK_SIZE)
+    }
+
+    /// Creates a factory whose instruments preallocate scratch buffers for
+    /// blocks of up to `max_block_size` frames.
+    pub fn with_max_block_size(sample_rate: f32, max_block_size: usize) -> Self {
+        InstrumentFactory {
+            sample_rate,
+            max_block_size: max_block_size.max(1),
+        }
+    }
+
+    pub fn max_block_size(&self) -> usize {
+        self.max_block_size
+    }
+
+    /// Parameters published by an instrument type, shared by its mono and polyphonic variants.
+    pub fn parameters(instrument_type: InstrumentType) -> &'static [ParamDescriptor] {
+        match instrument_type {
+            InstrumentType::Oscillator | InstrumentType::SamplePlayer => &[],
+            InstrumentType::UnisonOscillator => &UNISON_PARAMETERS,
+            InstrumentType::HiHat => &HIHAT_PARAMETERS,
+            InstrumentType::KickDrum => &KICK_PARAMETERS,
+            InstrumentType::SnareDrum => &SNARE_PARAMETERS,
+            InstrumentType::DFAM => &DFAM_PARAMETERS,
+            InstrumentType::PluckedString => &PLUCKED_STRING_PARAMETERS,
+            InstrumentType::GranularSampler => &GRANULAR_PARAMETERS,
+        }
     }
 
     pub fn create_simple_oscillator(
@@ -23,7 +82,7 @@ impl InstrumentFactory {
         instrument_id: InstrumentId,
         pan: f32,
     ) -> Box<dyn InstrumentTrait> {
-        Box::new(MonophonicOscillator::new(
+        self.finish(MonophonicOscillator::new(
             instrument_id,
             pan,
             self.sample_rate,
@@ -36,7 +95,7 @@ impl InstrumentFactory {
         pan: f32,
         waveform: Waveform,
     ) -> Box<dyn InstrumentTrait> {
-        Box::new(MonophonicOscillator::new_with_waveform(
+        self.finish(MonophonicOscillator::new_with_waveform(
             instrument_id,
             pan,
             self.sample_rate,
@@ -50,7 +109,7 @@ impl InstrumentFactory {
         pan: f32,
         max_polyphony: u8,
     ) -> Box<dyn InstrumentTrait> {
-        Box::new(PolyphonicOscillator::new(
+        self.finish(PolyphonicOscillator::new(
             instrument_id,
             pan,
             self.sample_rate,
@@ -58,8 +117,24 @@ impl InstrumentFactory {
         ))
     }
 
+    pub fn create_unison_oscillator(
+        &self,
+        instrument_id: InstrumentId,
+        pan: f32,
+        waveform: Waveform,
+        unison: UnisonParams,
+    ) -> Box<dyn InstrumentTrait> {
+        self.finish(UnisonOscillator::new(
+            instrument_id,
+            pan,
+            self.sample_rate,
+            waveform,
+            unison,
+        ))
+    }
+
     pub fn create_hihat(&self, instrument_id: InstrumentId, pan: f32) -> Box<dyn InstrumentTrait> {
-        Box::new(HiHat::new(instrument_id, pan, self.sample_rate))
+        self.finish(HiHat::new(instrument_id, pan, self.sample_rate))
     }
 
     pub fn create_kick_drum(
@@ -67,7 +142,7 @@ impl InstrumentFactory {
         instrument_id: InstrumentId,
         pan: f32,
     ) -> Box<dyn InstrumentTrait> {
-        Box::new(KickDrum::new(instrument_id, pan, self.sample_rate))
+        self.finish(KickDrum::new(instrument_id, pan, self.sample_rate))
     }
 
     pub fn create_snare_drum(
@@ -75,11 +150,25 @@ impl InstrumentFactory {
         instrument_id: InstrumentId,
         pan: f32,
     ) -> Box<dyn InstrumentTrait> {
-        Box::new(SnareDrum::new(instrument_id, pan, self.sample_rate))
+        self.finish(SnareDrum::new(instrument_id, pan, self.sample_rate))
     }
 
     pub fn create_dfam(&self, instrument_id: InstrumentId, pan: f32) -> Box<dyn InstrumentTrait> {
-        Box::new(MoogDFAM::new(instrument_id, pan, self.sample_rate))
+        self.finish(MoogDFAM::new(instrument_id, pan, self.sample_rate))
+    }
+
+    pub fn create_plucked_string(
+        &self,
+        instrument_id: InstrumentId,
+        pan: f32,
+        max_polyphony: u8,
+    ) -> Box<dyn InstrumentTrait> {
+        self.finish(PluckedString::new(
+            instrument_id,
+            pan,
+            self.sample_rate,
+            max_polyphony,
+        ))
     }
 
     pub fn create_one_shot_sample_player(
@@ -88,7 +177,7 @@ impl InstrumentFactory {
         pan: f32,
         sample_data: Arc<SampleData>,
     ) -> Box<dyn InstrumentTrait> {
-        Box::new(crate::instruments::SamplePlayer::new_one_shot(
+        self.finish(crate::instruments::SamplePlayer::new_one_shot(
             instrument_id,
             sample_data.clone(),
             self.sample_rate,
@@ -96,6 +185,24 @@ impl InstrumentFactory {
         ))
     }
 
+    pub fn create_granular_sampler(
+        &self,
+        instrument_id: InstrumentId,
+        pan: f32,
+        sample_data: Arc<SampleData>,
+        max_polyphony: u8,
+        params: GranularParams,
+    ) -> Box<dyn InstrumentTrait> {
+        self.finish(GranularSampler::new(
+            instrument_id,
+            sample_data,
+            self.sample_rate,
+            pan,
+            max_polyphony,
+            params,
+        ))
+    }
+
     pub fn create_loop_sample_player(
         &self,
         instrument_id: InstrumentId,
@@ -110,7 +217,7 @@ impl InstrumentFactory {
             .expect("This sample doesn't have loop data");
 
         let loop_region = LoopRegion::new(start_frame as f64, end_frame as f64);
-        Box::new(crate::instruments::SamplePlayer::new_with_loop(
+        self.finish(crate::instruments::SamplePlayer::new_with_loop(
             instrument_id,
             sample_data.clone(),
             self.sample_rate,
@@ -118,4 +225,155 @@ impl InstrumentFactory {
             loop_region,
         ))
     }
+
+    /// Boxes a new instrument prepared for the factory's sample rate and maximum block.
+    fn finish(&self, instrument: impl InstrumentTrait + 'static) -> Box<dyn InstrumentTrait> {
+        let mut instrument = Box::new(instrument);
+        instrument.prepare(self.sample_rate, self.max_block_size);
+        instrument
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EffectFactory, EnvelopeState, VoiceActivity};
+
+    #[test]
+    fn created_instruments_publish_queried_parameters() {
+        let factory = InstrumentFactory::new(48_000.0);
+        let cases = [
+            (
+                InstrumentType::UnisonOscillator,
+                factory.create_unison_oscillator(
+                    0,
+                    0.0,
+                    Waveform::Sawtooth,
+                    UnisonParams::default(),
+                ),
+            ),
+            (InstrumentType::HiHat, factory.create_hihat(1, 0.0)),
+            (InstrumentType::KickDrum, factory.create_kick_drum(2, 0.0)),
+            (InstrumentType::SnareDrum, factory.create_snare_drum(3, 0.0)),
+            (InstrumentType::DFAM, factory.create_dfam(4, 0.0)),
+            (
+                InstrumentType::PluckedString,
+                factory.create_plucked_string(5, 0.0, 4),
+            ),
+            (
+                InstrumentType::Oscillator,
+                factory.create_simple_oscillator(6, 0.0),
+            ),
+        ];
+        for (instrument_type, instrument) in cases {
+            let queried = InstrumentFactory::parameters(instrument_type);
+            assert_eq!(instrument.parameters(), queried, "{instrument_type:?}");
+            for (position, descriptor) in queried.iter().enumerate() {
+                assert_eq!(descriptor.index as usize, position);
+                assert_eq!(descriptor.clamp(descriptor.default), descriptor.default);
+            }
+        }
+    }
+
+    #[test]
+    fn small_scratch_buffers_render_long_blocks_in_chunks() {
+        fn render(factory: &InstrumentFactory) -> Vec<f32> {
+            let mut instrument =
+                factory.create_oscillator_with_waveform(0, 0.3, Waveform::Sawtooth);
+            instrument.note_on(57, 100);
+            let mut left = vec![0.0; 1000];
+            let mut right = vec![0.0; 1000];
+            instrument.process(&mut left, &mut right, 48_000.0);
+            left.extend(right);
+            left
+        }
+
+        let chunked = render(&InstrumentFactory::with_max_block_size(48_000.0, 64));
+        assert_eq!(chunked, render(&InstrumentFactory::new(48_000.0)));
+        assert!(chunked.iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn prepared_instruments_match_instruments_built_at_the_new_rate() {
+        fn build(rate: f32) -> Vec<Box<dyn InstrumentTrait>> {
+            let instruments = InstrumentFactory::new(rate);
+            let effects = EffectFactory::new(rate);
+            let mut kick = instruments.create_kick_drum(0, 0.0);
+            kick.add_effect(effects.create_mono_reverb(1));
+            let mut dfam = instruments.create_dfam(1, 0.0);
+            dfam.add_effect(effects.create_mono_delay(1, 0.01, 2, 0.5, 0.5));
+            let mut hihat = instruments.create_hihat(3, 0.0);
+            hihat.add_effect(effects.create_moog_ladder(1, 3_000.0, 1.0));
+            vec![
+                kick,
+                dfam,
+                instruments.create_snare_drum(2, 0.0),
+                hihat,
+                instruments.create_plucked_string(4, 0.0, 2),
+                instruments.create_polyphonic_oscillator(5, 0.0, 2),
+            ]
+        }
+
+        let prepared = build(48_000.0).into_iter().map(|mut instrument| {
+            instrument.prepare(96_000.0, DEFAULT_MAX_BLOCK_SIZE);
+            instrument
+        });
+        for (mut prepared, mut native) in prepared.zip(build(96_000.0)) {
+            let mut rendered = [[0.0; 4096]; 4];
+            for (instrument, output) in [&mut prepared, &mut native]
+                .into_iter()
+                .zip(rendered.chunks_mut(2))
+            {
+                instrument.note_on(45, 110);
+                let [left, right] = output else {
+                    unreachable!()
+                };
+                instrument.process(left, right, 96_000.0);
+            }
+            assert_eq!(rendered[0], rendered[2], "instrument {}", native.id());
+            assert_eq!(rendered[1], rendered[3], "instrument {}", native.id());
+            assert!(rendered[0].iter().any(|sample| *sample != 0.0));
+        }
+    }
+
+    #[test]
+    fn polyphonic_instruments_report_each_sounding_voice() {
+        fn activity(instrument: &dyn InstrumentTrait) -> Vec<(usize, VoiceActivity)> {
+            let mut voices = Vec::new();
+            instrument.voice_activity(&mut |index, activity| voices.push((index, activity)));
+            voices
+        }
+
+        let mut instrument =
+            InstrumentFactory::new(48_000.0).create_polyphonic_oscillator(0, 0.0, 4);
+        let mut left = [0.0; 256];
+        let mut right = [0.0; 256];
+        assert!(activity(instrument.as_ref()).is_empty());
+
+        instrument.note_on(60, 255);
+        instrument.note_on(64, 255);
+        instrument.process(&mut left, &mut right, 48_000.0);
+        let voices = activity(instrument.as_ref());
+        assert_eq!(
+            voices
+                .iter()
+                .map(|(index, voice)| (*index, voice.note, voice.stage))
+                .collect::<Vec<_>>(),
+            [
+                (0, 60, Some(EnvelopeState::Attack)),
+                (1, 64, Some(EnvelopeState::Attack))
+            ]
+        );
+        assert!(voices.iter().all(|(_, voice)| voice.level > 0.0));
+
+        instrument.note_off();
+        instrument.process(&mut left, &mut right, 48_000.0);
+        assert!(activity(instrument.as_ref())
+            .iter()
+            .all(|(_, voice)| voice.stage == Some(EnvelopeState::Release)));
+        for _ in 0..48_000 * 10 / 256 {
+            instrument.process(&mut left, &mut right, 48_000.0);
+        }
+        assert!(activity(instrument.as_ref()).is_empty());
+    }
 }
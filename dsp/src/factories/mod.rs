@@ -1,7 +1,9 @@
 mod effect_factory;
 mod instrument_factory;
+mod registry;
 mod voice_factory;
 
 pub use effect_factory::*;
 pub use instrument_factory::*;
+pub use registry::*;
 pub use voice_factory::*;
@@ -0,0 +1,822 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::commands::{EnvelopeCmd, SynthCmd};
+use crate::effects::{DistortionType, FilterType};
+use crate::id::{EffectId, InstrumentId, SampleId};
+use crate::instruments::{
+    DetuneCurve, GrainWindow, GranularParams, UnisonParams, Waveform, DEFAULT_GRANULAR_POLYPHONY,
+    DEFAULT_PLUCKED_STRING_POLYPHONY, MAX_UNISON_VOICES,
+};
+use crate::{
+    find_parameter, EffectFactory, EffectType, InstrumentFactory, InstrumentTrait, InstrumentType,
+    MonoEffect, SampleData, VoiceEffects, MAX_VOICE_EFFECTS,
+};
+
+/// Serialized values of the voice amplitude envelope, accepted by every instrument type.
+pub const AMP_ENVELOPE_KEYS: [&str; 4] = ["amp_attack", "amp_decay", "amp_sustain", "amp_release"];
+
+/// Serialized description of an effect: its registered type, the version its values were
+/// written for, and plain parameter values keyed by id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectSpec {
+    pub type_id: &'static str,
+    pub version: u32,
+    pub effect_id: EffectId,
+    pub values: Vec<(&'static str, f32)>,
+}
+
+/// Serialized description of an instrument and the effects on its voices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentSpec {
+    pub type_id: &'static str,
+    pub version: u32,
+    pub values: Vec<(&'static str, f32)>,
+    pub effects: Vec<EffectSpec>,
+}
+
+/// Read-only view of serialized values handed to a registered constructor.
+pub struct ParamValues<'a> {
+    type_id: &'a str,
+    values: &'a [(&'static str, f32)],
+    samples: &'a HashMap<SampleId, Arc<SampleData>>,
+}
+
+impl ParamValues<'_> {
+    pub fn get(&self, id: &str) -> Option<f32> {
+        self.values
+            .iter()
+            .find(|(key, _)| *key == id)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn get_or(&self, id: &str, default: f32) -> f32 {
+        self.get(id).unwrap_or(default)
+    }
+
+    /// Reads a value that selects one of `choices` by position.
+    pub fn choice<T: Copy>(&self, id: &str, choices: &[T]) -> Result<Option<T>, RegistryError> {
+        let Some(value) = self.get(id) else {
+            return Ok(None);
+        };
+        let index = value.round();
+        if index < 0.0 || index as usize >= choices.len() {
+            return Err(RegistryError::InvalidValue {
+                type_id: self.type_id.to_string(),
+                parameter: id.to_string(),
+                value,
+            });
+        }
+        Ok(Some(choices[index as usize]))
+    }
+
+    /// Looks up the sample whose id is stored under `id`, sample 0 when it is absent.
+    pub fn sample(&self, id: &str) -> Result<Arc<SampleData>, RegistryError> {
+        let sample_id = self.get_or(id, 0.0).round().max(0.0) as SampleId;
+        self.samples
+            .get(&sample_id)
+            .cloned()
+            .ok_or_else(|| RegistryError::UnknownSample {
+                type_id: self.type_id.to_string(),
+                sample_id,
+            })
+    }
+}
+
+/// Why a serialized instrument or effect could not be built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    UnknownType {
+        type_id: String,
+    },
+    UnsupportedVersion {
+        type_id: String,
+        version: u32,
+        supported: Vec<u32>,
+    },
+    /// The value is neither a published parameter nor a construction option of the type.
+    UnknownParameter {
+        type_id: String,
+        parameter: String,
+    },
+    InvalidValue {
+        type_id: String,
+        parameter: String,
+        value: f32,
+    },
+    /// The spec refers to a sample that was not added with [`Registry::add_sample`].
+    UnknownSample {
+        type_id: String,
+        sample_id: SampleId,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::UnknownType { type_id } => write!(f, "unknown type '{type_id}'"),
+            RegistryError::UnsupportedVersion {
+                type_id,
+                version,
+                supported,
+            } => write!(
+                f,
+                "'{type_id}' version {version} is not supported (supported: {supported:?})"
+            ),
+            RegistryError::UnknownParameter { type_id, parameter } => {
+                write!(f, "'{type_id}' has no parameter '{parameter}'")
+            }
+            RegistryError::InvalidValue {
+                type_id,
+                parameter,
+                value,
+            } => write!(
+                f,
+                "invalid value {value} for '{type_id}' parameter '{parameter}'"
+            ),
+            RegistryError::UnknownSample { type_id, sample_id } => {
+                write!(f, "'{type_id}' refers to unknown sample {sample_id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+pub type InstrumentConstructor = fn(
+    &InstrumentFactory,
+    InstrumentId,
+    &ParamValues,
+) -> Result<Box<dyn InstrumentTrait>, RegistryError>;
+
+pub type EffectConstructor =
+    fn(&EffectFactory, EffectId, &ParamValues) -> Result<Box<dyn MonoEffect>, RegistryError>;
+
+struct Registration<C> {
+    type_id: &'static str,
+    version: u32,
+    /// Values consumed by the constructor rather than applied as parameters afterwards.
+    options: &'static [&'static str],
+    constructor: C,
+}
+
+/// Builds instruments and effects from serialized specs, keyed by stable type ids and
+/// versions.
+///
+/// A constructor creates the processor from its options; every other value is then
+/// applied through the processor's published parameters, clamped to their ranges.
+/// Instruments additionally accept [`AMP_ENVELOPE_KEYS`]. Sample-based instruments
+/// read their audio from the samples added with [`Registry::add_sample`].
+pub struct Registry {
+    instruments: Vec<Registration<InstrumentConstructor>>,
+    effects: Vec<Registration<EffectConstructor>>,
+    samples: HashMap<SampleId, Arc<SampleData>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self {
+            instruments: Vec::new(),
+            effects: Vec::new(),
+            samples: HashMap::new(),
+        }
+    }
+
+    /// A registry with every built-in instrument and effect at its current version.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register_instrument(
+            "oscillator",
+            1,
+            &[
+                "waveform",
+                "voices",
+                "detune",
+                "detune_curve",
+                "phase_randomness",
+                "stereo_spread",
+            ],
+            build_oscillator,
+        );
+        registry.register_instrument("hihat", 1, &[], |factory, id, _| {
+            Ok(factory.create_hihat(id, 0.0))
+        });
+        registry.register_instrument("kick_drum", 1, &[], |factory, id, _| {
+            Ok(factory.create_kick_drum(id, 0.0))
+        });
+        registry.register_instrument("snare_drum", 1, &[], |factory, id, _| {
+            Ok(factory.create_snare_drum(id, 0.0))
+        });
+        registry.register_instrument("dfam", 1, &[], |factory, id, _| {
+            Ok(factory.create_dfam(id, 0.0))
+        });
+        registry.register_instrument(
+            "plucked_string",
+            1,
+            &["polyphony"],
+            |factory, id, values| {
+                let polyphony = polyphony(values, DEFAULT_PLUCKED_STRING_POLYPHONY);
+                Ok(factory.create_plucked_string(id, 0.0, polyphony))
+            },
+        );
+        registry.register_instrument("sample", 1, &["sample"], |factory, id, values| {
+            let sample = values.sample("sample")?;
+            Ok(
+                if sample.loop_start.is_some() && sample.loop_end.is_some() {
+                    factory.create_loop_sample_player(id, 0.0, sample)
+                } else {
+                    factory.create_one_shot_sample_player(id, 0.0, sample)
+                },
+            )
+        });
+        registry.register_instrument(
+            "granular",
+            1,
+            &["sample", "polyphony", "window"],
+            |factory, id, values| {
+                let params = GranularParams {
+                    window: values
+                        .choice("window", &GrainWindow::ALL)?
+                        .unwrap_or_default(),
+                    ..GranularParams::default()
+                };
+                Ok(factory.create_granular_sampler(
+                    id,
+                    0.0,
+                    values.sample("sample")?,
+                    polyphony(values, DEFAULT_GRANULAR_POLYPHONY),
+                    params,
+                ))
+            },
+        );
+
+        registry.register_effect(EffectType::Reverb.type_id(), 1, &[], |factory, id, _| {
+            Ok(factory.create_mono_reverb(id))
+        });
+        registry.register_effect(
+            EffectType::Delay.type_id(),
+            1,
+            &[],
+            |factory, id, values| {
+                Ok(factory.create_mono_delay(
+                    id,
+                    values.get_or("time", 0.3),
+                    values.get_or("num_taps", 1.0) as usize,
+                    values.get_or("feedback", 0.3),
+                    values.get_or("mix", 0.3),
+                ))
+            },
+        );
+        registry.register_effect(EffectType::Gain.type_id(), 1, &[], |factory, id, _| {
+            Ok(factory.create_mono_gain(id, 0.0))
+        });
+        registry.register_effect(
+            EffectType::MoogLadder.type_id(),
+            1,
+            &[],
+            |factory, id, values| {
+                Ok(factory.create_moog_ladder(
+                    id,
+                    values.get_or("cutoff", 1000.0),
+                    values.get_or("resonance", 0.0),
+                ))
+            },
+        );
+        registry.register_effect(
+            EffectType::Filter.type_id(),
+            1,
+            &["filter_type"],
+            |factory, id, values| {
+                let filter_type = values
+                    .choice("filter_type", &FilterType::ALL)?
+                    .unwrap_or(FilterType::LowPass);
+                Ok(factory.create_filter(id, filter_type, 1000.0, 0.707))
+            },
+        );
+        registry.register_effect(
+            EffectType::Distortion.type_id(),
+            1,
+            &["distortion_type"],
+            |factory, id, values| {
+                let distortion_type = values
+                    .choice("distortion_type", &DistortionType::ALL)?
+                    .unwrap_or(DistortionType::Soft);
+                Ok(factory.create_distortion(id, distortion_type, 1.0, 1.0, 1.0))
+            },
+        );
+        registry
+    }
+
+    /// Makes a sample available to specs that refer to `sample_id`, replacing any
+    /// sample already stored under that id.
+    pub fn add_sample(&mut self, sample_id: SampleId, sample: Arc<SampleData>) {
+        self.samples.insert(sample_id, sample);
+    }
+
+    /// Registers an instrument type. Registering an existing type and version replaces it.
+    pub fn register_instrument(
+        &mut self,
+        type_id: &'static str,
+        version: u32,
+        options: &'static [&'static str],
+        constructor: InstrumentConstructor,
+    ) {
+        register(
+            &mut self.instruments,
+            Registration {
+                type_id,
+                version,
+                options,
+                constructor,
+            },
+        );
+    }
+
+    /// Registers an effect type. Registering an existing type and version replaces it.
+    pub fn register_effect(
+        &mut self,
+        type_id: &'static str,
+        version: u32,
+        options: &'static [&'static str],
+        constructor: EffectConstructor,
+    ) {
+        register(
+            &mut self.effects,
+            Registration {
+                type_id,
+                version,
+                options,
+                constructor,
+            },
+        );
+    }
+
+    /// Resolves a type or value id read from a serialized spec to the static id the
+    /// registry knows it by. Covers registered types and their options,
+    /// [`AMP_ENVELOPE_KEYS`], and the parameters of every built-in instrument and
+    /// effect type. Returns `None` for ids no spec could contain.
+    pub fn intern(&self, id: &str) -> Option<&'static str> {
+        let registered = self
+            .instruments
+            .iter()
+            .map(|r| (r.type_id, r.options))
+            .chain(self.effects.iter().map(|r| (r.type_id, r.options)))
+            .flat_map(|(type_id, options)| std::iter::once(type_id).chain(options.iter().copied()));
+        registered
+            .chain(AMP_ENVELOPE_KEYS)
+            .find(|known| *known == id)
+            .or_else(|| builtin_parameter_id(id))
+    }
+
+    /// Builds a fully configured instrument, including one instance of each effect per voice.
+    pub fn create_instrument(
+        &self,
+        instrument_factory: &InstrumentFactory,
+        effect_factory: &EffectFactory,
+        instrument_id: InstrumentId,
+        spec: &InstrumentSpec,
+    ) -> Result<Box<dyn InstrumentTrait>, RegistryError> {
+        let registration = lookup(&self.instruments, spec.type_id, spec.version)?;
+        let values = ParamValues {
+            type_id: spec.type_id,
+            values: &spec.values,
+            samples: &self.samples,
+        };
+        let mut instrument =
+            (registration.constructor)(instrument_factory, instrument_id, &values)?;
+
+        for &(id, value) in &spec.values {
+            if registration.options.contains(&id) {
+                continue;
+            }
+            if let Some(descriptor) = find_parameter(instrument.parameters(), id) {
+                instrument.set_parameter(descriptor.index, descriptor.clamp(value));
+            } else if let Some(command) = amp_envelope_command(id, value) {
+                instrument.try_handle_command(&SynthCmd::EnvelopeCommand {
+                    envelope_id: Some(0),
+                    command,
+                });
+            } else {
+                return Err(unknown_parameter(spec.type_id, id));
+            }
+        }
+
+        let voices = instrument.voice_count().min(MAX_VOICE_EFFECTS);
+        for effect in &spec.effects {
+            let mut effects = VoiceEffects::new();
+            for _ in 0..voices {
+                effects.push(self.create_effect(effect_factory, effect)?);
+            }
+            instrument.add_voice_effects(effects);
+        }
+        Ok(instrument)
+    }
+
+    /// Builds a configured mono effect.
+    pub fn create_effect(
+        &self,
+        effect_factory: &EffectFactory,
+        spec: &EffectSpec,
+    ) -> Result<Box<dyn MonoEffect>, RegistryError> {
+        let registration = lookup(&self.effects, spec.type_id, spec.version)?;
+        let values = ParamValues {
+            type_id: spec.type_id,
+            values: &spec.values,
+            samples: &self.samples,
+        };
+        let mut effect = (registration.constructor)(effect_factory, spec.effect_id, &values)?;
+
+        for &(id, value) in &spec.values {
+            if registration.options.contains(&id) {
+                continue;
+            }
+            let descriptor = find_parameter(effect.parameters(), id)
+                .ok_or_else(|| unknown_parameter(spec.type_id, id))?;
+            effect.set_parameter(descriptor.index, descriptor.clamp(value));
+        }
+        Ok(effect)
+    }
+}
+
+/// Resolves `id` to the static id a built-in instrument or effect type publishes a
+/// parameter under, or `None` if none of them has such a parameter.
+pub fn builtin_parameter_id(id: &str) -> Option<&'static str> {
+    InstrumentType::ALL
+        .into_iter()
+        .flat_map(InstrumentFactory::parameters)
+        .chain(
+            EffectType::ALL
+                .into_iter()
+                .flat_map(EffectFactory::parameters),
+        )
+        .map(|descriptor| descriptor.id)
+        .find(|known| *known == id)
+}
+
+fn register<C>(registrations: &mut Vec<Registration<C>>, registration: Registration<C>) {
+    registrations.retain(|existing| {
+        existing.type_id != registration.type_id || existing.version != registration.version
+    });
+    registrations.push(registration);
+}
+
+fn lookup<'a, C>(
+    registrations: &'a [Registration<C>],
+    type_id: &str,
+    version: u32,
+) -> Result<&'a Registration<C>, RegistryError> {
+    let mut supported = Vec::new();
+    for registration in registrations.iter().filter(|r| r.type_id == type_id) {
+        if registration.version == version {
+            return Ok(registration);
+        }
+        supported.push(registration.version);
+    }
+    if supported.is_empty() {
+        return Err(RegistryError::UnknownType {
+            type_id: type_id.to_string(),
+        });
+    }
+    supported.sort_unstable();
+    Err(RegistryError::UnsupportedVersion {
+        type_id: type_id.to_string(),
+        version,
+        supported,
+    })
+}
+
+fn unknown_parameter(type_id: &str, parameter: &str) -> RegistryError {
+    RegistryError::UnknownParameter {
+        type_id: type_id.to_string(),
+        parameter: parameter.to_string(),
+    }
+}
+
+fn amp_envelope_command(id: &str, value: f32) -> Option<EnvelopeCmd> {
+    match id {
+        "amp_attack" => Some(EnvelopeCmd::SetAttack { attack: value }),
+        "amp_decay" => Some(EnvelopeCmd::SetDecay { decay: value }),
+        "amp_sustain" => Some(EnvelopeCmd::SetSustain { sustain: value }),
+        "amp_release" => Some(EnvelopeCmd::SetRelease { release: value }),
+        _ => None,
+    }
+}
+
+fn polyphony(values: &ParamValues, default: u8) -> u8 {
+    values
+        .get_or("polyphony", default.into())
+        .round()
+        .clamp(1.0, u8::MAX.into()) as u8
+}
+
+fn build_oscillator(
+    factory: &InstrumentFactory,
+    id: InstrumentId,
+    values: &ParamValues,
+) -> Result<Box<dyn InstrumentTrait>, RegistryError> {
+    let waveform = values
+        .choice("waveform", &Waveform::ALL)?
+        .unwrap_or(Waveform::Sine);
+    let voices = values
+        .get_or("voices", 1.0)
+        .round()
+        .clamp(1.0, MAX_UNISON_VOICES as f32) as u8;
+    if voices <= 1 {
+        return Ok(factory.create_oscillator_with_waveform(id, 0.0, waveform));
+    }
+    let defaults = UnisonParams::default();
+    let unison = UnisonParams {
+        voices,
+        detune_cents: values.get_or("detune", defaults.detune_cents),
+        detune_curve: values
+            .choice("detune_curve", &DetuneCurve::ALL)?
+            .unwrap_or(defaults.detune_curve),
+        phase_randomness: values.get_or("phase_randomness", defaults.phase_randomness),
+        stereo_spread: values.get_or("stereo_spread", defaults.stereo_spread),
+    };
+    Ok(factory.create_unison_oscillator(id, 0.0, waveform, unison))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn build(spec: &InstrumentSpec) -> Result<Box<dyn InstrumentTrait>, RegistryError> {
+        Registry::with_builtins().create_instrument(
+            &InstrumentFactory::new(SAMPLE_RATE),
+            &EffectFactory::new(SAMPLE_RATE),
+            0,
+            spec,
+        )
+    }
+
+    fn spec(type_id: &'static str, values: Vec<(&'static str, f32)>) -> InstrumentSpec {
+        InstrumentSpec {
+            type_id,
+            version: 1,
+            values,
+            effects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn builds_registered_types_with_effects() {
+        let mut kick = spec(
+            "kick_drum",
+            vec![("pitch_env_amount", 80.0), ("drive", 0.5)],
+        );
+        kick.effects.push(EffectSpec {
+            type_id: "reverb",
+            version: 1,
+            effect_id: 1,
+            values: vec![("mix", 0.2), ("room_size", 1.5)],
+        });
+        let mut instrument = build(&kick).unwrap();
+
+        let mut left = vec![0.0; 256];
+        let mut right = vec![0.0; 256];
+        instrument.note_on(36, 100);
+        instrument.process(&mut left, &mut right, SAMPLE_RATE);
+        assert!(left.iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn built_instruments_report_the_values_they_were_given() {
+        let mut kick = spec(
+            "kick_drum",
+            vec![("drive", 0.5), ("pitch_decay", 0.2), ("amp_release", 0.4)],
+        );
+        kick.effects.push(EffectSpec {
+            type_id: "delay",
+            version: 1,
+            effect_id: 1,
+            values: vec![("feedback", 0.6)],
+        });
+        let instrument = build(&kick).unwrap();
+
+        let drive = find_parameter(instrument.parameters(), "drive").unwrap();
+        assert_eq!(instrument.parameter(drive.index), Some(0.5));
+        let pitch_decay = find_parameter(instrument.parameters(), "pitch_decay").unwrap();
+        assert_eq!(instrument.parameter(pitch_decay.index), Some(0.2));
+
+        let delay = instrument.voice_effect(0).unwrap();
+        let feedback = find_parameter(delay.parameters(), "feedback").unwrap();
+        assert_eq!(delay.parameter(feedback.index), Some(0.6));
+        assert!(instrument.voice_effect(1).is_none());
+    }
+
+    #[test]
+    fn dfam_sequencer_values_round_trip_through_parameters() {
+        let dfam = build(&spec(
+            "dfam",
+            vec![
+                ("sequencer_enabled", 1.0),
+                ("clock_rate", 6.0),
+                ("clock_mode", 1.0),
+                ("step_pitch_3", -7.0),
+                ("step_velocity_7", 0.25),
+            ],
+        ))
+        .unwrap();
+
+        let value = |id| dfam.parameter(find_parameter(dfam.parameters(), id).unwrap().index);
+        assert_eq!(value("sequencer_enabled"), Some(1.0));
+        assert_eq!(value("clock_mode"), Some(1.0));
+        assert_eq!(value("clock_rate"), Some(6.0));
+        assert_eq!(value("step_pitch_3"), Some(-7.0));
+        assert_eq!(value("step_pitch_4"), Some(0.0));
+        assert_eq!(value("step_velocity_7"), Some(0.25));
+    }
+
+    #[test]
+    fn plucked_strings_default_to_the_model_polyphony() {
+        let plucked = build(&spec("plucked_string", Vec::new())).unwrap();
+        assert_eq!(
+            plucked.voice_count(),
+            usize::from(DEFAULT_PLUCKED_STRING_POLYPHONY)
+        );
+        let plucked = build(&spec("plucked_string", vec![("polyphony", 2.0)])).unwrap();
+        assert_eq!(plucked.voice_count(), 2);
+    }
+
+    #[test]
+    fn sample_instruments_read_registered_samples() {
+        let mut registry = Registry::with_builtins();
+        let sample = |loop_start, loop_end| {
+            Arc::new(SampleData {
+                data: vec![0.5; 4_800],
+                sample_rate: SAMPLE_RATE,
+                channels: 1,
+                loop_start,
+                loop_end,
+            })
+        };
+        registry.add_sample(3, sample(None, None));
+        registry.add_sample(4, sample(Some(100), Some(400)));
+        let factory = InstrumentFactory::new(SAMPLE_RATE);
+        let effects = EffectFactory::new(SAMPLE_RATE);
+        let create =
+            |spec: &InstrumentSpec| registry.create_instrument(&factory, &effects, 0, spec);
+
+        for spec in [
+            spec("sample", vec![("sample", 3.0)]),
+            spec("sample", vec![("sample", 4.0)]),
+            spec(
+                "granular",
+                vec![
+                    ("sample", 3.0),
+                    ("polyphony", 2.0),
+                    ("window", 3.0),
+                    ("density", 200.0),
+                ],
+            ),
+        ] {
+            let mut instrument = create(&spec).unwrap();
+            let mut left = vec![0.0; 512];
+            let mut right = vec![0.0; 512];
+            instrument.note_on(60, 100);
+            instrument.process(&mut left, &mut right, SAMPLE_RATE);
+            assert!(left.iter().any(|sample| *sample != 0.0), "{spec:?}");
+        }
+
+        let granular = create(&spec("granular", vec![("sample", 3.0)])).unwrap();
+        assert_eq!(
+            granular.voice_count(),
+            usize::from(DEFAULT_GRANULAR_POLYPHONY)
+        );
+        assert!(matches!(
+            create(&spec("sample", vec![("sample", 9.0)])),
+            Err(RegistryError::UnknownSample { sample_id: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn filter_and_distortion_are_built_from_specs() {
+        let registry = Registry::with_builtins();
+        let factory = EffectFactory::new(SAMPLE_RATE);
+        let effect = |type_id, values| EffectSpec {
+            type_id,
+            version: 1,
+            effect_id: 1,
+            values,
+        };
+
+        let filter = registry
+            .create_effect(
+                &factory,
+                &effect("filter", vec![("filter_type", 1.0), ("cutoff", 250.0)]),
+            )
+            .unwrap();
+        let cutoff = find_parameter(filter.parameters(), "cutoff").unwrap();
+        assert_eq!(filter.parameter(cutoff.index), Some(250.0));
+
+        let mut distortion = registry
+            .create_effect(
+                &factory,
+                &effect(
+                    "distortion",
+                    vec![("distortion_type", 3.0), ("drive", 12.0)],
+                ),
+            )
+            .unwrap();
+        let drive = find_parameter(distortion.parameters(), "drive").unwrap();
+        assert_eq!(distortion.parameter(drive.index), Some(12.0));
+        let mut buffer = [0.9, -0.7, 0.3, f32::NAN];
+        distortion.process(&mut buffer, SAMPLE_RATE);
+        assert!(
+            buffer[..3].iter().all(|sample| sample.abs() <= 1.0),
+            "{buffer:?}"
+        );
+    }
+
+    #[test]
+    fn intern_resolves_ids_a_spec_can_contain() {
+        let registry = Registry::with_builtins();
+        for id in [
+            "kick_drum",
+            "reverb",
+            "polyphony",
+            "amp_attack",
+            "drive",
+            "room_size",
+        ] {
+            assert_eq!(registry.intern(id), Some(id));
+        }
+        assert_eq!(registry.intern("wobble"), None);
+    }
+
+    #[test]
+    fn unknown_types_versions_and_parameters_are_reported() {
+        assert!(matches!(
+            build(&spec("theremin", Vec::new())),
+            Err(RegistryError::UnknownType { type_id }) if type_id == "theremin"
+        ));
+
+        let mut future = spec("hihat", Vec::new());
+        future.version = 2;
+        assert!(matches!(
+            build(&future),
+            Err(RegistryError::UnsupportedVersion { version: 2, supported, .. }) if supported == [1]
+        ));
+
+        assert!(matches!(
+            build(&spec("hihat", vec![("wobble", 1.0)])),
+            Err(RegistryError::UnknownParameter { parameter, .. }) if parameter == "wobble"
+        ));
+
+        assert!(matches!(
+            build(&spec("oscillator", vec![("waveform", 9.0)])),
+            Err(RegistryError::InvalidValue { parameter, .. }) if parameter == "waveform"
+        ));
+    }
+
+    #[test]
+    fn every_effect_type_is_registered_under_its_type_id() {
+        let registry = Registry::with_builtins();
+        for effect_type in EffectType::ALL {
+            assert!(
+                registry
+                    .effects
+                    .iter()
+                    .any(|registration| registration.type_id == effect_type.type_id()),
+                "{effect_type:?}"
+            );
+            assert_eq!(
+                EffectType::from_type_id(effect_type.type_id()),
+                Some(effect_type)
+            );
+        }
+    }
+
+    #[test]
+    fn newer_versions_can_be_registered_alongside_older_ones() {
+        let mut registry = Registry::with_builtins();
+        registry.register_instrument("hihat", 2, &[], |factory, id, _| {
+            Ok(factory.create_plucked_string(id, 0.0, 3))
+        });
+        let factory = InstrumentFactory::new(SAMPLE_RATE);
+        let effects = EffectFactory::new(SAMPLE_RATE);
+
+        let mut current = spec("hihat", Vec::new());
+        let legacy = registry
+            .create_instrument(&factory, &effects, 0, &current)
+            .unwrap();
+        assert_eq!(legacy.voice_count(), 1);
+
+        current.version = 2;
+        let upgraded = registry
+            .create_instrument(&factory, &effects, 0, &current)
+            .unwrap();
+        assert_eq!(upgraded.voice_count(), 3);
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     id::VoiceId,
     instruments::{OscillatorNode, SamplePlayerNode},
     synth_infra::{Voice, VoiceTrait},
-    Envelope, MonoEffectChain, SampleData,
+    Envelope, MonoEffectChain, SampleData, DEFAULT_MAX_BLOCK_SIZE,
 };
 
 pub enum InstrumentDefinition {
@@ -16,12 +16,22 @@ pub enum InstrumentDefinition {
 /// This ensures all allocations happen before a voice is sent to the audio thread.
 pub struct VoiceFactory {
     sample_rate: f32,
+    max_block_size: usize,
     // Could also hold references to instrument definitions, etc.
 }
 
 impl VoiceFactory {
     pub fn new(sample_rate: f32) -> Self {
-        Self { sample_rate }
+        Self::with_max_block_size(sample_rate, DEFAULT_MAX_BLOCK_SIZE)
+    }
+
+    /// Creates a factory whose voices preallocate scratch buffers for blocks of
+    /// up to `max_block_size` frames.
+    pub fn with_max_block_size(sample_rate: f32, max_block_size: usize) -> Self {
+        Self {
+            sample_rate,
+            max_block_size: max_block_size.max(1),
+        }
     }
 
     /// Creates a new, fully initialized voice, ready to be sent to the audio thread.
@@ -35,7 +45,10 @@ impl VoiceFactory {
         // instrument definition from the `sequencer` crate would live.
         // All `Box::new` calls happen here, safely in the NRT world.
         let envelope = Envelope::new(self.sample_rate);
-        Self::create_instrument(voice_id, instrument, pan, envelope, self.sample_rate)
+        let mut voice =
+            Self::create_instrument(voice_id, instrument, pan, envelope, self.sample_rate);
+        voice.prepare(self.sample_rate, self.max_block_size);
+        voice
     }
 
     #[allow(
@@ -54,7 +67,10 @@ impl VoiceFactory {
     ) -> Box<dyn VoiceTrait> {
         let envelope =
             Self::create_envelope(attack_s, decay_s, sustain, release_s, self.sample_rate);
-        Self::create_instrument(voice_id, instrument, pan, envelope, self.sample_rate)
+        let mut voice =
+            Self::create_instrument(voice_id, instrument, pan, envelope, self.sample_rate);
+        voice.prepare(self.sample_rate, self.max_block_size);
+        voice
     }
 
     fn create_envelope(
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use crate::id::InstrumentId;
+use crate::instruments::{GranularNode, GranularParams, PolyphonicInstrument, VoiceSlot};
+use crate::{Envelope, MonoEffectChain, SampleData, Voice};
+
+/// Seed for grain scheduling randomness of the first voice; later voices derive their own.
+const GRAIN_SEED: u32 = 0x6A09_E667;
+
+/// Voices allocated when a spec does not ask for a polyphony.
+pub const DEFAULT_GRANULAR_POLYPHONY: u8 = 4;
+
+/// Polyphonic granular instrument. Every voice reads the same shared sample, so
+/// textures can be built from anything the resource manager has loaded.
+pub type GranularSampler = PolyphonicInstrument<GranularNode>;
+
+impl GranularSampler {
+    pub fn new(
+        instrument_id: InstrumentId,
+        sample_data: Arc<SampleData>,
+        sample_rate: f32,
+        pan: f32,
+        max_polyphony: u8,
+        params: GranularParams,
+    ) -> Self {
+        // Slow pad-style default envelope; hydration can override it with envelope commands.
+        let envelope = Envelope::new_adsr(sample_rate, 0.3, 0.5, 1.0, 1.5);
+        let voices = (0..max_polyphony as u32)
+            .map(|index| VoiceSlot {
+                note_id: None,
+                inner: Voice::new(
+                    0,
+                    GranularNode::new(
+                        sample_data.clone(),
+                        sample_rate,
+                        params,
+                        GRAIN_SEED.wrapping_add(index.wrapping_mul(0x9E37_79B9)),
+                    ),
+                    envelope.clone(),
+                    pan,
+                    MonoEffectChain::new(10),
+                ),
+            })
+            .collect();
+
+        GranularSampler {
+            instrument_id,
+            voices,
+        }
+    }
+}
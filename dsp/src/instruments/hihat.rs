@@ -1,11 +1,11 @@
 use crate::{
     id::InstrumentId,
-    instruments::{MonophonicInstrument, NoiseGenerator, VoiceSlot},
+    instruments::{HiHatVoice, MonophonicInstrument, VoiceSlot},
     Envelope, MonoEffectChain, Voice,
 };
 
 /// short noise burst. Use short decays.
-pub type HiHat = MonophonicInstrument<NoiseGenerator>;
+pub type HiHat = MonophonicInstrument<HiHatVoice>;
 
 impl HiHat {
     pub fn new(instrument_id: InstrumentId, pan: f32, sample_rate: f32) -> Self {
@@ -16,7 +16,7 @@ impl HiHat {
         let voice = VoiceSlot {
             inner: Voice::new(
                 0,
-                NoiseGenerator::default(),
+                HiHatVoice::new(sample_rate),
                 envelope,
                 pan,
                 MonoEffectChain::new(10),
@@ -1,24 +1,31 @@
+mod granular_sampler;
 mod hihat;
 mod kick_drum;
 mod monophonic_osc;
 mod moog_dfam;
+mod plucked_string;
 mod polyphonic_osc;
 mod sample_player;
 mod snare_drum;
 mod synth_nodes;
+mod unison_osc;
 
+pub use granular_sampler::*;
 pub use hihat::*;
 pub use kick_drum::*;
 pub use monophonic_osc::*;
 pub use moog_dfam::*;
+pub use plucked_string::*;
 pub use polyphonic_osc::*;
 pub use sample_player::*;
 pub use snare_drum::*;
 pub use synth_nodes::*;
+pub use unison_osc::*;
 
 use crate::{
     id::{EffectId, NoteId},
-    InstrumentTrait, MonoEffect, SynthNode, Voice, VoiceEffects, VoiceTrait,
+    InstrumentTrait, MonoEffect, ParamDescriptor, SynthNode, Voice, VoiceActivity, VoiceEffects,
+    VoiceTrait,
 };
 
 /// A Voice container used by instruments to handle envelope lifecycles and sample generation.
@@ -56,6 +63,12 @@ impl<S: SynthNode> InstrumentTrait for MonophonicInstrument<S> {
         self.voice.inner.set_pan(pan);
     }
 
+    fn voice_activity(&self, report: &mut dyn FnMut(usize, VoiceActivity)) {
+        if let Some(activity) = self.voice.inner.activity() {
+            report(0, activity);
+        }
+    }
+
     fn add_effect(&mut self, effect: Box<dyn MonoEffect>) {
         self.voice.inner.add_effect(effect);
     }
@@ -66,6 +79,30 @@ impl<S: SynthNode> InstrumentTrait for MonophonicInstrument<S> {
             .set_effect_parameter(effect_id, param_index, value);
     }
 
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        self.voice.inner.node.parameters()
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        self.voice.inner.node.set_parameter(index, value);
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        self.voice.inner.node.parameter(index)
+    }
+
+    fn voice_effect(&self, index: usize) -> Option<&dyn MonoEffect> {
+        self.voice.inner.effect(index)
+    }
+
+    fn amp_envelope(&self) -> Option<[f32; 4]> {
+        self.voice.inner.amp_envelope()
+    }
+
+    fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        self.voice.inner.prepare(sample_rate, max_block_size);
+    }
+
     fn try_handle_command(&mut self, cmd: &crate::SynthCmd) -> bool {
         self.voice.inner.try_handle_command(cmd)
     }
@@ -141,6 +178,24 @@ impl<S: SynthNode> InstrumentTrait for PolyphonicInstrument<S> {
         log::warn!("PolyphonicInstrument: add_effect is a no-op; use add_voice_effects instead");
     }
 
+    fn voice_activity(&self, report: &mut dyn FnMut(usize, VoiceActivity)) {
+        for (index, voice) in self.voices.iter().enumerate() {
+            if let Some(activity) = voice.inner.activity() {
+                report(index, activity);
+            }
+        }
+    }
+
+    fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        for voice in &mut self.voices {
+            voice.inner.prepare(sample_rate, max_block_size);
+        }
+    }
+
     fn add_voice_effects(&mut self, effects: VoiceEffects) {
         for (slot, effect) in self.voices.iter_mut().zip(effects) {
             slot.inner.add_effect(effect);
@@ -155,6 +210,30 @@ impl<S: SynthNode> InstrumentTrait for PolyphonicInstrument<S> {
         }
     }
 
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        self.voices
+            .first()
+            .map_or(&[], |voice| voice.inner.node.parameters())
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        for voice in &mut self.voices {
+            voice.inner.node.set_parameter(index, value);
+        }
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        self.voices.first()?.inner.node.parameter(index)
+    }
+
+    fn voice_effect(&self, index: usize) -> Option<&dyn MonoEffect> {
+        self.voices.first()?.inner.effect(index)
+    }
+
+    fn amp_envelope(&self) -> Option<[f32; 4]> {
+        self.voices.first()?.inner.amp_envelope()
+    }
+
     // TODO this is very dodgy, we are only stating the command was handled if at least one voice handled it
     fn try_handle_command(&mut self, cmd: &crate::SynthCmd) -> bool {
         let mut handled = false;
@@ -15,6 +15,7 @@ impl MoogDFAM {
                 OscillatorNode::new_with_waveform(Waveform::Square),
                 OscillatorNode::new_with_waveform(Waveform::Square),
                 NoiseGenerator::default(),
+                sample_rate,
             ),
             envelope,
             pan,
@@ -0,0 +1,37 @@
+use crate::id::InstrumentId;
+use crate::instruments::{PluckedStringNode, PolyphonicInstrument, VoiceSlot};
+use crate::{MonoEffectChain, Voice};
+
+/// Seed for the excitation noise of the first voice; later voices derive their own.
+const PLUCK_NOISE_SEED: u32 = 0x5EED_1F0D;
+
+/// Voices allocated when a spec does not ask for a polyphony.
+pub const DEFAULT_PLUCKED_STRING_POLYPHONY: u8 = 8;
+
+/// Polyphonic Karplus-Strong string. The delay loop decays on its own, so voices
+/// carry no amplitude envelope and free themselves once the string falls silent.
+pub type PluckedString = PolyphonicInstrument<PluckedStringNode>;
+
+impl PluckedString {
+    pub fn new(instrument_id: InstrumentId, pan: f32, sample_rate: f32, max_polyphony: u8) -> Self {
+        let voices = (0..max_polyphony as u32)
+            .map(|index| VoiceSlot {
+                note_id: None,
+                inner: Voice::new_no_envelope(
+                    0,
+                    PluckedStringNode::new(
+                        sample_rate,
+                        PLUCK_NOISE_SEED.wrapping_add(index.wrapping_mul(0x9E37_79B9)),
+                    ),
+                    pan,
+                    MonoEffectChain::new(10),
+                ),
+            })
+            .collect();
+
+        PluckedString {
+            instrument_id,
+            voices,
+        }
+    }
+}
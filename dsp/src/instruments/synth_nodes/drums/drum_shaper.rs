@@ -0,0 +1,278 @@
+use crate::{
+    commands::DrumCmd, instruments::NoiseGenerator, ParamDescriptor, ParamSkew, ParamUnit,
+};
+
+/// Corner of the one-pole filter behind the noise colour control.
+const NOISE_COLOR_CUTOFF_HZ: f32 = 3000.0;
+/// Time constant of the click transient.
+const CLICK_TIME_SECONDS: f32 = 0.003;
+/// Seed for the click transient, kept apart from the body noise so enabling the
+/// click does not shift the noise sequence.
+const CLICK_NOISE_SEED: u32 = 0x0BAD_C0DE;
+
+/// Drum parameters in [`DrumCmd`] order, then the pitch sweep depth of the voice's
+/// pitch envelope; only the noise mix and sweep defaults differ per drum.
+pub(crate) const fn drum_parameters(noise_mix: f32, pitch_env_amount: f32) -> [ParamDescriptor; 7] {
+    [
+        ParamDescriptor::new(
+            0,
+            "tone_frequency",
+            "Tone",
+            ParamUnit::Hertz,
+            0.0,
+            2000.0,
+            0.0,
+        ),
+        ParamDescriptor::new(
+            1,
+            "noise_mix",
+            "Noise Mix",
+            ParamUnit::Generic,
+            0.0,
+            1.0,
+            noise_mix,
+        ),
+        ParamDescriptor::new(
+            2,
+            "noise_color",
+            "Noise Color",
+            ParamUnit::Generic,
+            -1.0,
+            1.0,
+            0.0,
+        ),
+        ParamDescriptor::new(3, "click", "Click", ParamUnit::Generic, 0.0, 1.0, 0.0),
+        ParamDescriptor::new(
+            4,
+            "pitch_decay",
+            "Pitch Decay",
+            ParamUnit::Seconds,
+            0.001,
+            1.0,
+            0.05,
+        )
+        .with_skew(ParamSkew::Logarithmic),
+        ParamDescriptor::new(5, "drive", "Drive", ParamUnit::Generic, 0.0, 1.0, 0.0),
+        ParamDescriptor::new(
+            6,
+            "pitch_env_amount",
+            "Pitch Sweep",
+            ParamUnit::Hertz,
+            -2000.0,
+            2000.0,
+            pitch_env_amount,
+        ),
+    ]
+}
+
+/// Maps a [`drum_parameters`] index to its command.
+pub(crate) fn drum_command(index: u32, value: f32) -> Option<DrumCmd> {
+    Some(match index {
+        0 => DrumCmd::SetToneFrequency { frequency: value },
+        1 => DrumCmd::SetNoiseMix { mix: value },
+        2 => DrumCmd::SetNoiseColor { color: value },
+        3 => DrumCmd::SetClick { amount: value },
+        4 => DrumCmd::SetPitchDecay { seconds: value },
+        5 => DrumCmd::SetDrive { amount: value },
+        _ => return None,
+    })
+}
+
+/// Tone/noise shaping shared by the synthesized drum voices. Every control has a
+/// neutral setting that leaves the signal untouched, so existing kits keep their sound.
+pub(crate) struct DrumShaper {
+    /// Fixed tone frequency in Hz; 0.0 follows the triggering note.
+    tone_frequency: f32,
+    /// 0.0 is tone only, 1.0 is noise only.
+    noise_mix: f32,
+    /// -1.0 (dark) to 1.0 (bright); 0.0 leaves the noise white.
+    noise_color: f32,
+    color_coefficient: f32,
+    color_state: f32,
+    click_amount: f32,
+    click_noise: NoiseGenerator,
+    click_level: f32,
+    click_decay: f32,
+    /// 0.0 is clean, 1.0 is heavy tanh saturation.
+    drive: f32,
+    drive_gain: f32,
+    drive_normalization: f32,
+}
+
+impl DrumShaper {
+    pub(crate) fn new(sample_rate: f32, noise_mix: f32) -> Self {
+        let mut shaper = Self {
+            tone_frequency: 0.0,
+            noise_mix,
+            noise_color: 0.0,
+            color_coefficient: 0.0,
+            color_state: 0.0,
+            click_amount: 0.0,
+            click_noise: NoiseGenerator::new(CLICK_NOISE_SEED),
+            click_level: 0.0,
+            click_decay: 0.0,
+            drive: 0.0,
+            drive_gain: 1.0,
+            drive_normalization: 1.0,
+        };
+        shaper.set_sample_rate(sample_rate);
+        shaper.set_drive(0.0);
+        shaper
+    }
+
+    pub(crate) fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.color_coefficient =
+            1.0 - (-std::f32::consts::TAU * NOISE_COLOR_CUTOFF_HZ / sample_rate).exp();
+        self.click_decay = (-1.0 / (CLICK_TIME_SECONDS * sample_rate)).exp();
+    }
+
+    /// Tone frequency for `note`, honouring a fixed frequency when one is set.
+    pub(crate) fn tone_frequency(&self, note: u8) -> f32 {
+        if self.tone_frequency > 0.0 {
+            self.tone_frequency
+        } else {
+            utils::note::midi_to_frequency(note)
+        }
+    }
+
+    pub(crate) fn trigger(&mut self) {
+        self.click_level = 1.0;
+    }
+
+    /// `false` when the tone is fully mixed out and need not be rendered.
+    #[inline]
+    pub(crate) fn uses_tone(&self) -> bool {
+        self.noise_mix < 1.0
+    }
+
+    /// `false` when the noise is fully mixed out and need not be rendered.
+    #[inline]
+    pub(crate) fn uses_noise(&self) -> bool {
+        self.noise_mix > 0.0
+    }
+
+    /// Colours the noise and blends it with the tone.
+    #[inline]
+    pub(crate) fn mix(&mut self, tone: f32, noise: f32) -> f32 {
+        let noise = self.color(noise);
+        if self.noise_mix >= 1.0 {
+            noise
+        } else if self.noise_mix <= 0.0 {
+            tone
+        } else {
+            (1.0 - self.noise_mix) * tone + self.noise_mix * noise
+        }
+    }
+
+    /// Adds the click transient and applies drive to a mixed sample.
+    #[inline]
+    pub(crate) fn finish(&mut self, sample: f32) -> f32 {
+        let mut sample = sample;
+        if self.click_amount > 0.0 && self.click_level > 1.0e-4 {
+            sample += self.click_amount * self.click_level * self.click_noise.next_sample();
+            self.click_level *= self.click_decay;
+        }
+        if self.drive > 0.0 {
+            sample = (sample * self.drive_gain).tanh() * self.drive_normalization;
+        }
+        sample
+    }
+
+    /// Applies a shaping command. Pitch decay belongs to the voice's pitch envelope
+    /// and is left for the caller.
+    pub(crate) fn handle_command(&mut self, command: &DrumCmd) -> bool {
+        match command {
+            DrumCmd::SetToneFrequency { frequency } => self.tone_frequency = frequency.max(0.0),
+            DrumCmd::SetNoiseMix { mix } => self.noise_mix = mix.clamp(0.0, 1.0),
+            DrumCmd::SetNoiseColor { color } => self.noise_color = color.clamp(-1.0, 1.0),
+            DrumCmd::SetClick { amount } => self.click_amount = amount.clamp(0.0, 1.0),
+            DrumCmd::SetDrive { amount } => self.set_drive(*amount),
+            DrumCmd::SetPitchDecay { .. } => return false,
+        }
+        true
+    }
+
+    /// Reads back a [`drum_parameters`] value. Pitch decay and sweep belong to the voice's
+    /// pitch envelope and are left for the caller.
+    pub(crate) fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.tone_frequency),
+            1 => Some(self.noise_mix),
+            2 => Some(self.noise_color),
+            3 => Some(self.click_amount),
+            5 => Some(self.drive),
+            _ => None,
+        }
+    }
+
+    fn set_drive(&mut self, amount: f32) {
+        self.drive = amount.clamp(0.0, 1.0);
+        self.drive_gain = 1.0 + 9.0 * self.drive;
+        // Keep a full-scale input at full scale regardless of drive.
+        self.drive_normalization = 1.0 / self.drive_gain.tanh();
+    }
+
+    #[inline]
+    fn color(&mut self, noise: f32) -> f32 {
+        if self.noise_color == 0.0 {
+            return noise;
+        }
+        self.color_state += self.color_coefficient * (noise - self.color_state);
+        if self.noise_color < 0.0 {
+            // Crossfade towards the lowpassed noise.
+            noise + self.noise_color.abs() * (self.color_state - noise)
+        } else {
+            // Subtract the lowpassed part, leaving the highs.
+            noise - self.noise_color * self.color_state
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    #[test]
+    fn neutral_settings_leave_the_signal_untouched() {
+        let mut shaper = DrumShaper::new(SAMPLE_RATE, 0.7);
+        shaper.trigger();
+        for (tone, noise) in [(0.5, -0.25), (-1.0, 0.75), (0.125, 0.0)] {
+            let expected = 0.3 * tone + 0.7 * noise;
+            let mixed = shaper.mix(tone, noise);
+            assert_eq!(shaper.finish(mixed), expected);
+        }
+    }
+
+    #[test]
+    fn drive_saturates_but_keeps_full_scale() {
+        let mut shaper = DrumShaper::new(SAMPLE_RATE, 0.0);
+        shaper.handle_command(&DrumCmd::SetDrive { amount: 1.0 });
+        assert!((shaper.finish(1.0) - 1.0).abs() < 1e-6);
+        assert!(shaper.finish(0.1) > 0.1);
+        assert!(shaper.finish(4.0) < 1.01);
+    }
+
+    #[test]
+    fn click_decays_within_a_few_milliseconds() {
+        let mut shaper = DrumShaper::new(SAMPLE_RATE, 0.0);
+        shaper.handle_command(&DrumCmd::SetClick { amount: 1.0 });
+        shaper.trigger();
+        let early: f32 = (0..48).map(|_| shaper.finish(0.0).abs()).sum();
+        for _ in 0..2400 {
+            shaper.finish(0.0);
+        }
+        let late: f32 = (0..48).map(|_| shaper.finish(0.0).abs()).sum();
+        assert!(early > 1.0);
+        assert_eq!(late, 0.0);
+    }
+
+    #[test]
+    fn fixed_tone_frequency_overrides_the_note() {
+        let mut shaper = DrumShaper::new(SAMPLE_RATE, 0.0);
+        assert!((shaper.tone_frequency(69) - 440.0).abs() < 1e-3);
+        shaper.handle_command(&DrumCmd::SetToneFrequency { frequency: 55.0 });
+        assert_eq!(shaper.tone_frequency(69), 55.0);
+    }
+}
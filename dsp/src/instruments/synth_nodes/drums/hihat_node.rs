@@ -0,0 +1,122 @@
+use utils::note::velocity_to_amplitude;
+
+use crate::{
+    commands::{DrumCmd, EnvelopeCmd},
+    instruments::{
+        drum_command, drum_parameters, DrumShaper, NoiseGenerator, OscillatorNode, Waveform,
+    },
+    Envelope, ParamDescriptor, PitchEnvelope, SynthCmd, SynthNode,
+};
+
+/// Frequency ratios of the square-wave cluster behind the metallic tone,
+/// after the six oscillators of the TR-808 cymbal circuit.
+const METALLIC_RATIOS: [f32; 6] = [1.0, 1.483, 1.8, 2.546, 2.63, 3.897];
+
+/// Hi-hat voice: white noise by default, blendable with a metallic square cluster.
+/// The amplitude envelope lives on the owning voice.
+pub struct HiHatVoice {
+    noise: NoiseGenerator,
+    metal: [OscillatorNode; METALLIC_RATIOS.len()],
+    /// Optional sweep of the metallic cluster, flat until a frequency delta is set.
+    pitch_env: PitchEnvelope,
+    shaper: DrumShaper,
+    volume: f32,
+}
+
+impl HiHatVoice {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut pitch_env = Envelope::new(sample_rate);
+        pitch_env.set_parameters(0.001, 0.05, 0.0, 0.1);
+        Self {
+            noise: NoiseGenerator::default(),
+            metal: [OscillatorNode::new_with_waveform(Waveform::Square); METALLIC_RATIOS.len()],
+            pitch_env: PitchEnvelope::new(0.0, pitch_env),
+            shaper: DrumShaper::new(sample_rate, 1.0),
+            volume: 0.7,
+        }
+    }
+
+    #[inline]
+    fn metallic_sample(&mut self, sample_rate: f32) -> f32 {
+        let base = self.pitch_env.next_freq();
+        let mut sum = 0.0;
+        for (osc, ratio) in self.metal.iter_mut().zip(METALLIC_RATIOS) {
+            osc.set_frequency(base * ratio);
+            sum += osc.next_sample(sample_rate);
+        }
+        sum / METALLIC_RATIOS.len() as f32
+    }
+}
+
+pub(crate) const HIHAT_PARAMETERS: [ParamDescriptor; 7] = drum_parameters(1.0, 0.0);
+
+impl SynthNode for HiHatVoice {
+    fn process(&mut self, mono_buf: &mut [f32], sample_rate: f32) {
+        for sample in mono_buf.iter_mut() {
+            let noise = self.noise.next_sample();
+            let tone = if self.shaper.uses_tone() {
+                self.metallic_sample(sample_rate)
+            } else {
+                0.0
+            };
+            let mixed = self.shaper.mix(tone, noise);
+            *sample = self.shaper.finish(self.volume * mixed);
+        }
+    }
+
+    fn note_on(&mut self, note: u8, velocity: u8) {
+        self.volume = velocity_to_amplitude(velocity);
+        self.pitch_env.note_on(self.shaper.tone_frequency(note));
+        self.shaper.trigger();
+    }
+
+    fn note_off(&mut self) {
+        self.pitch_env.note_off();
+    }
+
+    fn prepare(&mut self, sample_rate: f32) {
+        self.pitch_env.set_sample_rate(sample_rate);
+        self.shaper.set_sample_rate(sample_rate);
+    }
+
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &HIHAT_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        if index == 6 {
+            self.pitch_env.set_freq_delta(value);
+        } else if let Some(command) = drum_command(index, value) {
+            self.try_handle_command(&SynthCmd::DrumCommand { command });
+        }
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            4 => Some(self.pitch_env.decay()),
+            6 => Some(self.pitch_env.freq_delta()),
+            _ => self.shaper.parameter(index),
+        }
+    }
+
+    fn try_handle_command(&mut self, command: &SynthCmd) -> bool {
+        match command {
+            SynthCmd::EnvelopeCommand {
+                envelope_id: None,
+                command: command @ EnvelopeCmd::SetPitchEnvFreqDelta { .. },
+            } => self.pitch_env.handle_command(command),
+            SynthCmd::DrumCommand {
+                command: DrumCmd::SetPitchDecay { seconds },
+            } => {
+                self.pitch_env.set_decay(*seconds);
+                true
+            }
+            SynthCmd::DrumCommand { command } => self.shaper.handle_command(command),
+            _ => false,
+        }
+    }
+}
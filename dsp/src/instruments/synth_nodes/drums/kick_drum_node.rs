@@ -1,12 +1,15 @@
+use crate::commands::{DrumCmd, EnvelopeCmd};
 use crate::id::EnvelopeId;
+use crate::instruments::{drum_command, drum_parameters, DrumShaper, NoiseGenerator};
 use crate::synth_infra::{Envelope, PitchEnvelope};
-use crate::{instruments::OscillatorNode, SynthCmd, SynthNode};
-use utils::note::midi_to_frequency;
+use crate::{instruments::OscillatorNode, ParamDescriptor, SynthCmd, SynthNode};
 
 pub struct KickDrumVoice {
     osc: OscillatorNode, // sine or triangle wave
+    noise: NoiseGenerator,
     amp_env: Envelope,
     pitch_env: PitchEnvelope,
+    shaper: DrumShaper,
 }
 
 // Provide a convenience constructor for the concrete Envelope+PitchEnvelope combo:
@@ -18,13 +21,18 @@ impl KickDrumVoice {
         let osc = OscillatorNode::new();
         KickDrumVoice {
             osc,
+            noise: NoiseGenerator::default(),
             amp_env: env,
             pitch_env,
+            // Pure tone by default; noise adds a beater/room layer.
+            shaper: DrumShaper::new(sample_rate, 0.0),
         }
     }
 }
 
 // // Generic impl for audio path — no vtable calls
+pub(crate) const KICK_PARAMETERS: [ParamDescriptor; 7] = drum_parameters(0.0, 50.0);
+
 impl SynthNode for KickDrumVoice {
     fn process(&mut self, mono_buf: &mut [f32], sample_rate: f32) {
         for sample in mono_buf.iter_mut() {
@@ -34,14 +42,21 @@ impl SynthNode for KickDrumVoice {
             }
             let freq = self.pitch_env.next_freq();
             self.osc.set_frequency(freq);
-            *sample = self.osc.next_sample(sample_rate) * self.amp_env.process();
+            let noise = if self.shaper.uses_noise() {
+                self.noise.next_sample()
+            } else {
+                0.0
+            };
+            let body = self.shaper.mix(self.osc.next_sample(sample_rate), noise);
+            *sample = self.shaper.finish(body * self.amp_env.process());
         }
     }
 
     fn note_on(&mut self, note: u8, _velocity: u8) {
-        let start = midi_to_frequency(note);
+        let start = self.shaper.tone_frequency(note);
         self.pitch_env.note_on(start);
         self.amp_env.gate(true);
+        self.shaper.trigger();
     }
 
     fn note_off(&mut self) {
@@ -49,10 +64,36 @@ impl SynthNode for KickDrumVoice {
         self.pitch_env.note_off();
     }
 
+    fn prepare(&mut self, sample_rate: f32) {
+        self.amp_env.set_sample_rate(sample_rate);
+        self.pitch_env.set_sample_rate(sample_rate);
+        self.shaper.set_sample_rate(sample_rate);
+    }
+
     fn is_active(&self) -> bool {
         self.osc.is_active() || self.amp_env.is_active() || self.pitch_env.is_active()
     }
 
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &KICK_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        if index == 6 {
+            self.pitch_env.set_freq_delta(value);
+        } else if let Some(command) = drum_command(index, value) {
+            self.try_handle_command(&SynthCmd::DrumCommand { command });
+        }
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            4 => Some(self.pitch_env.decay()),
+            6 => Some(self.pitch_env.freq_delta()),
+            _ => self.shaper.parameter(index),
+        }
+    }
+
     fn try_handle_command(&mut self, command: &crate::commands::SynthCmd) -> bool {
         match command {
             SynthCmd::EnvelopeCommand {
@@ -62,11 +103,22 @@ impl SynthNode for KickDrumVoice {
                 Some(0) => self.amp_env.handle_command(command),
                 Some(1) => self.pitch_env.handle_command(command),
                 Some(_) => false,
+                // The sweep depth only exists on the pitch envelope, so it needs no ID.
+                None if matches!(command, EnvelopeCmd::SetPitchEnvFreqDelta { .. }) => {
+                    self.pitch_env.handle_command(command)
+                }
                 None => {
                     log::warn!("No Envelope ID defined commands are ignored");
                     false
                 }
             },
+            SynthCmd::DrumCommand {
+                command: DrumCmd::SetPitchDecay { seconds },
+            } => {
+                self.pitch_env.set_decay(*seconds);
+                true
+            }
+            SynthCmd::DrumCommand { command } => self.shaper.handle_command(command),
             _ => false,
         }
     }
@@ -74,8 +126,18 @@ impl SynthNode for KickDrumVoice {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KickDrumEnvelope {
-    Oscillator = 0,
-    Noise,
+    Amplitude = 0,
+    Pitch,
+}
+
+// The variants were once named after the oscillator and noise; keep the old
+// names so that existing callers still build.
+#[allow(non_upper_case_globals)]
+impl KickDrumEnvelope {
+    #[deprecated(note = "renamed to `KickDrumEnvelope::Amplitude`")]
+    pub const Oscillator: Self = Self::Amplitude;
+    #[deprecated(note = "renamed to `KickDrumEnvelope::Pitch`")]
+    pub const Noise: Self = Self::Pitch;
 }
 
 impl From<KickDrumEnvelope> for EnvelopeId {
@@ -1,8 +1,13 @@
+mod drum_shaper;
+mod hihat_node;
 mod kick_drum_node;
 mod noise_generator;
 mod snare_drum_node;
 
-pub(crate) use kick_drum_node::KickDrumVoice;
+pub(crate) use drum_shaper::{drum_command, drum_parameters, DrumShaper};
+pub(crate) use hihat_node::{HiHatVoice, HIHAT_PARAMETERS};
+pub use kick_drum_node::KickDrumEnvelope;
+pub(crate) use kick_drum_node::{KickDrumVoice, KICK_PARAMETERS};
 pub(crate) use noise_generator::NoiseGenerator;
 pub use snare_drum_node::SnareDrumEnvelope;
-pub(crate) use snare_drum_node::SnareDrumVoice;
+pub(crate) use snare_drum_node::{SnareDrumVoice, SNARE_PARAMETERS};
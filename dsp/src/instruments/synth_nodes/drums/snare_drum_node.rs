@@ -1,7 +1,8 @@
 use crate::{
+    commands::{DrumCmd, EnvelopeCmd},
     id::EnvelopeId,
-    instruments::{NoiseGenerator, OscillatorNode},
-    Envelope, SynthCmd, SynthNode,
+    instruments::{drum_command, drum_parameters, DrumShaper, NoiseGenerator, OscillatorNode},
+    Envelope, ParamDescriptor, PitchEnvelope, SynthCmd, SynthNode,
 };
 
 pub struct SnareDrumVoice {
@@ -9,6 +10,9 @@ pub struct SnareDrumVoice {
     osc_env: Envelope,     // quick decay for body part
     noise: NoiseGenerator, // medium decay for sizzle part
     noise_env: Envelope,
+    /// Optional body sweep, flat until a frequency delta is set.
+    pitch_env: PitchEnvelope,
+    shaper: DrumShaper,
 }
 
 impl SnareDrumVoice {
@@ -19,15 +23,22 @@ impl SnareDrumVoice {
         let mut noise_env = Envelope::new(sample_rate);
         noise_env.set_parameters(0.01, 0.2, 0.0, 0.1); // very quick attack, very short decay
 
+        let mut pitch_env = Envelope::new(sample_rate);
+        pitch_env.set_parameters(0.001, 0.05, 0.0, 0.1);
+
         SnareDrumVoice {
             osc: OscillatorNode::new(),
             osc_env,
             noise: NoiseGenerator::default(),
             noise_env,
+            pitch_env: PitchEnvelope::new(0.0, pitch_env),
+            shaper: DrumShaper::new(sample_rate, 0.7),
         }
     }
 }
 
+pub(crate) const SNARE_PARAMETERS: [ParamDescriptor; 7] = drum_parameters(0.7, 0.0);
+
 impl SynthNode for SnareDrumVoice {
     fn process(&mut self, mono_buf: &mut [f32], sample_rate: f32) {
         for sample in mono_buf.iter_mut() {
@@ -35,28 +46,58 @@ impl SynthNode for SnareDrumVoice {
                 *sample = 0.0;
                 continue;
             }
+            self.osc.set_frequency(self.pitch_env.next_freq());
             let tone = self.osc.next_sample(sample_rate) * self.osc_env.process();
             let noise = self.noise.next_sample() * self.noise_env.process();
-            *sample = 0.3 * tone + 0.7 * noise; // mix and scale down
+            let mixed = self.shaper.mix(tone, noise);
+            *sample = self.shaper.finish(mixed);
         }
     }
 
     fn note_on(&mut self, note: u8, _velocity: u8) {
-        let freq = utils::note::midi_to_frequency(note);
-        self.osc.set_frequency(freq);
+        self.pitch_env.note_on(self.shaper.tone_frequency(note));
         self.osc_env.gate(true);
         self.noise_env.gate(true);
+        self.shaper.trigger();
     }
 
     fn note_off(&mut self) {
         self.osc_env.gate(false);
         self.noise_env.gate(false);
+        self.pitch_env.note_off();
+    }
+
+    fn prepare(&mut self, sample_rate: f32) {
+        self.osc_env.set_sample_rate(sample_rate);
+        self.noise_env.set_sample_rate(sample_rate);
+        self.pitch_env.set_sample_rate(sample_rate);
+        self.shaper.set_sample_rate(sample_rate);
     }
 
     fn is_active(&self) -> bool {
         self.osc_env.is_active() || self.noise_env.is_active()
     }
 
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &SNARE_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        if index == 6 {
+            self.pitch_env.set_freq_delta(value);
+        } else if let Some(command) = drum_command(index, value) {
+            self.try_handle_command(&SynthCmd::DrumCommand { command });
+        }
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            4 => Some(self.pitch_env.decay()),
+            6 => Some(self.pitch_env.freq_delta()),
+            _ => self.shaper.parameter(index),
+        }
+    }
+
     fn try_handle_command(&mut self, command: &crate::commands::SynthCmd) -> bool {
         match command {
             SynthCmd::EnvelopeCommand {
@@ -68,11 +109,22 @@ impl SynthNode for SnareDrumVoice {
                     1 => self.noise_env.handle_command(command),
                     _ => false,
                 },
+                // The sweep depth only exists on the pitch envelope, so it needs no ID.
+                None if matches!(command, EnvelopeCmd::SetPitchEnvFreqDelta { .. }) => {
+                    self.pitch_env.handle_command(command)
+                }
                 None => {
                     log::error!("Envelope ID must be specified for SnareDrumVoice");
                     false
                 }
             },
+            SynthCmd::DrumCommand {
+                command: DrumCmd::SetPitchDecay { seconds },
+            } => {
+                self.pitch_env.set_decay(*seconds);
+                true
+            }
+            SynthCmd::DrumCommand { command } => self.shaper.handle_command(command),
             _ => false,
         }
     }
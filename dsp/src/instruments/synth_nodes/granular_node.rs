@@ -0,0 +1,485 @@
+use std::sync::Arc;
+
+use crate::commands::{GranularCmd, SynthCmd};
+use crate::{ParamDescriptor, ParamSkew, ParamUnit, SampleData, SynthNode};
+
+/// Upper bound on overlapping grains per voice. Grains live in a fixed array so
+/// spawning never allocates on the audio thread; spawns beyond this are skipped.
+pub const MAX_GRAINS: usize = 64;
+/// Resolution of the precomputed grain window.
+const WINDOW_TABLE_SIZE: usize = 512;
+/// MIDI note at which the sample plays back at its original pitch.
+const BASE_NOTE: u8 = 60;
+
+pub(crate) const GRANULAR_PARAMETERS: [ParamDescriptor; 6] = [
+    ParamDescriptor::new(0, "position", "Position", ParamUnit::Generic, 0.0, 1.0, 0.5),
+    ParamDescriptor::new(1, "spray", "Spray", ParamUnit::Generic, 0.0, 1.0, 0.05),
+    ParamDescriptor::new(
+        2,
+        "grain_size",
+        "Grain Size",
+        ParamUnit::Seconds,
+        0.001,
+        2.0,
+        0.08,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+    ParamDescriptor::new(
+        3,
+        "density",
+        "Density",
+        ParamUnit::Generic,
+        0.0,
+        1000.0,
+        40.0,
+    ),
+    ParamDescriptor::new(4, "pitch", "Pitch", ParamUnit::Semitones, -48.0, 48.0, 0.0),
+    ParamDescriptor::new(
+        5,
+        "reverse_probability",
+        "Reverse",
+        ParamUnit::Generic,
+        0.0,
+        1.0,
+        0.0,
+    ),
+];
+
+/// Amplitude window applied over the lifetime of each grain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrainWindow {
+    #[default]
+    Hann,
+    Triangle,
+    /// Linear fades over the first and last quarter with a flat top.
+    Trapezoid,
+    Gaussian,
+}
+
+impl GrainWindow {
+    /// Every window, in the order used to serialize them as a number.
+    pub const ALL: [GrainWindow; 4] = [
+        GrainWindow::Hann,
+        GrainWindow::Triangle,
+        GrainWindow::Trapezoid,
+        GrainWindow::Gaussian,
+    ];
+
+    /// Window amplitude at `x` in `[0, 1]`.
+    fn amplitude(self, x: f32) -> f32 {
+        match self {
+            GrainWindow::Hann => 0.5 - 0.5 * (std::f32::consts::TAU * x).cos(),
+            GrainWindow::Triangle => 1.0 - (2.0 * x - 1.0).abs(),
+            GrainWindow::Trapezoid => (4.0 * x.min(1.0 - x)).min(1.0),
+            GrainWindow::Gaussian => {
+                let d = (x - 0.5) / 0.15;
+                (-0.5 * d * d).exp()
+            }
+        }
+    }
+}
+
+/// Granular controls, mirrored by [`GranularCmd`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GranularParams {
+    /// Normalised read position in the sample, 0.0 (start) to 1.0 (end).
+    pub position: f32,
+    /// Random offset around `position` as a fraction of the sample length.
+    pub spray: f32,
+    /// Grain length in seconds.
+    pub grain_size: f32,
+    /// Grains started per second.
+    pub density: f32,
+    /// Transposition in semitones on top of the played note.
+    pub pitch: f32,
+    pub window: GrainWindow,
+    /// Probability (0.0..1.0) that a grain plays its slice backwards.
+    pub reverse_probability: f32,
+}
+
+impl Default for GranularParams {
+    fn default() -> Self {
+        Self {
+            position: 0.5,
+            spray: 0.05,
+            grain_size: 0.08,
+            density: 40.0,
+            pitch: 0.0,
+            window: GrainWindow::Hann,
+            reverse_probability: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Grain {
+    active: bool,
+    /// Read position in source frames.
+    position: f64,
+    /// Source frames advanced per output sample; negative for reversed grains.
+    increment: f64,
+    age: u32,
+    length: u32,
+}
+
+/// Granular sampler over a shared [`SampleData`]. Grains are short windowed slices
+/// read around a movable position, overlapped at a configurable density.
+pub struct GranularNode {
+    sample: Arc<SampleData>,
+    output_sample_rate: f32,
+    params: GranularParams,
+    grains: [Grain; MAX_GRAINS],
+    window_table: [f32; WINDOW_TABLE_SIZE],
+    /// Playback rate for the current note, before the `pitch` offset.
+    note_ratio: f64,
+    /// Fractional grain count accumulated towards the next spawn.
+    spawn_phase: f32,
+    is_playing: bool,
+    rng_state: u32,
+}
+
+impl GranularNode {
+    pub fn new(
+        sample: Arc<SampleData>,
+        output_sample_rate: f32,
+        params: GranularParams,
+        seed: u32,
+    ) -> Self {
+        let mut node = Self {
+            sample,
+            output_sample_rate,
+            params,
+            grains: [Grain::default(); MAX_GRAINS],
+            window_table: [0.0; WINDOW_TABLE_SIZE],
+            note_ratio: 1.0,
+            spawn_phase: 0.0,
+            is_playing: false,
+            rng_state: seed.max(1),
+        };
+        node.set_params(params);
+        node.update_window_table();
+        node
+    }
+
+    pub fn params(&self) -> GranularParams {
+        self.params
+    }
+
+    /// Applies `params`, rebuilding the window table only when the window
+    /// changes.
+    pub fn set_params(&mut self, params: GranularParams) {
+        let window_changed = params.window != self.params.window;
+        self.params = GranularParams {
+            position: params.position.clamp(0.0, 1.0),
+            spray: params.spray.clamp(0.0, 1.0),
+            grain_size: params.grain_size.clamp(0.001, 2.0),
+            density: params.density.clamp(0.0, 1000.0),
+            pitch: params.pitch.clamp(-48.0, 48.0),
+            window: params.window,
+            reverse_probability: params.reverse_probability.clamp(0.0, 1.0),
+        };
+        if window_changed {
+            self.update_window_table();
+        }
+    }
+
+    fn update_window_table(&mut self) {
+        let last = (WINDOW_TABLE_SIZE - 1) as f32;
+        for (i, value) in self.window_table.iter_mut().enumerate() {
+            *value = self.params.window.amplitude(i as f32 / last);
+        }
+    }
+
+    fn frame_count(&self) -> usize {
+        self.sample.data.len() / self.sample.channels.max(1) as usize
+    }
+
+    /// Mono sample at an integer frame, zero outside the buffer.
+    fn frame_at(&self, index: isize) -> f32 {
+        if index < 0 {
+            return 0.0;
+        }
+        let index = index as usize;
+        if self.sample.channels > 1 {
+            let frame_index = index * self.sample.channels as usize;
+            let left = self.sample.data.get(frame_index).copied().unwrap_or(0.0);
+            let right = self
+                .sample
+                .data
+                .get(frame_index + 1)
+                .copied()
+                .unwrap_or(0.0);
+            (left + right) * 0.5
+        } else {
+            self.sample.data.get(index).copied().unwrap_or(0.0)
+        }
+    }
+
+    #[inline]
+    fn next_random(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+        (self.rng_state >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    fn spawn_grain(&mut self) {
+        let Some(slot) = self.grains.iter().position(|grain| !grain.active) else {
+            return;
+        };
+        let frames = self.frame_count();
+        if frames == 0 {
+            return;
+        }
+
+        let jitter = (self.next_random() * 2.0 - 1.0) * self.params.spray;
+        let start = ((self.params.position + jitter).clamp(0.0, 1.0) * (frames - 1) as f32) as f64;
+        let rate = self.note_ratio * (self.params.pitch as f64 / 12.0).exp2();
+        let reverse = self.next_random() < self.params.reverse_probability;
+        let length = (self.params.grain_size * self.output_sample_rate).max(1.0) as u32;
+
+        self.grains[slot] = Grain {
+            active: true,
+            position: start,
+            increment: if reverse { -rate } else { rate },
+            age: 0,
+            length,
+        };
+    }
+
+    fn handle_granular_command(&mut self, command: &GranularCmd) {
+        let mut params = self.params;
+        match command {
+            GranularCmd::SetPosition { position } => params.position = *position,
+            GranularCmd::SetSpray { spray } => params.spray = *spray,
+            GranularCmd::SetGrainSize { seconds } => params.grain_size = *seconds,
+            GranularCmd::SetDensity { grains_per_second } => params.density = *grains_per_second,
+            GranularCmd::SetPitch { semitones } => params.pitch = *semitones,
+            GranularCmd::SetWindow { window } => params.window = *window,
+            GranularCmd::SetReverseProbability { probability } => {
+                params.reverse_probability = *probability
+            }
+        }
+        self.set_params(params);
+    }
+}
+
+impl SynthNode for GranularNode {
+    fn process(&mut self, output_buffer: &mut [f32], sample_rate: f32) {
+        // Expected number of overlapping grains; scale so dense clouds don't clip.
+        let overlap = (self.params.density * self.params.grain_size).max(1.0);
+        let gain = 1.0 / overlap.sqrt();
+        let spawn_increment = self.params.density / sample_rate;
+        let window_scale = (WINDOW_TABLE_SIZE - 1) as f32;
+
+        for out in output_buffer.iter_mut() {
+            if self.is_playing {
+                self.spawn_phase += spawn_increment;
+                while self.spawn_phase >= 1.0 {
+                    self.spawn_phase -= 1.0;
+                    self.spawn_grain();
+                }
+            }
+
+            let mut sum = 0.0;
+            for i in 0..MAX_GRAINS {
+                let grain = self.grains[i];
+                if !grain.active {
+                    continue;
+                }
+                let phase = grain.age as f32 / grain.length as f32;
+                let window = self.window_table[(phase * window_scale) as usize];
+
+                let index_floor = grain.position.floor();
+                let fraction = (grain.position - index_floor) as f32;
+                let sample0 = self.frame_at(index_floor as isize);
+                let sample1 = self.frame_at(index_floor as isize + 1);
+                sum += (sample0 + (sample1 - sample0) * fraction) * window;
+
+                let grain = &mut self.grains[i];
+                grain.position += grain.increment;
+                grain.age += 1;
+                if grain.age >= grain.length {
+                    grain.active = false;
+                }
+            }
+            *out = sum * gain;
+        }
+    }
+
+    fn note_on(&mut self, note: u8, _velocity: u8) {
+        let sr_correction = self.sample.sample_rate as f64 / self.output_sample_rate as f64;
+        let semitones = note as f64 - BASE_NOTE as f64;
+        self.note_ratio = sr_correction * (semitones / 12.0).exp2();
+        // Start the first grain immediately rather than one spawn interval later.
+        self.spawn_phase = 1.0;
+        self.is_playing = true;
+    }
+
+    fn note_off(&mut self) {
+        // Stop spawning; grains already in flight play out.
+        self.is_playing = false;
+    }
+
+    fn prepare(&mut self, sample_rate: f32) {
+        self.output_sample_rate = sample_rate;
+    }
+
+    fn is_active(&self) -> bool {
+        self.is_playing || self.grains.iter().any(|grain| grain.active)
+    }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &GRANULAR_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        let command = match index {
+            0 => GranularCmd::SetPosition { position: value },
+            1 => GranularCmd::SetSpray { spray: value },
+            2 => GranularCmd::SetGrainSize { seconds: value },
+            3 => GranularCmd::SetDensity {
+                grains_per_second: value,
+            },
+            4 => GranularCmd::SetPitch { semitones: value },
+            5 => GranularCmd::SetReverseProbability { probability: value },
+            _ => return,
+        };
+        self.handle_granular_command(&command);
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.params.position),
+            1 => Some(self.params.spray),
+            2 => Some(self.params.grain_size),
+            3 => Some(self.params.density),
+            4 => Some(self.params.pitch),
+            5 => Some(self.params.reverse_probability),
+            _ => None,
+        }
+    }
+
+    fn try_handle_command(&mut self, command: &SynthCmd) -> bool {
+        match command {
+            SynthCmd::GranularCommand { command } => {
+                self.handle_granular_command(command);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn ramp_sample(frames: usize) -> Arc<SampleData> {
+        Arc::new(SampleData {
+            data: (0..frames).map(|i| i as f32 / frames as f32).collect(),
+            sample_rate: SAMPLE_RATE,
+            channels: 1,
+            loop_start: None,
+            loop_end: None,
+        })
+    }
+
+    #[test]
+    fn grains_read_around_the_position() {
+        let params = GranularParams {
+            position: 0.25,
+            spray: 0.0,
+            density: 10.0,
+            grain_size: 0.01,
+            window: GrainWindow::Trapezoid,
+            ..GranularParams::default()
+        };
+        let mut node = GranularNode::new(ramp_sample(48_000), SAMPLE_RATE, params, 1);
+        node.note_on(BASE_NOTE, 127);
+        let mut out = vec![0.0; 480];
+        node.process(&mut out, SAMPLE_RATE);
+
+        // Flat top of the first grain reads the ramp at ~25%.
+        let mid = out[240];
+        assert!((mid - 0.255).abs() < 0.01, "mid sample {mid}");
+    }
+
+    #[test]
+    fn reversed_grains_read_backwards() {
+        let params = GranularParams {
+            spray: 0.0,
+            reverse_probability: 1.0,
+            window: GrainWindow::Trapezoid,
+            ..GranularParams::default()
+        };
+        let mut node = GranularNode::new(ramp_sample(48_000), SAMPLE_RATE, params, 1);
+        node.note_on(BASE_NOTE, 127);
+        let mut out = vec![0.0; 1200];
+        node.process(&mut out, SAMPLE_RATE);
+        // Both samples fall on the flat top of the first grain, before the second spawns.
+        assert!(out[1100] < out[1000], "ramp should descend");
+    }
+
+    #[test]
+    fn voice_frees_after_note_off_once_grains_finish() {
+        let mut node =
+            GranularNode::new(ramp_sample(4800), SAMPLE_RATE, GranularParams::default(), 1);
+        node.note_on(BASE_NOTE, 127);
+        let mut out = vec![0.0; 1024];
+        node.process(&mut out, SAMPLE_RATE);
+        node.note_off();
+        assert!(node.is_active());
+        for _ in 0..10 {
+            node.process(&mut out, SAMPLE_RATE);
+        }
+        assert!(!node.is_active());
+        assert!(out.iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn window_table_follows_window_changes() {
+        let mut node =
+            GranularNode::new(ramp_sample(4800), SAMPLE_RATE, GranularParams::default(), 1);
+        let quarter = WINDOW_TABLE_SIZE / 4;
+        let hann = node.window_table[quarter];
+
+        // A marker in the table shows whether it was rebuilt.
+        node.window_table[quarter] = -1.0;
+        node.set_params(GranularParams {
+            position: 0.5,
+            ..node.params()
+        });
+        assert_eq!(node.window_table[quarter], -1.0);
+
+        node.set_params(GranularParams {
+            window: GrainWindow::Trapezoid,
+            ..node.params()
+        });
+        let last = (WINDOW_TABLE_SIZE - 1) as f32;
+        assert_eq!(
+            node.window_table[quarter],
+            GrainWindow::Trapezoid.amplitude(quarter as f32 / last)
+        );
+        assert_ne!(node.window_table[quarter], hann);
+    }
+
+    #[test]
+    fn grain_pool_is_bounded() {
+        let params = GranularParams {
+            density: 1000.0,
+            grain_size: 2.0,
+            ..GranularParams::default()
+        };
+        let mut node = GranularNode::new(ramp_sample(48_000), SAMPLE_RATE, params, 1);
+        node.note_on(BASE_NOTE, 127);
+        let mut out = vec![0.0; 4800];
+        node.process(&mut out, SAMPLE_RATE);
+        assert_eq!(
+            node.grains.iter().filter(|grain| grain.active).count(),
+            MAX_GRAINS
+        );
+    }
+}
@@ -1,10 +1,16 @@
 mod drums;
+mod granular_node;
 mod moog_node;
 mod oscillator_node;
+mod plucked_string_node;
 mod sample_player_node;
+mod unison_oscillator_node;
 
-pub use drums::SnareDrumEnvelope;
 pub(crate) use drums::*;
+pub use drums::{KickDrumEnvelope, SnareDrumEnvelope};
+pub use granular_node::*;
 pub use moog_node::*;
 pub use oscillator_node::*;
+pub use plucked_string_node::*;
 pub use sample_player_node::*;
+pub use unison_oscillator_node::*;
@@ -1,39 +1,627 @@
+use crate::commands::{DFAMCmd, SynthCmd};
+use crate::effects::MoogLadder;
 use crate::instruments::{NoiseGenerator, OscillatorNode};
-use crate::SynthNode;
+use crate::{MonoEffect, ParamDescriptor, ParamSkew, ParamUnit, SynthNode};
 
+/// Number of steps in the DFAM sequencer.
+pub const DFAM_STEPS: usize = 8;
+/// Tempo assumed until the host reports one.
+const DEFAULT_TEMPO_BPM: f32 = 120.0;
+/// Ratio between the two VCOs before any FM is applied.
+const VCO2_DETUNE: f32 = 1.01;
+
+/// Serialized DFAM step pitches in semitones, by step.
+pub const DFAM_STEP_PITCH_KEYS: [&str; DFAM_STEPS] = [
+    "step_pitch_0",
+    "step_pitch_1",
+    "step_pitch_2",
+    "step_pitch_3",
+    "step_pitch_4",
+    "step_pitch_5",
+    "step_pitch_6",
+    "step_pitch_7",
+];
+/// Serialized DFAM step velocities, by step.
+pub const DFAM_STEP_VELOCITY_KEYS: [&str; DFAM_STEPS] = [
+    "step_velocity_0",
+    "step_velocity_1",
+    "step_velocity_2",
+    "step_velocity_3",
+    "step_velocity_4",
+    "step_velocity_5",
+    "step_velocity_6",
+    "step_velocity_7",
+];
+const STEP_PITCH_NAMES: [&str; DFAM_STEPS] = [
+    "Step 1 Pitch",
+    "Step 2 Pitch",
+    "Step 3 Pitch",
+    "Step 4 Pitch",
+    "Step 5 Pitch",
+    "Step 6 Pitch",
+    "Step 7 Pitch",
+    "Step 8 Pitch",
+];
+const STEP_VELOCITY_NAMES: [&str; DFAM_STEPS] = [
+    "Step 1 Velocity",
+    "Step 2 Velocity",
+    "Step 3 Velocity",
+    "Step 4 Velocity",
+    "Step 5 Velocity",
+    "Step 6 Velocity",
+    "Step 7 Velocity",
+    "Step 8 Velocity",
+];
+
+/// Index of the first step pitch parameter; the step velocities follow them.
+const FIRST_STEP_PITCH: u32 = 11;
+const FIRST_STEP_VELOCITY: u32 = FIRST_STEP_PITCH + DFAM_STEPS as u32;
+const PARAMETER_COUNT: u32 = FIRST_STEP_VELOCITY + DFAM_STEPS as u32;
+
+pub(crate) const DFAM_PARAMETERS: [ParamDescriptor; PARAMETER_COUNT as usize] = dfam_parameters();
+
+/// The voice parameters, then the sequencer: `clock_mode` 0 follows the host tempo with
+/// `clock_rate` steps per beat and 1 runs free at `clock_rate` steps per second.
+const fn dfam_parameters() -> [ParamDescriptor; PARAMETER_COUNT as usize] {
+    let mut parameters = [DFAM_VOICE_PARAMETERS[0]; PARAMETER_COUNT as usize];
+    let mut index = 0;
+    while index < DFAM_VOICE_PARAMETERS.len() {
+        parameters[index] = DFAM_VOICE_PARAMETERS[index];
+        index += 1;
+    }
+    parameters[8] = ParamDescriptor::new(
+        8,
+        "sequencer_enabled",
+        "Sequencer",
+        ParamUnit::Generic,
+        0.0,
+        1.0,
+        0.0,
+    )
+    .with_skew(ParamSkew::Stepped);
+    parameters[9] =
+        ParamDescriptor::new(9, "clock_mode", "Clock", ParamUnit::Generic, 0.0, 1.0, 0.0)
+            .with_skew(ParamSkew::Stepped);
+    parameters[10] = ParamDescriptor::new(
+        10,
+        "clock_rate",
+        "Clock Rate",
+        ParamUnit::Generic,
+        0.1,
+        64.0,
+        4.0,
+    )
+    .with_skew(ParamSkew::Logarithmic);
+    let mut step = 0;
+    while step < DFAM_STEPS {
+        let pitch = FIRST_STEP_PITCH + step as u32;
+        parameters[pitch as usize] = ParamDescriptor::new(
+            pitch,
+            DFAM_STEP_PITCH_KEYS[step],
+            STEP_PITCH_NAMES[step],
+            ParamUnit::Semitones,
+            -48.0,
+            48.0,
+            0.0,
+        );
+        let velocity = FIRST_STEP_VELOCITY + step as u32;
+        parameters[velocity as usize] = ParamDescriptor::new(
+            velocity,
+            DFAM_STEP_VELOCITY_KEYS[step],
+            STEP_VELOCITY_NAMES[step],
+            ParamUnit::Generic,
+            0.0,
+            1.0,
+            1.0,
+        );
+        step += 1;
+    }
+    parameters
+}
+
+const DFAM_VOICE_PARAMETERS: [ParamDescriptor; 8] = [
+    ParamDescriptor::new(0, "fm_amount", "FM", ParamUnit::Generic, 0.0, 4.0, 0.0),
+    ParamDescriptor::new(
+        1,
+        "vco_decay",
+        "VCO Decay",
+        ParamUnit::Seconds,
+        0.005,
+        2.0,
+        0.15,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+    ParamDescriptor::new(
+        2,
+        "vco_env_amount",
+        "VCO EG",
+        ParamUnit::Semitones,
+        -48.0,
+        48.0,
+        0.0,
+    ),
+    ParamDescriptor::new(
+        3,
+        "vcf_cutoff",
+        "Cutoff",
+        ParamUnit::Hertz,
+        20.0,
+        16_000.0,
+        500.0,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+    ParamDescriptor::new(
+        4,
+        "vcf_resonance",
+        "Resonance",
+        ParamUnit::Generic,
+        0.0,
+        4.0,
+        0.5,
+    ),
+    ParamDescriptor::new(
+        5,
+        "vcf_decay",
+        "VCF Decay",
+        ParamUnit::Seconds,
+        0.005,
+        2.0,
+        0.2,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+    ParamDescriptor::new(
+        6,
+        "vcf_env_amount",
+        "VCF EG",
+        ParamUnit::Octaves,
+        -6.0,
+        6.0,
+        0.0,
+    ),
+    ParamDescriptor::new(
+        7,
+        "vca_decay",
+        "VCA Decay",
+        ParamUnit::Seconds,
+        0.005,
+        2.0,
+        0.3,
+    )
+    .with_skew(ParamSkew::Logarithmic),
+];
+
+/// How the DFAM sequencer advances.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SequencerClock {
+    /// Follows the host tempo, advancing `steps_per_beat` steps per beat.
+    Tempo { steps_per_beat: f32 },
+    /// Runs at a fixed rate independent of the host tempo.
+    Free { steps_per_second: f32 },
+}
+
+impl SequencerClock {
+    /// Steps per beat or per second, depending on the mode.
+    pub fn rate(self) -> f32 {
+        match self {
+            SequencerClock::Tempo { steps_per_beat } => steps_per_beat,
+            SequencerClock::Free { steps_per_second } => steps_per_second,
+        }
+    }
+
+    /// The same rate, following the host tempo or running free.
+    fn with_mode(self, free: bool) -> Self {
+        Self::new(free, self.rate())
+    }
+
+    fn with_rate(self, rate: f32) -> Self {
+        Self::new(matches!(self, SequencerClock::Free { .. }), rate)
+    }
+
+    fn new(free: bool, rate: f32) -> Self {
+        if free {
+            SequencerClock::Free {
+                steps_per_second: rate,
+            }
+        } else {
+            SequencerClock::Tempo {
+                steps_per_beat: rate,
+            }
+        }
+    }
+}
+
+impl Default for SequencerClock {
+    fn default() -> Self {
+        SequencerClock::Tempo {
+            steps_per_beat: 4.0,
+        }
+    }
+}
+
+/// One-shot exponential decay, retriggered on every step.
+#[derive(Clone, Copy)]
+struct DecayEnvelope {
+    level: f32,
+    seconds: f32,
+    coefficient: f32,
+}
+
+impl DecayEnvelope {
+    fn new(seconds: f32) -> Self {
+        Self {
+            level: 0.0,
+            seconds,
+            coefficient: 0.0,
+        }
+    }
+
+    /// -60 dB after `seconds`.
+    fn update(&mut self, sample_rate: f32) {
+        self.coefficient = 10f32.powf(-3.0 / (self.seconds.max(0.001) * sample_rate));
+    }
+
+    #[inline]
+    fn next(&mut self) -> f32 {
+        let level = self.level;
+        self.level *= self.coefficient;
+        level
+    }
+}
+
+/// DFAM-style percussion voice: two square VCOs with linear FM and noise through a
+/// resonant ladder filter, with VCO, VCF and VCA decay envelopes and an optional
+/// 8-step pitch/velocity sequencer.
 pub struct MoogNode {
     vco1: OscillatorNode,
     vco2: OscillatorNode,
     noise: NoiseGenerator,
+    vcf: MoogLadder,
+    base_frequency: f32,
+    fm_amount: f32,
+    vco_env: DecayEnvelope,
+    /// Pitch sweep depth of the VCO envelope in semitones.
+    vco_env_amount: f32,
+    vcf_env: DecayEnvelope,
+    vcf_cutoff: f32,
+    /// Cutoff sweep depth of the VCF envelope in octaves.
+    vcf_env_amount: f32,
+    vca_env: DecayEnvelope,
+    step_pitches: [f32; DFAM_STEPS],
+    step_velocities: [f32; DFAM_STEPS],
+    sequencer_enabled: bool,
+    running: bool,
+    clock: SequencerClock,
+    tempo_bpm: f32,
+    step_index: usize,
+    /// Fraction of the current step that has elapsed.
+    step_phase: f32,
+    step_pitch: f32,
+    step_velocity: f32,
+    previous_vco2: f32,
 }
 
 impl MoogNode {
-    pub fn new(vco1: OscillatorNode, vco2: OscillatorNode, noise: NoiseGenerator) -> Self {
-        MoogNode { vco1, vco2, noise }
+    pub fn new(
+        vco1: OscillatorNode,
+        vco2: OscillatorNode,
+        noise: NoiseGenerator,
+        sample_rate: f32,
+    ) -> Self {
+        let vcf_cutoff = 500.0;
+        MoogNode {
+            vco1,
+            vco2,
+            noise,
+            vcf: MoogLadder::new(0, sample_rate, vcf_cutoff, 0.5),
+            base_frequency: 0.0,
+            fm_amount: 0.0,
+            vco_env: DecayEnvelope::new(0.15),
+            vco_env_amount: 0.0,
+            vcf_env: DecayEnvelope::new(0.2),
+            vcf_cutoff,
+            vcf_env_amount: 0.0,
+            vca_env: DecayEnvelope::new(0.3),
+            step_pitches: [0.0; DFAM_STEPS],
+            step_velocities: [1.0; DFAM_STEPS],
+            sequencer_enabled: false,
+            running: false,
+            clock: SequencerClock::default(),
+            tempo_bpm: DEFAULT_TEMPO_BPM,
+            step_index: 0,
+            step_phase: 0.0,
+            step_pitch: 0.0,
+            step_velocity: 1.0,
+            previous_vco2: 0.0,
+        }
+    }
+
+    /// Steps advanced per sample, or zero while the sequencer is stopped.
+    fn steps_per_sample(&self, sample_rate: f32) -> f32 {
+        if !self.running {
+            return 0.0;
+        }
+        let steps_per_second = match self.clock {
+            SequencerClock::Tempo { steps_per_beat } => self.tempo_bpm / 60.0 * steps_per_beat,
+            SequencerClock::Free { steps_per_second } => steps_per_second,
+        };
+        steps_per_second.max(0.0) / sample_rate
+    }
+
+    fn trigger_step(&mut self) {
+        if self.sequencer_enabled {
+            self.step_pitch = self.step_pitches[self.step_index];
+            self.step_velocity = self.step_velocities[self.step_index];
+        } else {
+            self.step_pitch = 0.0;
+            self.step_velocity = 1.0;
+        }
+        self.vco_env.level = 1.0;
+        self.vcf_env.level = 1.0;
+        self.vca_env.level = 1.0;
+    }
+
+    fn handle_dfam_command(&mut self, command: &DFAMCmd) {
+        match command {
+            DFAMCmd::SetSequencerEnabled { enabled } => {
+                self.sequencer_enabled = *enabled;
+                if !enabled {
+                    self.running = false;
+                }
+            }
+            DFAMCmd::SetClock { clock } => self.clock = *clock,
+            DFAMCmd::SetStepPitch { step, semitones } => {
+                if let Some(pitch) = self.step_pitches.get_mut(*step as usize) {
+                    *pitch = *semitones;
+                }
+            }
+            DFAMCmd::SetStepVelocity { step, velocity } => {
+                if let Some(level) = self.step_velocities.get_mut(*step as usize) {
+                    *level = velocity.clamp(0.0, 1.0);
+                }
+            }
+            DFAMCmd::SetFmAmount { amount } => self.fm_amount = amount.max(0.0),
+            DFAMCmd::SetVcoDecay { seconds } => self.vco_env.seconds = *seconds,
+            DFAMCmd::SetVcoEnvAmount { semitones } => self.vco_env_amount = *semitones,
+            DFAMCmd::SetVcfCutoff { frequency } => {
+                self.vcf_cutoff = frequency.max(20.0);
+                self.vcf.set_cutoff(self.vcf_cutoff);
+            }
+            DFAMCmd::SetVcfResonance { resonance } => self.vcf.set_resonance(*resonance),
+            DFAMCmd::SetVcfDecay { seconds } => self.vcf_env.seconds = *seconds,
+            DFAMCmd::SetVcfEnvAmount { octaves } => self.vcf_env_amount = *octaves,
+            DFAMCmd::SetVcaDecay { seconds } => self.vca_env.seconds = *seconds,
+        }
     }
 }
 
 impl SynthNode for MoogNode {
     fn process(&mut self, mono_buf: &mut [f32], sample_rate: f32) {
+        self.vco_env.update(sample_rate);
+        self.vcf_env.update(sample_rate);
+        self.vca_env.update(sample_rate);
+        let steps_per_sample = self.steps_per_sample(sample_rate);
+        let nyquist = sample_rate * 0.45;
+
         for sample in mono_buf.iter_mut() {
+            if steps_per_sample > 0.0 {
+                self.step_phase += steps_per_sample;
+                if self.step_phase >= 1.0 {
+                    self.step_phase -= 1.0;
+                    self.step_index = (self.step_index + 1) % DFAM_STEPS;
+                    self.trigger_step();
+                }
+            }
+
+            let vco_env = self.vco_env.next();
+            let vcf_env = self.vcf_env.next();
+            let vca_env = self.vca_env.next();
+
+            let semitones = self.step_pitch + self.vco_env_amount * vco_env;
+            let frequency = self.base_frequency * (semitones / 12.0).exp2();
+            // Linear FM from VCO 2 into VCO 1, using the previous sample to avoid a feedback loop.
+            let fm = 1.0 + self.fm_amount * self.previous_vco2;
+            self.vco1.set_frequency(frequency * fm);
+            self.vco2.set_frequency(frequency * VCO2_DETUNE);
+
+            if self.vcf_env_amount != 0.0 {
+                let octaves = self.vcf_env_amount * vcf_env * self.step_velocity;
+                self.vcf
+                    .set_cutoff((self.vcf_cutoff * octaves.exp2()).clamp(20.0, nyquist));
+            }
+
             let vco1_sample = self.vco1.next_sample(sample_rate);
             let vco2_sample = self.vco2.next_sample(sample_rate);
+            self.previous_vco2 = vco2_sample;
             let noise_sample = self.noise.next_sample();
-            *sample = 0.4 * vco1_sample + 0.4 * vco2_sample + 0.2 * noise_sample;
+            let mix = 0.4 * vco1_sample + 0.4 * vco2_sample + 0.2 * noise_sample;
+            let filtered = self.vcf.process_sample(mix);
+
+            // In manual mode the instrument amp envelope shapes each note; the sequencer
+            // retriggers within one held note, so it needs its own VCA decay.
+            *sample = if self.sequencer_enabled {
+                filtered * vca_env * self.step_velocity
+            } else {
+                filtered
+            };
         }
     }
 
     fn note_on(&mut self, note: u8, _velocity: u8) {
-        let freq = utils::note::midi_to_frequency(note);
-        self.vco1.set_frequency(freq);
-        self.vco2.set_frequency(freq * 1.01); // slight detune
+        self.base_frequency = utils::note::midi_to_frequency(note);
+        self.step_index = 0;
+        self.step_phase = 0.0;
+        self.running = self.sequencer_enabled;
+        self.trigger_step();
     }
 
     fn note_off(&mut self) {
-        // Implement note off behavior if needed
+        self.running = false;
+    }
+
+    fn prepare(&mut self, sample_rate: f32) {
+        // The decay envelopes and step clock follow the per-block sample rate.
+        self.vcf.set_sample_rate(sample_rate);
     }
 
     fn is_active(&self) -> bool {
         true
     }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &DFAM_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        let command = match index {
+            0 => DFAMCmd::SetFmAmount { amount: value },
+            1 => DFAMCmd::SetVcoDecay { seconds: value },
+            2 => DFAMCmd::SetVcoEnvAmount { semitones: value },
+            3 => DFAMCmd::SetVcfCutoff { frequency: value },
+            4 => DFAMCmd::SetVcfResonance { resonance: value },
+            5 => DFAMCmd::SetVcfDecay { seconds: value },
+            6 => DFAMCmd::SetVcfEnvAmount { octaves: value },
+            7 => DFAMCmd::SetVcaDecay { seconds: value },
+            8 => DFAMCmd::SetSequencerEnabled {
+                enabled: value >= 0.5,
+            },
+            9 => DFAMCmd::SetClock {
+                clock: self.clock.with_mode(value >= 0.5),
+            },
+            10 => DFAMCmd::SetClock {
+                clock: self.clock.with_rate(value),
+            },
+            FIRST_STEP_PITCH..FIRST_STEP_VELOCITY => DFAMCmd::SetStepPitch {
+                step: (index - FIRST_STEP_PITCH) as u8,
+                semitones: value,
+            },
+            FIRST_STEP_VELOCITY..PARAMETER_COUNT => DFAMCmd::SetStepVelocity {
+                step: (index - FIRST_STEP_VELOCITY) as u8,
+                velocity: value,
+            },
+            _ => return,
+        };
+        self.handle_dfam_command(&command);
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.fm_amount),
+            1 => Some(self.vco_env.seconds),
+            2 => Some(self.vco_env_amount),
+            3 => Some(self.vcf_cutoff),
+            4 => MonoEffect::parameter(&self.vcf, 1),
+            5 => Some(self.vcf_env.seconds),
+            6 => Some(self.vcf_env_amount),
+            7 => Some(self.vca_env.seconds),
+            8 => Some(f32::from(u8::from(self.sequencer_enabled))),
+            9 => Some(match self.clock {
+                SequencerClock::Tempo { .. } => 0.0,
+                SequencerClock::Free { .. } => 1.0,
+            }),
+            10 => Some(self.clock.rate()),
+            FIRST_STEP_PITCH..FIRST_STEP_VELOCITY => {
+                Some(self.step_pitches[(index - FIRST_STEP_PITCH) as usize])
+            }
+            FIRST_STEP_VELOCITY..PARAMETER_COUNT => {
+                Some(self.step_velocities[(index - FIRST_STEP_VELOCITY) as usize])
+            }
+            _ => None,
+        }
+    }
+
+    fn try_handle_command(&mut self, command: &SynthCmd) -> bool {
+        match command {
+            SynthCmd::SetTempo { bpm } => {
+                self.tempo_bpm = *bpm;
+                true
+            }
+            SynthCmd::DFAMCommand { command } => {
+                self.handle_dfam_command(command);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruments::Waveform;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn node() -> MoogNode {
+        MoogNode::new(
+            OscillatorNode::new_with_waveform(Waveform::Square),
+            OscillatorNode::new_with_waveform(Waveform::Square),
+            NoiseGenerator::default(),
+            SAMPLE_RATE,
+        )
+    }
+
+    fn send(node: &mut MoogNode, command: DFAMCmd) {
+        assert!(node.try_handle_command(&SynthCmd::DFAMCommand { command }));
+    }
+
+    #[test]
+    fn tempo_synced_sequencer_advances_one_step_per_sixteenth() {
+        let mut node = node();
+        send(&mut node, DFAMCmd::SetSequencerEnabled { enabled: true });
+        node.try_handle_command(&SynthCmd::SetTempo { bpm: 120.0 });
+        node.note_on(48, 127);
+
+        // 120 BPM at four steps per beat is 8 steps per second, 6000 samples per step.
+        let mut buf = vec![0.0; 6000 * 3 + 10];
+        node.process(&mut buf, SAMPLE_RATE);
+        assert_eq!(node.step_index, 3);
+    }
+
+    #[test]
+    fn free_running_clock_ignores_tempo() {
+        let mut node = node();
+        send(&mut node, DFAMCmd::SetSequencerEnabled { enabled: true });
+        send(
+            &mut node,
+            DFAMCmd::SetClock {
+                clock: SequencerClock::Free {
+                    steps_per_second: 2.0,
+                },
+            },
+        );
+        node.try_handle_command(&SynthCmd::SetTempo { bpm: 300.0 });
+        node.note_on(48, 127);
+
+        let mut buf = vec![0.0; 24_010];
+        node.process(&mut buf, SAMPLE_RATE);
+        assert_eq!(node.step_index, 1);
+    }
+
+    #[test]
+    fn step_velocity_scales_each_hit() {
+        let mut node = node();
+        send(&mut node, DFAMCmd::SetSequencerEnabled { enabled: true });
+        send(
+            &mut node,
+            DFAMCmd::SetStepVelocity {
+                step: 0,
+                velocity: 0.0,
+            },
+        );
+        node.note_on(48, 127);
+        let mut buf = vec![0.0; 1000];
+        node.process(&mut buf, SAMPLE_RATE);
+        assert!(buf.iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn note_off_stops_the_sequencer() {
+        let mut node = node();
+        send(&mut node, DFAMCmd::SetSequencerEnabled { enabled: true });
+        node.note_on(48, 127);
+        node.note_off();
+        let mut buf = vec![0.0; 48_000];
+        node.process(&mut buf, SAMPLE_RATE);
+        assert_eq!(node.step_index, 0);
+    }
 }
@@ -22,6 +22,17 @@ pub enum Waveform {
     NesTriangle,
 }
 
+impl Waveform {
+    /// Every waveform, in the order used to serialize them as a number.
+    pub const ALL: [Waveform; 5] = [
+        Waveform::Sine,
+        Waveform::Square,
+        Waveform::Sawtooth,
+        Waveform::Triangle,
+        Waveform::NesTriangle,
+    ];
+}
+
 impl Default for OscillatorNode {
     fn default() -> Self {
         Self::new()
@@ -57,6 +68,11 @@ impl OscillatorNode {
         self.phase = 0.0;
     }
 
+    /// Sets the current phase in radians, wrapped into `[0, TAU)`.
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(std::f32::consts::TAU);
+    }
+
     #[inline]
     fn nes_triangle_sample(phase: f32) -> f32 {
         // NES APU triangle: 32-step repeating sequence (4-bit amplitude)
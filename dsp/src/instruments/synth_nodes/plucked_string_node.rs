@@ -0,0 +1,345 @@
+use utils::note::midi_to_frequency;
+
+use crate::commands::{PluckedStringCmd, SynthCmd};
+use crate::{ParamDescriptor, ParamSkew, ParamUnit, SynthNode};
+
+/// Lowest fundamental the delay line is sized for. Lower notes are clamped to this pitch.
+const MIN_FREQUENCY: f32 = 20.0;
+/// Decay time used once the note is released, as if a finger muted the string.
+const RELEASE_DECAY_SECONDS: f32 = 0.08;
+/// Peak level under which the string is considered silent and the voice is freed.
+const SILENCE_THRESHOLD: f32 = 1.0e-4;
+
+pub(crate) const PLUCKED_STRING_PARAMETERS: [ParamDescriptor; 4] = [
+    ParamDescriptor::new(0, "damping", "Damping", ParamUnit::Generic, 0.0, 1.0, 0.5),
+    ParamDescriptor::new(
+        1,
+        "brightness",
+        "Brightness",
+        ParamUnit::Generic,
+        0.0,
+        1.0,
+        0.7,
+    ),
+    ParamDescriptor::new(
+        2,
+        "pick_position",
+        "Pick Position",
+        ParamUnit::Generic,
+        0.0,
+        0.5,
+        0.2,
+    ),
+    ParamDescriptor::new(3, "decay", "Decay", ParamUnit::Seconds, 0.01, 10.0, 2.0)
+        .with_skew(ParamSkew::Logarithmic),
+];
+
+/// Karplus-Strong plucked string. A noise burst shaped by the pick position and
+/// brightness is fed into a tuned delay loop whose lowpass and gain set the timbre and decay.
+pub struct PluckedStringNode {
+    /// Circular delay line sized at construction for [`MIN_FREQUENCY`] at the given sample rate.
+    delay_line: Vec<f32>,
+    write_pos: usize,
+    /// Loop delay in samples, compensated for the loop filter's group delay.
+    delay_samples: f32,
+    frequency: f32,
+    /// Previous loop sample used by the two-point damping filter.
+    previous: f32,
+    /// Per-period loop gain derived from the decay time.
+    loop_gain: f32,
+    damping: f32,
+    brightness: f32,
+    pick_position: f32,
+    decay_seconds: f32,
+    released: bool,
+    active: bool,
+    rng_state: u32,
+    /// Sample rate the delay line was sized for; note-on needs it to shape the excitation.
+    sample_rate: f32,
+}
+
+impl PluckedStringNode {
+    pub fn new(sample_rate: f32, seed: u32) -> Self {
+        let capacity = (sample_rate / MIN_FREQUENCY).ceil() as usize + 4;
+        let mut node = Self {
+            delay_line: vec![0.0; capacity],
+            write_pos: 0,
+            delay_samples: 0.0,
+            frequency: 0.0,
+            previous: 0.0,
+            loop_gain: 0.0,
+            damping: 0.5,
+            brightness: 0.7,
+            pick_position: 0.2,
+            decay_seconds: 2.0,
+            released: false,
+            active: false,
+            rng_state: seed.max(1),
+            sample_rate,
+        };
+        node.update_loop(sample_rate);
+        node
+    }
+
+    /// 0.0 keeps every harmonic ringing, 1.0 is the classic Karplus-Strong two-point average.
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping.clamp(0.0, 1.0);
+    }
+
+    /// 0.0 plucks with a soft, dark excitation, 1.0 with raw white noise.
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.brightness = brightness.clamp(0.0, 1.0);
+    }
+
+    /// Relative position along the string, 0.0 at the bridge, 0.5 in the middle.
+    pub fn set_pick_position(&mut self, position: f32) {
+        self.pick_position = position.clamp(0.0, 0.5);
+    }
+
+    /// Time in seconds for a held note to decay by 60 dB.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay_seconds = seconds.max(0.01);
+    }
+
+    fn period_samples(&self, sample_rate: f32) -> f32 {
+        let max_period = (self.delay_line.len() - 3) as f32;
+        (sample_rate / self.frequency.max(MIN_FREQUENCY)).min(max_period)
+    }
+
+    /// Recomputes the loop delay and gain for the current pitch and decay.
+    fn update_loop(&mut self, sample_rate: f32) {
+        let period = self.period_samples(sample_rate);
+        // The two-point damping filter delays the loop by half its weight.
+        self.delay_samples = (period - 0.5 * self.damping).max(1.0);
+        let decay = if self.released {
+            RELEASE_DECAY_SECONDS.min(self.decay_seconds)
+        } else {
+            self.decay_seconds
+        };
+        // -60 dB over `decay` seconds, applied once per trip around the loop.
+        let periods = decay * sample_rate / period;
+        self.loop_gain = 10f32.powf(-3.0 / periods);
+    }
+
+    #[inline]
+    fn next_random(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+        (self.rng_state >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+
+    /// Fills one period of the delay line with the shaped excitation.
+    fn excite(&mut self, sample_rate: f32) {
+        let period = (self.period_samples(sample_rate).round() as usize).max(2);
+        self.delay_line.fill(0.0);
+        self.write_pos = 0;
+        self.previous = 0.0;
+
+        // Brightness: one-pole lowpass over white noise.
+        let coefficient = 0.05 + 0.95 * self.brightness;
+        let mut state = 0.0;
+        for i in 0..period {
+            let noise = self.next_random();
+            state += coefficient * (noise - state);
+            self.delay_line[i] = state;
+        }
+
+        // Pick position: a feedforward comb notches the harmonics with a node at the pick point.
+        let pick = (self.pick_position * period as f32).round() as usize;
+        if pick > 0 {
+            for i in (pick..period).rev() {
+                self.delay_line[i] -= self.delay_line[i - pick];
+            }
+        }
+
+        // Remove DC and normalise so every pluck starts at the same peak level.
+        let mean = self.delay_line[..period].iter().sum::<f32>() / period as f32;
+        let mut peak = 0.0f32;
+        for sample in &mut self.delay_line[..period] {
+            *sample -= mean;
+            peak = peak.max(sample.abs());
+        }
+        if peak > 0.0 {
+            for sample in &mut self.delay_line[..period] {
+                *sample /= peak;
+            }
+        }
+        self.write_pos = period % self.delay_line.len();
+    }
+
+    #[inline]
+    fn read_delayed(&self) -> f32 {
+        let len = self.delay_line.len();
+        let read_pos = self.write_pos as f32 - self.delay_samples;
+        let read_pos = if read_pos < 0.0 {
+            read_pos + len as f32
+        } else {
+            read_pos
+        };
+        let index = read_pos as usize % len;
+        let next = (index + 1) % len;
+        let frac = read_pos.fract();
+        self.delay_line[index] + (self.delay_line[next] - self.delay_line[index]) * frac
+    }
+
+    fn handle_plucked_string_command(&mut self, command: &PluckedStringCmd) {
+        match command {
+            PluckedStringCmd::SetDamping { damping } => self.set_damping(*damping),
+            PluckedStringCmd::SetBrightness { brightness } => self.set_brightness(*brightness),
+            PluckedStringCmd::SetPickPosition { position } => self.set_pick_position(*position),
+            PluckedStringCmd::SetDecay { seconds } => self.set_decay(*seconds),
+        }
+    }
+}
+
+impl SynthNode for PluckedStringNode {
+    fn process(&mut self, mono_buf: &mut [f32], sample_rate: f32) {
+        if !self.active {
+            mono_buf.fill(0.0);
+            return;
+        }
+        // Cheap enough per block and keeps parameter changes glitch-free.
+        self.update_loop(sample_rate);
+
+        let mut peak = 0.0f32;
+        let weight = 0.5 * self.damping;
+        for sample in mono_buf.iter_mut() {
+            let current = self.read_delayed();
+            let filtered = (1.0 - weight) * current + weight * self.previous;
+            self.previous = current;
+            self.delay_line[self.write_pos] = filtered * self.loop_gain;
+            self.write_pos = (self.write_pos + 1) % self.delay_line.len();
+            peak = peak.max(current.abs());
+            *sample = current;
+        }
+        if peak < SILENCE_THRESHOLD {
+            self.active = false;
+        }
+    }
+
+    fn note_on(&mut self, note: u8, _velocity: u8) {
+        self.frequency = midi_to_frequency(note);
+        self.released = false;
+        self.active = true;
+        self.update_loop(self.sample_rate);
+        self.excite(self.sample_rate);
+    }
+
+    fn note_off(&mut self) {
+        self.released = true;
+    }
+
+    fn prepare(&mut self, sample_rate: f32) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        let capacity = (sample_rate / MIN_FREQUENCY).ceil() as usize + 4;
+        self.delay_line = vec![0.0; capacity];
+        self.write_pos = 0;
+        self.previous = 0.0;
+        self.active = false;
+        self.sample_rate = sample_rate;
+        self.update_loop(sample_rate);
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &PLUCKED_STRING_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        let command = match index {
+            0 => PluckedStringCmd::SetDamping { damping: value },
+            1 => PluckedStringCmd::SetBrightness { brightness: value },
+            2 => PluckedStringCmd::SetPickPosition { position: value },
+            3 => PluckedStringCmd::SetDecay { seconds: value },
+            _ => return,
+        };
+        self.handle_plucked_string_command(&command);
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(self.damping),
+            1 => Some(self.brightness),
+            2 => Some(self.pick_position),
+            3 => Some(self.decay_seconds),
+            _ => None,
+        }
+    }
+
+    fn try_handle_command(&mut self, command: &SynthCmd) -> bool {
+        match command {
+            SynthCmd::PluckedStringCommand { command } => {
+                self.handle_plucked_string_command(command);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn render(node: &mut PluckedStringNode, frames: usize) -> Vec<f32> {
+        let mut buf = vec![0.0; frames];
+        node.process(&mut buf, SAMPLE_RATE);
+        buf
+    }
+
+    #[test]
+    fn output_is_periodic_at_the_note_frequency() {
+        let mut node = PluckedStringNode::new(SAMPLE_RATE, 1);
+        node.set_damping(0.0);
+        node.note_on(69, 127);
+        let output = render(&mut node, 4096);
+
+        // Autocorrelation peaks at one period of A4 (≈109 samples at 48 kHz).
+        let window = &output[1024..2048];
+        let correlation = |lag: usize| -> f32 {
+            window
+                .iter()
+                .zip(&output[1024 + lag..2048 + lag])
+                .map(|(a, b)| a * b)
+                .sum()
+        };
+        let best_lag = (60..200)
+            .max_by(|a, b| correlation(*a).total_cmp(&correlation(*b)))
+            .unwrap();
+        assert!((108..=110).contains(&best_lag), "best lag {best_lag}");
+    }
+
+    #[test]
+    fn string_decays_to_silence_and_frees_the_voice() {
+        let mut node = PluckedStringNode::new(SAMPLE_RATE, 1);
+        node.set_decay(0.1);
+        node.note_on(60, 127);
+        assert!(node.is_active());
+        for _ in 0..100 {
+            render(&mut node, 512);
+        }
+        assert!(!node.is_active());
+    }
+
+    #[test]
+    fn release_shortens_the_decay() {
+        let mut held = PluckedStringNode::new(SAMPLE_RATE, 1);
+        let mut released = PluckedStringNode::new(SAMPLE_RATE, 1);
+        held.note_on(60, 127);
+        released.note_on(60, 127);
+        released.note_off();
+
+        let held_tail = render(&mut held, 9600).split_off(8600);
+        let released_tail = render(&mut released, 9600).split_off(8600);
+        let peak = |buf: &[f32]| buf.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+        assert!(peak(&released_tail) < peak(&held_tail) * 0.1);
+    }
+}
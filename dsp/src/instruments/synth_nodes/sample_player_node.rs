@@ -147,6 +147,10 @@ impl SynthNode for SamplePlayerNode {
         // Looped samples will keep looping while envelope fades out
     }
 
+    fn prepare(&mut self, sample_rate: f32) {
+        self.output_sample_rate = sample_rate;
+    }
+
     fn is_active(&self) -> bool {
         // Sample node is active as long as it's playing
         // For looped samples, this stays true indefinitely
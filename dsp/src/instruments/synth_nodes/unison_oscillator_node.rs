@@ -0,0 +1,400 @@
+use utils::note::midi_to_frequency;
+
+use crate::commands::{SynthCmd, UnisonCmd};
+use crate::instruments::{OscillatorNode, Waveform};
+use crate::{ParamDescriptor, ParamSkew, ParamUnit, SynthNode};
+
+/// Upper bound on stacked oscillators per voice. The stack is a fixed array so
+/// changing the voice count at runtime never allocates on the audio thread.
+pub const MAX_UNISON_VOICES: usize = 8;
+
+pub(crate) const UNISON_PARAMETERS: [ParamDescriptor; 5] = [
+    ParamDescriptor::new(
+        0,
+        "voices",
+        "Voices",
+        ParamUnit::Count,
+        1.0,
+        MAX_UNISON_VOICES as f32,
+        1.0,
+    )
+    .with_skew(ParamSkew::Stepped),
+    ParamDescriptor::new(1, "detune", "Detune", ParamUnit::Cents, 0.0, 100.0, 10.0),
+    ParamDescriptor::new(
+        2,
+        "phase_randomness",
+        "Phase Random",
+        ParamUnit::Generic,
+        0.0,
+        1.0,
+        1.0,
+    ),
+    ParamDescriptor::new(
+        3,
+        "stereo_spread",
+        "Stereo Spread",
+        ParamUnit::Generic,
+        0.0,
+        1.0,
+        0.5,
+    ),
+    ParamDescriptor::new(
+        4,
+        "detune_curve",
+        "Detune Curve",
+        ParamUnit::Generic,
+        0.0,
+        (DetuneCurve::ALL.len() - 1) as f32,
+        0.0,
+    )
+    .with_skew(ParamSkew::Stepped),
+];
+
+/// Shapes how detune is distributed across the unison stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DetuneCurve {
+    /// Voices are spaced evenly between `-detune` and `+detune`.
+    #[default]
+    Linear,
+    /// Voices cluster around the centre pitch and only the outer voices reach the full detune.
+    Exponential,
+    /// Voices are pushed towards the outer edges of the detune range.
+    Logarithmic,
+}
+
+impl DetuneCurve {
+    /// Every curve, in the order used to serialize them as a number.
+    pub const ALL: [DetuneCurve; 3] = [
+        DetuneCurve::Linear,
+        DetuneCurve::Exponential,
+        DetuneCurve::Logarithmic,
+    ];
+
+    /// Maps an evenly spaced offset in `[-1, 1]` onto the curve.
+    #[inline]
+    fn shape(self, offset: f32) -> f32 {
+        match self {
+            DetuneCurve::Linear => offset,
+            DetuneCurve::Exponential => offset * offset.abs(),
+            DetuneCurve::Logarithmic => offset.signum() * offset.abs().sqrt(),
+        }
+    }
+}
+
+/// Configuration for a unison oscillator stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnisonParams {
+    /// Number of stacked oscillators, clamped to `1..=MAX_UNISON_VOICES`.
+    pub voices: u8,
+    /// Detune of the outermost voices in cents.
+    pub detune_cents: f32,
+    pub detune_curve: DetuneCurve,
+    /// 0.0 restarts every oscillator at phase zero, 1.0 picks a fully random start phase on each note.
+    pub phase_randomness: f32,
+    /// 0.0 keeps the stack centred, 1.0 spreads the outer voices hard left and right.
+    pub stereo_spread: f32,
+}
+
+impl Default for UnisonParams {
+    fn default() -> Self {
+        Self {
+            voices: 1,
+            detune_cents: 10.0,
+            detune_curve: DetuneCurve::Linear,
+            phase_randomness: 1.0,
+            stereo_spread: 0.5,
+        }
+    }
+}
+
+/// Stacks up to [`MAX_UNISON_VOICES`] detuned copies of an [`OscillatorNode`] and
+/// renders them as a stereo image.
+pub struct UnisonOscillatorNode {
+    oscillators: [OscillatorNode; MAX_UNISON_VOICES],
+    params: UnisonParams,
+    base_frequency: f32,
+    /// Per-oscillator frequency ratio derived from the detune curve.
+    ratios: [f32; MAX_UNISON_VOICES],
+    gains_left: [f32; MAX_UNISON_VOICES],
+    gains_right: [f32; MAX_UNISON_VOICES],
+    /// Xorshift state used for start phase randomisation.
+    rng_state: u32,
+}
+
+impl UnisonOscillatorNode {
+    /// `seed` must be non-zero; voices of a polyphonic instrument should use distinct seeds
+    /// so their start phases differ.
+    pub fn new(waveform: Waveform, params: UnisonParams, seed: u32) -> Self {
+        let mut node = Self {
+            oscillators: [OscillatorNode::new_with_waveform(waveform); MAX_UNISON_VOICES],
+            params,
+            base_frequency: 0.0,
+            ratios: [1.0; MAX_UNISON_VOICES],
+            gains_left: [0.0; MAX_UNISON_VOICES],
+            gains_right: [0.0; MAX_UNISON_VOICES],
+            rng_state: seed.max(1),
+        };
+        node.set_params(params);
+        node
+    }
+
+    pub fn params(&self) -> UnisonParams {
+        self.params
+    }
+
+    pub fn set_params(&mut self, params: UnisonParams) {
+        self.params = UnisonParams {
+            voices: params.voices.clamp(1, MAX_UNISON_VOICES as u8),
+            detune_cents: params.detune_cents.max(0.0),
+            detune_curve: params.detune_curve,
+            phase_randomness: params.phase_randomness.clamp(0.0, 1.0),
+            stereo_spread: params.stereo_spread.clamp(0.0, 1.0),
+        };
+        self.update_stack();
+    }
+
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        for osc in self.oscillators.iter_mut() {
+            osc.set_waveform(waveform);
+        }
+    }
+
+    fn voice_count(&self) -> usize {
+        self.params.voices as usize
+    }
+
+    /// Recomputes detune ratios and pan gains for the active part of the stack.
+    fn update_stack(&mut self) {
+        let voices = self.voice_count();
+        // Keep the summed level roughly constant regardless of the voice count.
+        let normalization = 1.0 / (voices as f32).sqrt();
+        for i in 0..voices {
+            let offset = if voices == 1 {
+                0.0
+            } else {
+                2.0 * i as f32 / (voices - 1) as f32 - 1.0
+            };
+            let cents = self.params.detune_cents * self.params.detune_curve.shape(offset);
+            self.ratios[i] = (cents / 1200.0).exp2();
+
+            // Balance law rather than constant power: a centred voice contributes
+            // unity gain to both sides, so a zero spread collapses to a mono signal.
+            let pan = offset * self.params.stereo_spread;
+            self.gains_left[i] = (1.0 - pan).min(1.0) * normalization;
+            self.gains_right[i] = (1.0 + pan).min(1.0) * normalization;
+        }
+        self.update_frequencies();
+    }
+
+    fn update_frequencies(&mut self) {
+        for i in 0..self.voice_count() {
+            self.oscillators[i].set_frequency(self.base_frequency * self.ratios[i]);
+        }
+    }
+
+    #[inline]
+    fn next_random(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+        (self.rng_state >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    fn handle_unison_command(&mut self, command: &UnisonCmd) {
+        let mut params = self.params;
+        match command {
+            UnisonCmd::SetVoices { voices } => params.voices = *voices,
+            UnisonCmd::SetDetune { cents } => params.detune_cents = *cents,
+            UnisonCmd::SetDetuneCurve { curve } => params.detune_curve = *curve,
+            UnisonCmd::SetPhaseRandomness { amount } => params.phase_randomness = *amount,
+            UnisonCmd::SetStereoSpread { spread } => params.stereo_spread = *spread,
+        }
+        self.set_params(params);
+    }
+}
+
+impl SynthNode for UnisonOscillatorNode {
+    fn process(&mut self, mono_buf: &mut [f32], sample_rate: f32) {
+        let voices = self.voice_count();
+        for sample in mono_buf.iter_mut() {
+            let mut sum = 0.0;
+            for i in 0..voices {
+                let gain = 0.5 * (self.gains_left[i] + self.gains_right[i]);
+                sum += self.oscillators[i].next_sample(sample_rate) * gain;
+            }
+            *sample = sum;
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn process_stereo(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], sample_rate: f32) {
+        let voices = self.voice_count();
+        for (left, right) in left_buf.iter_mut().zip(right_buf.iter_mut()) {
+            let mut sum_left = 0.0;
+            let mut sum_right = 0.0;
+            for i in 0..voices {
+                let sample = self.oscillators[i].next_sample(sample_rate);
+                sum_left += sample * self.gains_left[i];
+                sum_right += sample * self.gains_right[i];
+            }
+            *left = sum_left;
+            *right = sum_right;
+        }
+    }
+
+    fn note_on(&mut self, note: u8, _velocity: u8) {
+        self.base_frequency = midi_to_frequency(note);
+        self.update_frequencies();
+        for i in 0..self.voice_count() {
+            let phase = self.next_random() * self.params.phase_randomness * std::f32::consts::TAU;
+            self.oscillators[i].set_phase(phase);
+        }
+    }
+
+    fn note_off(&mut self) {
+        // the voice envelope handles the release
+    }
+
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &UNISON_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, index: u32, value: f32) {
+        let command = match index {
+            0 => UnisonCmd::SetVoices {
+                voices: value.round().clamp(1.0, MAX_UNISON_VOICES as f32) as u8,
+            },
+            1 => UnisonCmd::SetDetune { cents: value },
+            2 => UnisonCmd::SetPhaseRandomness { amount: value },
+            3 => UnisonCmd::SetStereoSpread { spread: value },
+            4 => UnisonCmd::SetDetuneCurve {
+                curve: DetuneCurve::ALL
+                    [(value.round().max(0.0) as usize).min(DetuneCurve::ALL.len() - 1)],
+            },
+            _ => return,
+        };
+        self.handle_unison_command(&command);
+    }
+
+    fn parameter(&self, index: u32) -> Option<f32> {
+        match index {
+            0 => Some(f32::from(self.params.voices)),
+            1 => Some(self.params.detune_cents),
+            2 => Some(self.params.phase_randomness),
+            3 => Some(self.params.stereo_spread),
+            4 => DetuneCurve::ALL
+                .iter()
+                .position(|curve| *curve == self.params.detune_curve)
+                .map(|index| index as f32),
+            _ => None,
+        }
+    }
+
+    fn try_handle_command(&mut self, command: &SynthCmd) -> bool {
+        match command {
+            SynthCmd::SetWaveform {
+                voice_id: _,
+                waveform,
+            } => {
+                self.set_waveform(*waveform);
+                true
+            }
+            SynthCmd::UnisonCommand { command } => {
+                self.handle_unison_command(command);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn render(node: &mut UnisonOscillatorNode, frames: usize) -> (Vec<f32>, Vec<f32>) {
+        let mut left = vec![0.0; frames];
+        let mut right = vec![0.0; frames];
+        node.process_stereo(&mut left, &mut right, SAMPLE_RATE);
+        (left, right)
+    }
+
+    #[test]
+    fn single_voice_matches_plain_oscillator() {
+        let params = UnisonParams {
+            voices: 1,
+            phase_randomness: 0.0,
+            ..UnisonParams::default()
+        };
+        let mut unison = UnisonOscillatorNode::new(Waveform::Sawtooth, params, 1);
+        let mut plain = OscillatorNode::new_with_waveform(Waveform::Sawtooth);
+        unison.note_on(69, 127);
+        plain.note_on(69, 127);
+
+        let (left, right) = render(&mut unison, 256);
+        let mut expected = vec![0.0; 256];
+        plain.process(&mut expected, SAMPLE_RATE);
+
+        assert_eq!(left, expected);
+        assert_eq!(right, expected);
+    }
+
+    #[test]
+    fn zero_spread_is_mono() {
+        let params = UnisonParams {
+            voices: 5,
+            stereo_spread: 0.0,
+            ..UnisonParams::default()
+        };
+        let mut node = UnisonOscillatorNode::new(Waveform::Sawtooth, params, 7);
+        node.note_on(57, 127);
+        let (left, right) = render(&mut node, 512);
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn spread_widens_the_image() {
+        let params = UnisonParams {
+            voices: 4,
+            stereo_spread: 1.0,
+            ..UnisonParams::default()
+        };
+        let mut node = UnisonOscillatorNode::new(Waveform::Sawtooth, params, 7);
+        node.note_on(57, 127);
+        let (left, right) = render(&mut node, 512);
+        let side_energy: f32 = left.iter().zip(&right).map(|(l, r)| (l - r).powi(2)).sum();
+        assert!(side_energy > 1.0, "side energy {side_energy}");
+    }
+
+    #[test]
+    fn detune_curve_keeps_outer_voices_at_full_detune() {
+        let params = UnisonParams {
+            voices: 3,
+            detune_cents: 1200.0,
+            detune_curve: DetuneCurve::Exponential,
+            ..UnisonParams::default()
+        };
+        let node = UnisonOscillatorNode::new(Waveform::Sine, params, 1);
+        assert!((node.ratios[0] - 0.5).abs() < 1e-6);
+        assert_eq!(node.ratios[1], 1.0);
+        assert!((node.ratios[2] - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn voice_count_is_clamped() {
+        let mut node = UnisonOscillatorNode::new(Waveform::Sine, UnisonParams::default(), 1);
+        node.try_handle_command(&SynthCmd::UnisonCommand {
+            command: UnisonCmd::SetVoices { voices: 200 },
+        });
+        assert_eq!(node.params().voices as usize, MAX_UNISON_VOICES);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::id::InstrumentId;
+use crate::instruments::{
+    MonophonicInstrument, UnisonOscillatorNode, UnisonParams, VoiceSlot, Waveform,
+};
+use crate::{Envelope, MonoEffectChain, Voice};
+
+/// Seed for the start phase randomisation of the unison stack.
+const UNISON_PHASE_SEED: u32 = 0x2545_F491;
+
+pub type UnisonOscillator = MonophonicInstrument<UnisonOscillatorNode>;
+
+impl UnisonOscillator {
+    pub fn new(
+        instrument_id: InstrumentId,
+        pan: f32,
+        sample_rate: f32,
+        waveform: Waveform,
+        unison: UnisonParams,
+    ) -> Self {
+        let envelope = Envelope::new(sample_rate);
+        let voice = Voice::new(
+            0,
+            UnisonOscillatorNode::new(waveform, unison, UNISON_PHASE_SEED),
+            envelope,
+            pan,
+            MonoEffectChain::new(10),
+        );
+        // Note ID is unused in a monophonic instrument.
+        let voice = VoiceSlot {
+            inner: voice,
+            note_id: None,
+        };
+        UnisonOscillator {
+            instrument_id,
+            voice,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::id::EffectId;
+use crate::{EffectType, ParamDescriptor};
 
 /// A trait for any real-time audio effect. Not to be confused with command transformer effects,
 /// such as an Arpeggiator for example, which operates from the NRT world and would constitute a
@@ -11,6 +12,12 @@ pub trait StereoEffect: Send + Sync {
     /// Returns the unique identifier for this effect instance.
     fn id(&self) -> EffectId;
 
+    /// The factory type this effect was built as, so that it can be rebuilt.
+    /// `None` for effects the [`crate::EffectFactory`] cannot create.
+    fn effect_type(&self) -> Option<EffectType> {
+        None
+    }
+
     /// Processes a block of stereo audio.
     ///
     /// This method is called on every audio block and must be real-time safe.
@@ -32,6 +39,22 @@ pub trait StereoEffect: Send + Sync {
     /// * `value` - The new value for the parameter.
     fn set_parameter(&mut self, index: u32, value: f32);
 
+    /// Describes the parameters accepted by [`Self::set_parameter`].
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &[]
+    }
+
+    /// Current plain value of a parameter, as last applied by
+    /// [`Self::set_parameter`]. `None` if the effect does not report it.
+    fn parameter(&self, _index: u32) -> Option<f32> {
+        None
+    }
+
+    /// Adapts the effect to a new sample rate and maximum block size, keeping
+    /// its parameters. May allocate and clear delay lines, so it is only called
+    /// off the audio thread.
+    fn prepare(&mut self, _sample_rate: f32, _max_block_size: usize) {}
+
     /// Attempts to handle a command specific to this effect.
     /// Returns `true` if the command was handled, `false` if not applicable.
     fn try_handle_command(&mut self, _command: &crate::commands::EffectCmd) -> bool {
@@ -76,6 +99,42 @@ impl StereoEffectChain {
         }
     }
 
+    /// Prepares every effect in the chain; see [`StereoEffect::prepare`].
+    pub fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        for effect in &mut self.effects {
+            effect.prepare(sample_rate, max_block_size);
+        }
+    }
+
+    /// Returns `true` when [`Self::add_effect`] would reject another effect.
+    pub fn is_full(&self) -> bool {
+        self.effects.len() == self.effects.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Removes every effect, keeping the chain's capacity.
+    pub fn drain(&mut self) -> impl Iterator<Item = Box<dyn StereoEffect>> + '_ {
+        self.effects.drain(..)
+    }
+
+    /// The effects in processing order.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn StereoEffect> {
+        self.effects.iter().map(|effect| effect.as_ref())
+    }
+
+    /// The effects in processing order, for hosts that drive or measure them
+    /// one at a time instead of calling [`Self::process`].
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn StereoEffect>> {
+        self.effects.iter_mut()
+    }
+
     /// Processes the audio through all effects in the chain, in order.
     pub fn process(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], sample_rate: f32) {
         for effect in &mut self.effects {
@@ -125,6 +184,22 @@ pub trait MonoEffect: Send + Sync {
     /// * `value` - The new value for the parameter.
     fn set_parameter(&mut self, index: u32, value: f32);
 
+    /// Describes the parameters accepted by [`Self::set_parameter`].
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &[]
+    }
+
+    /// Current plain value of a parameter, as last applied by
+    /// [`Self::set_parameter`]. `None` if the effect does not report it.
+    fn parameter(&self, _index: u32) -> Option<f32> {
+        None
+    }
+
+    /// Adapts the effect to a new sample rate and maximum block size, keeping
+    /// its parameters. May allocate and clear delay lines, so it is only called
+    /// off the audio thread.
+    fn prepare(&mut self, _sample_rate: f32, _max_block_size: usize) {}
+
     /// Attempts to handle a command specific to this effect.
     /// Returns `true` if the command was handled, `false` if not applicable.
     fn try_handle_command(&mut self, _command: &crate::commands::EffectCmd) -> bool {
@@ -170,6 +245,18 @@ impl MonoEffectChain {
         }
     }
 
+    /// Prepares every effect in the chain; see [`MonoEffect::prepare`].
+    pub fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        for effect in &mut self.effects {
+            effect.prepare(sample_rate, max_block_size);
+        }
+    }
+
+    /// The effect at `index` in processing order.
+    pub fn get(&self, index: usize) -> Option<&dyn MonoEffect> {
+        self.effects.get(index).map(|effect| effect.as_ref())
+    }
+
     /// Processes the audio through all mono effects in the chain, in order.
     pub fn process(&mut self, buf: &mut [f32], sample_rate: f32) {
         for effect in &mut self.effects {
@@ -27,6 +27,10 @@ pub struct Envelope {
     decay_coef: f32,
     release_coef: f32,
     sustain_level: f32,
+    // Segment lengths in seconds, kept so coefficients survive sample-rate changes.
+    attack_s: f32,
+    decay_s: f32,
+    release_s: f32,
 }
 
 impl Envelope {
@@ -42,6 +46,9 @@ impl Envelope {
             decay_coef: 0.0,
             release_coef: 0.0,
             sustain_level: 1.0,
+            attack_s: 0.0,
+            decay_s: 0.0,
+            release_s: 0.0,
         };
         env.set_parameters(0.1, 0.1, 0.8, 0.5); // Default ADSR values
         env
@@ -64,6 +71,9 @@ impl Envelope {
             decay_coef: 0.0,
             release_coef: 0.0,
             sustain_level: 1.0,
+            attack_s: 0.0,
+            decay_s: 0.0,
+            release_s: 0.0,
         };
         env.set_parameters(attack_s, decay_s, sustain, release_s);
         env
@@ -73,12 +83,23 @@ impl Envelope {
         // Pre-calculate coefficients to avoid expensive math in the audio loop.
         // This formula creates an exponential curve.
 
+        self.attack_s = attack_s;
+        self.decay_s = decay_s;
+        self.release_s = release_s;
         self.attack_coef = Self::time_to_coef(attack_s, self.sample_rate);
         self.decay_coef = Self::time_to_coef(decay_s, self.sample_rate);
         self.release_coef = Self::time_to_coef(release_s, self.sample_rate);
         self.sustain_level = sustain.clamp(0.0, 1.0);
     }
 
+    /// Recomputes the segment coefficients so every segment keeps its length in seconds.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.set_attack(self.attack_s);
+        self.set_decay(self.decay_s);
+        self.set_release(self.release_s);
+    }
+
     pub fn gate(&mut self, is_on: bool) {
         if is_on {
             self.state = EnvelopeState::Attack;
@@ -127,12 +148,33 @@ impl Envelope {
         self.state != EnvelopeState::Idle
     }
 
+    /// Returns the gain produced by the last call to [`Self::process`].
+    pub fn level(&self) -> f32 {
+        if self.state == EnvelopeState::Idle {
+            0.0
+        } else {
+            self.output
+        }
+    }
+
+    /// Attack, decay and release in seconds and the sustain level, in the order
+    /// accepted by [`Self::set_parameters`].
+    pub fn adsr(&self) -> [f32; 4] {
+        [
+            self.attack_s,
+            self.decay_s,
+            self.sustain_level,
+            self.release_s,
+        ]
+    }
+
     /// Returns the current state of the envelope. Useful for diagnostics and tests.
     pub fn state(&self) -> EnvelopeState {
         self.state
     }
 
     pub fn set_attack(&mut self, attack_s: f32) {
+        self.attack_s = attack_s;
         self.attack_coef = Self::time_to_coef(attack_s, self.sample_rate);
         if self.state == EnvelopeState::Attack {
             self.coefficient = self.attack_coef;
@@ -140,6 +182,7 @@ impl Envelope {
     }
 
     pub fn set_decay(&mut self, decay_s: f32) {
+        self.decay_s = decay_s;
         self.decay_coef = Self::time_to_coef(decay_s, self.sample_rate);
         if self.state == EnvelopeState::Decay {
             self.coefficient = self.decay_coef;
@@ -154,6 +197,7 @@ impl Envelope {
     }
 
     pub fn set_release(&mut self, release_s: f32) {
+        self.release_s = release_s;
         self.release_coef = Self::time_to_coef(release_s, self.sample_rate);
         if self.state == EnvelopeState::Release {
             self.coefficient = self.release_coef;
@@ -39,10 +39,29 @@ impl PitchEnvelope {
         self.freq_delta = freq_delta;
     }
 
+    /// Hz the sweep adds to the start frequency at its peak.
+    pub fn freq_delta(&self) -> f32 {
+        self.freq_delta
+    }
+
+    /// Sets how long the sweep takes to return from the attack peak.
+    pub fn set_decay(&mut self, decay_s: f32) {
+        self.adsr.set_decay(decay_s);
+    }
+
+    /// Seconds the sweep takes to return from the attack peak.
+    pub fn decay(&self) -> f32 {
+        self.adsr.adsr()[1]
+    }
+
     pub fn set_parameters(&mut self, a: f32, d: f32, s: f32, r: f32) {
         self.adsr.set_parameters(a, d, s, r);
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.adsr.set_sample_rate(sample_rate);
+    }
+
     pub fn handle_command(&mut self, command: &EnvelopeCmd) -> bool {
         match command {
             EnvelopeCmd::SetPitchEnvFreqDelta { freq_delta } => {
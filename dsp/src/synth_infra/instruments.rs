@@ -1,6 +1,6 @@
 use crate::{
     id::{EffectId, InstrumentId},
-    MonoEffect, VoiceEffects,
+    MonoEffect, ParamDescriptor, VoiceActivity, VoiceEffects,
 };
 
 /// A trait for a complete instrument, which is responsible for managing
@@ -40,8 +40,50 @@ pub trait InstrumentTrait: Send + Sync {
         // ArrayVec with fixed capacity, dropping it does not deallocate heap memory.
     }
 
+    /// Calls `report` with the voice index and activity of every active voice.
+    /// Runs on the audio thread after each block, so it must not allocate.
+    fn voice_activity(&self, _report: &mut dyn FnMut(usize, VoiceActivity)) {}
+
+    /// Number of voices that each need their own effect instance.
+    fn voice_count(&self) -> usize {
+        1
+    }
+
     /// Set a parameter on one of the instrument's effects.
     fn set_effect_parameter(&mut self, effect_id: EffectId, param_index: u32, value: f32);
 
+    /// Describes the instrument's own parameters, accepted by [`Self::set_parameter`].
+    fn parameters(&self) -> &'static [ParamDescriptor] {
+        &[]
+    }
+
+    /// Sets one of the instrument's own parameters by its descriptor index.
+    fn set_parameter(&mut self, _index: u32, _value: f32) {}
+
+    /// Current plain value of one of the instrument's own parameters. `None`
+    /// if the instrument does not report it.
+    fn parameter(&self, _index: u32) -> Option<f32> {
+        None
+    }
+
+    /// The per-voice effect at `index` in chain order, read from the first
+    /// voice. Every voice holds its own instance with the same parameters.
+    fn voice_effect(&self, _index: usize) -> Option<&dyn MonoEffect> {
+        None
+    }
+
+    /// Amplitude envelope settings as returned by [`crate::Envelope::adsr`],
+    /// or `None` if the instrument has no amplitude envelope.
+    fn amp_envelope(&self) -> Option<[f32; 4]> {
+        None
+    }
+
+    /// Adapts voices and effects to a new sample rate and sizes scratch buffers
+    /// for blocks of up to `max_block_size` frames; longer blocks are still
+    /// rendered, in chunks. Parameters keep their values in seconds and hertz.
+    /// Allocates, so call it before the instrument reaches the audio thread or
+    /// while it is not being processed.
+    fn prepare(&mut self, _sample_rate: f32, _max_block_size: usize) {}
+
     fn try_handle_command(&mut self, cmd: &crate::SynthCmd) -> bool;
 }
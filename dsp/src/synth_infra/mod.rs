@@ -1,6 +1,7 @@
 mod effects;
 mod envelopes;
 mod instruments;
+mod parameters;
 mod samples;
 mod synth_node;
 // TODO: Remove this deprecation once the feature flag is in place
@@ -11,6 +12,7 @@ mod voice;
 pub use effects::*;
 pub use envelopes::*;
 pub use instruments::*;
+pub use parameters::*;
 pub use samples::*;
 pub use synth_node::*;
 // TODO: Remove this deprecation once the feature flag is in place
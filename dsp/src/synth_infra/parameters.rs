@@ -0,0 +1,189 @@
+/// Physical unit of a parameter value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamUnit {
+    /// Unitless amount, usually `0..1`.
+    Generic,
+    Decibels,
+    Seconds,
+    Hertz,
+    Semitones,
+    /// Hundredths of a semitone, e.g. unison detune.
+    Cents,
+    Octaves,
+    /// Whole-number count, e.g. delay taps or unison voices.
+    Count,
+}
+
+impl ParamUnit {
+    /// Short display suffix, empty for unitless values.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            ParamUnit::Generic | ParamUnit::Count => "",
+            ParamUnit::Decibels => "dB",
+            ParamUnit::Seconds => "s",
+            ParamUnit::Hertz => "Hz",
+            ParamUnit::Semitones => "st",
+            ParamUnit::Cents => "ct",
+            ParamUnit::Octaves => "oct",
+        }
+    }
+}
+
+/// How a parameter's range maps onto the normalized `0..1` control range used by
+/// hosts and remote controllers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamSkew {
+    Linear,
+    /// Equal ratios per normalized step, for frequencies and times. Requires `min > 0`.
+    Logarithmic,
+    /// The normalized value is a linear amplitude where `1.0` is `max` dB and `0.0` is
+    /// silence, floored at `min` dB.
+    Decibel,
+    /// Whole numbers evenly spread across the range.
+    Stepped,
+}
+
+/// Describes one automatable parameter of an effect or instrument: how it is
+/// addressed, displayed, bounded and mapped to a normalized control value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamDescriptor {
+    /// Index passed to `set_parameter`.
+    pub index: u32,
+    /// Stable machine-readable identifier, used by OSC and song hydration.
+    pub id: &'static str,
+    /// Human-readable label.
+    pub name: &'static str,
+    pub unit: ParamUnit,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub skew: ParamSkew,
+}
+
+impl ParamDescriptor {
+    pub const fn new(
+        index: u32,
+        id: &'static str,
+        name: &'static str,
+        unit: ParamUnit,
+        min: f32,
+        max: f32,
+        default: f32,
+    ) -> Self {
+        Self {
+            index,
+            id,
+            name,
+            unit,
+            min,
+            max,
+            default,
+            skew: ParamSkew::Linear,
+        }
+    }
+
+    pub const fn with_skew(mut self, skew: ParamSkew) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    /// Clamps a plain value into range, rounding stepped parameters.
+    pub fn clamp(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min, self.max);
+        match self.skew {
+            ParamSkew::Stepped => value.round(),
+            _ => value,
+        }
+    }
+
+    /// Maps a plain value to `0..1`.
+    pub fn normalize(&self, value: f32) -> f32 {
+        let value = self.clamp(value);
+        let normalized = match self.skew {
+            ParamSkew::Linear | ParamSkew::Stepped => (value - self.min) / (self.max - self.min),
+            ParamSkew::Logarithmic => (value / self.min).ln() / (self.max / self.min).ln(),
+            ParamSkew::Decibel => {
+                if value <= self.min {
+                    0.0
+                } else {
+                    10f32.powf((value - self.max) / 20.0)
+                }
+            }
+        };
+        normalized.clamp(0.0, 1.0)
+    }
+
+    /// Maps a `0..1` control value to a plain value in range.
+    pub fn denormalize(&self, normalized: f32) -> f32 {
+        let normalized = normalized.clamp(0.0, 1.0);
+        let value = match self.skew {
+            ParamSkew::Linear | ParamSkew::Stepped => self.min + normalized * (self.max - self.min),
+            ParamSkew::Logarithmic => self.min * (self.max / self.min).powf(normalized),
+            ParamSkew::Decibel => {
+                if normalized <= 0.0 {
+                    self.min
+                } else {
+                    20.0 * normalized.log10() + self.max
+                }
+            }
+        };
+        self.clamp(value)
+    }
+}
+
+/// Looks up a descriptor by its stable id.
+pub fn find_parameter<'a>(
+    parameters: &'a [ParamDescriptor],
+    id: &str,
+) -> Option<&'a ParamDescriptor> {
+    parameters.iter().find(|descriptor| descriptor.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_round_trips() {
+        let param = ParamDescriptor::new(0, "mix", "Mix", ParamUnit::Generic, 0.0, 2.0, 1.0);
+        assert_eq!(param.normalize(0.5), 0.25);
+        assert_eq!(param.denormalize(0.25), 0.5);
+        assert_eq!(param.normalize(5.0), 1.0);
+    }
+
+    #[test]
+    fn logarithmic_spreads_octaves_evenly() {
+        let param = ParamDescriptor::new(
+            0,
+            "cutoff",
+            "Cutoff",
+            ParamUnit::Hertz,
+            20.0,
+            20480.0,
+            1000.0,
+        )
+        .with_skew(ParamSkew::Logarithmic);
+        // 20 Hz to 20480 Hz is ten octaves, so 640 Hz sits halfway.
+        assert!((param.denormalize(0.5) - 640.0).abs() < 0.01);
+        assert!((param.normalize(640.0) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn decibel_treats_normalized_values_as_amplitude() {
+        let param = ParamDescriptor::new(0, "gain", "Gain", ParamUnit::Decibels, -120.0, 0.0, 0.0)
+            .with_skew(ParamSkew::Decibel);
+        assert!((param.denormalize(0.5) + 6.0206).abs() < 1e-3);
+        assert_eq!(param.denormalize(1.0), 0.0);
+        assert_eq!(param.denormalize(0.0), -120.0);
+        assert!((param.normalize(-6.0206) - 0.5).abs() < 1e-4);
+        assert_eq!(param.normalize(-120.0), 0.0);
+    }
+
+    #[test]
+    fn stepped_values_are_rounded() {
+        let param = ParamDescriptor::new(1, "taps", "Taps", ParamUnit::Count, 1.0, 5.0, 1.0)
+            .with_skew(ParamSkew::Stepped);
+        assert_eq!(param.clamp(2.6), 3.0);
+        assert_eq!(param.denormalize(0.5), 3.0);
+    }
+}
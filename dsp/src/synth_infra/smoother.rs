@@ -17,18 +17,28 @@ pub struct Smoother<T: Smoothable> {
     value: T,
     target: T,
     coeff: f32,
+    smoothing_time: f32,
 }
 
 impl<T: Smoothable> Smoother<T> {
     pub fn new(sample_rate: f32, smoothing_time: f32, initial: T) -> Self {
-        let coeff = 1.0 - (-1.0 / (smoothing_time * sample_rate)).exp();
         Self {
             value: initial,
             target: initial,
-            coeff,
+            coeff: Self::coefficient(sample_rate, smoothing_time),
+            smoothing_time,
         }
     }
 
+    /// Keeps the smoothing time constant in seconds at a new sample rate.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.coeff = Self::coefficient(sample_rate, self.smoothing_time);
+    }
+
+    fn coefficient(sample_rate: f32, smoothing_time: f32) -> f32 {
+        1.0 - (-1.0 / (smoothing_time * sample_rate)).exp()
+    }
+
     /// Set a new target value (e.g. from modulation or UI)
     pub fn set_target(&mut self, target: T) {
         self.target = target;
@@ -45,6 +55,11 @@ impl<T: Smoothable> Smoother<T> {
         self.value
     }
 
+    /// The value the smoother is heading towards.
+    pub fn target(&self) -> T {
+        self.target
+    }
+
     /// Immediately jump to a value (e.g. voice reset)
     pub fn reset(&mut self, value: T) {
         self.value = value;
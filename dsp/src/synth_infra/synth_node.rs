@@ -16,6 +16,37 @@ pub trait SynthNode: Send + Sync {
     /// once the sample has finished playing.
     fn is_active(&self) -> bool;
 
+    /// Returns `true` if this node renders its own stereo image through
+    /// [`SynthNode::process_stereo`]. Mono nodes are panned by the owning voice.
+    fn is_stereo(&self) -> bool {
+        false
+    }
+
+    /// Renders the next block of audio into a stereo pair. Only called for nodes
+    /// whose [`SynthNode::is_stereo`] returns `true`; the default duplicates the mono render.
+    fn process_stereo(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], sample_rate: f32) {
+        self.process(left_buf, sample_rate);
+        right_buf.copy_from_slice(left_buf);
+    }
+
+    /// Re-derives sample-rate dependent state such as envelope coefficients and
+    /// delay lines. May allocate, so it is only called off the audio thread.
+    fn prepare(&mut self, _sample_rate: f32) {}
+
+    /// Describes the parameters accepted by [`SynthNode::set_parameter`].
+    fn parameters(&self) -> &'static [crate::ParamDescriptor] {
+        &[]
+    }
+
+    /// Sets a parameter by its descriptor index.
+    fn set_parameter(&mut self, _index: u32, _value: f32) {}
+
+    /// Current plain value of a parameter, as last applied by
+    /// [`SynthNode::set_parameter`]. `None` if the node does not report it.
+    fn parameter(&self, _index: u32) -> Option<f32> {
+        None
+    }
+
     /// Attempts to handle a command specific to this voice type.
     /// Returns `true` if the command was handled, `false` if not applicable.
     fn try_handle_command(&mut self, _command: &crate::commands::SynthCmd) -> bool {
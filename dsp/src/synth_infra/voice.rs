@@ -4,9 +4,23 @@ use crate::{
     commands::SynthCmd,
     id::{EffectId, VoiceId},
     synth_infra::synth_node::SynthNode,
-    EffectCmd, Envelope, MonoEffect, MonoEffectChain,
+    EffectCmd, Envelope, EnvelopeState, MonoEffect, MonoEffectChain,
 };
 
+/// Scratch buffer length used when a host does not choose a maximum block size.
+pub const DEFAULT_MAX_BLOCK_SIZE: usize = 4096;
+
+/// What a sounding voice is doing, reported for UIs and diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceActivity {
+    /// Note passed to the last `note_on`.
+    pub note: u8,
+    /// Envelope stage, or `None` for voices whose node shapes its own amplitude.
+    pub stage: Option<EnvelopeState>,
+    /// Envelope gain times velocity gain, as of the last processed sample.
+    pub level: f32,
+}
+
 /// A trait for a generic, type-erased `Voice`. This is used for dynamic dispatch
 /// in the `VoiceManager` to hold a heterogeneous collection of voices.
 pub trait VoiceTrait: Send + Sync {
@@ -27,6 +41,16 @@ pub trait VoiceTrait: Send + Sync {
     /// its underlying `SynthNode` has finished.
     fn is_active(&self) -> bool;
 
+    /// Describes the voice while it is active.
+    fn activity(&self) -> Option<VoiceActivity>;
+
+    /// Settings of the amplitude envelope as returned by [`crate::Envelope::adsr`],
+    /// or `None` for voices without one.
+    fn amp_envelope(&self) -> Option<[f32; 4]>;
+
+    /// The effect at `index` in this voice's effect chain.
+    fn effect(&self, index: usize) -> Option<&dyn MonoEffect>;
+
     /// Sets the stereo pan for this voice.
     fn set_pan(&mut self, pan: f32);
 
@@ -38,6 +62,11 @@ pub trait VoiceTrait: Send + Sync {
 
     /// Set effect parameter
     fn set_effect_parameter(&mut self, effect_id: EffectId, param_index: u32, value: f32);
+
+    /// Adapts the node, envelope and effects to a new sample rate and resizes
+    /// the scratch buffers. Allocates, so call it before the voice reaches the
+    /// audio thread.
+    fn prepare(&mut self, sample_rate: f32, max_block_size: usize);
 }
 
 /// A `Voice` represents a single, monophonic musical event. It bundles a sound
@@ -50,10 +79,14 @@ pub struct Voice<S: SynthNode> {
     pan: f32, // -1.0 (L) to 1.0 (R)
     // Pre-allocated buffer for mono processing.
     mono_buf: Vec<f32>,
+    // Pre-allocated side channel for stereo nodes, empty for mono nodes.
+    side_buf: Vec<f32>,
     /// Per voice effect chain.
     effect_chain: MonoEffectChain,
     /// Per-note velocity gain (0.0..1.0) set on note_on.
     velocity_gain: f32,
+    /// Note set on note_on, kept for activity reports.
+    note: u8,
 }
 
 impl<S: SynthNode> Voice<S> {
@@ -66,8 +99,8 @@ impl<S: SynthNode> Voice<S> {
         effect_chain: MonoEffectChain,
     ) -> Self {
         // Pre-allocate the internal mono buffer for the voice.
-        const MAX_BUFFER_SIZE: usize = 4096;
-        let mono_buf = vec![0.0; MAX_BUFFER_SIZE];
+        let mono_buf = vec![0.0; DEFAULT_MAX_BLOCK_SIZE];
+        let side_buf = stereo_buffer(&node, DEFAULT_MAX_BLOCK_SIZE);
 
         Self {
             id,
@@ -75,8 +108,10 @@ impl<S: SynthNode> Voice<S> {
             envelope: Some(envelope),
             pan,
             mono_buf,
+            side_buf,
             effect_chain,
             velocity_gain: 1.0,
+            note: 0,
         }
     }
 
@@ -88,8 +123,8 @@ impl<S: SynthNode> Voice<S> {
         effect_chain: MonoEffectChain,
     ) -> Self {
         // Pre-allocate the internal mono buffer for the voice.
-        const MAX_BUFFER_SIZE: usize = 4096;
-        let mono_buf = vec![0.0; MAX_BUFFER_SIZE];
+        let mono_buf = vec![0.0; DEFAULT_MAX_BLOCK_SIZE];
+        let side_buf = stereo_buffer(&node, DEFAULT_MAX_BLOCK_SIZE);
 
         Self {
             id,
@@ -97,24 +132,33 @@ impl<S: SynthNode> Voice<S> {
             envelope: None,
             pan,
             mono_buf,
+            side_buf,
             effect_chain,
             velocity_gain: 1.0,
+            note: 0,
         }
     }
-}
 
-// Implementation of the object-safe trait for the generic Voice.
-impl<S: SynthNode> VoiceTrait for Voice<S> {
-    fn id(&self) -> VoiceId {
-        self.id
-    }
-
-    fn process(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], sample_rate: f32) {
+    /// Renders a block no longer than the scratch buffers.
+    fn process_block(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], sample_rate: f32) {
         let frame_count = left_buf.len();
         let mono_processing_buf = &mut self.mono_buf[..frame_count];
+        let stereo = !self.side_buf.is_empty();
+        let side_buf = &mut self.side_buf[..if stereo { frame_count } else { 0 }];
 
-        // 1. Generate mono audio from the synth node.
-        self.node.process(mono_processing_buf, sample_rate);
+        // 1. Generate audio from the synth node. Stereo nodes are converted to mid/side
+        //    so the mono insert chain below runs on the mid signal and the side stays dry.
+        if stereo {
+            self.node
+                .process_stereo(mono_processing_buf, side_buf, sample_rate);
+            for (mid, side) in mono_processing_buf.iter_mut().zip(side_buf.iter_mut()) {
+                let (left, right) = (*mid, *side);
+                *mid = (left + right) * 0.5;
+                *side = (left - right) * 0.5;
+            }
+        } else {
+            self.node.process(mono_processing_buf, sample_rate);
+        }
 
         // 2. Process the mono signal through the per-voice insert effects.
         //    Since our Effect trait works on stereo buffers, we pass the mono buffer
@@ -133,9 +177,44 @@ impl<S: SynthNode> VoiceTrait for Voice<S> {
                 Some(env) => env.process(),
                 None => 1.0,
             };
-            let mono_sample = mono_processing_buf[i] * envelope_val * self.velocity_gain;
-            left_buf[i] += mono_sample * gain_left;
-            right_buf[i] += mono_sample * gain_right;
+            if stereo {
+                let gain = envelope_val * self.velocity_gain;
+                let mid = mono_processing_buf[i];
+                let side = side_buf[i];
+                left_buf[i] += (mid + side) * gain * gain_left;
+                right_buf[i] += (mid - side) * gain * gain_right;
+            } else {
+                let mono_sample = mono_processing_buf[i] * envelope_val * self.velocity_gain;
+                left_buf[i] += mono_sample * gain_left;
+                right_buf[i] += mono_sample * gain_right;
+            }
+        }
+    }
+}
+
+fn stereo_buffer<S: SynthNode>(node: &S, len: usize) -> Vec<f32> {
+    if node.is_stereo() {
+        vec![0.0; len]
+    } else {
+        Vec::new()
+    }
+}
+
+// Implementation of the object-safe trait for the generic Voice.
+impl<S: SynthNode> VoiceTrait for Voice<S> {
+    fn id(&self) -> VoiceId {
+        self.id
+    }
+
+    fn process(&mut self, left_buf: &mut [f32], right_buf: &mut [f32], sample_rate: f32) {
+        // Blocks longer than the scratch buffers are rendered in chunks rather
+        // than growing the buffers on the audio thread.
+        let chunk_size = self.mono_buf.len();
+        for (left, right) in left_buf
+            .chunks_mut(chunk_size)
+            .zip(right_buf.chunks_mut(chunk_size))
+        {
+            self.process_block(left, right, sample_rate);
         }
     }
 
@@ -143,6 +222,7 @@ impl<S: SynthNode> VoiceTrait for Voice<S> {
         // Reset per-voice insert effects to avoid carrying state between notes
         // self.effect_chain.reset();
         self.node.note_on(note, velocity);
+        self.note = note;
         // Map 0..255 velocity to 0.0..1.0 amplitude and store per-voice (full range)
         self.velocity_gain = utils::note::velocity_to_amplitude(velocity);
         if let Some(env) = &mut self.envelope {
@@ -164,6 +244,29 @@ impl<S: SynthNode> VoiceTrait for Voice<S> {
         }
     }
 
+    fn activity(&self) -> Option<VoiceActivity> {
+        if !self.is_active() {
+            return None;
+        }
+        let (stage, envelope_level) = match &self.envelope {
+            Some(env) => (Some(env.state()), env.level()),
+            None => (None, 1.0),
+        };
+        Some(VoiceActivity {
+            note: self.note,
+            stage,
+            level: envelope_level * self.velocity_gain,
+        })
+    }
+
+    fn amp_envelope(&self) -> Option<[f32; 4]> {
+        self.envelope.as_ref().map(Envelope::adsr)
+    }
+
+    fn effect(&self, index: usize) -> Option<&dyn MonoEffect> {
+        self.effect_chain.get(index)
+    }
+
     fn set_pan(&mut self, pan: f32) {
         self.pan = pan.clamp(-1.0, 1.0);
     }
@@ -204,4 +307,20 @@ impl<S: SynthNode> VoiceTrait for Voice<S> {
         self.effect_chain
             .set_effect_parameter(effect_id, param_index, value);
     }
+
+    fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        self.node.prepare(sample_rate);
+        if let Some(envelope) = &mut self.envelope {
+            envelope.set_sample_rate(sample_rate);
+        }
+        self.effect_chain.prepare(sample_rate, max_block_size);
+
+        let max_block_size = max_block_size.max(1);
+        self.mono_buf.resize(max_block_size, 0.0);
+        self.mono_buf.shrink_to_fit();
+        if !self.side_buf.is_empty() {
+            self.side_buf.resize(max_block_size, 0.0);
+            self.side_buf.shrink_to_fit();
+        }
+    }
 }
@@ -0,0 +1,219 @@
+//! Lock-free snapshot of the voices that are sounding, for note displays.
+//!
+//! After every block the engine asks each instrument for its active voices
+//! and republishes them here. Like [`crate::InstalledInstruments`], a sequence
+//! counter lets readers retry instead of observing a half-written list, so the
+//! audio thread never waits for them. Voices beyond the capacity are counted
+//! but not listed.
+
+use std::{
+    hint,
+    sync::atomic::{fence, AtomicU32, AtomicUsize, Ordering},
+};
+
+use dsp::{id::InstrumentId, EnvelopeState, VoiceActivity};
+
+/// Voices listed per snapshot unless a host chooses otherwise.
+pub const DEFAULT_VOICE_ACTIVITY_CAPACITY: usize = 256;
+
+/// One sounding voice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActiveVoice {
+    pub instrument_id: InstrumentId,
+    /// Voice slot within the instrument; always `0` for monophonic instruments.
+    pub voice: u16,
+    pub note: u8,
+    /// Envelope stage, or `None` for voices without an amplitude envelope.
+    pub stage: Option<EnvelopeState>,
+    /// Envelope gain times velocity gain, `0.0..=1.0`.
+    pub level: f32,
+}
+
+/// Voices sounding at the end of the latest block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VoiceSnapshot {
+    /// Total number of active voices, including any that did not fit.
+    pub active: usize,
+    /// Active voices in instrument mix order, then voice order.
+    pub voices: Vec<ActiveVoice>,
+}
+
+#[derive(Debug)]
+struct VoiceCell {
+    instrument_id: AtomicU32,
+    /// Voice index, note and stage packed as `voice << 16 | note << 8 | stage`.
+    packed: AtomicU32,
+    level: AtomicU32,
+}
+
+/// Shared between the engine and control threads, usually behind an `Arc`
+/// obtained from [`crate::Engine::voice_activity`].
+#[derive(Debug)]
+pub struct VoiceActivityState {
+    /// Odd while the audio thread is writing.
+    sequence: AtomicUsize,
+    active: AtomicUsize,
+    voices: Box<[VoiceCell]>,
+}
+
+impl VoiceActivityState {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            sequence: AtomicUsize::new(0),
+            active: AtomicUsize::new(0),
+            voices: (0..capacity)
+                .map(|_| VoiceCell {
+                    instrument_id: AtomicU32::new(0),
+                    packed: AtomicU32::new(0),
+                    level: AtomicU32::new(0),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the voices published after the latest block.
+    pub fn snapshot(&self) -> VoiceSnapshot {
+        let mut voices = Vec::with_capacity(self.voices.len());
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            if before.is_multiple_of(2) {
+                voices.clear();
+                let active = self.active.load(Ordering::Relaxed);
+                voices.extend(
+                    self.voices[..active.min(self.voices.len())]
+                        .iter()
+                        .map(|cell| {
+                            let packed = cell.packed.load(Ordering::Relaxed);
+                            ActiveVoice {
+                                instrument_id: cell.instrument_id.load(Ordering::Relaxed),
+                                voice: (packed >> 16) as u16,
+                                note: (packed >> 8) as u8,
+                                stage: decode_stage(packed as u8),
+                                level: f32::from_bits(cell.level.load(Ordering::Relaxed)),
+                            }
+                        }),
+                );
+                fence(Ordering::Acquire);
+                if self.sequence.load(Ordering::Relaxed) == before {
+                    return VoiceSnapshot { active, voices };
+                }
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Starts replacing the published list. Realtime-safe; only the engine
+    /// writes. The new list becomes visible when the writer is dropped.
+    pub(crate) fn writer(&self) -> VoiceActivityWriter<'_> {
+        self.sequence.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        VoiceActivityWriter {
+            state: self,
+            active: 0,
+        }
+    }
+}
+
+pub(crate) struct VoiceActivityWriter<'a> {
+    state: &'a VoiceActivityState,
+    active: usize,
+}
+
+impl VoiceActivityWriter<'_> {
+    pub(crate) fn push(
+        &mut self,
+        instrument_id: InstrumentId,
+        voice: usize,
+        activity: VoiceActivity,
+    ) {
+        if let Some(cell) = self.state.voices.get(self.active) {
+            let voice = voice.min(u16::MAX as usize) as u32;
+            let packed = voice << 16
+                | u32::from(activity.note) << 8
+                | u32::from(encode_stage(activity.stage));
+            cell.instrument_id.store(instrument_id, Ordering::Relaxed);
+            cell.packed.store(packed, Ordering::Relaxed);
+            cell.level
+                .store(activity.level.to_bits(), Ordering::Relaxed);
+        }
+        self.active += 1;
+    }
+}
+
+impl Drop for VoiceActivityWriter<'_> {
+    fn drop(&mut self) {
+        self.state.active.store(self.active, Ordering::Relaxed);
+        self.state.sequence.fetch_add(1, Ordering::Release);
+    }
+}
+
+fn encode_stage(stage: Option<EnvelopeState>) -> u8 {
+    match stage {
+        None => 0,
+        Some(EnvelopeState::Idle) => 1,
+        Some(EnvelopeState::Attack) => 2,
+        Some(EnvelopeState::Decay) => 3,
+        Some(EnvelopeState::Sustain) => 4,
+        Some(EnvelopeState::Release) => 5,
+    }
+}
+
+fn decode_stage(stage: u8) -> Option<EnvelopeState> {
+    match stage {
+        1 => Some(EnvelopeState::Idle),
+        2 => Some(EnvelopeState::Attack),
+        3 => Some(EnvelopeState::Decay),
+        4 => Some(EnvelopeState::Sustain),
+        5 => Some(EnvelopeState::Release),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activity(note: u8, stage: Option<EnvelopeState>) -> VoiceActivity {
+        VoiceActivity {
+            note,
+            stage,
+            level: 0.5,
+        }
+    }
+
+    #[test]
+    fn writer_publishes_voices_when_dropped() {
+        let state = VoiceActivityState::new(2);
+        {
+            let mut writer = state.writer();
+            writer.push(3, 1, activity(60, Some(EnvelopeState::Sustain)));
+            writer.push(4, 0, activity(36, None));
+            writer.push(4, 1, activity(38, Some(EnvelopeState::Release)));
+        }
+
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.active, 3);
+        assert_eq!(
+            snapshot.voices,
+            [
+                ActiveVoice {
+                    instrument_id: 3,
+                    voice: 1,
+                    note: 60,
+                    stage: Some(EnvelopeState::Sustain),
+                    level: 0.5,
+                },
+                ActiveVoice {
+                    instrument_id: 4,
+                    voice: 0,
+                    note: 36,
+                    stage: None,
+                    level: 0.5,
+                },
+            ]
+        );
+
+        drop(state.writer());
+        assert_eq!(state.snapshot(), VoiceSnapshot::default());
+    }
+}
@@ -1,8 +1,12 @@
+use std::sync::Arc;
+
 use dsp::{
     id::{EffectId, InstrumentId},
-    InstrumentTrait, MonoEffect, StereoEffect, SynthCmd, VoiceEffects,
+    InstrumentSpec, InstrumentTrait, MonoEffect, StereoEffect, SynthCmd, VoiceEffects,
 };
 
+use crate::{EngineCapture, ParamOwner, ParamTarget, RestoredEngine};
+
 /// Commands that target one instrument and its owned voice/effect state.
 #[allow(
     clippy::large_enum_variant,
@@ -12,6 +16,15 @@ pub enum InstrumentCmd {
     AddInstrument {
         instrument: Box<dyn InstrumentTrait>,
     },
+    /// Installs an instrument built from `spec`, which is kept so that
+    /// [`crate::Engine::snapshot`] can describe it.
+    AddInstrumentFromSpec {
+        instrument: Box<dyn InstrumentTrait>,
+        spec: Arc<InstrumentSpec>,
+    },
+    RemoveInstrument {
+        instrument_id: InstrumentId,
+    },
     AddEffect {
         instrument_id: InstrumentId,
         effect: Box<dyn MonoEffect>,
@@ -32,6 +45,13 @@ pub enum InstrumentCmd {
         instrument_id: InstrumentId,
         synth_cmd: SynthCmd,
     },
+    /// Sets one of the instrument's published parameters, see
+    /// [`InstrumentTrait::parameters`].
+    SetParameter {
+        instrument_id: InstrumentId,
+        param_index: u32,
+        value: f32,
+    },
     SetEffectParameter {
         instrument_id: InstrumentId,
         effect_id: EffectId,
@@ -53,6 +73,23 @@ pub enum MixerCmd {
         param_index: u32,
         value: f32,
     },
+    /// Routes a [`crate::ParameterBank`] slot to a parameter.
+    BindParameter {
+        slot: usize,
+        target: ParamTarget,
+        smoothing_seconds: f32,
+    },
+    /// Routes a slot to the parameter of `owner` whose descriptor id is
+    /// `param_id`, resolved against the installed instrument or effect.
+    BindParameterById {
+        slot: usize,
+        owner: ParamOwner,
+        param_id: &'static str,
+        smoothing_seconds: f32,
+    },
+    UnbindParameter {
+        slot: usize,
+    },
     RemoveMasterEffect {
         effect_index: usize,
     },
@@ -72,6 +109,18 @@ pub enum MixerCmd {
 pub enum EngineCommand {
     Instrument(InstrumentCmd),
     Mixer(MixerCmd),
+    State(StateCmd),
+}
+
+/// Commands that read or replace the whole sound state on the audio thread,
+/// so hosts never have to stop processing to do it.
+pub enum StateCmd {
+    /// Fills the capture with [`crate::Engine::capture`] and sends it back
+    /// through the engine's [`crate::CaptureSender`].
+    Capture(EngineCapture),
+    /// Swaps in processors built with [`crate::EngineSnapshot::build`]; the
+    /// old ones are retired.
+    Restore(RestoredEngine),
 }
 
 impl From<InstrumentCmd> for EngineCommand {
@@ -85,3 +134,9 @@ impl From<MixerCmd> for EngineCommand {
         Self::Mixer(value)
     }
 }
+
+impl From<StateCmd> for EngineCommand {
+    fn from(value: StateCmd) -> Self {
+        Self::State(value)
+    }
+}
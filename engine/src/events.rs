@@ -0,0 +1,74 @@
+//! Notifications from the audio thread to control threads.
+//!
+//! The engine cannot return errors from commands it applies inside the audio
+//! callback, so outcomes a host must know about are queued here instead. The
+//! channel is bounded and preallocated; when it is full, events are dropped
+//! rather than blocking the audio thread.
+
+use std::sync::mpsc::{self, Receiver, SyncSender};
+
+use dsp::id::InstrumentId;
+
+use crate::ParamOwner;
+
+/// Channel capacity used by hosts that do not choose their own.
+pub const DEFAULT_EVENT_CAPACITY: usize = 64;
+
+/// Something the engine did that the host did not directly ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineEvent {
+    /// `AddInstrument` was refused because every instrument slot is in use.
+    /// The instrument was retired without being installed.
+    InstrumentRejected {
+        instrument_id: InstrumentId,
+        capacity: usize,
+    },
+    /// `RemoveInstrument` named an id that is not installed.
+    InstrumentNotFound { instrument_id: InstrumentId },
+    /// `BindParameter` named a slot the parameter bank does not have.
+    ParameterBindingRejected { slot: usize, slot_count: usize },
+    /// `BindParameterById` named an owner that is not installed or a parameter
+    /// it does not publish.
+    ParameterNotFound {
+        owner: ParamOwner,
+        param_id: &'static str,
+    },
+}
+
+/// Audio-thread end of the event channel, handed to
+/// [`crate::Engine::set_event_sender`].
+pub struct EventSender {
+    sender: SyncSender<EngineEvent>,
+}
+
+impl EventSender {
+    /// Queues `event` without blocking or allocating. Returns `false` if the
+    /// channel is full or the receiver is gone.
+    pub fn try_send(&self, event: EngineEvent) -> bool {
+        self.sender.try_send(event).is_ok()
+    }
+}
+
+/// Control-thread end of the event channel.
+pub struct EventReceiver {
+    receiver: Receiver<EngineEvent>,
+}
+
+impl EventReceiver {
+    /// Returns the next queued event without blocking.
+    pub fn try_recv(&self) -> Option<EngineEvent> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Takes every event queued so far.
+    pub fn drain(&self) -> impl Iterator<Item = EngineEvent> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+}
+
+/// Creates a bounded event channel. The buffer is allocated here, so sending
+/// never allocates.
+pub fn event_channel(capacity: usize) -> (EventSender, EventReceiver) {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    (EventSender { sender }, EventReceiver { receiver })
+}
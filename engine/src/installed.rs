@@ -0,0 +1,93 @@
+//! Lock-free view of which instruments the engine has installed.
+//!
+//! The audio thread republishes the sorted id list after every add, removal,
+//! or clear. Control threads read it with [`InstalledInstruments::ids`]; a
+//! sequence counter makes readers retry instead of observing a half-written
+//! list, so the audio thread never waits for them.
+
+use std::{
+    hint,
+    sync::atomic::{fence, AtomicU32, AtomicUsize, Ordering},
+};
+
+use dsp::id::InstrumentId;
+
+/// Installed instrument ids shared between the engine and control threads,
+/// usually behind an `Arc` obtained from [`crate::Engine::installed_instruments`].
+#[derive(Debug)]
+pub struct InstalledInstruments {
+    /// Odd while the audio thread is writing.
+    sequence: AtomicUsize,
+    len: AtomicUsize,
+    ids: Box<[AtomicU32]>,
+}
+
+impl InstalledInstruments {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            sequence: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            ids: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+        }
+    }
+
+    /// Maximum number of ids the engine can install.
+    pub fn capacity(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns the installed ids in ascending order.
+    pub fn ids(&self) -> Vec<InstrumentId> {
+        let mut ids = Vec::with_capacity(self.ids.len());
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            if before.is_multiple_of(2) {
+                ids.clear();
+                let len = self.len.load(Ordering::Relaxed).min(self.ids.len());
+                ids.extend(self.ids[..len].iter().map(|id| id.load(Ordering::Relaxed)));
+                fence(Ordering::Acquire);
+                if self.sequence.load(Ordering::Relaxed) == before {
+                    return ids;
+                }
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Returns `true` if `instrument_id` is currently installed.
+    pub fn contains(&self, instrument_id: InstrumentId) -> bool {
+        self.ids().binary_search(&instrument_id).is_ok()
+    }
+
+    /// Replaces the published list. Realtime-safe; only the engine writes.
+    pub(crate) fn publish(&self, ids: impl Iterator<Item = InstrumentId>) {
+        self.sequence.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        let mut len = 0;
+        for (slot, id) in self.ids.iter().zip(ids) {
+            slot.store(id, Ordering::Relaxed);
+            len += 1;
+        }
+        self.len.store(len, Ordering::Relaxed);
+        self.sequence.fetch_add(1, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publish_replaces_the_previous_list() {
+        let installed = InstalledInstruments::new(4);
+        assert!(installed.ids().is_empty());
+
+        installed.publish([1, 5, 9].into_iter());
+        assert_eq!(installed.ids(), [1, 5, 9]);
+        assert!(installed.contains(5));
+
+        installed.publish([5].into_iter());
+        assert_eq!(installed.ids(), [5]);
+        assert!(!installed.contains(1));
+    }
+}
@@ -1,17 +1,42 @@
+mod activity;
 mod commands;
+mod events;
+mod installed;
+mod parameters;
+mod retire;
+mod snapshot;
+mod stems;
+mod telemetry;
 
+use std::{collections::VecDeque, sync::Arc, time::Instant};
+
+pub use activity::*;
 pub use commands::*;
 use dsp::{
+    find_parameter,
     id::{EffectId, InstrumentId},
-    InstrumentTrait, MonoEffect, StereoEffect, StereoEffectChain, SynthCmd, VoiceEffects,
+    InstrumentSpec, InstrumentTrait, MonoEffect, StereoEffect, StereoEffectChain, SynthCmd,
+    VoiceEffects, DEFAULT_MAX_BLOCK_SIZE,
 };
+pub use events::*;
+pub use installed::*;
+pub use parameters::*;
+pub use retire::*;
+pub use snapshot::*;
+pub use stems::*;
+pub use telemetry::*;
 
-const DEFAULT_INSTRUMENT_CAPACITY: usize = 64;
+/// Instrument slots available unless a host chooses another hard limit.
+pub const DEFAULT_INSTRUMENT_CAPACITY: usize = 64;
 const DEFAULT_MASTER_EFFECT_CAPACITY: usize = 8;
+const DEFAULT_TEMPO_BPM: f32 = 120.0;
 
 struct InstrumentSlot {
     id: InstrumentId,
     instrument: Box<dyn InstrumentTrait>,
+    /// How the instrument was built, when the host said; lets [`Engine::snapshot`]
+    /// describe it so it can be rebuilt.
+    spec: Option<Arc<InstrumentSpec>>,
 }
 
 /// Host-independent runtime for instrument dispatch, mixing, and master effects.
@@ -21,10 +46,34 @@ struct InstrumentSlot {
 /// buffers and composition adapters decide which methods to call and when.
 pub struct Engine {
     // Sorted, contiguous slots provide deterministic mix order and avoid
-    // per-block hashing/tree traversal. A fixed hard capacity/stealing policy
-    // remains part of #137; this vector is preallocated to the current limit.
+    // per-block hashing/tree traversal. The vector is preallocated to the hard
+    // instrument capacity and never grows past it.
     instruments: Vec<InstrumentSlot>,
+    /// Ids of `instruments`, republished for control threads on every change.
+    installed: Arc<InstalledInstruments>,
+    /// Processing time per instrument, master effect and block.
+    dsp_load: Arc<DspLoadState>,
+    /// Voices sounding at the end of the latest block.
+    voice_activity: Arc<VoiceActivityState>,
     master_effects: StereoEffectChain,
+    /// Last tempo reported by the host; replayed to instruments added later.
+    tempo_bpm: f32,
+    /// Parameter bank slots bound to targets, preallocated to one binding per
+    /// slot and never grown past it.
+    parameter_bindings: Vec<ParamBinding>,
+    /// Slots of the bank passed to [`Self::apply_parameters`].
+    parameter_slots: usize,
+    /// Displaced state is shipped here instead of being dropped in place.
+    retire_sender: Option<RetireSender>,
+    /// Filled captures go back to the host here.
+    capture_sender: Option<CaptureSender>,
+    /// Displaced state the full retirement channel could not take yet; resent
+    /// on the next block. Preallocated with the sender.
+    retire_backlog: VecDeque<Retired>,
+    /// Outcomes of RT commands that hosts need to hear about.
+    event_sender: Option<EventSender>,
+    /// Longest block handed to instruments and effects; longer host blocks are split.
+    max_block_size: usize,
 }
 
 impl Default for Engine {
@@ -35,9 +84,175 @@ impl Default for Engine {
 
 impl Engine {
     pub fn new() -> Self {
+        Self::with_max_block_size(DEFAULT_MAX_BLOCK_SIZE)
+    }
+
+    /// Creates an engine that renders in sub-blocks of at most `max_block_size`
+    /// frames. Instruments should be built for the same size, e.g. with
+    /// `InstrumentFactory::with_max_block_size`.
+    pub fn with_max_block_size(max_block_size: usize) -> Self {
         Self {
             instruments: Vec::with_capacity(DEFAULT_INSTRUMENT_CAPACITY),
+            installed: Arc::new(InstalledInstruments::new(DEFAULT_INSTRUMENT_CAPACITY)),
+            dsp_load: Arc::new(DspLoadState::new(
+                DEFAULT_INSTRUMENT_CAPACITY,
+                DEFAULT_MASTER_EFFECT_CAPACITY,
+            )),
+            voice_activity: Arc::new(VoiceActivityState::new(DEFAULT_VOICE_ACTIVITY_CAPACITY)),
             master_effects: StereoEffectChain::new(DEFAULT_MASTER_EFFECT_CAPACITY),
+            tempo_bpm: DEFAULT_TEMPO_BPM,
+            parameter_bindings: Vec::with_capacity(DEFAULT_PARAMETER_SLOTS),
+            parameter_slots: DEFAULT_PARAMETER_SLOTS,
+            retire_sender: None,
+            capture_sender: None,
+            retire_backlog: VecDeque::new(),
+            event_sender: None,
+            max_block_size: max_block_size.max(1),
+        }
+    }
+
+    /// Sets the hard limit on installed instruments; `AddInstrument` for a new
+    /// id beyond it is rejected. Call before any instrument is added, since this
+    /// replaces the slot storage.
+    pub fn with_instrument_capacity(mut self, capacity: usize) -> Self {
+        self.instruments = Vec::with_capacity(capacity);
+        self.installed = Arc::new(InstalledInstruments::new(capacity));
+        self.dsp_load = Arc::new(DspLoadState::new(capacity, DEFAULT_MASTER_EFFECT_CAPACITY));
+        self
+    }
+
+    /// Sets the slot count of the [`ParameterBank`] the host will pass to
+    /// [`Self::apply_parameters`]; binding a slot beyond it is rejected. Call
+    /// before any parameter is bound, since this replaces the binding storage.
+    pub fn with_parameter_slots(mut self, slot_count: usize) -> Self {
+        self.parameter_bindings = Vec::with_capacity(slot_count);
+        self.parameter_slots = slot_count;
+        self
+    }
+
+    pub fn max_block_size(&self) -> usize {
+        self.max_block_size
+    }
+
+    pub fn instrument_capacity(&self) -> usize {
+        self.installed.capacity()
+    }
+
+    /// Returns a handle control threads can query for the installed instrument
+    /// ids while the engine runs on the audio thread.
+    pub fn installed_instruments(&self) -> Arc<InstalledInstruments> {
+        self.installed.clone()
+    }
+
+    /// Returns a handle for reading how long instruments, master effects and
+    /// whole blocks take to process relative to the real-time budget.
+    pub fn dsp_load_state(&self) -> Arc<DspLoadState> {
+        self.dsp_load.clone()
+    }
+
+    /// Returns a handle for reading which voices are sounding, republished
+    /// after every block.
+    pub fn voice_activity(&self) -> Arc<VoiceActivityState> {
+        self.voice_activity.clone()
+    }
+
+    /// Publishes an empty voice list, for hosts that stop calling
+    /// [`Self::process`] while voices are still releasing.
+    pub fn clear_voice_activity(&self) {
+        drop(self.voice_activity.writer());
+    }
+
+    fn publish_voice_activity(&self) {
+        let mut writer = self.voice_activity.writer();
+        for slot in &self.instruments {
+            slot.instrument
+                .voice_activity(&mut |voice, activity| writer.push(slot.id, voice, activity));
+        }
+    }
+
+    /// Installed instrument ids in ascending (mix) order.
+    pub fn instrument_ids(&self) -> impl Iterator<Item = InstrumentId> + '_ {
+        self.instruments.iter().map(|slot| slot.id)
+    }
+
+    /// Adapts every installed instrument and master effect to a new sample rate
+    /// and maximum block size, e.g. after the host switches output device.
+    /// Parameters keep their musical values. Delay lines sized in samples are
+    /// reallocated, and so cleared, only when the sample rate changes; at the
+    /// current rate they keep their tails, and sounding voices carry on.
+    ///
+    /// This allocates, so hosts must call it while the engine is not being
+    /// processed on the audio thread.
+    pub fn prepare(&mut self, sample_rate: f32, max_block_size: usize) {
+        self.max_block_size = max_block_size.max(1);
+        for slot in &mut self.instruments {
+            slot.instrument.prepare(sample_rate, self.max_block_size);
+        }
+        self.master_effects
+            .prepare(sample_rate, self.max_block_size);
+    }
+
+    /// Routes replaced and cleared instruments, and effects that cannot be
+    /// installed, to a [`RetireCollector`] so that the audio thread never frees
+    /// them. Without a sender they are dropped in place, which is fine for
+    /// offline renders. Items the channel has no room for wait in a backlog of
+    /// up to [`RETIRE_BACKLOG_CAPACITY`] items and are resent every block.
+    pub fn set_retire_sender(&mut self, sender: RetireSender) {
+        self.retire_sender = Some(sender);
+        self.retire_backlog = VecDeque::with_capacity(RETIRE_BACKLOG_CAPACITY);
+    }
+
+    /// Reports rejected commands, such as adding an instrument beyond the
+    /// capacity, to an [`EventReceiver`]. Without a sender they are silent.
+    pub fn set_event_sender(&mut self, sender: EventSender) {
+        self.event_sender = Some(sender);
+    }
+
+    fn report(&self, event: EngineEvent) {
+        if let Some(sender) = &self.event_sender {
+            // A full channel drops the event; the engine state is still consistent.
+            sender.try_send(event);
+        }
+    }
+
+    fn publish_installed(&self) {
+        self.installed.publish(self.instrument_ids());
+        self.dsp_load.clear_instruments();
+        self.clear_voice_activity();
+    }
+
+    fn retire(&mut self, item: Retired) {
+        let Some(sender) = &self.retire_sender else {
+            return;
+        };
+        // Queue behind the backlog so the channel sees items in order.
+        let rejected = if self.retire_backlog.is_empty() {
+            sender.try_send(item)
+        } else {
+            Err(item)
+        };
+        if let Err(item) = rejected {
+            if self.retire_backlog.len() < self.retire_backlog.capacity() {
+                self.retire_backlog.push_back(item);
+            } else {
+                // The collector has stalled for longer than the backlog
+                // covers; dropping here is the only option left that does
+                // not block or grow the backlog.
+                drop(item);
+            }
+        }
+    }
+
+    /// Moves backlogged retirements into the channel while it has room.
+    fn flush_retire_backlog(&mut self) {
+        let Some(sender) = &self.retire_sender else {
+            return;
+        };
+        while let Some(item) = self.retire_backlog.pop_front() {
+            if let Err(item) = sender.try_send(item) {
+                self.retire_backlog.push_front(item);
+                break;
+            }
         }
     }
 
@@ -45,12 +260,21 @@ impl Engine {
         match command {
             EngineCommand::Instrument(command) => self.handle_instrument_command(command),
             EngineCommand::Mixer(command) => self.handle_mixer_command(command),
+            EngineCommand::State(command) => self.handle_state_command(command),
         }
     }
 
     fn handle_instrument_command(&mut self, command: InstrumentCmd) {
         match command {
-            InstrumentCmd::AddInstrument { instrument } => self.add_instrument(instrument),
+            InstrumentCmd::AddInstrument { instrument } => {
+                self.add_instrument(instrument);
+            }
+            InstrumentCmd::AddInstrumentFromSpec { instrument, spec } => {
+                self.add_instrument_from_spec(instrument, spec);
+            }
+            InstrumentCmd::RemoveInstrument { instrument_id } => {
+                self.remove_instrument(instrument_id);
+            }
             InstrumentCmd::AddEffect {
                 instrument_id,
                 effect,
@@ -71,6 +295,11 @@ impl Engine {
             } => {
                 self.try_handle_synth_command(instrument_id, &synth_cmd);
             }
+            InstrumentCmd::SetParameter {
+                instrument_id,
+                param_index,
+                value,
+            } => self.set_instrument_parameter(instrument_id, param_index, value),
             InstrumentCmd::SetEffectParameter {
                 instrument_id,
                 effect_id,
@@ -88,6 +317,22 @@ impl Engine {
                 param_index,
                 value,
             } => self.set_master_effect_parameter(effect_id, param_index, value),
+            MixerCmd::BindParameter {
+                slot,
+                target,
+                smoothing_seconds,
+            } => {
+                self.bind_parameter(slot, target, smoothing_seconds);
+            }
+            MixerCmd::BindParameterById {
+                slot,
+                owner,
+                param_id,
+                smoothing_seconds,
+            } => {
+                self.bind_parameter_by_id(slot, owner, param_id, smoothing_seconds);
+            }
+            MixerCmd::UnbindParameter { slot } => self.unbind_parameter(slot),
             // Effect-chain mutation semantics are intentionally deferred to #136.
             MixerCmd::RemoveMasterEffect { .. } | MixerCmd::ReorderMasterEffects { .. } => {}
         }
@@ -112,30 +357,329 @@ impl Engine {
     /// prefix are rendered. The longer channel's tail is left untouched. Host
     /// adapters should still provide equal lengths, but malformed input must
     /// not panic in an audio callback.
+    ///
+    /// Blocks longer than [`Self::max_block_size`] are rendered as consecutive
+    /// sub-blocks, each mixed and passed through the master chain in turn.
     pub fn process(&mut self, left: &mut [f32], right: &mut [f32], sample_rate: f32) {
+        self.render(left, right, None, sample_rate);
+    }
+
+    /// Renders like [`Self::process`] and also leaves each instrument's own
+    /// output, before the master effect chain, in `stems`. The master mix is
+    /// bit-identical to [`Self::process`]: each instrument still renders on
+    /// top of the mix so far, and its stem is what it added, up to float
+    /// rounding.
+    ///
+    /// At most [`StemBuses::max_frame_count`] frames are rendered; the rest of
+    /// the buffers is left untouched.
+    pub fn process_with_stems(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        stems: &mut StemBuses,
+        sample_rate: f32,
+    ) {
+        let frame_count = left.len().min(right.len()).min(stems.max_frame_count());
+        stems.begin(self.instrument_ids(), frame_count);
+        self.render(
+            &mut left[..frame_count],
+            &mut right[..frame_count],
+            Some(stems),
+            sample_rate,
+        );
+    }
+
+    fn render(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        mut stems: Option<&mut StemBuses>,
+        sample_rate: f32,
+    ) {
+        self.flush_retire_backlog();
         let frame_count = left.len().min(right.len());
-        let left = &mut left[..frame_count];
-        let right = &mut right[..frame_count];
+        let left = left[..frame_count].chunks_mut(self.max_block_size);
+        let right = right[..frame_count].chunks_mut(self.max_block_size);
 
-        for slot in &mut self.instruments {
-            slot.instrument.process(left, right, sample_rate);
+        for (block, (left, right)) in left.zip(right).enumerate() {
+            let block_started = Instant::now();
+            let budget = budget_nanos(left.len(), sample_rate);
+            let frames = block * self.max_block_size..block * self.max_block_size + left.len();
+            for (index, slot) in self.instruments.iter_mut().enumerate() {
+                let started = Instant::now();
+                match stems
+                    .as_deref_mut()
+                    .and_then(|stems| stems.bus_mut(index, frames.clone()))
+                {
+                    Some((stem_left, stem_right)) => {
+                        // Render on top of the mix so far, exactly as without
+                        // stems, and take the stem as the difference.
+                        stem_left.copy_from_slice(left);
+                        stem_right.copy_from_slice(right);
+                        slot.instrument.process(stem_left, stem_right, sample_rate);
+                        split_stem(left, stem_left);
+                        split_stem(right, stem_right);
+                    }
+                    None => slot.instrument.process(left, right, sample_rate),
+                }
+                self.dsp_load
+                    .record_instrument(index, slot.id, started.elapsed(), budget);
+            }
+            for (index, effect) in self.master_effects.iter_mut().enumerate() {
+                let started = Instant::now();
+                effect.process(left, right, sample_rate);
+                self.dsp_load
+                    .record_master_effect(index, effect.id(), started.elapsed(), budget);
+            }
+            self.dsp_load.record_block(
+                block_started.elapsed(),
+                budget,
+                self.instruments.len(),
+                self.master_effects.len(),
+            );
+        }
+        self.publish_voice_activity();
+    }
+
+    /// Routes a [`ParameterBank`] slot to a parameter, replacing any previous
+    /// binding of the slot. Values are smoothed with the given time constant.
+    /// A slot outside the bank is reported as
+    /// [`EngineEvent::ParameterBindingRejected`] and returns `false`.
+    pub fn bind_parameter(
+        &mut self,
+        slot: usize,
+        target: ParamTarget,
+        smoothing_seconds: f32,
+    ) -> bool {
+        self.unbind_parameter(slot);
+        // Each slot holds at most one binding, so a full vector means a slot
+        // outside the bank; pushing would reallocate on the audio thread.
+        if slot >= self.parameter_slots
+            || self.parameter_bindings.len() == self.parameter_bindings.capacity()
+        {
+            self.report(EngineEvent::ParameterBindingRejected {
+                slot,
+                slot_count: self.parameter_slots,
+            });
+            return false;
+        }
+        self.parameter_bindings
+            .push(ParamBinding::new(slot, target, smoothing_seconds));
+        true
+    }
+
+    /// Binds `slot` like [`Self::bind_parameter`] to the parameter of `owner`
+    /// whose descriptor id is `param_id`. An owner that is not installed or a
+    /// parameter it does not publish is reported as
+    /// [`EngineEvent::ParameterNotFound`] and returns `false`.
+    pub fn bind_parameter_by_id(
+        &mut self,
+        slot: usize,
+        owner: ParamOwner,
+        param_id: &'static str,
+        smoothing_seconds: f32,
+    ) -> bool {
+        match self.parameter_target(owner, param_id) {
+            Some(target) => self.bind_parameter(slot, target, smoothing_seconds),
+            None => {
+                self.report(EngineEvent::ParameterNotFound { owner, param_id });
+                false
+            }
         }
-        self.master_effects.process(left, right, sample_rate);
     }
 
-    pub fn add_instrument(&mut self, instrument: Box<dyn InstrumentTrait>) {
+    /// Resolves the parameter of `owner` whose descriptor id is `param_id`.
+    /// `None` if the owner is not installed or does not publish it.
+    pub fn parameter_target(&self, owner: ParamOwner, param_id: &str) -> Option<ParamTarget> {
+        let parameters = match owner {
+            ParamOwner::MasterEffect { effect_id } => self
+                .master_effects
+                .iter()
+                .find(|effect| effect.id() == effect_id)?
+                .parameters(),
+            ParamOwner::InstrumentEffect {
+                instrument_id,
+                effect_id,
+            } => {
+                let instrument = self.instrument(instrument_id)?;
+                (0..)
+                    .map_while(|index| instrument.voice_effect(index))
+                    .find(|effect| effect.id() == effect_id)?
+                    .parameters()
+            }
+            ParamOwner::Instrument { instrument_id } => {
+                self.instrument(instrument_id)?.parameters()
+            }
+        };
+        Some(owner.target(find_parameter(parameters, param_id)?.index))
+    }
+
+    pub fn unbind_parameter(&mut self, slot: usize) {
+        self.parameter_bindings
+            .retain(|binding| binding.slot != slot);
+    }
+
+    /// Polls every bound slot of `bank` and applies changed or still-smoothing
+    /// values. Hosts call this once per block, before [`Self::process`], with the
+    /// block's frame count.
+    pub fn apply_parameters(&mut self, bank: &ParameterBank, frame_count: usize, sample_rate: f32) {
+        let block_seconds = frame_count as f32 / sample_rate;
+        for index in 0..self.parameter_bindings.len() {
+            let binding = &mut self.parameter_bindings[index];
+            let Some(value) = binding.poll(bank, block_seconds) else {
+                continue;
+            };
+            match binding.target {
+                ParamTarget::MasterEffect {
+                    effect_id,
+                    param_index,
+                } => self.set_master_effect_parameter(effect_id, param_index, value),
+                ParamTarget::InstrumentEffect {
+                    instrument_id,
+                    effect_id,
+                    param_index,
+                } => self.set_instrument_effect_parameter(
+                    instrument_id,
+                    effect_id,
+                    param_index,
+                    value,
+                ),
+                ParamTarget::Instrument {
+                    instrument_id,
+                    param_index,
+                } => {
+                    if let Some(instrument) = self.instrument_mut(instrument_id) {
+                        instrument.set_parameter(param_index, value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Installs `instrument`, replacing any instrument with the same id. A new
+    /// id is rejected once [`Self::instrument_capacity`] slots are in use; the
+    /// instrument is then retired and [`EngineEvent::InstrumentRejected`] is
+    /// reported. Returns whether the instrument was installed.
+    ///
+    /// Instruments installed this way are listed as skipped by
+    /// [`Self::snapshot`]; use [`Self::add_instrument_from_spec`] for ones that
+    /// should survive a snapshot.
+    pub fn add_instrument(&mut self, instrument: Box<dyn InstrumentTrait>) -> bool {
+        self.install(instrument, None)
+    }
+
+    /// Installs `instrument` like [`Self::add_instrument`], remembering the
+    /// spec it was built from so that [`Self::snapshot`] can describe it.
+    pub fn add_instrument_from_spec(
+        &mut self,
+        instrument: Box<dyn InstrumentTrait>,
+        spec: Arc<InstrumentSpec>,
+    ) -> bool {
+        self.install(instrument, Some(spec))
+    }
+
+    fn install(
+        &mut self,
+        mut instrument: Box<dyn InstrumentTrait>,
+        spec: Option<Arc<InstrumentSpec>>,
+    ) -> bool {
         let id = instrument.id();
         match self.instruments.binary_search_by_key(&id, |slot| slot.id) {
-            Ok(index) => self.instruments[index].instrument = instrument,
-            Err(index) => self
-                .instruments
-                .insert(index, InstrumentSlot { id, instrument }),
+            Ok(index) => {
+                instrument.try_handle_command(&SynthCmd::SetTempo {
+                    bpm: self.tempo_bpm,
+                });
+                let previous = std::mem::replace(
+                    &mut self.instruments[index],
+                    InstrumentSlot {
+                        id,
+                        instrument,
+                        spec,
+                    },
+                );
+                self.retire_slot(previous);
+                true
+            }
+            Err(_) if self.instruments.len() >= self.instrument_capacity() => {
+                self.retire_slot(InstrumentSlot {
+                    id,
+                    instrument,
+                    spec,
+                });
+                self.report(EngineEvent::InstrumentRejected {
+                    instrument_id: id,
+                    capacity: self.instrument_capacity(),
+                });
+                false
+            }
+            Err(index) => {
+                instrument.try_handle_command(&SynthCmd::SetTempo {
+                    bpm: self.tempo_bpm,
+                });
+                self.instruments.insert(
+                    index,
+                    InstrumentSlot {
+                        id,
+                        instrument,
+                        spec,
+                    },
+                );
+                self.publish_installed();
+                true
+            }
+        }
+    }
+
+    fn retire_slot(&mut self, slot: InstrumentSlot) {
+        self.retire(Retired::Instrument(slot.instrument));
+        if let Some(spec) = slot.spec {
+            self.retire(Retired::InstrumentSpec(spec));
+        }
+    }
+
+    /// Uninstalls the instrument with `instrument_id` and retires it, cutting
+    /// off any sounding notes. Reports [`EngineEvent::InstrumentNotFound`] and
+    /// returns `false` if no such instrument is installed.
+    pub fn remove_instrument(&mut self, instrument_id: InstrumentId) -> bool {
+        match self
+            .instruments
+            .binary_search_by_key(&instrument_id, |slot| slot.id)
+        {
+            Ok(index) => {
+                let slot = self.instruments.remove(index);
+                self.publish_installed();
+                self.retire_slot(slot);
+                true
+            }
+            Err(_) => {
+                self.report(EngineEvent::InstrumentNotFound { instrument_id });
+                false
+            }
         }
     }
 
+    /// Broadcasts the host tempo to every instrument, e.g. for tempo-synced sequencers.
+    pub fn set_tempo(&mut self, bpm: f32) {
+        self.tempo_bpm = bpm;
+        let command = SynthCmd::SetTempo { bpm };
+        for slot in &mut self.instruments {
+            slot.instrument.try_handle_command(&command);
+        }
+    }
+
+    pub fn tempo(&self) -> f32 {
+        self.tempo_bpm
+    }
+
     pub fn clear_instruments(&mut self) {
         self.stop_all_notes();
-        self.instruments.clear();
+        // Drain keeps the vector's capacity; only the instruments are retired.
+        let mut instruments = std::mem::take(&mut self.instruments);
+        for slot in instruments.drain(..) {
+            self.retire_slot(slot);
+        }
+        self.instruments = instruments;
+        self.publish_installed();
     }
 
     pub fn add_effect_to_instrument(
@@ -143,8 +687,9 @@ impl Engine {
         instrument_id: InstrumentId,
         effect: Box<dyn MonoEffect>,
     ) {
-        if let Some(instrument) = self.instrument_mut(instrument_id) {
-            instrument.add_effect(effect);
+        match self.instrument_mut(instrument_id) {
+            Some(instrument) => instrument.add_effect(effect),
+            None => self.retire(Retired::MonoEffect(effect)),
         }
     }
 
@@ -153,8 +698,9 @@ impl Engine {
         instrument_id: InstrumentId,
         effects: VoiceEffects,
     ) {
-        if let Some(instrument) = self.instrument_mut(instrument_id) {
-            instrument.add_voice_effects(effects);
+        match self.instrument_mut(instrument_id) {
+            Some(instrument) => instrument.add_voice_effects(effects),
+            None => self.retire(Retired::VoiceEffects(effects)),
         }
     }
 
@@ -179,6 +725,17 @@ impl Engine {
             .is_some_and(|instrument| instrument.try_handle_command(command))
     }
 
+    pub fn set_instrument_parameter(
+        &mut self,
+        instrument_id: InstrumentId,
+        param_index: u32,
+        value: f32,
+    ) {
+        if let Some(instrument) = self.instrument_mut(instrument_id) {
+            instrument.set_parameter(param_index, value);
+        }
+    }
+
     pub fn set_instrument_effect_parameter(
         &mut self,
         instrument_id: InstrumentId,
@@ -191,6 +748,14 @@ impl Engine {
         }
     }
 
+    fn instrument(&self, instrument_id: InstrumentId) -> Option<&dyn InstrumentTrait> {
+        let index = self
+            .instruments
+            .binary_search_by_key(&instrument_id, |slot| slot.id)
+            .ok()?;
+        Some(self.instruments[index].instrument.as_ref())
+    }
+
     fn instrument_mut(
         &mut self,
         instrument_id: InstrumentId,
@@ -203,7 +768,11 @@ impl Engine {
     }
 
     pub fn add_master_effect(&mut self, effect: Box<dyn StereoEffect>) {
-        self.master_effects.add_effect(effect);
+        if self.master_effects.is_full() {
+            self.retire(Retired::StereoEffect(effect));
+        } else {
+            self.master_effects.add_effect(effect);
+        }
     }
 
     pub fn set_master_effect_parameter(
@@ -343,6 +912,26 @@ mod tests {
         assert_eq!(f32::from_bits(effect_value.load(Ordering::Relaxed)), 0.75);
     }
 
+    #[test]
+    fn stems_hold_each_instrument_before_the_master_chain() {
+        let mut engine = Engine::with_max_block_size(3);
+        engine.add_instrument(test_instrument(5));
+        engine.add_instrument(test_instrument(2));
+        engine.add_master_effect(Box::new(ScaleEffect { id: 9, scale: 2.0 }));
+        let mut stems = StemBuses::new(1, 4);
+
+        let mut left = [0.0; 6];
+        let mut right = [0.0; 6];
+        engine.process_with_stems(&mut left, &mut right, &mut stems, 48_000.0);
+
+        // Only the first four frames fit the buses; the second instrument has
+        // no bus but is still mixed.
+        assert_eq!(left, [1.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(right, [2.0, 2.0, 2.0, 2.0, 0.0, 0.0]);
+        let stems = stems.iter().collect::<Vec<_>>();
+        assert_eq!(stems, [(2, &[0.25; 4][..], &[0.5; 4][..])]);
+    }
+
     #[test]
     fn instrument_slots_remain_sorted_and_replace_duplicate_ids() {
         let counters = || {
@@ -373,6 +962,61 @@ mod tests {
         );
     }
 
+    fn test_instrument(id: InstrumentId) -> Box<dyn InstrumentTrait> {
+        Box::new(TestInstrument {
+            id,
+            note_ons: Arc::new(AtomicUsize::new(0)),
+            note_offs: Arc::new(AtomicUsize::new(0)),
+            effect_value: Arc::new(AtomicU32::new(0)),
+        })
+    }
+
+    #[test]
+    fn full_engines_reject_new_ids_but_still_replace_installed_ones() {
+        let (event_sender, events) = event_channel(DEFAULT_EVENT_CAPACITY);
+        let mut engine = Engine::new().with_instrument_capacity(2);
+        engine.set_event_sender(event_sender);
+
+        assert!(engine.add_instrument(test_instrument(4)));
+        assert!(engine.add_instrument(test_instrument(2)));
+        assert!(!engine.add_instrument(test_instrument(3)));
+        assert!(engine.add_instrument(test_instrument(4)));
+
+        assert_eq!(engine.instrument_ids().collect::<Vec<_>>(), [2, 4]);
+        assert_eq!(
+            events.drain().collect::<Vec<_>>(),
+            [EngineEvent::InstrumentRejected {
+                instrument_id: 3,
+                capacity: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn removed_instruments_stop_rendering_and_free_their_slot() {
+        let (event_sender, events) = event_channel(DEFAULT_EVENT_CAPACITY);
+        let mut engine = Engine::new().with_instrument_capacity(1);
+        engine.set_event_sender(event_sender);
+        let installed = engine.installed_instruments();
+        engine.add_instrument(test_instrument(7));
+        assert_eq!(installed.ids(), [7]);
+
+        engine.handle_command(InstrumentCmd::RemoveInstrument { instrument_id: 7 }.into());
+        engine.handle_command(InstrumentCmd::RemoveInstrument { instrument_id: 7 }.into());
+        let mut left = [0.0; 2];
+        let mut right = [0.0; 2];
+        engine.process(&mut left, &mut right, 48_000.0);
+
+        assert_eq!(left, [0.0; 2]);
+        assert!(installed.ids().is_empty());
+        assert_eq!(
+            events.drain().collect::<Vec<_>>(),
+            [EngineEvent::InstrumentNotFound { instrument_id: 7 }]
+        );
+        assert!(engine.add_instrument(test_instrument(8)));
+        assert_eq!(installed.ids(), [8]);
+    }
+
     #[test]
     fn renders_only_complete_frames_when_channel_lengths_differ() {
         let mut engine = Engine::new();
@@ -391,6 +1035,139 @@ mod tests {
         assert_eq!(right, [0.5, 0.5]);
     }
 
+    #[test]
+    fn splits_blocks_longer_than_the_max_block_size() {
+        struct BlockLengths(Arc<AtomicUsize>);
+
+        impl StereoEffect for BlockLengths {
+            fn id(&self) -> EffectId {
+                0
+            }
+
+            fn process(&mut self, left: &mut [f32], _right: &mut [f32], _sample_rate: f32) {
+                self.0.fetch_max(left.len(), Ordering::Relaxed);
+            }
+
+            fn set_parameter(&mut self, _index: u32, _value: f32) {}
+        }
+
+        let longest_block = Arc::new(AtomicUsize::new(0));
+        let mut engine = Engine::with_max_block_size(4);
+        engine.add_instrument(Box::new(TestInstrument {
+            id: 3,
+            note_ons: Arc::new(AtomicUsize::new(0)),
+            note_offs: Arc::new(AtomicUsize::new(0)),
+            effect_value: Arc::new(AtomicU32::new(0)),
+        }));
+        engine.add_master_effect(Box::new(BlockLengths(longest_block.clone())));
+        let mut left = [0.0; 10];
+        let mut right = [0.0; 10];
+
+        engine.process(&mut left, &mut right, 48_000.0);
+
+        assert_eq!(left, [0.25; 10]);
+        assert_eq!(right, [0.5; 10]);
+        assert_eq!(longest_block.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn prepare_updates_block_size_and_master_effects() {
+        struct PreparedRate(Arc<AtomicU32>);
+
+        impl StereoEffect for PreparedRate {
+            fn id(&self) -> EffectId {
+                0
+            }
+
+            fn process(&mut self, _left: &mut [f32], _right: &mut [f32], _sample_rate: f32) {}
+
+            fn set_parameter(&mut self, _index: u32, _value: f32) {}
+
+            fn prepare(&mut self, sample_rate: f32, _max_block_size: usize) {
+                self.0.store(sample_rate.to_bits(), Ordering::Relaxed);
+            }
+        }
+
+        let prepared_rate = Arc::new(AtomicU32::new(0));
+        let mut engine = Engine::new();
+        engine.add_master_effect(Box::new(PreparedRate(prepared_rate.clone())));
+
+        engine.prepare(96_000.0, 256);
+
+        assert_eq!(engine.max_block_size(), 256);
+        assert_eq!(
+            f32::from_bits(prepared_rate.load(Ordering::Relaxed)),
+            96_000.0
+        );
+    }
+
+    #[test]
+    fn voice_activity_lists_sounding_voices_after_each_block() {
+        let factory = dsp::InstrumentFactory::new(48_000.0);
+        let mut engine = Engine::new();
+        let voice_activity = engine.voice_activity();
+        engine.add_instrument(factory.create_polyphonic_oscillator(7, 0.0, 4));
+        engine.add_instrument(factory.create_simple_oscillator(2, 0.0));
+        engine.note_on(7, 60, 200);
+        engine.note_on(7, 67, 200);
+        engine.note_on(2, 36, 200);
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+
+        assert_eq!(voice_activity.snapshot().active, 0);
+        engine.process(&mut left, &mut right, 48_000.0);
+        let snapshot = voice_activity.snapshot();
+        assert_eq!(snapshot.active, 3);
+        assert_eq!(
+            snapshot
+                .voices
+                .iter()
+                .map(|voice| (voice.instrument_id, voice.voice, voice.note))
+                .collect::<Vec<_>>(),
+            [(2, 0, 36), (7, 0, 60), (7, 1, 67)]
+        );
+        assert!(snapshot
+            .voices
+            .iter()
+            .all(|voice| voice.stage == Some(dsp::EnvelopeState::Attack) && voice.level > 0.0));
+
+        engine.remove_instrument(2);
+        assert_eq!(voice_activity.snapshot(), VoiceSnapshot::default());
+        engine.process(&mut left, &mut right, 48_000.0);
+        assert_eq!(voice_activity.snapshot().active, 2);
+    }
+
+    #[test]
+    fn dsp_load_reports_every_instrument_master_effect_and_block() {
+        let mut engine = Engine::with_max_block_size(4);
+        let dsp_load = engine.dsp_load_state();
+        engine.add_instrument(test_instrument(5));
+        engine.add_instrument(test_instrument(2));
+        engine.add_master_effect(Box::new(ScaleEffect { id: 9, scale: 1.0 }));
+        let mut left = [0.0; 10];
+        let mut right = [0.0; 10];
+
+        engine.process(&mut left, &mut right, 48_000.0);
+        let report = dsp_load.take_report();
+
+        assert_eq!(report.blocks, 3);
+        assert_eq!(
+            report.budget.as_nanos(),
+            u128::from(2 * budget_nanos(4, 48_000.0) + budget_nanos(2, 48_000.0))
+        );
+        assert_eq!(
+            report
+                .instruments
+                .iter()
+                .map(|load| load.instrument_id)
+                .collect::<Vec<_>>(),
+            [2, 5]
+        );
+        assert_eq!(report.master_effects[0].effect_id, 9);
+        assert!(report.block.peak >= report.instruments[0].load.peak);
+        assert!(report.block.average >= 0.0);
+    }
+
     #[test]
     fn missing_instrument_ids_are_no_ops() {
         let mut engine = Engine::new();
@@ -406,4 +1183,115 @@ mod tests {
         assert_eq!(left, [0.0; 2]);
         assert_eq!(right, [0.0; 2]);
     }
+
+    #[test]
+    fn bound_parameter_bank_slots_drive_master_effects() {
+        let mut engine = Engine::new();
+        engine.add_master_effect(Box::new(ScaleEffect { id: 9, scale: 1.0 }));
+        engine.handle_command(
+            MixerCmd::BindParameter {
+                slot: 2,
+                target: ParamTarget::MasterEffect {
+                    effect_id: 9,
+                    param_index: 0,
+                },
+                smoothing_seconds: 0.0,
+            }
+            .into(),
+        );
+        let bank = ParameterBank::new(4);
+        bank.set(2, 0.5);
+        bank.set(3, 4.0);
+
+        let mut left = [1.0; 4];
+        let mut right = [1.0; 4];
+        engine.apply_parameters(&bank, left.len(), 48_000.0);
+        engine.process(&mut left, &mut right, 48_000.0);
+        assert_eq!(left, [0.5; 4]);
+
+        engine.handle_command(MixerCmd::UnbindParameter { slot: 2 }.into());
+        bank.set(2, 2.0);
+        engine.apply_parameters(&bank, left.len(), 48_000.0);
+        let mut left = [1.0; 4];
+        engine.process(&mut left, &mut right, 48_000.0);
+        assert_eq!(left, [0.5; 4]);
+    }
+
+    #[test]
+    fn parameters_are_bound_by_descriptor_id() {
+        let (event_sender, events) = event_channel(DEFAULT_EVENT_CAPACITY);
+        let mut engine = Engine::new();
+        engine.set_event_sender(event_sender);
+        let effects = dsp::EffectFactory::new(48_000.0);
+        engine.add_instrument(dsp::InstrumentFactory::new(48_000.0).create_kick_drum(1, 0.0));
+        let mut voice_effects = VoiceEffects::new();
+        voice_effects.push(effects.create_mono_delay(3, 0.1, 1, 0.2, 0.5));
+        engine.add_voice_effects_to_instrument(1, voice_effects);
+        engine.add_master_effect(effects.create_stereo_gain(5, 0.0));
+
+        let kick = ParamOwner::Instrument { instrument_id: 1 };
+        assert_eq!(
+            engine.parameter_target(kick, "drive"),
+            Some(ParamTarget::Instrument {
+                instrument_id: 1,
+                param_index: 5,
+            })
+        );
+        let delay = ParamOwner::InstrumentEffect {
+            instrument_id: 1,
+            effect_id: 3,
+        };
+        assert!(engine.bind_parameter_by_id(0, delay, "feedback", 0.0));
+        assert!(engine.bind_parameter_by_id(1, kick, "drive", 0.0));
+        let gain = ParamOwner::MasterEffect { effect_id: 5 };
+        assert!(engine.bind_parameter_by_id(2, gain, "gain", 0.0));
+
+        let bank = ParameterBank::default();
+        bank.set(1, 0.75);
+        engine.apply_parameters(&bank, 64, 48_000.0);
+        let instrument = engine.instrument(1).unwrap();
+        assert_eq!(instrument.parameter(5), Some(0.75));
+
+        assert!(!engine.bind_parameter_by_id(3, kick, "feedback", 0.0));
+        let missing = ParamOwner::Instrument { instrument_id: 2 };
+        assert!(!engine.bind_parameter_by_id(3, missing, "drive", 0.0));
+        assert_eq!(
+            events.drain().collect::<Vec<_>>(),
+            [
+                EngineEvent::ParameterNotFound {
+                    owner: kick,
+                    param_id: "feedback"
+                },
+                EngineEvent::ParameterNotFound {
+                    owner: missing,
+                    param_id: "drive"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parameter_bindings_outside_the_bank_are_rejected() {
+        let (event_sender, events) = event_channel(DEFAULT_EVENT_CAPACITY);
+        let mut engine = Engine::new().with_parameter_slots(2);
+        engine.set_event_sender(event_sender);
+        let target = ParamTarget::MasterEffect {
+            effect_id: 9,
+            param_index: 0,
+        };
+
+        assert!(engine.bind_parameter(0, target, 0.0));
+        assert!(engine.bind_parameter(1, target, 0.0));
+        assert!(engine.bind_parameter(1, target, 0.0));
+        assert!(!engine.bind_parameter(2, target, 0.0));
+
+        assert_eq!(engine.parameter_bindings.len(), 2);
+        assert_eq!(
+            events.drain().collect::<Vec<_>>(),
+            [EngineEvent::ParameterBindingRejected {
+                slot: 2,
+                slot_count: 2
+            }]
+        );
+    }
 }
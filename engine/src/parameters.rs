@@ -0,0 +1,240 @@
+//! Coalescing parameter transport for high-rate control such as knob drags and
+//! automation.
+//!
+//! Control threads write plain values into a fixed [`ParameterBank`] of atomic
+//! slots; the latest write to a slot wins and nothing is queued, so writes can
+//! never overflow. The engine polls the bank once per block, and every slot
+//! bound to a [`ParamTarget`] glides towards its newest value before being
+//! applied.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use dsp::id::{EffectId, InstrumentId};
+
+/// Slot count used by hosts that do not choose their own.
+pub const DEFAULT_PARAMETER_SLOTS: usize = 64;
+/// Time constant used when smoothing bound parameters.
+pub const DEFAULT_PARAMETER_SMOOTHING_SECONDS: f32 = 0.02;
+
+/// Remaining distance, relative to the target, below which smoothing snaps.
+const SNAP_THRESHOLD: f32 = 1e-4;
+
+/// Parameter that a bank slot drives once bound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamTarget {
+    MasterEffect {
+        effect_id: EffectId,
+        param_index: u32,
+    },
+    InstrumentEffect {
+        instrument_id: InstrumentId,
+        effect_id: EffectId,
+        param_index: u32,
+    },
+    /// One of the instrument's own descriptor-registered parameters.
+    Instrument {
+        instrument_id: InstrumentId,
+        param_index: u32,
+    },
+}
+
+/// Instrument or effect that publishes a parameter, for binding it by descriptor id
+/// with [`crate::MixerCmd::BindParameterById`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamOwner {
+    MasterEffect {
+        effect_id: EffectId,
+    },
+    InstrumentEffect {
+        instrument_id: InstrumentId,
+        effect_id: EffectId,
+    },
+    Instrument {
+        instrument_id: InstrumentId,
+    },
+}
+
+impl ParamOwner {
+    /// The target addressing the owner's parameter at `param_index`.
+    pub fn target(self, param_index: u32) -> ParamTarget {
+        match self {
+            ParamOwner::MasterEffect { effect_id } => ParamTarget::MasterEffect {
+                effect_id,
+                param_index,
+            },
+            ParamOwner::InstrumentEffect {
+                instrument_id,
+                effect_id,
+            } => ParamTarget::InstrumentEffect {
+                instrument_id,
+                effect_id,
+                param_index,
+            },
+            ParamOwner::Instrument { instrument_id } => ParamTarget::Instrument {
+                instrument_id,
+                param_index,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ParamSlot {
+    value: AtomicU32,
+    changed: AtomicBool,
+}
+
+/// Fixed set of lock-free parameter slots shared between control threads and the
+/// audio thread, usually behind an `Arc`.
+#[derive(Debug)]
+pub struct ParameterBank {
+    slots: Box<[ParamSlot]>,
+}
+
+impl Default for ParameterBank {
+    fn default() -> Self {
+        Self::new(DEFAULT_PARAMETER_SLOTS)
+    }
+}
+
+impl ParameterBank {
+    pub fn new(slot_count: usize) -> Self {
+        Self {
+            slots: (0..slot_count)
+                .map(|_| ParamSlot {
+                    value: AtomicU32::new(0),
+                    changed: AtomicBool::new(false),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Publishes a plain value, replacing any value the audio thread has not read
+    /// yet. Returns `false` if the slot does not exist. Realtime-safe.
+    pub fn set(&self, slot: usize, value: f32) -> bool {
+        let Some(slot) = self.slots.get(slot) else {
+            return false;
+        };
+        slot.value.store(value.to_bits(), Ordering::Relaxed);
+        slot.changed.store(true, Ordering::Release);
+        true
+    }
+
+    /// Takes the value written since the last call, if any.
+    pub fn take(&self, slot: usize) -> Option<f32> {
+        let slot = self.slots.get(slot)?;
+        if !slot.changed.swap(false, Ordering::Acquire) {
+            return None;
+        }
+        Some(f32::from_bits(slot.value.load(Ordering::Relaxed)))
+    }
+}
+
+/// Audio-thread state of one bound slot.
+pub(crate) struct ParamBinding {
+    pub(crate) slot: usize,
+    pub(crate) target: ParamTarget,
+    smoothing_seconds: f32,
+    /// Last applied value; `None` until the slot is first written.
+    current: Option<f32>,
+    goal: f32,
+}
+
+impl ParamBinding {
+    pub(crate) fn new(slot: usize, target: ParamTarget, smoothing_seconds: f32) -> Self {
+        Self {
+            slot,
+            target,
+            smoothing_seconds: smoothing_seconds.max(0.0),
+            current: None,
+            goal: 0.0,
+        }
+    }
+
+    /// Reads the bank and advances smoothing by one block, returning the value to
+    /// apply if it changed. The first value written to a slot is applied as-is,
+    /// since the parameter's prior value is unknown.
+    pub(crate) fn poll(&mut self, bank: &ParameterBank, block_seconds: f32) -> Option<f32> {
+        if let Some(value) = bank.take(self.slot) {
+            self.goal = value;
+            if self.current.is_none() {
+                self.current = Some(value);
+                return Some(value);
+            }
+        }
+
+        let current = self.current?;
+        if current == self.goal {
+            return None;
+        }
+        let next = if self.smoothing_seconds <= 0.0 {
+            self.goal
+        } else {
+            let coefficient = 1.0 - (-block_seconds / self.smoothing_seconds).exp();
+            let next = current + (self.goal - current) * coefficient;
+            if (self.goal - next).abs() <= SNAP_THRESHOLD * self.goal.abs().max(1.0) {
+                self.goal
+            } else {
+                next
+            }
+        };
+        self.current = Some(next);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bank_keeps_only_the_latest_write() {
+        let bank = ParameterBank::new(2);
+        assert!(bank.set(1, 0.25));
+        assert!(bank.set(1, 0.5));
+        assert!(!bank.set(2, 1.0));
+
+        assert_eq!(bank.take(0), None);
+        assert_eq!(bank.take(1), Some(0.5));
+        assert_eq!(bank.take(1), None);
+    }
+
+    #[test]
+    fn binding_snaps_first_value_then_glides_to_later_ones() {
+        let bank = ParameterBank::new(1);
+        let mut binding = ParamBinding::new(
+            0,
+            ParamTarget::MasterEffect {
+                effect_id: 0,
+                param_index: 0,
+            },
+            0.01,
+        );
+        assert_eq!(binding.poll(&bank, 0.001), None);
+
+        bank.set(0, 1.0);
+        assert_eq!(binding.poll(&bank, 0.001), Some(1.0));
+        assert_eq!(binding.poll(&bank, 0.001), None);
+
+        bank.set(0, 0.0);
+        let first = binding.poll(&bank, 0.001).unwrap();
+        assert!(first > 0.8 && first < 1.0, "{first}");
+
+        let mut last = first;
+        for _ in 0..200 {
+            if let Some(value) = binding.poll(&bank, 0.001) {
+                assert!(value <= last);
+                last = value;
+            }
+        }
+        assert_eq!(last, 0.0);
+    }
+}
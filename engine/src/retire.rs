@@ -0,0 +1,104 @@
+//! Deferred deallocation of state displaced on the audio thread.
+//!
+//! Replacing or clearing instruments would otherwise drop their boxes, voice
+//! buffers, samples, and effect chains inside the audio callback. The engine
+//! instead moves them into a preallocated channel; a non-RT thread drains it
+//! with [`RetireCollector::collect`], which is where the memory is freed.
+
+use std::sync::{
+    mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError},
+    Arc,
+};
+
+use dsp::{InstrumentSpec, InstrumentTrait, MonoEffect, StereoEffect, VoiceEffects};
+
+use crate::{EngineCapture, RestoredEngine};
+
+/// Channel capacity used by hosts that do not choose their own.
+pub const DEFAULT_RETIRE_CAPACITY: usize = 256;
+/// Items an engine holds on to while its retirement channel is full.
+pub const RETIRE_BACKLOG_CAPACITY: usize = 256;
+
+/// State the engine no longer owns, waiting to be dropped off the audio thread.
+#[allow(
+    clippy::large_enum_variant,
+    reason = "VoiceEffects stays inline so retiring it does not allocate on the audio thread"
+)]
+pub enum Retired {
+    Instrument(Box<dyn InstrumentTrait>),
+    /// Spec kept alongside a displaced instrument.
+    InstrumentSpec(Arc<InstrumentSpec>),
+    MonoEffect(Box<dyn MonoEffect>),
+    VoiceEffects(VoiceEffects),
+    StereoEffect(Box<dyn StereoEffect>),
+    /// A capture the host was not there to receive.
+    Capture(EngineCapture),
+    /// The emptied storage of a restored engine.
+    RestoredEngine(RestoredEngine),
+}
+
+/// Audio-thread end of the retirement channel, handed to
+/// [`crate::Engine::set_retire_sender`].
+pub struct RetireSender {
+    sender: SyncSender<Retired>,
+}
+
+impl RetireSender {
+    /// Queues `item` without blocking or allocating. If the channel is full or
+    /// the collector is gone the item is handed back to the caller.
+    #[allow(
+        clippy::result_large_err,
+        reason = "the rejected item is returned inline so the caller decides where it is dropped"
+    )]
+    pub fn try_send(&self, item: Retired) -> Result<(), Retired> {
+        self.sender.try_send(item).map_err(|error| match error {
+            TrySendError::Full(item) | TrySendError::Disconnected(item) => item,
+        })
+    }
+}
+
+/// Non-RT end of the retirement channel.
+pub struct RetireCollector {
+    receiver: Receiver<Retired>,
+    disconnected: bool,
+}
+
+impl RetireCollector {
+    /// Drops everything retired so far and returns how many items were freed.
+    ///
+    /// Collectors should poll rather than block: a receiver parked inside the
+    /// channel makes the next send wake it, which the audio thread must avoid.
+    pub fn collect(&mut self) -> usize {
+        let mut count = 0;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(_) => count += 1,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    break;
+                }
+            }
+        }
+        count
+    }
+
+    /// Returns `true` once [`Self::collect`] has drained the channel after
+    /// every [`RetireSender`] was dropped, so a collector thread can exit.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+}
+
+/// Creates a bounded retirement channel. The buffer is allocated here, so
+/// sending never allocates.
+pub fn retire_channel(capacity: usize) -> (RetireSender, RetireCollector) {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    (
+        RetireSender { sender },
+        RetireCollector {
+            receiver,
+            disconnected: false,
+        },
+    )
+}
@@ -0,0 +1,726 @@
+//! Capturing and restoring the engine's sound state.
+//!
+//! Hydration commands describe instruments as they were built, so parameter
+//! changes made afterwards are lost when a host rebuilds the engine from them.
+//! [`Engine::snapshot`] instead reads every published parameter back from the
+//! live instruments and master effects and folds the values into the specs the
+//! instruments were installed with.
+//!
+//! Both directions are split so that hosts can do them while the engine keeps
+//! playing. [`Engine::capture`] copies the live values into preallocated
+//! storage on the audio thread and [`EngineCapture::into_snapshot`] describes
+//! them on a control thread. A control thread builds the processors with
+//! [`EngineSnapshot::build`], which allocates and can fail, and
+//! [`Engine::restore`] swaps them in.
+//!
+//! Instruments publish everything a command can change, such as DFAM
+//! sequencer steps or drum pitch sweeps, as parameters, so those changes are
+//! captured too. Instruments installed without a spec are listed as skipped
+//! rather than described.
+
+use std::{
+    fmt,
+    ops::Range,
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+};
+
+use dsp::{
+    find_parameter,
+    id::{EffectId, InstrumentId},
+    EffectFactory, EffectType, InstrumentFactory, InstrumentSpec, InstrumentTrait, ParamDescriptor,
+    Registry, RegistryError, StereoEffect, StereoEffectChain, AMP_ENVELOPE_KEYS,
+};
+
+use crate::{
+    Engine, Retired, StateCmd, DEFAULT_INSTRUMENT_CAPACITY, DEFAULT_MASTER_EFFECT_CAPACITY,
+    DEFAULT_TEMPO_BPM,
+};
+
+/// An instrument as it can be rebuilt through the [`Registry`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentSnapshot {
+    pub instrument_id: InstrumentId,
+    /// The install spec with current parameter values.
+    pub spec: InstrumentSpec,
+}
+
+/// A master effect as it can be rebuilt through [`EffectFactory::create_stereo`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterEffectSnapshot {
+    pub effect_type: EffectType,
+    pub effect_id: EffectId,
+    /// Current plain values keyed by parameter id.
+    pub values: Vec<(&'static str, f32)>,
+}
+
+/// Sound state of an [`Engine`], returned by [`Engine::snapshot`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineSnapshot {
+    pub tempo_bpm: f32,
+    /// Installed instruments in mix order.
+    pub instruments: Vec<InstrumentSnapshot>,
+    /// Master effects in chain order.
+    pub master_effects: Vec<MasterEffectSnapshot>,
+    /// Instruments installed without a spec, which cannot be rebuilt.
+    pub skipped_instruments: Vec<InstrumentId>,
+    /// Master effects of no known type, which cannot be rebuilt.
+    pub skipped_master_effects: Vec<EffectId>,
+}
+
+/// Why an [`EngineSnapshot`] could not be built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestoreError {
+    Instrument {
+        instrument_id: InstrumentId,
+        error: RegistryError,
+    },
+    /// The effect type has no stereo variant.
+    UnsupportedMasterEffect {
+        effect_id: EffectId,
+        effect_type: EffectType,
+    },
+    UnknownMasterEffectParameter {
+        effect_id: EffectId,
+        parameter: String,
+    },
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestoreError::Instrument {
+                instrument_id,
+                error,
+            } => write!(f, "instrument {instrument_id}: {error}"),
+            RestoreError::UnsupportedMasterEffect {
+                effect_id,
+                effect_type,
+            } => write!(
+                f,
+                "master effect {effect_id}: {effect_type:?} has no stereo variant"
+            ),
+            RestoreError::UnknownMasterEffectParameter {
+                effect_id,
+                parameter,
+            } => write!(
+                f,
+                "master effect {effect_id} has no parameter '{parameter}'"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RestoreError {}
+
+/// Processors built from an [`EngineSnapshot`], ready for [`Engine::restore`].
+pub struct RestoredEngine {
+    tempo_bpm: f32,
+    instruments: Vec<(Box<dyn InstrumentTrait>, Arc<InstrumentSpec>)>,
+    master_effects: Vec<Box<dyn StereoEffect>>,
+}
+
+impl EngineSnapshot {
+    /// Builds every instrument and master effect in the snapshot. Allocates, so
+    /// call it from a control thread with factories for the engine's current
+    /// sample rate and block size.
+    pub fn build(
+        &self,
+        registry: &Registry,
+        instrument_factory: &InstrumentFactory,
+        effect_factory: &EffectFactory,
+    ) -> Result<RestoredEngine, RestoreError> {
+        let instruments = self
+            .instruments
+            .iter()
+            .map(|snapshot| {
+                registry
+                    .create_instrument(
+                        instrument_factory,
+                        effect_factory,
+                        snapshot.instrument_id,
+                        &snapshot.spec,
+                    )
+                    .map(|instrument| (instrument, Arc::new(snapshot.spec.clone())))
+                    .map_err(|error| RestoreError::Instrument {
+                        instrument_id: snapshot.instrument_id,
+                        error,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        let master_effects = self
+            .master_effects
+            .iter()
+            .map(|snapshot| build_master_effect(effect_factory, snapshot))
+            .collect::<Result<_, _>>()?;
+        Ok(RestoredEngine {
+            tempo_bpm: self.tempo_bpm,
+            instruments,
+            master_effects,
+        })
+    }
+}
+
+fn build_master_effect(
+    factory: &EffectFactory,
+    snapshot: &MasterEffectSnapshot,
+) -> Result<Box<dyn StereoEffect>, RestoreError> {
+    let mut effect = factory
+        .create_stereo(snapshot.effect_type, snapshot.effect_id)
+        .ok_or(RestoreError::UnsupportedMasterEffect {
+            effect_id: snapshot.effect_id,
+            effect_type: snapshot.effect_type,
+        })?;
+    for &(id, value) in &snapshot.values {
+        let descriptor = find_parameter(effect.parameters(), id).ok_or_else(|| {
+            RestoreError::UnknownMasterEffectParameter {
+                effect_id: snapshot.effect_id,
+                parameter: id.to_string(),
+            }
+        })?;
+        effect.set_parameter(descriptor.index, descriptor.clamp(value));
+    }
+    Ok(effect)
+}
+
+/// Live state read by [`Engine::capture`] without allocating, turned into an
+/// [`EngineSnapshot`] on a control thread with [`Self::into_snapshot`].
+///
+/// Storage is reserved up front. When the engine holds more than fits, the
+/// capture still counts what it needed, so [`Self::grow`] can make room for
+/// another attempt.
+pub struct EngineCapture {
+    request: u64,
+    tempo_bpm: f32,
+    instruments: Vec<CapturedInstrument>,
+    master_effects: Vec<CapturedMasterEffect>,
+    voice_effects: Vec<Option<CapturedParameters>>,
+    values: Vec<Option<f32>>,
+    required: CaptureSize,
+}
+
+struct CapturedInstrument {
+    instrument_id: InstrumentId,
+    spec: Option<Arc<InstrumentSpec>>,
+    parameters: CapturedParameters,
+    amp_envelope: Option<[f32; 4]>,
+    /// Range of [`EngineCapture::voice_effects`], one per effect in the spec.
+    voice_effects: Range<usize>,
+}
+
+struct CapturedMasterEffect {
+    effect_id: EffectId,
+    effect_type: Option<EffectType>,
+    parameters: CapturedParameters,
+}
+
+/// Values of `descriptors`, in order, within [`EngineCapture::values`].
+struct CapturedParameters {
+    descriptors: &'static [ParamDescriptor],
+    values: Range<usize>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct CaptureSize {
+    instruments: usize,
+    master_effects: usize,
+    voice_effects: usize,
+    values: usize,
+}
+
+/// Parameter values reserved per processor by [`EngineCapture::new`].
+const CAPTURE_VALUES_PER_PROCESSOR: usize = 32;
+/// Voice effects reserved per instrument by [`EngineCapture::new`].
+const CAPTURE_VOICE_EFFECTS_PER_INSTRUMENT: usize = 4;
+
+impl Default for EngineCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EngineCapture {
+    /// Reserves room for an engine with the default instrument and master
+    /// effect capacities.
+    pub fn new() -> Self {
+        let instruments = DEFAULT_INSTRUMENT_CAPACITY;
+        let master_effects = DEFAULT_MASTER_EFFECT_CAPACITY;
+        let voice_effects = instruments * CAPTURE_VOICE_EFFECTS_PER_INSTRUMENT;
+        let mut capture = Self {
+            request: 0,
+            tempo_bpm: DEFAULT_TEMPO_BPM,
+            instruments: Vec::new(),
+            master_effects: Vec::new(),
+            voice_effects: Vec::new(),
+            values: Vec::new(),
+            required: CaptureSize {
+                instruments,
+                master_effects,
+                voice_effects,
+                values: (instruments + master_effects + voice_effects)
+                    * CAPTURE_VALUES_PER_PROCESSOR,
+            },
+        };
+        capture.grow();
+        capture
+    }
+
+    /// Tags the capture so that a host can tell the answer to its latest
+    /// request from answers to requests it gave up on. The engine keeps it.
+    pub fn set_request(&mut self, request: u64) {
+        self.request = request;
+    }
+
+    pub fn request(&self) -> u64 {
+        self.request
+    }
+
+    /// `true` if the last capture did not fit and [`Self::grow`] is needed.
+    fn overflowed(&self) -> bool {
+        self.instruments.len() < self.required.instruments
+            || self.master_effects.len() < self.required.master_effects
+            || self.voice_effects.len() < self.required.voice_effects
+            || self.values.len() < self.required.values
+    }
+
+    /// Empties the capture and reserves everything the last capture needed.
+    pub fn grow(&mut self) {
+        self.instruments.clear();
+        self.master_effects.clear();
+        self.voice_effects.clear();
+        self.values.clear();
+        self.instruments.reserve_exact(self.required.instruments);
+        self.master_effects
+            .reserve_exact(self.required.master_effects);
+        self.voice_effects
+            .reserve_exact(self.required.voice_effects);
+        self.values.reserve_exact(self.required.values);
+        self.required = CaptureSize::default();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+            && self.master_effects.is_empty()
+            && self.voice_effects.is_empty()
+            && self.values.is_empty()
+    }
+
+    /// Describes the captured state, folding parameter values into the specs
+    /// the instruments were installed with. Hands the capture back if the
+    /// engine held more than it had room for.
+    #[allow(
+        clippy::result_large_err,
+        reason = "the capture is handed back so its storage can be grown and reused"
+    )]
+    pub fn into_snapshot(self) -> Result<EngineSnapshot, Self> {
+        if self.overflowed() {
+            return Err(self);
+        }
+        let mut snapshot = EngineSnapshot {
+            tempo_bpm: self.tempo_bpm,
+            instruments: Vec::new(),
+            master_effects: Vec::new(),
+            skipped_instruments: Vec::new(),
+            skipped_master_effects: Vec::new(),
+        };
+        for captured in &self.instruments {
+            let Some(spec) = &captured.spec else {
+                snapshot.skipped_instruments.push(captured.instrument_id);
+                continue;
+            };
+            let mut spec = InstrumentSpec::clone(spec);
+            self.read_parameters(&mut spec.values, &captured.parameters);
+            if let Some(envelope) = captured.amp_envelope {
+                for (key, value) in AMP_ENVELOPE_KEYS.into_iter().zip(envelope) {
+                    set_value(&mut spec.values, key, value);
+                }
+            }
+            let voice_effects = &self.voice_effects[captured.voice_effects.clone()];
+            for (effect_spec, parameters) in spec.effects.iter_mut().zip(voice_effects) {
+                if let Some(parameters) = parameters {
+                    self.read_parameters(&mut effect_spec.values, parameters);
+                }
+            }
+            snapshot.instruments.push(InstrumentSnapshot {
+                instrument_id: captured.instrument_id,
+                spec,
+            });
+        }
+        for captured in &self.master_effects {
+            match captured.effect_type {
+                Some(effect_type) => {
+                    let mut values = Vec::new();
+                    self.read_parameters(&mut values, &captured.parameters);
+                    snapshot.master_effects.push(MasterEffectSnapshot {
+                        effect_type,
+                        effect_id: captured.effect_id,
+                        values,
+                    });
+                }
+                None => snapshot.skipped_master_effects.push(captured.effect_id),
+            }
+        }
+        Ok(snapshot)
+    }
+
+    fn read_parameters(
+        &self,
+        values: &mut Vec<(&'static str, f32)>,
+        parameters: &CapturedParameters,
+    ) {
+        let captured = &self.values[parameters.values.clone()];
+        for (descriptor, value) in parameters.descriptors.iter().zip(captured) {
+            if let Some(value) = *value {
+                set_value(values, descriptor.id, value);
+            }
+        }
+    }
+
+    /// Stores the value of every descriptor, or only counts them when there
+    /// is no room left.
+    fn capture_parameters(
+        &mut self,
+        descriptors: &'static [ParamDescriptor],
+        parameter: impl Fn(u32) -> Option<f32>,
+    ) -> CapturedParameters {
+        let start = self.values.len();
+        self.required.values += descriptors.len();
+        if self.values.capacity() - start >= descriptors.len() {
+            self.values.extend(
+                descriptors
+                    .iter()
+                    .map(|descriptor| parameter(descriptor.index)),
+            );
+        }
+        CapturedParameters {
+            descriptors,
+            values: start..self.values.len(),
+        }
+    }
+}
+
+/// Pushes `item` only if `items` has room for it, so that it never allocates.
+fn push_within<T>(items: &mut Vec<T>, required: &mut usize, item: T) {
+    *required += 1;
+    if items.len() < items.capacity() {
+        items.push(item);
+    }
+}
+
+/// Audio-thread end of the capture channel, handed to
+/// [`Engine::set_capture_sender`].
+pub struct CaptureSender {
+    sender: SyncSender<EngineCapture>,
+}
+
+impl CaptureSender {
+    /// Queues `capture` without blocking or allocating. If the channel is full
+    /// or the receiver is gone the capture is handed back to the caller.
+    #[allow(
+        clippy::result_large_err,
+        reason = "the rejected capture is returned inline so the caller decides where it is dropped"
+    )]
+    pub fn try_send(&self, capture: EngineCapture) -> Result<(), EngineCapture> {
+        self.sender.try_send(capture).map_err(|error| match error {
+            TrySendError::Full(capture) | TrySendError::Disconnected(capture) => capture,
+        })
+    }
+}
+
+/// Control-thread end of the capture channel.
+pub struct CaptureReceiver {
+    receiver: Receiver<EngineCapture>,
+}
+
+impl CaptureReceiver {
+    /// Returns the next filled capture without blocking.
+    pub fn try_recv(&self) -> Option<EngineCapture> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Creates a bounded capture channel. The buffer is allocated here, so sending
+/// never allocates.
+pub fn capture_channel(capacity: usize) -> (CaptureSender, CaptureReceiver) {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    (CaptureSender { sender }, CaptureReceiver { receiver })
+}
+
+impl Engine {
+    /// Sends captures filled by [`StateCmd::Capture`] to a [`CaptureReceiver`].
+    /// Without a sender, or when the channel is full, they are retired.
+    pub fn set_capture_sender(&mut self, sender: CaptureSender) {
+        self.capture_sender = Some(sender);
+    }
+
+    pub(crate) fn handle_state_command(&mut self, command: StateCmd) {
+        match command {
+            StateCmd::Capture(mut capture) => {
+                self.capture(&mut capture);
+                let rejected = match &self.capture_sender {
+                    Some(sender) => sender.try_send(capture),
+                    None => Err(capture),
+                };
+                if let Err(capture) = rejected {
+                    self.retire(Retired::Capture(capture));
+                }
+            }
+            StateCmd::Restore(restored) => self.restore(restored),
+        }
+    }
+
+    /// Reads the installed instruments, master effects and tempo with the
+    /// current value of every published parameter into `capture`, which must
+    /// be empty, as returned by [`EngineCapture::new`] or
+    /// [`EngineCapture::grow`]. Never allocates, so it is safe on the audio
+    /// thread.
+    pub fn capture(&self, capture: &mut EngineCapture) {
+        debug_assert!(capture.is_empty(), "captures must start empty");
+        capture.tempo_bpm = self.tempo_bpm;
+        for slot in &self.instruments {
+            let instrument = slot.instrument.as_ref();
+            let parameters = capture
+                .capture_parameters(instrument.parameters(), |index| instrument.parameter(index));
+            let first_voice_effect = capture.voice_effects.len();
+            let effect_count = slot.spec.as_ref().map_or(0, |spec| spec.effects.len());
+            for index in 0..effect_count {
+                let parameters = instrument.voice_effect(index).map(|effect| {
+                    capture.capture_parameters(effect.parameters(), |index| effect.parameter(index))
+                });
+                push_within(
+                    &mut capture.voice_effects,
+                    &mut capture.required.voice_effects,
+                    parameters,
+                );
+            }
+            push_within(
+                &mut capture.instruments,
+                &mut capture.required.instruments,
+                CapturedInstrument {
+                    instrument_id: slot.id,
+                    spec: slot.spec.clone(),
+                    parameters,
+                    amp_envelope: instrument.amp_envelope(),
+                    voice_effects: first_voice_effect..capture.voice_effects.len(),
+                },
+            );
+        }
+        for effect in self.master_effects.iter() {
+            let parameters =
+                capture.capture_parameters(effect.parameters(), |index| effect.parameter(index));
+            push_within(
+                &mut capture.master_effects,
+                &mut capture.required.master_effects,
+                CapturedMasterEffect {
+                    effect_id: effect.id(),
+                    effect_type: effect.effect_type(),
+                    parameters,
+                },
+            );
+        }
+    }
+
+    /// Describes the installed instruments, master effects and tempo with the
+    /// current value of every published parameter. Allocates, so call it while
+    /// the engine is not being processed, as for [`Self::prepare`]; hosts that
+    /// keep processing send [`StateCmd::Capture`] instead.
+    pub fn snapshot(&self) -> EngineSnapshot {
+        let mut capture = EngineCapture::new();
+        loop {
+            self.capture(&mut capture);
+            match capture.into_snapshot() {
+                Ok(snapshot) => return snapshot,
+                Err(overflowed) => {
+                    capture = overflowed;
+                    capture.grow();
+                }
+            }
+        }
+    }
+
+    /// Replaces every instrument and master effect with ones built from a
+    /// snapshot and restores its tempo. Displaced state and the emptied
+    /// `restored` are retired, so this is safe on the audio thread; hosts
+    /// send it as [`StateCmd::Restore`].
+    pub fn restore(&mut self, mut restored: RestoredEngine) {
+        self.clear_instruments();
+        // Draining in place keeps the chain's capacity.
+        let mut master_effects =
+            std::mem::replace(&mut self.master_effects, StereoEffectChain::new(0));
+        for effect in master_effects.drain() {
+            self.retire(Retired::StereoEffect(effect));
+        }
+        self.master_effects = master_effects;
+        self.tempo_bpm = restored.tempo_bpm;
+        for (instrument, spec) in restored.instruments.drain(..) {
+            self.add_instrument_from_spec(instrument, spec);
+        }
+        for effect in restored.master_effects.drain(..) {
+            self.add_master_effect(effect);
+        }
+        self.retire(Retired::RestoredEngine(restored));
+    }
+}
+
+fn set_value(values: &mut Vec<(&'static str, f32)>, id: &'static str, value: f32) {
+    match values.iter_mut().find(|(key, _)| *key == id) {
+        Some((_, existing)) => *existing = value,
+        None => values.push((id, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dsp::EffectSpec;
+
+    use super::*;
+    use crate::InstrumentCmd;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn kick_spec() -> InstrumentSpec {
+        InstrumentSpec {
+            type_id: "kick_drum",
+            version: 1,
+            values: vec![("pitch_env_amount", 80.0), ("drive", 0.2)],
+            effects: vec![EffectSpec {
+                type_id: "delay",
+                version: 1,
+                effect_id: 1,
+                values: vec![("mix", 0.3)],
+            }],
+        }
+    }
+
+    fn render(engine: &mut Engine) -> Vec<f32> {
+        engine.note_on(1, 36, 100);
+        let mut left = vec![0.0; 512];
+        let mut right = vec![0.0; 512];
+        engine.process(&mut left, &mut right, SAMPLE_RATE);
+        left
+    }
+
+    #[test]
+    fn restored_engines_keep_parameters_changed_after_install() {
+        let registry = Registry::with_builtins();
+        let instruments = InstrumentFactory::new(SAMPLE_RATE);
+        let effects = EffectFactory::new(SAMPLE_RATE);
+        let spec = kick_spec();
+        let mut engine = Engine::new();
+        engine.handle_command(
+            InstrumentCmd::AddInstrumentFromSpec {
+                instrument: registry
+                    .create_instrument(&instruments, &effects, 1, &spec)
+                    .unwrap(),
+                spec: Arc::new(spec),
+            }
+            .into(),
+        );
+        engine.add_instrument(instruments.create_hihat(2, 0.0));
+        engine.add_master_effect(effects.create_stereo_gain(7, 1.0));
+        engine.set_tempo(96.0);
+
+        let drive = find_parameter(
+            InstrumentFactory::parameters(dsp::InstrumentType::KickDrum),
+            "drive",
+        )
+        .unwrap();
+        engine
+            .instrument_mut(1)
+            .unwrap()
+            .set_parameter(drive.index, 0.7);
+        engine
+            .instrument_mut(1)
+            .unwrap()
+            .try_handle_command(&dsp::SynthCmd::EnvelopeCommand {
+                envelope_id: None,
+                command: dsp::EnvelopeCmd::SetPitchEnvFreqDelta { freq_delta: 120.0 },
+            });
+        engine.set_instrument_effect_parameter(1, 1, 2, 0.5);
+        engine.set_master_effect_parameter(7, 0, -6.0);
+
+        let snapshot = engine.snapshot();
+        assert_eq!(snapshot.tempo_bpm, 96.0);
+        assert_eq!(snapshot.skipped_instruments, [2]);
+        let kick = &snapshot.instruments[0].spec;
+        assert!(kick.values.contains(&("drive", 0.7)));
+        assert!(kick.values.contains(&("pitch_env_amount", 120.0)));
+        assert!(kick.effects[0].values.contains(&("feedback", 0.5)));
+        assert_eq!(snapshot.master_effects[0].effect_type, EffectType::Gain);
+        let gain = snapshot.master_effects[0].values[0];
+        assert_eq!(gain.0, "gain");
+        assert!((gain.1 + 6.0).abs() < 1e-4);
+
+        let mut restored = Engine::new();
+        restored.restore(snapshot.build(&registry, &instruments, &effects).unwrap());
+        assert_eq!(restored.instrument_ids().collect::<Vec<_>>(), [1]);
+        assert_eq!(restored.tempo(), 96.0);
+
+        engine.remove_instrument(2);
+        assert_eq!(render(&mut restored), render(&mut engine));
+    }
+
+    #[test]
+    fn captures_that_did_not_fit_grow_to_what_they_needed() {
+        let registry = Registry::with_builtins();
+        let instruments = InstrumentFactory::new(SAMPLE_RATE);
+        let effects = EffectFactory::new(SAMPLE_RATE);
+        let spec = kick_spec();
+        let mut engine = Engine::new();
+        engine.add_instrument_from_spec(
+            registry
+                .create_instrument(&instruments, &effects, 1, &spec)
+                .unwrap(),
+            Arc::new(spec),
+        );
+        engine.add_master_effect(effects.create_stereo_gain(7, 1.0));
+
+        let mut capture = EngineCapture {
+            request: 0,
+            tempo_bpm: DEFAULT_TEMPO_BPM,
+            instruments: Vec::new(),
+            master_effects: Vec::new(),
+            voice_effects: Vec::new(),
+            values: Vec::new(),
+            required: CaptureSize::default(),
+        };
+        engine.capture(&mut capture);
+        let mut capture = capture.into_snapshot().err().unwrap();
+        capture.grow();
+        engine.capture(&mut capture);
+        assert_eq!(capture.into_snapshot().ok().unwrap(), engine.snapshot());
+    }
+
+    #[test]
+    fn snapshots_that_cannot_be_rebuilt_are_reported() {
+        let mut spec = kick_spec();
+        spec.type_id = "theremin";
+        let snapshot = EngineSnapshot {
+            tempo_bpm: 120.0,
+            instruments: vec![InstrumentSnapshot {
+                instrument_id: 4,
+                spec,
+            }],
+            master_effects: Vec::new(),
+            skipped_instruments: Vec::new(),
+            skipped_master_effects: Vec::new(),
+        };
+        let error = snapshot
+            .build(
+                &Registry::with_builtins(),
+                &InstrumentFactory::new(SAMPLE_RATE),
+                &EffectFactory::new(SAMPLE_RATE),
+            )
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            RestoreError::Instrument {
+                instrument_id: 4,
+                error: RegistryError::UnknownType { .. }
+            }
+        ));
+    }
+}
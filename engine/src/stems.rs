@@ -0,0 +1,113 @@
+use dsp::id::InstrumentId;
+
+/// One instrument's stereo output for the latest block.
+struct StemBus {
+    instrument_id: InstrumentId,
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+/// Per-instrument stereo buses filled by [`crate::Engine::process_with_stems`].
+///
+/// Each bus holds one installed instrument's output before the master effect
+/// chain, in mix order. Buses are allocated up front so that rendering into
+/// them never allocates; instruments beyond [`Self::capacity`] still reach the
+/// master mix but get no stem.
+pub struct StemBuses {
+    buses: Vec<StemBus>,
+    /// Buses written by the latest block.
+    len: usize,
+    /// Frames written to each bus by the latest block.
+    frame_count: usize,
+    max_frame_count: usize,
+}
+
+impl StemBuses {
+    /// Creates buses for up to `capacity` instruments and blocks of up to
+    /// `max_frame_count` frames.
+    pub fn new(capacity: usize, max_frame_count: usize) -> Self {
+        Self {
+            buses: (0..capacity)
+                .map(|_| StemBus {
+                    instrument_id: 0,
+                    left: vec![0.0; max_frame_count],
+                    right: vec![0.0; max_frame_count],
+                })
+                .collect(),
+            len: 0,
+            frame_count: 0,
+            max_frame_count,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buses.len()
+    }
+
+    pub fn max_frame_count(&self) -> usize {
+        self.max_frame_count
+    }
+
+    /// Number of stems written by the latest block.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Frames in each stem of the latest block.
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// The latest block's stems as `(instrument_id, left, right)`, in mix order.
+    pub fn iter(&self) -> impl Iterator<Item = (InstrumentId, &[f32], &[f32])> + '_ {
+        self.buses[..self.len].iter().map(|bus| {
+            (
+                bus.instrument_id,
+                &bus.left[..self.frame_count],
+                &bus.right[..self.frame_count],
+            )
+        })
+    }
+
+    /// Starts a block of `frame_count` frames for `instrument_ids`.
+    pub(crate) fn begin(
+        &mut self,
+        instrument_ids: impl Iterator<Item = InstrumentId>,
+        frame_count: usize,
+    ) {
+        self.len = 0;
+        self.frame_count = frame_count.min(self.max_frame_count);
+        for (bus, instrument_id) in self.buses.iter_mut().zip(instrument_ids) {
+            bus.instrument_id = instrument_id;
+            self.len += 1;
+        }
+    }
+
+    /// The `frames` of the bus for the instrument at mix position `index`, if
+    /// it has one. The caller overwrites them.
+    pub(crate) fn bus_mut(
+        &mut self,
+        index: usize,
+        frames: std::ops::Range<usize>,
+    ) -> Option<(&mut [f32], &mut [f32])> {
+        if index >= self.len {
+            return None;
+        }
+        let bus = &mut self.buses[index];
+        Some((&mut bus.left[frames.clone()], &mut bus.right[frames]))
+    }
+}
+
+/// Takes `bus`, rendered on top of `mix`, as the new mix and leaves only what
+/// was added to it in `bus`.
+pub(crate) fn split_stem(mix: &mut [f32], bus: &mut [f32]) {
+    for (mix, bus) in mix.iter_mut().zip(bus) {
+        let summed = *bus;
+        *bus = summed - *mix;
+        *mix = summed;
+    }
+}
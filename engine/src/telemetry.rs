@@ -0,0 +1,273 @@
+//! Lock-free DSP load telemetry shared between the audio thread and readers
+//! such as the OSC server or an offline render.
+//!
+//! The engine is the sole writer: for every block it renders it records how
+//! long each instrument, each master effect, and the whole block took. A
+//! reader calls [`DspLoadState::take_report`] on its own timer, which returns
+//! the figures accumulated since the previous call and starts a new window.
+//!
+//! Load is a fraction of the real-time budget, the time the block represents
+//! at the current sample rate: `1.0` means processing took exactly as long as
+//! playback, so anything close to it will underrun a live device. Peaks are
+//! stored as `f32` bits and held with `fetch_max`, which is order-preserving
+//! for non-negative floats, as in the output meter.
+
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use dsp::id::{EffectId, InstrumentId};
+
+/// Load over one read window, as fractions of the real-time budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DspLoad {
+    /// Total processing time divided by total budget.
+    pub average: f32,
+    /// Highest single-block ratio.
+    pub peak: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstrumentLoad {
+    pub instrument_id: InstrumentId,
+    pub load: DspLoad,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectLoad {
+    pub effect_id: EffectId,
+    pub load: DspLoad,
+}
+
+/// Figures accumulated over one read window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DspLoadReport {
+    /// Number of engine blocks rendered in the window.
+    pub blocks: u64,
+    /// Total real time the rendered blocks represent.
+    pub budget: Duration,
+    /// The whole engine block: instruments, mixing and master effects.
+    pub block: DspLoad,
+    /// Installed instruments in mix order.
+    pub instruments: Vec<InstrumentLoad>,
+    /// Master effects in chain order.
+    pub master_effects: Vec<EffectLoad>,
+}
+
+#[derive(Debug)]
+struct LoadCell {
+    id: AtomicU32,
+    busy_nanos: AtomicU64,
+    peak: AtomicU32,
+}
+
+impl LoadCell {
+    fn new() -> Self {
+        Self {
+            id: AtomicU32::new(0),
+            busy_nanos: AtomicU64::new(0),
+            peak: AtomicU32::new(0),
+        }
+    }
+
+    fn record(&self, id: u32, busy_nanos: u64, budget_nanos: u64) {
+        self.id.store(id, Ordering::Relaxed);
+        self.busy_nanos.fetch_add(busy_nanos, Ordering::Relaxed);
+        let ratio = busy_nanos as f32 / budget_nanos.max(1) as f32;
+        self.peak.fetch_max(ratio.to_bits(), Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.busy_nanos.store(0, Ordering::Relaxed);
+        self.peak.store(0, Ordering::Relaxed);
+    }
+
+    fn take(&self, budget_nanos: u64) -> (u32, DspLoad) {
+        let busy_nanos = self.busy_nanos.swap(0, Ordering::Relaxed);
+        let peak = f32::from_bits(self.peak.swap(0, Ordering::Relaxed));
+        let average = if budget_nanos == 0 {
+            0.0
+        } else {
+            busy_nanos as f32 / budget_nanos as f32
+        };
+        (self.id.load(Ordering::Relaxed), DspLoad { average, peak })
+    }
+}
+
+/// Realtime-safe DSP load counters, shared behind an `Arc` obtained from
+/// [`crate::Engine::dsp_load_state`].
+#[derive(Debug)]
+pub struct DspLoadState {
+    blocks: AtomicU64,
+    budget_nanos: AtomicU64,
+    block: LoadCell,
+    instruments: Box<[LoadCell]>,
+    instrument_count: AtomicUsize,
+    master_effects: Box<[LoadCell]>,
+    master_effect_count: AtomicUsize,
+}
+
+impl DspLoadState {
+    pub(crate) fn new(instrument_capacity: usize, master_effect_capacity: usize) -> Self {
+        Self {
+            blocks: AtomicU64::new(0),
+            budget_nanos: AtomicU64::new(0),
+            block: LoadCell::new(),
+            instruments: (0..instrument_capacity).map(|_| LoadCell::new()).collect(),
+            instrument_count: AtomicUsize::new(0),
+            master_effects: (0..master_effect_capacity)
+                .map(|_| LoadCell::new())
+                .collect(),
+            master_effect_count: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn record_instrument(
+        &self,
+        index: usize,
+        instrument_id: InstrumentId,
+        busy: Duration,
+        budget_nanos: u64,
+    ) {
+        if let Some(cell) = self.instruments.get(index) {
+            cell.record(instrument_id, duration_nanos(busy), budget_nanos);
+        }
+    }
+
+    pub(crate) fn record_master_effect(
+        &self,
+        index: usize,
+        effect_id: EffectId,
+        busy: Duration,
+        budget_nanos: u64,
+    ) {
+        if let Some(cell) = self.master_effects.get(index) {
+            cell.record(effect_id, duration_nanos(busy), budget_nanos);
+        }
+    }
+
+    /// Closes one engine block after its instruments and effects were recorded.
+    pub(crate) fn record_block(
+        &self,
+        busy: Duration,
+        budget_nanos: u64,
+        instrument_count: usize,
+        master_effect_count: usize,
+    ) {
+        self.block.record(0, duration_nanos(busy), budget_nanos);
+        self.instrument_count
+            .store(instrument_count, Ordering::Relaxed);
+        self.master_effect_count
+            .store(master_effect_count, Ordering::Relaxed);
+        self.budget_nanos.fetch_add(budget_nanos, Ordering::Relaxed);
+        self.blocks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Discards per-instrument figures after the instrument slots shifted, so
+    /// time spent by one instrument is not reported under another's id.
+    pub(crate) fn clear_instruments(&self) {
+        for cell in self.instruments.iter() {
+            cell.clear();
+        }
+    }
+
+    /// Returns the figures recorded since the last call and starts a new window.
+    /// Allocates the report, so call it from a control thread.
+    pub fn take_report(&self) -> DspLoadReport {
+        let blocks = self.blocks.swap(0, Ordering::Relaxed);
+        let budget_nanos = self.budget_nanos.swap(0, Ordering::Relaxed);
+        let (_, block) = self.block.take(budget_nanos);
+        let instrument_count = self
+            .instrument_count
+            .load(Ordering::Relaxed)
+            .min(self.instruments.len());
+        let master_effect_count = self
+            .master_effect_count
+            .load(Ordering::Relaxed)
+            .min(self.master_effects.len());
+        DspLoadReport {
+            blocks,
+            budget: Duration::from_nanos(budget_nanos),
+            block,
+            instruments: self.instruments[..instrument_count]
+                .iter()
+                .map(|cell| {
+                    let (instrument_id, load) = cell.take(budget_nanos);
+                    InstrumentLoad {
+                        instrument_id,
+                        load,
+                    }
+                })
+                .collect(),
+            master_effects: self.master_effects[..master_effect_count]
+                .iter()
+                .map(|cell| {
+                    let (effect_id, load) = cell.take(budget_nanos);
+                    EffectLoad { effect_id, load }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Real time represented by `frame_count` frames, in nanoseconds.
+pub(crate) fn budget_nanos(frame_count: usize, sample_rate: f32) -> u64 {
+    if sample_rate <= 0.0 {
+        return 0;
+    }
+    (frame_count as f64 * 1e9 / sample_rate as f64) as u64
+}
+
+fn duration_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_averages_over_the_window_and_holds_the_peak() {
+        let load = DspLoadState::new(2, 1);
+        for busy in [100, 300] {
+            load.record_instrument(0, 7, Duration::from_nanos(busy), 1_000);
+            load.record_master_effect(0, 3, Duration::from_nanos(50), 1_000);
+            load.record_block(Duration::from_nanos(busy + 100), 1_000, 1, 1);
+        }
+
+        let report = load.take_report();
+        assert_eq!(report.blocks, 2);
+        assert_eq!(report.budget, Duration::from_nanos(2_000));
+        assert_eq!(
+            report.block,
+            DspLoad {
+                average: 0.3,
+                peak: 0.4
+            }
+        );
+        assert_eq!(
+            report.instruments,
+            [InstrumentLoad {
+                instrument_id: 7,
+                load: DspLoad {
+                    average: 0.2,
+                    peak: 0.3
+                }
+            }]
+        );
+        assert_eq!(report.master_effects[0].effect_id, 3);
+        assert_eq!(report.master_effects[0].load.average, 0.05);
+
+        let next = load.take_report();
+        assert_eq!(next.blocks, 0);
+        assert_eq!(next.block, DspLoad::default());
+        assert_eq!(next.instruments[0].load, DspLoad::default());
+    }
+
+    #[test]
+    fn budget_is_the_real_time_a_block_represents() {
+        assert_eq!(budget_nanos(480, 48_000.0), 10_000_000);
+        assert_eq!(budget_nanos(480, 0.0), 0);
+    }
+}
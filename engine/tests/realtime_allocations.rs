@@ -0,0 +1,230 @@
+//! Asserts that rendering and instrument replacement never touch the heap.
+//!
+//! The test binary installs a counting global allocator. Only calls made on a
+//! thread inside [`assert_no_heap_traffic`] are counted, so the harness and
+//! other tests do not interfere. The allocator itself never panics: unwinding
+//! out of `GlobalAlloc` is undefined behaviour, so the guard panics afterwards.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use dsp::{EffectFactory, EffectSpec, InstrumentFactory, InstrumentSpec, Registry};
+use engine::{
+    capture_channel, event_channel, retire_channel, Engine, EngineCapture, InstrumentCmd, MixerCmd,
+    StateCmd, StemBuses, DEFAULT_EVENT_CAPACITY, DEFAULT_RETIRE_CAPACITY,
+};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+const BLOCK_SIZE: usize = 512;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static ARMED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn armed() -> bool {
+    ARMED.try_with(Cell::get).unwrap_or(false)
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if armed() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if armed() {
+            DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if armed() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn assert_no_heap_traffic(label: &str, f: impl FnOnce()) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let deallocations = DEALLOCATIONS.load(Ordering::Relaxed);
+    ARMED.with(|armed| armed.set(true));
+    f();
+    ARMED.with(|armed| armed.set(false));
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let deallocations = DEALLOCATIONS.load(Ordering::Relaxed) - deallocations;
+    assert_eq!(
+        (allocations, deallocations),
+        (0, 0),
+        "{label}: heap allocations and deallocations on the audio thread"
+    );
+}
+
+#[test]
+fn process_and_instrument_replacement_do_not_allocate() {
+    let instruments = InstrumentFactory::new(SAMPLE_RATE);
+    let effects = EffectFactory::new(SAMPLE_RATE);
+    let (retire_sender, mut collector) = retire_channel(DEFAULT_RETIRE_CAPACITY);
+    let (event_sender, events) = event_channel(DEFAULT_EVENT_CAPACITY);
+    let mut engine = Engine::new().with_instrument_capacity(4);
+    engine.set_retire_sender(retire_sender);
+    engine.set_event_sender(event_sender);
+
+    engine.add_instrument(instruments.create_polyphonic_oscillator(1, 0.0, 4));
+    engine.add_instrument(instruments.create_kick_drum(2, 0.0));
+    engine.add_instrument(instruments.create_dfam(3, 0.0));
+    engine.add_instrument(instruments.create_plucked_string(4, 0.0, 4));
+    engine.add_effect_to_instrument(4, effects.create_mono_reverb(1));
+    engine.add_master_effect(effects.create_stereo_reverb(1));
+    engine.add_master_effect(effects.create_stereo_gain(2, 0.8));
+    for (instrument_id, note) in [(1, 60), (1, 64), (2, 36), (3, 48), (4, 55)] {
+        engine.note_on(instrument_id, note, 100);
+    }
+
+    let mut left = vec![0.0; BLOCK_SIZE];
+    let mut right = vec![0.0; BLOCK_SIZE];
+    assert_no_heap_traffic("Engine::process", || {
+        for _ in 0..64 {
+            engine.process(&mut left, &mut right, SAMPLE_RATE);
+        }
+    });
+    assert!(left.iter().any(|sample| *sample != 0.0));
+
+    let mut stems = StemBuses::new(4, BLOCK_SIZE);
+    assert_no_heap_traffic("Engine::process_with_stems", || {
+        engine.process_with_stems(&mut left, &mut right, &mut stems, SAMPLE_RATE);
+    });
+    assert_eq!(stems.len(), 4);
+
+    let replacement = InstrumentCmd::AddInstrument {
+        instrument: instruments.create_kick_drum(1, 0.0),
+    }
+    .into();
+    let orphan_effect = InstrumentCmd::AddEffect {
+        instrument_id: 99,
+        effect: effects.create_mono_gain(3, 0.5),
+    }
+    .into();
+    let rejected = InstrumentCmd::AddInstrument {
+        instrument: instruments.create_hihat(5, 0.0),
+    }
+    .into();
+    let removal = InstrumentCmd::RemoveInstrument { instrument_id: 3 }.into();
+    let unbind = MixerCmd::UnbindParameter { slot: 0 }.into();
+    assert_no_heap_traffic("replacing, removing and clearing instruments", || {
+        engine.handle_command(replacement);
+        engine.handle_command(orphan_effect);
+        engine.handle_command(rejected);
+        engine.handle_command(removal);
+        engine.handle_command(unbind);
+        engine.process(&mut left, &mut right, SAMPLE_RATE);
+        engine.clear_instruments();
+        engine.process(&mut left, &mut right, SAMPLE_RATE);
+    });
+
+    // The replaced oscillator, the orphaned effect, the rejected hi-hat, the
+    // removed DFAM, and three cleared instruments.
+    assert_eq!(collector.collect(), 7);
+    assert_eq!(events.drain().count(), 1);
+    drop(engine);
+    collector.collect();
+    assert!(collector.is_disconnected());
+}
+
+#[test]
+fn a_full_retirement_channel_holds_displaced_instruments_back() {
+    let instruments = InstrumentFactory::new(SAMPLE_RATE);
+    let (retire_sender, mut collector) = retire_channel(1);
+    let mut engine = Engine::new();
+    engine.set_retire_sender(retire_sender);
+    engine.add_instrument(instruments.create_kick_drum(1, 0.0));
+
+    let mut replacements: Vec<_> = (0..3)
+        .map(|_| {
+            InstrumentCmd::AddInstrument {
+                instrument: instruments.create_kick_drum(1, 0.0),
+            }
+            .into()
+        })
+        .collect();
+    let mut left = vec![0.0; BLOCK_SIZE];
+    let mut right = vec![0.0; BLOCK_SIZE];
+    assert_no_heap_traffic("retiring into a full channel", || {
+        for replacement in replacements.drain(..) {
+            engine.handle_command(replacement);
+        }
+        engine.process(&mut left, &mut right, SAMPLE_RATE);
+    });
+
+    // One kick fits in the channel; the other two are resent block by block.
+    for _ in 0..3 {
+        assert_eq!(collector.collect(), 1);
+        assert_no_heap_traffic("resending backlogged retirements", || {
+            engine.process(&mut left, &mut right, SAMPLE_RATE);
+        });
+    }
+    assert_eq!(collector.collect(), 0);
+}
+
+#[test]
+fn capturing_and_restoring_through_commands_do_not_allocate() {
+    let registry = Registry::with_builtins();
+    let instruments = InstrumentFactory::new(SAMPLE_RATE);
+    let effects = EffectFactory::new(SAMPLE_RATE);
+    let (retire_sender, mut collector) = retire_channel(DEFAULT_RETIRE_CAPACITY);
+    let (capture_sender, captures) = capture_channel(1);
+    let mut engine = Engine::new();
+    engine.set_retire_sender(retire_sender);
+    engine.set_capture_sender(capture_sender);
+    let spec = InstrumentSpec {
+        type_id: "kick_drum",
+        version: 1,
+        values: vec![("drive", 0.2)],
+        effects: vec![EffectSpec {
+            type_id: "delay",
+            version: 1,
+            effect_id: 1,
+            values: vec![("mix", 0.3)],
+        }],
+    };
+    engine.add_instrument_from_spec(
+        registry
+            .create_instrument(&instruments, &effects, 1, &spec)
+            .unwrap(),
+        spec.into(),
+    );
+    engine.add_master_effect(effects.create_stereo_gain(2, 0.8));
+
+    let capture = StateCmd::Capture(EngineCapture::new()).into();
+    assert_no_heap_traffic("capturing the engine", || {
+        engine.handle_command(capture);
+    });
+    let snapshot = captures.try_recv().unwrap().into_snapshot().ok().unwrap();
+    assert_eq!(snapshot, engine.snapshot());
+
+    let restore =
+        StateCmd::Restore(snapshot.build(&registry, &instruments, &effects).unwrap()).into();
+    let mut left = vec![0.0; BLOCK_SIZE];
+    let mut right = vec![0.0; BLOCK_SIZE];
+    assert_no_heap_traffic("restoring the engine", || {
+        engine.handle_command(restore);
+        engine.process(&mut left, &mut right, SAMPLE_RATE);
+    });
+    assert_eq!(engine.snapshot(), snapshot);
+    // The kick, its spec, the gain, and the emptied restored engine.
+    assert_eq!(collector.collect(), 4);
+}
@@ -31,6 +31,27 @@ describe("mock EngineClient view", () => {
     expect(screen.getByText("82%")).toBeInTheDocument();
   });
 
+  it("lists sounding voices from voice activity events", async () => {
+    const client = new FakeEngineClient();
+    render(App, { props: { client } });
+
+    expect(screen.getByText("No voices sounding")).toBeInTheDocument();
+
+    client.emitVoiceFrame({
+      active: 2,
+      voices: [
+        { instrumentId: 1, voice: 0, note: 48, stage: "attack", level: 0.3 },
+        { instrumentId: 4, voice: 2, note: 67, stage: "release", level: 0.6 },
+      ],
+    });
+
+    await waitFor(() =>
+      expect(screen.getByLabelText("Active voices")).toHaveTextContent("2"),
+    );
+    expect(screen.getByText("Note 67")).toBeInTheDocument();
+    expect(meter("Instrument 4 voice 2 level").value).toBe(0.6);
+  });
+
   it("sends play, stop, and normalized gain requests to the injected client", async () => {
     const client = new FakeEngineClient();
     render(App, { props: { client } });
@@ -12,10 +12,35 @@ export interface StereoMeterFrame {
   readonly rms: StereoValue;
 }
 
+/** Amplitude envelope stage; "none" for voices without an envelope. */
+export type EnvelopeStage =
+  | "none"
+  | "idle"
+  | "attack"
+  | "decay"
+  | "sustain"
+  | "release";
+
+export interface ActiveVoice {
+  readonly instrumentId: number;
+  /** Voice slot within the instrument; 0 for monophonic instruments. */
+  readonly voice: number;
+  readonly note: number;
+  readonly stage: EnvelopeStage;
+  /** Linear normalized envelope and velocity gain. */
+  readonly level: number;
+}
+
+export interface VoiceActivityFrame {
+  /** Number of sounding voices, which may exceed the listed voices. */
+  readonly active: number;
+  readonly voices: readonly ActiveVoice[];
+}
+
 export type Unsubscribe = () => void;
 
 /**
- * The browser-facing boundary required by the current transport/gain/meter slice.
+ * The browser-facing boundary required by the current transport/gain/meter/voice slice.
  * Host implementations own all process, network, audio-device, and filesystem work.
  */
 export interface EngineClient {
@@ -32,4 +57,6 @@ export interface EngineClient {
   setMasterGain(normalizedGain: number): Promise<void>;
 
   subscribeMeters(listener: (frame: StereoMeterFrame) => void): Unsubscribe;
+
+  subscribeVoices(listener: (frame: VoiceActivityFrame) => void): Unsubscribe;
 }
@@ -56,6 +56,35 @@ describe("FakeEngineClient", () => {
     expect(meterListener).toHaveBeenCalledTimes(2);
   });
 
+  it("publishes voice activity frames until unsubscribed", () => {
+    const client = new FakeEngineClient();
+    const listener = vi.fn();
+    const unsubscribe = client.subscribeVoices(listener);
+    const frame = {
+      active: 1,
+      voices: [
+        {
+          instrumentId: 3,
+          voice: 0,
+          note: 60,
+          stage: "sustain",
+          level: 0.5,
+        },
+      ],
+    } as const;
+
+    expect(listener).toHaveBeenLastCalledWith({ active: 0, voices: [] });
+    client.emitVoiceFrame(frame);
+    expect(listener).toHaveBeenLastCalledWith(frame);
+
+    unsubscribe();
+    client.emitVoiceFrame({ active: 0, voices: [] });
+    expect(listener).toHaveBeenCalledTimes(2);
+    expect(() =>
+      client.emitVoiceFrame({ active: 0, voices: frame.voices }),
+    ).toThrow(RangeError);
+  });
+
   it("rejects invalid fake meter events without notifying listeners", () => {
     const client = new FakeEngineClient();
     const listener = vi.fn();
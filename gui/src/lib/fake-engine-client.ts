@@ -3,6 +3,7 @@ import type {
   EngineClient,
   StereoMeterFrame,
   Unsubscribe,
+  VoiceActivityFrame,
 } from "./engine-client";
 
 export type TransportRequest = "play" | "stop";
@@ -11,6 +12,7 @@ export interface FakeEngineClientOptions {
   connectionStatus?: ConnectionStatus;
   masterGain?: number;
   meterFrame?: StereoMeterFrame;
+  voiceFrame?: VoiceActivityFrame;
 }
 
 const DEFAULT_METER_FRAME: StereoMeterFrame = {
@@ -18,13 +20,17 @@ const DEFAULT_METER_FRAME: StereoMeterFrame = {
   rms: { left: 0, right: 0 },
 };
 
+const DEFAULT_VOICE_FRAME: VoiceActivityFrame = { active: 0, voices: [] };
+
 /** Deterministic in-memory client for browser development and tests. */
 export class FakeEngineClient implements EngineClient {
   #connectionStatus: ConnectionStatus;
   #masterGain: number;
   #meterFrame: StereoMeterFrame;
+  #voiceFrame: VoiceActivityFrame;
   #connectionListeners = new Set<(status: ConnectionStatus) => void>();
   #meterListeners = new Set<(frame: StereoMeterFrame) => void>();
+  #voiceListeners = new Set<(frame: VoiceActivityFrame) => void>();
   #transportRequests: TransportRequest[] = [];
   #gainWrites: number[] = [];
 
@@ -32,8 +38,10 @@ export class FakeEngineClient implements EngineClient {
     this.#connectionStatus = options.connectionStatus ?? "connected";
     this.#masterGain = options.masterGain ?? 0.75;
     this.#meterFrame = options.meterFrame ?? DEFAULT_METER_FRAME;
+    this.#voiceFrame = options.voiceFrame ?? DEFAULT_VOICE_FRAME;
     assertNormalizedGain(this.#masterGain);
     assertMeterFrame(this.#meterFrame);
+    assertVoiceFrame(this.#voiceFrame);
   }
 
   getConnectionStatus(): ConnectionStatus {
@@ -68,6 +76,14 @@ export class FakeEngineClient implements EngineClient {
     return () => this.#meterListeners.delete(listener);
   }
 
+  subscribeVoices(
+    listener: (frame: VoiceActivityFrame) => void,
+  ): Unsubscribe {
+    this.#voiceListeners.add(listener);
+    listener(this.#voiceFrame);
+    return () => this.#voiceListeners.delete(listener);
+  }
+
   /** Fake-only control used to drive deterministic connection changes. */
   setConnectionStatus(status: ConnectionStatus): void {
     this.#connectionStatus = status;
@@ -81,6 +97,13 @@ export class FakeEngineClient implements EngineClient {
     for (const listener of this.#meterListeners) listener(frame);
   }
 
+  /** Fake-only control used to drive deterministic voice activity events. */
+  emitVoiceFrame(frame: VoiceActivityFrame): void {
+    assertVoiceFrame(frame);
+    this.#voiceFrame = frame;
+    for (const listener of this.#voiceListeners) listener(frame);
+  }
+
   get masterGain(): number {
     return this.#masterGain;
   }
@@ -116,3 +139,16 @@ function assertMeterFrame(frame: StereoMeterFrame): void {
     }
   }
 }
+
+function assertVoiceFrame(frame: VoiceActivityFrame): void {
+  if (!Number.isInteger(frame.active) || frame.active < frame.voices.length) {
+    throw new RangeError("active voice count must cover every listed voice");
+  }
+  for (const voice of frame.voices) {
+    if (!Number.isFinite(voice.level) || voice.level < 0 || voice.level > 1) {
+      throw new RangeError(
+        "voice levels must be finite normalized values from 0 to 1",
+      );
+    }
+  }
+}
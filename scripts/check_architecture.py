@@ -80,10 +80,11 @@ ALLOWED = {
     "utils": {"serde", "serde_json"},
     "os_dls": {"riff"},
 }
-STANDALONE_OPTIONAL_DEPENDENCIES = {"cpal", "env_logger", "ringbuf", "rosc", "tokio"}
+STANDALONE_OPTIONAL_DEPENDENCIES = {"clap", "cpal", "env_logger", "ringbuf", "rosc", "tokio"}
 STANDALONE_EXAMPLES = {
     "cycle_waveforms",
     "envelope",
+    "granular_pad",
     "master_gain",
     "meter_listen",
     "osc_control",
@@ -154,9 +155,11 @@ def main() -> int:
 
     audio_backend = package_records.get("audio_backend")
     if audio_backend is not None:
+        # Development dependencies may share a name with an optional one.
         dependency_records = {
             dependency["name"]: dependency
             for dependency in audio_backend["dependencies"]
+            if dependency["kind"] is None
         }
         standalone_feature = set(audio_backend["features"].get("standalone", []))
         for dependency in sorted(STANDALONE_OPTIONAL_DEPENDENCIES):
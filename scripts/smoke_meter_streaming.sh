@@ -3,8 +3,9 @@ set -euo pipefail
 
 # Smoke test for /meter/level streaming (DSP -> GUI), issue #103.
 #
-# Opens the system default audio output device (via dsp-core) but does NOT
-# start playback, so it runs in SILENCE (no audible output). It verifies that
+# Runs dsp-core without an audio device (--null-audio) and does NOT start
+# playback, so it needs no sound hardware. Set DSP_CORE_ARGS="" to use the
+# default output device instead. It verifies that
 # the DSP core streams /meter/level to 127.0.0.1:9001 at ~30 Hz with the
 # expected 4-float layout.
 #
@@ -15,6 +16,7 @@ set -euo pipefail
 READY_TIMEOUT_SECONDS="${READY_TIMEOUT_SECONDS:-20}"
 LISTEN_SECONDS="${LISTEN_SECONDS:-2}"
 LOG_FILE="${LOG_FILE:-$(mktemp -t blight-dsp-core.XXXXXX.log)}"
+DSP_CORE_ARGS="${DSP_CORE_ARGS---null-audio}"
 export RUST_LOG="${RUST_LOG:-info}"
 DSP_PID=""
 
@@ -30,11 +32,12 @@ echo "Starting dsp-core (silent, no playback)..."
 echo "RUST_LOG=${RUST_LOG}"
 echo "Log: ${LOG_FILE}"
 
-cargo run -p audio_backend --bin dsp-core >"${LOG_FILE}" 2>&1 &
+# shellcheck disable=SC2086 # DSP_CORE_ARGS is a list of flags.
+cargo run -p audio_backend --bin dsp-core -- ${DSP_CORE_ARGS} >"${LOG_FILE}" 2>&1 &
 DSP_PID="$!"
 
 for ((i = 0; i < READY_TIMEOUT_SECONDS; i++)); do
-  if grep -q '^READY$' "${LOG_FILE}"; then
+  if grep -q '^READY\b' "${LOG_FILE}"; then
     echo "dsp-core is READY"
     break
   fi
@@ -49,7 +52,7 @@ for ((i = 0; i < READY_TIMEOUT_SECONDS; i++)); do
   sleep 1
 done
 
-if ! grep -q '^READY$' "${LOG_FILE}"; then
+if ! grep -q '^READY\b' "${LOG_FILE}"; then
   echo "Timed out waiting for dsp-core READY after ${READY_TIMEOUT_SECONDS}s" >&2
   echo "--- dsp-core log ---" >&2
   cat "${LOG_FILE}" >&2
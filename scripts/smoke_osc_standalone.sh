@@ -8,10 +8,12 @@ set -euo pipefail
 #   - osc_control:  127.0.0.1:9001
 #
 # It starts dsp-core, waits for READY, sends OSC messages via the example,
-# then shuts dsp-core down.
+# then shuts dsp-core down. Set DSP_CORE_ARGS=--null-audio to run without an
+# audio device, or DSP_CORE_ARGS="--render-to <wav>" to also keep the output.
 
 READY_TIMEOUT_SECONDS="${READY_TIMEOUT_SECONDS:-20}"
 LOG_FILE="${LOG_FILE:-$(mktemp -t blight-dsp-core.XXXXXX.log)}"
+DSP_CORE_ARGS="${DSP_CORE_ARGS:-}"
 export RUST_LOG="${RUST_LOG:-info}"
 DSP_PID=""
 
@@ -27,11 +29,12 @@ echo "Starting dsp-core..."
 echo "RUST_LOG=${RUST_LOG}"
 echo "Log: ${LOG_FILE}"
 
-cargo run -p audio_backend --bin dsp-core >"${LOG_FILE}" 2>&1 &
+# shellcheck disable=SC2086 # DSP_CORE_ARGS is a list of flags.
+cargo run -p audio_backend --bin dsp-core -- ${DSP_CORE_ARGS} >"${LOG_FILE}" 2>&1 &
 DSP_PID="$!"
 
 for ((i = 0; i < READY_TIMEOUT_SECONDS; i++)); do
-  if grep -q '^READY$' "${LOG_FILE}"; then
+  if grep -q '^READY\b' "${LOG_FILE}"; then
     echo "dsp-core is READY"
     break
   fi
@@ -46,7 +49,7 @@ for ((i = 0; i < READY_TIMEOUT_SECONDS; i++)); do
   sleep 1
 done
 
-if ! grep -q '^READY$' "${LOG_FILE}"; then
+if ! grep -q '^READY\b' "${LOG_FILE}"; then
   echo "Timed out waiting for dsp-core READY after ${READY_TIMEOUT_SECONDS}s" >&2
   echo "--- dsp-core log ---" >&2
   cat "${LOG_FILE}" >&2
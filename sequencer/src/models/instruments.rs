@@ -32,6 +32,29 @@ pub struct SimpleOscillatorParams {
     pub audio_effects: Vec<AudioEffect>,
     #[serde(default)]
     pub amp_envelope: AmpEnvelopeParams,
+    #[serde(default)]
+    pub unison: UnisonParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+/// Unison stacking for oscillator instruments. A single voice disables unison.
+pub struct UnisonParams {
+    pub voices: u8,
+    /// Detune of the outermost voices in cents.
+    pub detune_cents: f32,
+    pub detune_curve: DetuneCurve,
+    /// 0.0 restarts every voice at phase zero, 1.0 picks a random start phase per note.
+    pub phase_randomness: f32,
+    /// 0.0 keeps the stack centred, 1.0 spreads the outer voices hard left and right.
+    pub stereo_spread: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq, Eq)]
+/// How detune is distributed across unison voices.
+pub enum DetuneCurve {
+    Linear,
+    Exponential,
+    Logarithmic,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
@@ -40,6 +63,11 @@ pub struct HiHatParams {
     pub audio_effects: Vec<AudioEffect>,
     #[serde(default)]
     pub amp_envelope: AmpEnvelopeParams,
+    /// Sweep of the metallic tone layer.
+    #[serde(default = "PitchEnvelopeParams::flat")]
+    pub pitch_envelope: PitchEnvelopeParams,
+    #[serde(default = "DrumSynthParams::hihat")]
+    pub synthesis: DrumSynthParams,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
@@ -64,6 +92,8 @@ pub struct KickDrumParams {
     pub audio_effects: Vec<AudioEffect>,
     pub amp_envelope: AmpEnvelopeParams,
     pub pitch_envelope: PitchEnvelopeParams,
+    #[serde(default = "DrumSynthParams::kick")]
+    pub synthesis: DrumSynthParams,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
@@ -72,15 +102,266 @@ pub struct SnareDrumParams {
     pub audio_effects: Vec<AudioEffect>,
     #[serde(default)]
     pub amp_envelope: AmpEnvelopeParams,
+    /// Sweep of the tonal body.
+    #[serde(default = "PitchEnvelopeParams::flat")]
+    pub pitch_envelope: PitchEnvelopeParams,
+    #[serde(default = "DrumSynthParams::snare")]
+    pub synthesis: DrumSynthParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+/// Tone/noise shaping shared by the synthesized drums. Each drum has its own defaults,
+/// which reproduce the sound of kits saved before these controls existed.
+pub struct DrumSynthParams {
+    /// Fixed tone frequency in Hz; 0.0 follows the triggering note.
+    pub tone_frequency: f32,
+    /// 0.0 is tone only, 1.0 is noise only.
+    pub noise_mix: f32,
+    /// -1.0 (dark) to 1.0 (bright); 0.0 leaves the noise white.
+    pub noise_color: f32,
+    /// Level of the transient click at the start of each hit.
+    pub click: f32,
+    /// 0.0 is clean, 1.0 is heavy saturation.
+    pub drive: f32,
+}
+
+impl DrumSynthParams {
+    fn with_noise_mix(noise_mix: f32) -> Self {
+        Self {
+            tone_frequency: 0.0,
+            noise_mix,
+            noise_color: 0.0,
+            click: 0.0,
+            drive: 0.0,
+        }
+    }
+
+    pub fn kick() -> Self {
+        Self::with_noise_mix(0.0)
+    }
+
+    pub fn snare() -> Self {
+        Self::with_noise_mix(0.7)
+    }
+
+    pub fn hihat() -> Self {
+        Self::with_noise_mix(1.0)
+    }
+}
+
+impl PitchEnvelopeParams {
+    /// No sweep; used by drums whose pitch envelope was added after their first release.
+    pub fn flat() -> Self {
+        Self {
+            freq_delta: 0.0,
+            decay_time: 0.05,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 /// Parameters for a DFAM (Drummer From Another Mother) style percussion synth.
-/// For now it mirrors the structure of other simple percussion instruments.
 pub struct DFAMParams {
     pub audio_effects: Vec<AudioEffect>,
     #[serde(default)]
     pub amp_envelope: AmpEnvelopeParams,
+    #[serde(default)]
+    pub voice: DFAMVoiceParams,
+    #[serde(default)]
+    pub sequencer: DFAMSequencerParams,
+}
+
+impl DFAMParams {
+    /// The amp envelope the voice plays with. A running sequencer gates the envelope
+    /// once for the whole run and shapes each step with the VCA decay, so the envelope
+    /// holds at full sustain while the sequencer is enabled.
+    pub fn playback_amp_envelope(&self) -> AmpEnvelopeParams {
+        AmpEnvelopeParams {
+            sustain: if self.sequencer.enabled {
+                1.0
+            } else {
+                self.amp_envelope.sustain
+            },
+            ..self.amp_envelope.clone()
+        }
+    }
+}
+
+/// Number of steps in the DFAM sequencer.
+pub const DFAM_STEPS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+/// Oscillator, filter and decay settings of the DFAM voice.
+pub struct DFAMVoiceParams {
+    /// Depth of the VCO 2 to VCO 1 linear FM.
+    pub fm_amount: f32,
+    pub vco_decay: f32,
+    /// Pitch sweep depth of the VCO envelope in semitones.
+    pub vco_env_amount: f32,
+    pub vcf_cutoff: f32,
+    /// 0.0 to 4.0.
+    pub vcf_resonance: f32,
+    pub vcf_decay: f32,
+    /// Cutoff sweep depth of the VCF envelope in octaves.
+    pub vcf_env_amount: f32,
+    /// Per-step amplitude decay, used while the sequencer is enabled.
+    pub vca_decay: f32,
+}
+
+impl Default for DFAMVoiceParams {
+    fn default() -> Self {
+        Self {
+            fm_amount: 0.0,
+            vco_decay: 0.15,
+            vco_env_amount: 0.0,
+            vcf_cutoff: 500.0,
+            vcf_resonance: 0.5,
+            vcf_decay: 0.2,
+            vcf_env_amount: 0.0,
+            vca_decay: 0.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+/// The DFAM's built-in 8-step sequencer. While enabled, a note starts the sequence
+/// transposed to that note and note-off stops it.
+pub struct DFAMSequencerParams {
+    pub enabled: bool,
+    pub clock: SequencerClock,
+    /// Pitch offset of each step in semitones.
+    pub step_pitches: [f32; DFAM_STEPS],
+    /// Level of each step, 0.0 to 1.0.
+    pub step_velocities: [f32; DFAM_STEPS],
+}
+
+impl Default for DFAMSequencerParams {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            clock: SequencerClock::default(),
+            step_pitches: [0.0; DFAM_STEPS],
+            step_velocities: [1.0; DFAM_STEPS],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq)]
+/// How the DFAM sequencer advances.
+pub enum SequencerClock {
+    /// Follows the song tempo.
+    Tempo { steps_per_beat: f32 },
+    /// Runs at a fixed rate.
+    Free { steps_per_second: f32 },
+}
+
+impl Default for SequencerClock {
+    fn default() -> Self {
+        SequencerClock::Tempo {
+            steps_per_beat: 4.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+/// Parameters for a Karplus-Strong plucked string.
+pub struct PluckedStringParams {
+    pub audio_effects: Vec<AudioEffect>,
+    /// 0.0 keeps every harmonic ringing, 1.0 is the classic two-point average.
+    pub damping: f32,
+    /// 0.0 plucks with a soft, dark excitation, 1.0 with raw white noise.
+    pub brightness: f32,
+    /// Relative pick point along the string, 0.0 at the bridge, 0.5 in the middle.
+    pub pick_position: f32,
+    /// Seconds for a held note to decay by 60 dB.
+    pub decay: f32,
+    #[serde(default = "default_plucked_string_polyphony")]
+    pub polyphony: u8,
+}
+
+fn default_plucked_string_polyphony() -> u8 {
+    8
+}
+
+impl Default for PluckedStringParams {
+    fn default() -> Self {
+        Self {
+            audio_effects: Vec::new(),
+            damping: 0.5,
+            brightness: 0.7,
+            pick_position: 0.2,
+            decay: 2.0,
+            polyphony: default_plucked_string_polyphony(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+/// Parameters for a granular sampler playing one sample from the song's sample bank.
+pub struct GranularParams {
+    pub audio_effects: Vec<AudioEffect>,
+    #[serde(default = "default_granular_amp_envelope")]
+    pub amp_envelope: AmpEnvelopeParams,
+    /// Index of the sample in the song's sample bank.
+    pub sample: u8,
+    #[serde(default = "default_granular_polyphony")]
+    pub polyphony: u8,
+    #[serde(default)]
+    pub window: GrainWindow,
+    /// Normalised read position in the sample, 0.0 (start) to 1.0 (end).
+    pub position: f32,
+    /// Random offset around `position` as a fraction of the sample length.
+    pub spray: f32,
+    /// Grain length in seconds.
+    pub grain_size: f32,
+    /// Grains started per second.
+    pub density: f32,
+    /// Transposition in semitones on top of the played note.
+    pub pitch: f32,
+    /// Probability (0.0..1.0) that a grain plays its slice backwards.
+    pub reverse_probability: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Encode, Decode, PartialEq, Eq)]
+/// Amplitude window applied over the lifetime of each grain.
+pub enum GrainWindow {
+    #[default]
+    Hann,
+    Triangle,
+    /// Linear fades over the first and last quarter with a flat top.
+    Trapezoid,
+    Gaussian,
+}
+
+fn default_granular_amp_envelope() -> AmpEnvelopeParams {
+    AmpEnvelopeParams {
+        attack: 0.3,
+        decay: 0.5,
+        sustain: 1.0,
+        release: 1.5,
+    }
+}
+
+fn default_granular_polyphony() -> u8 {
+    4
+}
+
+impl Default for GranularParams {
+    fn default() -> Self {
+        Self {
+            audio_effects: Vec::new(),
+            amp_envelope: default_granular_amp_envelope(),
+            sample: 0,
+            polyphony: default_granular_polyphony(),
+            window: GrainWindow::default(),
+            position: 0.5,
+            spray: 0.05,
+            grain_size: 0.08,
+            density: 40.0,
+            pitch: 0.0,
+            reverse_probability: 0.0,
+        }
+    }
 }
 
 impl Default for AmpEnvelopeParams {
@@ -94,6 +375,18 @@ impl Default for AmpEnvelopeParams {
     }
 }
 
+impl Default for UnisonParams {
+    fn default() -> Self {
+        Self {
+            voices: 1,
+            detune_cents: 10.0,
+            detune_curve: DetuneCurve::Linear,
+            phase_randomness: 1.0,
+            stereo_spread: 0.5,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Encode, Decode, PartialEq, Eq)]
 /// Waveform types for the simple oscillator.
 pub enum Waveform {
@@ -115,6 +408,8 @@ pub enum InstrumentData {
     KickDrum(KickDrumParams),
     SnareDrum(SnareDrumParams),
     DFAM(DFAMParams),
+    PluckedString(PluckedStringParams),
+    Granular(GranularParams),
     // This can be extended in the future, e.g., for FM synthesis.
 }
 
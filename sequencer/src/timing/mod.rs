@@ -53,6 +53,15 @@ impl TimingState {
         self.samples_until_next_tick = self.tick_duration_samples;
     }
 
+    /// Switches to a new output rate, keeping the position within the current
+    /// tick so playback continues at the same musical time.
+    pub fn set_sample_rate(&mut self, new_sample_rate: f64) {
+        let scale = new_sample_rate / self.sample_rate;
+        self.sample_rate = new_sample_rate;
+        self.tick_duration_samples *= scale;
+        self.samples_until_next_tick *= scale;
+    }
+
     /// Sets a new TPL.
     pub fn set_tpl(&mut self, new_tpl: u32) {
         self.tpl = new_tpl;
@@ -139,6 +148,21 @@ mod tests {
         assert_eq!(ticks, 1);
     }
 
+    #[test]
+    fn test_sample_rate_change_keeps_tick_position() {
+        let mut timing_state = TimingState::new(48000.0); // 960 samples/tick
+
+        // Three quarters of the way through a tick
+        assert_eq!(timing_state.advance(720), 0);
+
+        timing_state.set_sample_rate(96000.0); // 1920 samples/tick
+
+        // The remaining quarter tick is now 480 samples long
+        assert_eq!(timing_state.advance(479), 0);
+        assert_eq!(timing_state.advance(1), 1);
+        assert_eq!(timing_state.advance(1920), 1);
+    }
+
     #[test]
     fn test_small_advances_accumulate() {
         let sample_rate = 48000.0;
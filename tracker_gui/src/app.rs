@@ -14,7 +14,9 @@ use crate::tabs::{
     CurrentTab, arrangement::ArrangementTab, chains::ChainsTab, phrases::PhrasesTab,
 };
 use crate::theme::ThemeManager;
-use crate::ui_components::{SongInfoEditor, TabSelector};
+use crate::ui_components::{
+    SongInfoEditor, TabSelector, show_recording_status, show_voice_activity,
+};
 use crate::ui_state::UiState;
 
 struct ThemeFeedback {
@@ -169,6 +171,15 @@ impl TrackerApp {
             self.audio_manager.toggle_looping();
         }
 
+        if actions.toggle_recording {
+            let (status, _) = self.audio_manager.recording_status();
+            if status.recording {
+                self.audio_manager.stop_recording();
+            } else if let Some(path) = FileOperations::choose_recording_path(&self.song) {
+                self.audio_manager.start_recording(&self.song, &path);
+            }
+        }
+
         if actions.show_shortcuts {
             self.show_shortcuts_window = true;
         }
@@ -232,19 +243,35 @@ impl Default for TrackerApp {
 impl eframe::App for TrackerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.prune_theme_feedback();
+        self.audio_manager.report_engine_events();
         self.handle_shortcuts(ctx);
 
+        let (recording, sample_rate) = self.audio_manager.recording_status();
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             let actions = MenuRenderer::show_menu_bar(
                 ui,
                 ctx,
                 self.audio_manager.is_playing,
                 self.audio_manager.loop_enabled,
+                recording.recording,
                 &self.theme_manager,
             );
             self.handle_menu_actions(actions, ctx);
         });
 
+        let voices = self.audio_manager.voice_snapshot();
+        egui::TopBottomPanel::bottom("voice_activity_panel").show(ctx, |ui| {
+            if recording.recording {
+                show_recording_status(ui, &recording, sample_rate);
+            }
+            show_voice_activity(ui, &voices);
+        });
+        if self.audio_manager.is_playing || voices.active > 0 || recording.recording {
+            // Keep the voice strip and recording time moving while the engine
+            // renders.
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading(format!(
                 "Blight Tracker — {}",
@@ -1,6 +1,10 @@
 use crate::instrument_manager::backend::hydrate_instrument;
-use audio_backend::{BlightAudio, SequencerCmd, TransportCmd};
+use audio_backend::{
+    BlightAudio, EngineEvent, RecordingStatus, SequencerCmd, TransportCmd, VoiceSnapshot,
+    id::InstrumentId,
+};
 use sequencer::models::Song;
+use std::path::Path;
 use std::sync::Arc;
 
 // Tracker GUI reuses a single effect id until proper routing is needed.
@@ -104,6 +108,41 @@ impl AudioManager {
         self.set_looping(enabled);
     }
 
+    /// Starts recording the master output to `path`, starting audio first if
+    /// needed. Everything heard is recorded, including live tweaks.
+    pub fn start_recording(&mut self, song: &Song, path: &Path) {
+        self.init_audio(song);
+
+        if let Some(audio) = &mut self.audio
+            && let Err(e) = audio.start_recording(path)
+        {
+            log::error!("Failed to start recording: {e:#}");
+        }
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(audio) = &mut self.audio {
+            match audio.stop_recording() {
+                Ok(report) if report.dropped_frames > 0 => log::warn!(
+                    "Recording {} dropped {} frames; the disk could not keep up",
+                    report.path.display(),
+                    report.dropped_frames
+                ),
+                Ok(_) => {}
+                Err(e) => log::error!("Failed to stop recording: {e:#}"),
+            }
+        }
+    }
+
+    /// Progress of the current recording and the output sample rate it is
+    /// recorded at.
+    pub fn recording_status(&self) -> (RecordingStatus, u32) {
+        self.audio
+            .as_ref()
+            .map(|audio| (audio.recording_status(), audio.output_info().sample_rate))
+            .unwrap_or_default()
+    }
+
     /// Sends a command to the audio thread via `BlightAudio::send_command`.
     /// UI systems should call this instead of touching the backend directly so
     /// every update flows through the same queue.
@@ -113,6 +152,56 @@ impl AudioManager {
         }
     }
 
+    /// Logs rejections reported by the audio engine, e.g. an instrument that did
+    /// not fit in the engine's instrument bank, and recordings that stopped on a
+    /// write error. Called once per UI frame.
+    pub fn report_engine_events(&self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        for event in audio.engine_events() {
+            match event {
+                EngineEvent::InstrumentRejected {
+                    instrument_id,
+                    capacity,
+                } => log::warn!(
+                    "Instrument {instrument_id} was not loaded: the engine holds at most {capacity} instruments"
+                ),
+                EngineEvent::InstrumentNotFound { instrument_id } => {
+                    log::debug!("Instrument {instrument_id} was not installed in the engine")
+                }
+                EngineEvent::ParameterBindingRejected { slot, slot_count } => log::warn!(
+                    "Parameter slot {slot} was not bound: the parameter bank has {slot_count} slots"
+                ),
+                EngineEvent::ParameterNotFound { owner, param_id } => {
+                    log::warn!(
+                        "Parameter {param_id} of {owner:?} was not bound: it is not installed"
+                    )
+                }
+            }
+        }
+        if let Some(failure) = audio.take_recording_failure() {
+            log::error!("Recording stopped: {failure}");
+        }
+    }
+
+    /// Ids of the instruments the engine has installed, or `None` before audio
+    /// starts.
+    pub fn installed_instrument_ids(&self) -> Option<Vec<InstrumentId>> {
+        self.audio
+            .as_ref()
+            .map(|audio| audio.installed_instrument_ids())
+    }
+
+    /// Voices the engine rendered in its latest block, or none before audio
+    /// starts.
+    pub fn voice_snapshot(&self) -> VoiceSnapshot {
+        self.audio
+            .as_ref()
+            .map(|audio| audio.voice_activity().snapshot())
+            .unwrap_or_default()
+    }
+
     /// Rebuilds the backend instruments/effects from the current `Song` data.
     /// Used when the app starts, when a song is loaded, or whenever we need to
     /// guarantee the mixer mirrors the editor state.
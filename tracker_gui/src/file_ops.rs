@@ -1,6 +1,7 @@
 use sequencer::cli::FileFormat;
 use sequencer::models::Song;
 use sequencer::project::{open_song_from_file, write_song_to_file};
+use std::path::PathBuf;
 
 pub struct FileOperations;
 
@@ -58,6 +59,14 @@ impl FileOperations {
         }
     }
 
+    /// Asks where to record the output, suggesting a file named after the song.
+    pub fn choose_recording_path(song: &Song) -> Option<PathBuf> {
+        rfd::FileDialog::new()
+            .add_filter("WAV files", &["wav"])
+            .set_file_name(format!("{}.wav", song.name))
+            .save_file()
+    }
+
     pub fn new_song() -> Song {
         Song::new("New Song")
     }
@@ -1,19 +1,28 @@
 use eframe::egui;
 use sequencer::models::{
     AmpEnvelopeParams, HiHatParams, Instrument, InstrumentData, KickDrumParams,
-    SimpleOscillatorParams, SnareDrumParams, Song, Waveform,
+    PluckedStringParams, SimpleOscillatorParams, SnareDrumParams, Song, UnisonParams, Waveform,
 };
+use std::time::Duration;
 
 use crate::audio::AudioManager;
 use crate::ui_components::{
-    DelayDefaults, EffectPanelConfig, ReverbDefaults, show_amp_envelope_editor, show_effect_panels,
+    DelayDefaults, EffectPanelConfig, ReverbDefaults, show_amp_envelope_editor, show_dfam_editor,
+    show_drum_editor, show_effect_panels, show_unison_editor,
 };
 
 pub mod backend;
-use backend::{ensure_backend_instrument, send_amp_envelope_to_backend};
+use backend::{
+    ensure_backend_instrument, remove_backend_instrument, send_amp_envelope_to_backend,
+    send_parameters_to_backend,
+};
 mod sync;
 use sync::InstrumentSync;
 
+/// How often the window refreshes while an instrument is not loaded, so that
+/// it notices the engine installing it.
+const INSTALLED_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 struct InstrumentUiMetadata {
     label: &'static str,
     ui_prefix: &'static str,
@@ -61,6 +70,12 @@ const SNARE_UI: InstrumentUiMetadata = InstrumentUiMetadata::new(
     Some(ReverbDefaults::new(0.25, 0.4, 1.0, 1.0, 0.3)),
     Some(DelayDefaults::new(0.2, 2, 0.25, 0.2)),
 );
+const PLUCK_UI: InstrumentUiMetadata = InstrumentUiMetadata::new(
+    "Plucked String",
+    "pluck",
+    Some(ReverbDefaults::new(0.3, 0.6, 1.0, 1.0, 0.2)),
+    Some(DelayDefaults::new(0.3, 3, 0.3, 0.35)),
+);
 const DFAM_UI: InstrumentUiMetadata = InstrumentUiMetadata::new(
     "DFAM",
     "dfam",
@@ -81,6 +96,17 @@ fn show_envelope_and_effects(
         send_amp_envelope_to_backend(audio_mgr, inst_id as u8, env)
     });
     ui.separator();
+    show_effects(ui, meta, inst_id, effects, audio_mgr, sync);
+}
+
+fn show_effects(
+    ui: &mut egui::Ui,
+    meta: &InstrumentUiMetadata,
+    inst_id: usize,
+    effects: &mut Vec<sequencer::models::AudioEffect>,
+    audio_mgr: &mut AudioManager,
+    sync: &mut InstrumentSync,
+) {
     ui.label("Effects:");
     show_effect_panels(
         ui,
@@ -102,6 +128,36 @@ pub struct InstrumentManagerWindow {
     sync: InstrumentSync,
 }
 
+/// Returns whether a setting changed.
+fn show_plucked_string_controls(ui: &mut egui::Ui, params: &mut PluckedStringParams) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Damping");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.damping, 0.0..=1.0))
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Brightness");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.brightness, 0.0..=1.0))
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Pick position");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.pick_position, 0.0..=0.5))
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Decay");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.decay, 0.05..=10.0).suffix(" s"))
+            .changed();
+    });
+    changed
+}
+
 fn waveform_display_name(w: Waveform) -> &'static str {
     match w {
         Waveform::Sine => "Sine",
@@ -131,6 +187,9 @@ impl InstrumentManagerWindow {
         let mut to_add_kick = false;
         let mut to_add_snare = false;
         let mut to_add_dfam = false;
+        let mut to_add_pluck = false;
+        let mut to_remove = None;
+        let installed = audio_mgr.installed_instrument_ids();
         egui::Window::new("Instruments")
             .open(&mut self.open)
             .resizable(true)
@@ -151,6 +210,9 @@ impl InstrumentManagerWindow {
                     if ui.button("Add DFAM").clicked() {
                         to_add_dfam = true;
                     }
+                    if ui.button("Add Pluck").clicked() {
+                        to_add_pluck = true;
+                    }
                 });
 
                 ui.separator();
@@ -163,7 +225,23 @@ impl InstrumentManagerWindow {
                             ui.horizontal(|ui| {
                                 ui.label(format!("ID {:02X}", inst.id as u8));
                                 ui.text_edit_singleline(&mut inst.name);
+                                // The engine rejects instruments beyond its
+                                // capacity; offer to retry once one is removed.
+                                let loaded = installed
+                                    .as_ref()
+                                    .is_none_or(|ids| ids.contains(&(inst.id as u8 as u32)));
+                                if !loaded {
+                                    ui.label("Not loaded");
+                                    if ui.button("Load").clicked() {
+                                        self.sync.queue_rehydrate(inst.id as u8);
+                                    }
+                                    ctx.request_repaint_after(INSTALLED_POLL_INTERVAL);
+                                }
+                                if ui.button("Remove").clicked() {
+                                    to_remove = Some(inst.id);
+                                }
                             });
+                            let mut parameters_changed = false;
                             match &mut inst.data {
                                 InstrumentData::SimpleOscillator(params) => {
                                     let meta = &OSCILLATOR_UI;
@@ -196,6 +274,19 @@ impl InstrumentManagerWindow {
                                             self.sync.queue_rehydrate(inst.id as u8);
                                         }
                                     });
+                                    show_unison_editor(
+                                        ui,
+                                        &mut params.unison,
+                                        inst.id,
+                                        meta.ui_prefix,
+                                        |_, voices_changed| {
+                                            if voices_changed {
+                                                self.sync.queue_rehydrate(inst.id as u8);
+                                            } else {
+                                                parameters_changed = true;
+                                            }
+                                        },
+                                    );
                                     ui.separator();
                                     show_envelope_and_effects(
                                         ui,
@@ -210,6 +301,14 @@ impl InstrumentManagerWindow {
                                 InstrumentData::HiHat(params) => {
                                     let meta = &HIHAT_UI;
                                     ui.label(meta.label);
+                                    show_drum_editor(
+                                        ui,
+                                        &mut params.pitch_envelope,
+                                        &mut params.synthesis,
+                                        inst.id,
+                                        meta.ui_prefix,
+                                        |_, _| parameters_changed = true,
+                                    );
                                     ui.separator();
                                     show_envelope_and_effects(
                                         ui,
@@ -224,6 +323,14 @@ impl InstrumentManagerWindow {
                                 InstrumentData::KickDrum(params) => {
                                     let meta = &KICK_UI;
                                     ui.label(meta.label);
+                                    show_drum_editor(
+                                        ui,
+                                        &mut params.pitch_envelope,
+                                        &mut params.synthesis,
+                                        inst.id,
+                                        meta.ui_prefix,
+                                        |_, _| parameters_changed = true,
+                                    );
                                     ui.separator();
                                     show_envelope_and_effects(
                                         ui,
@@ -238,6 +345,14 @@ impl InstrumentManagerWindow {
                                 InstrumentData::SnareDrum(params) => {
                                     let meta = &SNARE_UI;
                                     ui.label(meta.label);
+                                    show_drum_editor(
+                                        ui,
+                                        &mut params.pitch_envelope,
+                                        &mut params.synthesis,
+                                        inst.id,
+                                        meta.ui_prefix,
+                                        |_, _| parameters_changed = true,
+                                    );
                                     ui.separator();
                                     show_envelope_and_effects(
                                         ui,
@@ -252,12 +367,47 @@ impl InstrumentManagerWindow {
                                 InstrumentData::DFAM(params) => {
                                     let meta = &DFAM_UI;
                                     ui.label(meta.label);
+                                    let was_sequencing = params.sequencer.enabled;
+                                    show_dfam_editor(ui, params, inst.id, meta.ui_prefix, |_| {
+                                        parameters_changed = true
+                                    });
                                     ui.separator();
-                                    show_envelope_and_effects(
+                                    let mut envelope_changed = false;
+                                    show_amp_envelope_editor(
+                                        ui,
+                                        &mut params.amp_envelope,
+                                        inst.id,
+                                        meta.ui_prefix,
+                                        |_| envelope_changed = true,
+                                    );
+                                    if envelope_changed
+                                        || params.sequencer.enabled != was_sequencing
+                                    {
+                                        send_amp_envelope_to_backend(
+                                            audio_mgr,
+                                            inst.id as u8,
+                                            &params.playback_amp_envelope(),
+                                        );
+                                    }
+                                    ui.separator();
+                                    show_effects(
+                                        ui,
+                                        meta,
+                                        inst.id,
+                                        &mut params.audio_effects,
+                                        audio_mgr,
+                                        &mut self.sync,
+                                    );
+                                }
+                                InstrumentData::PluckedString(params) => {
+                                    let meta = &PLUCK_UI;
+                                    ui.label(meta.label);
+                                    parameters_changed |= show_plucked_string_controls(ui, params);
+                                    ui.separator();
+                                    show_effects(
                                         ui,
                                         meta,
                                         inst.id,
-                                        &mut params.amp_envelope,
                                         &mut params.audio_effects,
                                         audio_mgr,
                                         &mut self.sync,
@@ -267,10 +417,17 @@ impl InstrumentManagerWindow {
                                     ui.label("Instrument editing not yet supported for this type.");
                                 }
                             }
+                            if parameters_changed {
+                                send_parameters_to_backend(audio_mgr, inst.id as u8, &inst.data);
+                            }
                         });
                     }
                 }
             });
+        if let Some(id) = to_remove {
+            song.instrument_bank.retain(|inst| inst.id != id);
+            remove_backend_instrument(audio_mgr, id as u8);
+        }
         if to_add_osc {
             let id = Self::next_free_instrument_id(song) as usize;
             song.instrument_bank.push(Instrument {
@@ -285,6 +442,7 @@ impl InstrumentManagerWindow {
                         sustain: 0.8,
                         release: 0.2,
                     },
+                    unison: UnisonParams::default(),
                 }),
             });
             if let Some(inst) = song.instrument_bank.last() {
@@ -304,6 +462,8 @@ impl InstrumentManagerWindow {
                         sustain: 0.0,
                         release: 0.15,
                     },
+                    pitch_envelope: sequencer::models::PitchEnvelopeParams::flat(),
+                    synthesis: sequencer::models::DrumSynthParams::hihat(),
                 }),
             });
             if let Some(inst) = song.instrument_bank.last() {
@@ -327,6 +487,7 @@ impl InstrumentManagerWindow {
                         freq_delta: -100.0,
                         decay_time: 0.05,
                     },
+                    synthesis: sequencer::models::DrumSynthParams::kick(),
                 }),
             });
             if let Some(inst) = song.instrument_bank.last() {
@@ -346,6 +507,8 @@ impl InstrumentManagerWindow {
                         sustain: 0.0,
                         release: 0.3,
                     },
+                    pitch_envelope: sequencer::models::PitchEnvelopeParams::flat(),
+                    synthesis: sequencer::models::DrumSynthParams::snare(),
                 }),
             });
             if let Some(inst) = song.instrument_bank.last() {
@@ -365,12 +528,25 @@ impl InstrumentManagerWindow {
                         sustain: 0.0,
                         release: 0.15,
                     },
+                    voice: Default::default(),
+                    sequencer: Default::default(),
                 }),
             });
             if let Some(inst) = song.instrument_bank.last() {
                 ensure_backend_instrument(audio_mgr, inst.id as u8, &inst.data);
             }
         }
+        if to_add_pluck {
+            let id = Self::next_free_instrument_id(song) as usize;
+            song.instrument_bank.push(Instrument {
+                id,
+                name: format!("Pluck {:02X}", id as u8),
+                data: InstrumentData::PluckedString(PluckedStringParams::default()),
+            });
+            if let Some(inst) = song.instrument_bank.last() {
+                ensure_backend_instrument(audio_mgr, inst.id as u8, &inst.data);
+            }
+        }
         self.sync.apply_pending(song, audio_mgr);
     }
 }
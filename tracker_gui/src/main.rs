@@ -1,6 +1,5 @@
 mod app;
 mod audio;
-mod audio_utils;
 mod file_ops;
 mod instrument_manager;
 mod menu;
@@ -10,6 +10,7 @@ pub struct MenuActions {
     pub quit: bool,
     pub toggle_playback: bool,
     pub toggle_looping: bool,
+    pub toggle_recording: bool,
     pub show_instrument_manager: bool,
     pub show_shortcuts: bool,
     pub toggle_theme: bool,
@@ -25,6 +26,7 @@ impl MenuRenderer {
         ctx: &egui::Context,
         is_playing: bool,
         loop_enabled: bool,
+        is_recording: bool,
         theme_manager: &ThemeManager,
     ) -> MenuActions {
         let mut actions = MenuActions::default();
@@ -79,6 +81,18 @@ impl MenuRenderer {
                     actions.toggle_looping = true;
                     ui.close();
                 }
+
+                ui.separator();
+
+                let record_text = if is_recording {
+                    "⏹ Stop Recording"
+                } else {
+                    "⏺ Record…"
+                };
+                if ui.button(record_text).clicked() {
+                    actions.toggle_recording = true;
+                    ui.close();
+                }
             });
 
             // Instruments menu
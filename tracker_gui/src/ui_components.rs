@@ -11,6 +11,21 @@ pub use effect_controls::{DelayDefaults, EffectPanelConfig, ReverbDefaults, show
 pub mod envelope;
 pub use envelope::show_amp_envelope_editor;
 
+pub mod dfam;
+pub use dfam::show_dfam_editor;
+
+pub mod drum;
+pub use drum::show_drum_editor;
+
+pub mod unison;
+pub use unison::show_unison_editor;
+
+pub mod voices;
+pub use voices::show_voice_activity;
+
+pub mod recording;
+pub use recording::show_recording_status;
+
 pub struct SongInfoEditor;
 
 impl SongInfoEditor {
@@ -0,0 +1,125 @@
+use eframe::egui;
+use sequencer::models::{DFAM_STEPS, DFAMParams, SequencerClock};
+
+/// Voice and step sequencer editor for DFAM instruments.
+pub fn show_dfam_editor(
+    ui: &mut egui::Ui,
+    params: &mut DFAMParams,
+    instrument_id: usize,
+    ui_prefix: &'static str,
+    mut on_change: impl FnMut(&DFAMParams),
+) {
+    ui.push_id((ui_prefix, instrument_id as u32, "dfam"), |ui| {
+        let mut changed = false;
+        egui::CollapsingHeader::new("Voice")
+            .id_salt((ui_prefix, instrument_id as u32, "dfam_voice_hdr"))
+            .default_open(true)
+            .show(ui, |ui| {
+                let voice = &mut params.voice;
+                let mut slider = |ui: &mut egui::Ui, label: &str, slider: egui::Slider| {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        changed |= ui.add(slider).changed();
+                    });
+                };
+                slider(ui, "FM", egui::Slider::new(&mut voice.fm_amount, 0.0..=4.0));
+                slider(
+                    ui,
+                    "VCO decay",
+                    egui::Slider::new(&mut voice.vco_decay, 0.005..=2.0).suffix(" s"),
+                );
+                slider(
+                    ui,
+                    "VCO EG",
+                    egui::Slider::new(&mut voice.vco_env_amount, -48.0..=48.0).suffix(" st"),
+                );
+                slider(
+                    ui,
+                    "Cutoff",
+                    egui::Slider::new(&mut voice.vcf_cutoff, 20.0..=16000.0)
+                        .logarithmic(true)
+                        .suffix(" Hz"),
+                );
+                slider(
+                    ui,
+                    "Resonance",
+                    egui::Slider::new(&mut voice.vcf_resonance, 0.0..=4.0),
+                );
+                slider(
+                    ui,
+                    "VCF decay",
+                    egui::Slider::new(&mut voice.vcf_decay, 0.005..=2.0).suffix(" s"),
+                );
+                slider(
+                    ui,
+                    "VCF EG",
+                    egui::Slider::new(&mut voice.vcf_env_amount, -6.0..=6.0).suffix(" oct"),
+                );
+                slider(
+                    ui,
+                    "VCA decay",
+                    egui::Slider::new(&mut voice.vca_decay, 0.005..=2.0).suffix(" s"),
+                );
+            });
+
+        egui::CollapsingHeader::new("Sequencer")
+            .id_salt((ui_prefix, instrument_id as u32, "dfam_seq_hdr"))
+            .default_open(true)
+            .show(ui, |ui| {
+                let sequencer = &mut params.sequencer;
+                changed |= ui.checkbox(&mut sequencer.enabled, "Enabled").changed();
+
+                let synced = matches!(sequencer.clock, SequencerClock::Tempo { .. });
+                ui.horizontal(|ui| {
+                    if ui.radio(synced, "Tempo").clicked() && !synced {
+                        sequencer.clock = SequencerClock::default();
+                        changed = true;
+                    }
+                    if ui.radio(!synced, "Free").clicked() && synced {
+                        sequencer.clock = SequencerClock::Free {
+                            steps_per_second: 8.0,
+                        };
+                        changed = true;
+                    }
+                    match &mut sequencer.clock {
+                        SequencerClock::Tempo { steps_per_beat } => {
+                            changed |= ui
+                                .add(egui::Slider::new(steps_per_beat, 0.25..=8.0).suffix(" /beat"))
+                                .changed();
+                        }
+                        SequencerClock::Free { steps_per_second } => {
+                            changed |= ui
+                                .add(egui::Slider::new(steps_per_second, 0.1..=50.0).suffix(" /s"))
+                                .changed();
+                        }
+                    }
+                });
+
+                egui::Grid::new((ui_prefix, instrument_id as u32, "dfam_steps")).show(ui, |ui| {
+                    ui.label("Step");
+                    for step in 0..DFAM_STEPS {
+                        ui.label(format!("{}", step + 1));
+                    }
+                    ui.end_row();
+                    ui.label("Pitch");
+                    for pitch in sequencer.step_pitches.iter_mut() {
+                        changed |= ui
+                            .add(egui::DragValue::new(pitch).range(-24.0..=24.0).speed(0.1))
+                            .changed();
+                    }
+                    ui.end_row();
+                    ui.label("Velocity");
+                    for velocity in sequencer.step_velocities.iter_mut() {
+                        changed |= ui
+                            .add(egui::DragValue::new(velocity).range(0.0..=1.0).speed(0.01))
+                            .changed();
+                    }
+                    ui.end_row();
+                });
+            });
+
+        if changed {
+            on_change(params);
+        }
+    });
+}
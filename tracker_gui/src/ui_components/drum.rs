@@ -0,0 +1,76 @@
+use eframe::egui;
+use sequencer::models::{DrumSynthParams, PitchEnvelopeParams};
+
+/// Tone, noise and pitch sweep controls shared by the kick, snare and hi-hat editors.
+pub fn show_drum_editor(
+    ui: &mut egui::Ui,
+    pitch_envelope: &mut PitchEnvelopeParams,
+    synthesis: &mut DrumSynthParams,
+    instrument_id: usize,
+    ui_prefix: &'static str,
+    mut on_change: impl FnMut(&PitchEnvelopeParams, &DrumSynthParams),
+) {
+    ui.push_id((ui_prefix, instrument_id as u32, "drum"), |ui| {
+        egui::CollapsingHeader::new("Drum Synthesis")
+            .id_salt((ui_prefix, instrument_id as u32, "drum_hdr"))
+            .default_open(true)
+            .show(ui, |ui| {
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Tone freq");
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut synthesis.tone_frequency, 0.0..=2000.0)
+                                .suffix(" Hz"),
+                        )
+                        .on_hover_text("0 follows the played note")
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Noise mix");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut synthesis.noise_mix, 0.0..=1.0))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Noise color");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut synthesis.noise_color, -1.0..=1.0))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Click");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut synthesis.click, 0.0..=1.0))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Drive");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut synthesis.drive, 0.0..=1.0))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Pitch sweep");
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut pitch_envelope.freq_delta, -1000.0..=1000.0)
+                                .suffix(" Hz"),
+                        )
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Pitch decay");
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut pitch_envelope.decay_time, 0.001..=1.0)
+                                .suffix(" s"),
+                        )
+                        .changed();
+                });
+                if changed {
+                    on_change(pitch_envelope, synthesis);
+                }
+            });
+    });
+}
@@ -0,0 +1,21 @@
+use audio_backend::RecordingStatus;
+use eframe::egui;
+
+/// One-line recording indicator: elapsed time, plus the frames dropped so far
+/// when the writer could not keep up.
+pub fn show_recording_status(ui: &mut egui::Ui, status: &RecordingStatus, sample_rate: u32) {
+    let seconds = status.frames / u64::from(sample_rate.max(1));
+    ui.horizontal(|ui| {
+        ui.colored_label(
+            egui::Color32::from_rgb(255, 90, 90),
+            format!("⏺ REC {}:{:02}", seconds / 60, seconds % 60),
+        );
+        if status.dropped_frames > 0 {
+            ui.separator();
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 200, 120),
+                format!("{} frames dropped", status.dropped_frames),
+            );
+        }
+    });
+}
@@ -0,0 +1,81 @@
+use eframe::egui;
+use sequencer::models::{DetuneCurve, UnisonParams};
+
+/// Upper bound mirrored from the DSP unison stack.
+const MAX_UNISON_VOICES: u8 = 8;
+
+fn detune_curve_display_name(curve: DetuneCurve) -> &'static str {
+    match curve {
+        DetuneCurve::Linear => "Linear",
+        DetuneCurve::Exponential => "Exponential",
+        DetuneCurve::Logarithmic => "Logarithmic",
+    }
+}
+
+/// Unison editor for oscillator instruments. `on_change` receives the updated params and
+/// whether the voice count changed, which requires rebuilding the backend instrument.
+pub fn show_unison_editor(
+    ui: &mut egui::Ui,
+    params: &mut UnisonParams,
+    instrument_id: usize,
+    ui_prefix: &'static str,
+    mut on_change: impl FnMut(&UnisonParams, bool),
+) {
+    ui.push_id((ui_prefix, instrument_id as u32, "unison"), |ui| {
+        egui::CollapsingHeader::new("Unison")
+            .id_salt((ui_prefix, instrument_id as u32, "unison_hdr"))
+            .show(ui, |ui| {
+                let mut changed = false;
+                let mut voices = params.voices;
+                let mut detune = params.detune_cents;
+                let mut curve = params.detune_curve;
+                let mut phase = params.phase_randomness;
+                let mut spread = params.stereo_spread;
+
+                ui.horizontal(|ui| {
+                    ui.label("Voices");
+                    ui.add(egui::Slider::new(&mut voices, 1..=MAX_UNISON_VOICES));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Detune");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut detune, 0.0..=100.0).suffix(" ct"))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Curve");
+                    egui::ComboBox::from_id_salt((ui_prefix, instrument_id as u32, "curve"))
+                        .selected_text(detune_curve_display_name(curve))
+                        .show_ui(ui, |ui| {
+                            for c in [
+                                DetuneCurve::Linear,
+                                DetuneCurve::Exponential,
+                                DetuneCurve::Logarithmic,
+                            ] {
+                                changed |= ui
+                                    .selectable_value(&mut curve, c, detune_curve_display_name(c))
+                                    .changed();
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Phase random");
+                    changed |= ui.add(egui::Slider::new(&mut phase, 0.0..=1.0)).changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Stereo spread");
+                    changed |= ui.add(egui::Slider::new(&mut spread, 0.0..=1.0)).changed();
+                });
+
+                let voices_changed = voices != params.voices;
+                if changed || voices_changed {
+                    params.voices = voices;
+                    params.detune_cents = detune;
+                    params.detune_curve = curve;
+                    params.phase_randomness = phase;
+                    params.stereo_spread = spread;
+                    on_change(params, voices_changed);
+                }
+            });
+    });
+}
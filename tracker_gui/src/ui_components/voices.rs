@@ -0,0 +1,36 @@
+use audio_backend::{EnvelopeState, VoiceSnapshot};
+use eframe::egui;
+
+fn stage_label(stage: Option<EnvelopeState>) -> &'static str {
+    match stage {
+        None => "",
+        Some(EnvelopeState::Idle) => "idle",
+        Some(EnvelopeState::Attack) => "A",
+        Some(EnvelopeState::Decay) => "D",
+        Some(EnvelopeState::Sustain) => "S",
+        Some(EnvelopeState::Release) => "R",
+    }
+}
+
+/// One-line strip of the voices the engine is rendering: a voice count, then
+/// `instrument:note stage` per voice with its level as a bar.
+pub fn show_voice_activity(ui: &mut egui::Ui, snapshot: &VoiceSnapshot) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(format!("Voices: {}", snapshot.active));
+        for voice in &snapshot.voices {
+            ui.separator();
+            ui.label(format!(
+                "{:02X}:{} {}",
+                voice.instrument_id,
+                voice.note,
+                stage_label(voice.stage)
+            ))
+            .on_hover_text(format!("Voice {}", voice.voice));
+            ui.add(
+                egui::ProgressBar::new(voice.level.clamp(0.0, 1.0))
+                    .desired_width(32.0)
+                    .desired_height(6.0),
+            );
+        }
+    });
+}